@@ -397,6 +397,10 @@ pub trait CircuitCatalog: Send {
                     .as_ref()
                     .map(|handle| handle.fork()),
             },
+            OutputQuery::Select => OutputQueryHandles {
+                delta: None,
+                snapshot: handles.snapshot_handle.as_ref().map(|handle| handle.fork()),
+            },
         })
     }
 }
@@ -509,6 +513,21 @@ pub struct OutputCollectionHandles {
     /// outputs up to `N` quantiles of the input collection, computed using
     /// the [`dbsp::Stream::stream_key_quantiles`] operator.
     pub quantiles_handle: Option<Box<dyn SerCollectionHandle>>,
+
+    /// Input stream used to request a snapshot of the collection.
+    ///
+    /// Setting this input to `true` triggers the computation of the
+    /// snapshot, which is output to the `snapshot_handle` stream at the end
+    /// of the current clock cycle.
+    pub snapshot_trigger_handle: Option<InputHandle<bool>>,
+
+    /// Snapshot stream.
+    ///
+    /// When the `snapshot_trigger_handle` input is set to `true`, this stream
+    /// outputs the current contents of the collection, i.e., the integral of
+    /// the delta stream.  Used to evaluate
+    /// [select](`OutputQuery::Select`) queries.
+    pub snapshot_handle: Option<Box<dyn SerCollectionHandle>>,
 }
 
 /// Query result streams.
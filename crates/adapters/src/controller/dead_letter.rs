@@ -0,0 +1,118 @@
+//! Dead-letter output for input records that fail to parse.
+//!
+//! When an input connector is configured with a
+//! [`DeadLetterConfig`](pipeline_types::config::DeadLetterConfig), every
+//! [`ParseError`] reported by its parser is serialized as a JSON object and
+//! written, one object per line, to an output transport endpoint.  This allows
+//! the offending data to be inspected and reprocessed after fixing the
+//! schema or the producer.
+
+use crate::{
+    transport::{AsyncErrorCallback, OutputEndpoint, Step},
+    ControllerError, OutputTransport, ParseError,
+};
+use anyhow::Result as AnyResult;
+use pipeline_types::config::{
+    ConnectorConfig, DeadLetterConfig, FormatConfig, OutputEndpointConfig,
+};
+use pipeline_types::query::OutputQuery;
+use serde::Serialize;
+use serde_yaml::Value as YamlValue;
+use std::borrow::Cow;
+
+/// A record written to the dead-letter output.
+#[derive(Serialize)]
+struct DeadLetterRecord<'a> {
+    /// Name of the input endpoint that received the invalid data.
+    endpoint: &'a str,
+
+    /// Description of the error, event number, field, and invalid input.
+    #[serde(flatten)]
+    error: &'a ParseError,
+}
+
+/// Output endpoint that receives parse errors for an input endpoint.
+pub(crate) struct DeadLetterOutput {
+    endpoint_name: String,
+    endpoint: Box<dyn OutputEndpoint>,
+
+    /// Step number of the next batch.  Each call to [`Self::push`] writes a
+    /// batch of its own, so that endpoints that discard repeated steps don't
+    /// drop any errors.
+    step: Step,
+}
+
+impl DeadLetterOutput {
+    /// Creates and connects the dead-letter output for input endpoint
+    /// `endpoint_name` connected to `stream`.
+    pub(crate) fn new(
+        endpoint_name: &str,
+        stream: &str,
+        config: &DeadLetterConfig,
+        async_error_callback: AsyncErrorCallback,
+    ) -> Result<Self, ControllerError> {
+        let transport =
+            <dyn OutputTransport>::get_transport(&config.transport.name).ok_or_else(|| {
+                ControllerError::unsupported_dead_letter_transport(
+                    endpoint_name,
+                    &config.transport.name,
+                )
+            })?;
+
+        let output_config = OutputEndpointConfig {
+            stream: Cow::from(stream.to_string()),
+            query: OutputQuery::default(),
+            select: None,
+            connector_config: ConnectorConfig {
+                transport: config.transport.clone(),
+                format: FormatConfig {
+                    name: Cow::from("json"),
+                    config: YamlValue::Null,
+                },
+                max_buffered_records: u64::MAX,
+                dead_letter: None,
+                max_parse_errors: None,
+                max_records_per_second: None,
+                max_bytes_per_second: None,
+                weight: 1,
+            },
+        };
+
+        let mut endpoint = transport
+            .new_endpoint(&output_config)
+            .map_err(|e| ControllerError::output_transport_error(endpoint_name, true, e))?;
+        endpoint
+            .connect(async_error_callback)
+            .map_err(|e| ControllerError::output_transport_error(endpoint_name, true, e))?;
+
+        Ok(Self {
+            endpoint_name: endpoint_name.to_string(),
+            endpoint,
+            step: 0,
+        })
+    }
+
+    /// Writes `errors` to the dead-letter output.
+    pub(crate) fn push(&mut self, errors: &[ParseError]) -> AnyResult<()> {
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let mut buffer = Vec::new();
+        for error in errors {
+            serde_json::to_writer(
+                &mut buffer,
+                &DeadLetterRecord {
+                    endpoint: &self.endpoint_name,
+                    error,
+                },
+            )?;
+            buffer.push(b'\n');
+        }
+
+        self.endpoint.batch_start(self.step)?;
+        self.step += 1;
+        self.endpoint.push_buffer(&buffer)?;
+        self.endpoint.batch_end()
+    }
+}
@@ -85,6 +85,13 @@ pub enum ConfigError {
         endpoint_name: String,
         error: String,
     },
+
+    /// Input endpoint configuration specifies a dead-letter transport that
+    /// is not a registered output transport, e.g., `http`.
+    UnsupportedDeadLetterTransport {
+        endpoint_name: String,
+        transport_name: String,
+    },
 }
 
 impl StdError for ConfigError {}
@@ -105,6 +112,9 @@ impl DetailedError for ConfigError {
             Self::UnknownOutputStream { .. } => Cow::from("UnknownOutputStream"),
             Self::InputFormatNotSupported { .. } => Cow::from("InputFormatNotSupported"),
             Self::OutputFormatNotSupported { .. } => Cow::from("OutputFormatNotSupported"),
+            Self::UnsupportedDeadLetterTransport { .. } => {
+                Cow::from("UnsupportedDeadLetterTransport")
+            }
         }
     }
 }
@@ -198,6 +208,12 @@ impl Display for ConfigError {
                     "Format not supported on output endpoint '{endpoint_name}': {error}"
                 )
             }
+            Self::UnsupportedDeadLetterTransport {
+                endpoint_name,
+                transport_name,
+            } => {
+                write!(f, "Input endpoint '{endpoint_name}' specifies dead-letter transport '{transport_name}', which is not supported; dead-letter output requires an output transport such as 'file' or 'kafka'")
+            }
         }
     }
 }
@@ -301,6 +317,13 @@ impl ConfigError {
             error: error.to_owned(),
         }
     }
+
+    pub fn unsupported_dead_letter_transport(endpoint_name: &str, transport_name: &str) -> Self {
+        Self::UnsupportedDeadLetterTransport {
+            endpoint_name: endpoint_name.to_owned(),
+            transport_name: transport_name.to_owned(),
+        }
+    }
 }
 
 /// Controller error.
@@ -680,6 +703,15 @@ impl ControllerError {
         }
     }
 
+    pub fn unsupported_dead_letter_transport(endpoint_name: &str, transport_name: &str) -> Self {
+        Self::Config {
+            config_error: ConfigError::unsupported_dead_letter_transport(
+                endpoint_name,
+                transport_name,
+            ),
+        }
+    }
+
     pub fn input_transport_error(endpoint_name: &str, fatal: bool, error: AnyError) -> Self {
         Self::InputTransportError {
             endpoint_name: endpoint_name.to_owned(),
@@ -42,15 +42,17 @@ use crate::{
     InputTransport, OutputConsumer, OutputEndpoint, OutputFormat, OutputQueryHandles,
     OutputTransport, ParseError, Parser, PipelineState,
 };
-use anyhow::Error as AnyError;
+use anyhow::{anyhow, Error as AnyError};
 use crossbeam::channel::{self, Sender};
 use crossbeam::{
     queue::SegQueue,
     sync::{Parker, ShardedLock, Unparker},
 };
+use dead_letter::DeadLetterOutput;
 use log::trace;
 use log::{debug, error, info};
 use pipeline_types::query::OutputQuery;
+use select::SelectEncoder;
 use std::collections::HashMap;
 use std::sync::Condvar;
 use std::{
@@ -63,13 +65,15 @@ use std::{
     time::{Duration, Instant},
 };
 
+mod dead_letter;
 mod error;
+mod select;
 mod stats;
 
 pub use error::{ConfigError, ControllerError};
 pub use pipeline_types::config::{
-    ConnectorConfig, FormatConfig, InputEndpointConfig, OutputEndpointConfig, PipelineConfig,
-    RuntimeConfig, TransportConfig,
+    ConnectorConfig, DeadLetterConfig, FormatConfig, InputEndpointConfig, OutputEndpointConfig,
+    PipelineConfig, RuntimeConfig, TransportConfig,
 };
 pub use stats::{ControllerStatus, InputEndpointStatus, OutputEndpointStatus};
 
@@ -639,9 +643,20 @@ impl Controller {
             let step = controller.step.load(Ordering::Acquire);
             trace!("stepping to {step}");
 
+            // Shortest time until a rate-limited endpoint may resume.
+            let now = Instant::now();
+            let mut timeout: Option<Duration> = None;
+
             for (epid, ep) in controller.inputs.lock().unwrap().iter() {
                 let current_state = endpoint_states.entry(*epid).or_insert(EndpointState::Pause);
-                let desired_state = if global_pause || controller.status.input_endpoint_full(epid) {
+                let throttle = controller.status.input_endpoint_throttle(epid, now);
+                if let Some(delay) = throttle {
+                    timeout = Some(timeout.map_or(delay, |timeout| timeout.min(delay)));
+                }
+                let desired_state = if global_pause
+                    || controller.status.input_endpoint_full(epid)
+                    || throttle.is_some()
+                {
                     EndpointState::Pause
                 } else {
                     EndpointState::Run(step)
@@ -658,7 +673,10 @@ impl Controller {
                 }
             }
 
-            parker.park();
+            match timeout {
+                Some(timeout) => parker.park_timeout(timeout),
+                None => parker.park(),
+            }
         }
     }
 }
@@ -791,11 +809,10 @@ impl OutputEndpoints {
     }
 
     fn remove(&mut self, endpoint_id: &EndpointId) -> Option<OutputEndpointDescr> {
-        self.by_id.remove(endpoint_id).map(|descr| {
+        self.by_id.remove(endpoint_id).inspect(|descr| {
             self.by_stream
                 .get_mut(&(descr.stream_name.clone(), descr.query))
                 .map(|(_, endpoints)| endpoints.remove(endpoint_id));
-            descr
         })
     }
 }
@@ -926,12 +943,39 @@ impl ControllerInner {
             &endpoint_config.connector_config.format.config,
         )?;
 
-        // Create probe.
         let endpoint_id = inputs.keys().next_back().map(|k| k + 1).unwrap_or(0);
+
+        // Create dead-letter output.
+        let dead_letter = match &endpoint_config.connector_config.dead_letter {
+            Some(dead_letter_config) => {
+                let self_weak = Arc::downgrade(self);
+                let endpoint_name_str = endpoint_name.to_string();
+                let dead_letter = DeadLetterOutput::new(
+                    endpoint_name,
+                    &endpoint_config.stream,
+                    dead_letter_config,
+                    Box::new(move |fatal: bool, e: AnyError| {
+                        if let Some(controller) = self_weak.upgrade() {
+                            controller.input_transport_error(
+                                endpoint_id,
+                                &endpoint_name_str,
+                                fatal,
+                                e.context("dead-letter output error"),
+                            )
+                        }
+                    }),
+                )?;
+                Some(Arc::new(Mutex::new(dead_letter)))
+            }
+            None => None,
+        };
+
+        // Create probe.
         let probe = Box::new(InputProbe::new(
             endpoint_id,
             endpoint_name,
             parser,
+            dead_letter,
             self.clone(),
             self.circuit_thread_unparker.clone(),
             self.backpressure_thread_unparker.clone(),
@@ -1085,11 +1129,14 @@ impl ControllerInner {
                     &endpoint_config.connector_config.format.name,
                 )
             })?;
-        let encoder = format.new_encoder(
+        let mut encoder = format.new_encoder(
             endpoint_name,
             &endpoint_config.connector_config.format.config,
             probe,
         )?;
+        if let Some(select) = &endpoint_config.select {
+            encoder = Box::new(SelectEncoder::new(encoder, select.clone()));
+        }
 
         let parker = Parker::new();
         let endpoint_descr = OutputEndpointDescr::new(
@@ -1252,9 +1299,22 @@ impl ControllerInner {
         ));
     }
 
+    /// Process a parse error.
+    ///
+    /// Update endpoint stats and notify the error callback.  Fails the
+    /// endpoint if the error pushes the number of parse errors over its
+    /// `max_parse_errors` threshold.
     fn parse_error(&self, endpoint_id: EndpointId, endpoint_name: &str, error: ParseError) {
-        self.status.parse_error(endpoint_id);
+        let exceeded = self.status.parse_error(endpoint_id);
         self.error(ControllerError::parse_error(endpoint_name, error));
+        if exceeded {
+            self.input_transport_error(
+                endpoint_id,
+                endpoint_name,
+                true,
+                anyhow!("number of parse errors exceeds the 'max_parse_errors' threshold"),
+            );
+        }
     }
 
     fn encode_error(&self, endpoint_id: EndpointId, endpoint_name: &str, error: AnyError) {
@@ -1292,6 +1352,8 @@ struct InputProbe {
     endpoint_id: EndpointId,
     endpoint_name: String,
     parser: Box<dyn Parser>,
+    /// Dead-letter output shared by all forks of the probe.
+    dead_letter: Option<Arc<Mutex<DeadLetterOutput>>>,
     controller: Arc<ControllerInner>,
     circuit_thread_unparker: Unparker,
     backpressure_thread_unparker: Unparker,
@@ -1302,6 +1364,7 @@ impl InputProbe {
         endpoint_id: EndpointId,
         endpoint_name: &str,
         parser: Box<dyn Parser>,
+        dead_letter: Option<Arc<Mutex<DeadLetterOutput>>>,
         controller: Arc<ControllerInner>,
         circuit_thread_unparker: Unparker,
         backpressure_thread_unparker: Unparker,
@@ -1310,22 +1373,46 @@ impl InputProbe {
             endpoint_id,
             endpoint_name: endpoint_name.to_owned(),
             parser,
+            dead_letter,
             controller,
             circuit_thread_unparker,
             backpressure_thread_unparker,
         }
     }
 
+    /// True if the endpoint has failed after exceeding its
+    /// `max_parse_errors` threshold and must discard its input.
+    fn failed(&self) -> bool {
+        self.controller
+            .status
+            .parse_errors_exceeded(&self.endpoint_id)
+    }
+
+    /// Report parse errors to the controller and the dead-letter output.
+    fn parse_errors(&self, errors: &[ParseError]) {
+        for error in errors.iter() {
+            self.controller
+                .parse_error(self.endpoint_id, &self.endpoint_name, error.clone());
+        }
+        if let Some(dead_letter) = &self.dead_letter {
+            if let Err(e) = dead_letter.lock().unwrap().push(errors) {
+                self.controller.input_transport_error(
+                    self.endpoint_id,
+                    &self.endpoint_name,
+                    false,
+                    e.context("failed to write parse errors to the dead-letter output"),
+                );
+            }
+        }
+    }
+
     fn input_common(
         &mut self,
         data: &[u8],
         num_records: usize,
         errors: Vec<ParseError>,
     ) -> Vec<ParseError> {
-        for error in errors.iter() {
-            self.controller
-                .parse_error(self.endpoint_id, &self.endpoint_name, error.clone());
-        }
+        self.parse_errors(&errors);
         self.controller.status.input_batch(
             self.endpoint_id,
             data.len(),
@@ -1342,11 +1429,17 @@ impl InputProbe {
 /// `InputConsumer` interface exposed to the transport endpoint.
 impl InputConsumer for InputProbe {
     fn input_fragment(&mut self, data: &[u8]) -> Vec<ParseError> {
+        if self.failed() {
+            return Vec::new();
+        }
         let (num_records, errors) = self.parser.input_fragment(data);
         self.input_common(data, num_records, errors)
     }
 
     fn input_chunk(&mut self, data: &[u8]) -> Vec<ParseError> {
+        if self.failed() {
+            return Vec::new();
+        }
         let (num_records, errors) = self.parser.input_chunk(data);
         self.input_common(data, num_records, errors)
     }
@@ -1356,11 +1449,15 @@ impl InputConsumer for InputProbe {
         // no new data has been received, the parser may contain some partially
         // parsed data and may be waiting for, e.g., and end-of-line or
         // end-of-file to finish parsing it).
-        let (num_records, errors) = self.parser.eoi();
-        for error in errors.iter() {
-            self.controller
-                .parse_error(self.endpoint_id, &self.endpoint_name, error.clone());
-        }
+        //
+        // A failed endpoint discards partially parsed data, but still reports
+        // end-of-input so that the pipeline can complete.
+        let (num_records, errors) = if self.failed() {
+            (0, Vec::new())
+        } else {
+            self.parser.eoi()
+        };
+        self.parse_errors(&errors);
         self.controller
             .status
             .eoi(self.endpoint_id, num_records, &self.circuit_thread_unparker);
@@ -1378,6 +1475,7 @@ impl InputConsumer for InputProbe {
             self.endpoint_id,
             &self.endpoint_name,
             self.parser.fork(),
+            self.dead_letter.clone(),
             self.controller.clone(),
             self.circuit_thread_unparker.clone(),
             self.backpressure_thread_unparker.clone(),
@@ -1452,6 +1550,30 @@ impl OutputConsumer for OutputProbe {
         }
     }
 
+    fn key_fields(&self) -> Option<&[String]> {
+        self.endpoint.key_fields()
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) {
+        let num_bytes = key.len() + val.map_or(0, <[u8]>::len);
+
+        match self.endpoint.push_key(key, val) {
+            Ok(()) => {
+                self.controller
+                    .status
+                    .output_buffer(self.endpoint_id, num_bytes);
+            }
+            Err(error) => {
+                self.controller.output_transport_error(
+                    self.endpoint_id,
+                    &self.endpoint_name,
+                    false,
+                    error,
+                );
+            }
+        }
+    }
+
     fn batch_end(&mut self) {
         self.endpoint.batch_end().unwrap_or_else(|e| {
             self.controller
@@ -1464,11 +1586,17 @@ impl OutputConsumer for OutputProbe {
 mod test {
     use crate::{
         test::{generate_test_batch, test_circuit, wait, TestStruct, DEFAULT_TIMEOUT_MS},
-        Controller, PipelineConfig,
+        ConfigError, Controller, ControllerError, PipelineConfig,
     };
     use csv::{ReaderBuilder as CsvReaderBuilder, WriterBuilder as CsvWriterBuilder};
-    use std::fs::remove_file;
-    use tempfile::NamedTempFile;
+    use serde_json::Value as JsonValue;
+    use std::{
+        fs::{read_to_string, remove_file},
+        io::Write,
+        sync::{atomic::Ordering, Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use tempfile::{NamedTempFile, TempDir};
 
     use proptest::prelude::*;
 
@@ -1572,4 +1700,272 @@ outputs:
             assert_eq!(actual, expected);
         }
     }
+
+    /// Starts a pipeline that reads `input` from a CSV file and writes parse
+    /// errors to a dead-letter file.  Returns the controller, the path to the
+    /// dead-letter file, and the errors reported by the controller.
+    fn dead_letter_pipeline(
+        input: &str,
+        max_parse_errors: Option<u64>,
+    ) -> (Controller, TempDir, Arc<Mutex<Vec<String>>>) {
+        let tempdir = TempDir::new().unwrap();
+        let input_path = tempdir.path().join("input.csv");
+        let output_path = tempdir.path().join("output.csv");
+        let dead_letter_path = tempdir.path().join("dead_letter.json");
+        std::fs::File::create(&input_path)
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+
+        let max_parse_errors = max_parse_errors
+            .map(|max_parse_errors| format!("max_parse_errors: {max_parse_errors}"))
+            .unwrap_or_default();
+        let config_str = format!(
+            r#"
+name: test
+workers: 1
+inputs:
+    test_input1:
+        stream: test_input1
+        transport:
+            name: file
+            config:
+                path: {input_path:?}
+        format:
+            name: csv
+        dead_letter:
+            transport:
+                name: file
+                config:
+                    path: {dead_letter_path:?}
+        {max_parse_errors}
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {output_path:?}
+        format:
+            name: csv
+        "#
+        );
+        let config: PipelineConfig = serde_yaml::from_str(&config_str).unwrap();
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        let controller = Controller::with_config(
+            |workers| Ok(test_circuit(workers)),
+            &config,
+            Box::new(move |e| errors_clone.lock().unwrap().push(e.to_string())),
+        )
+        .unwrap();
+        controller.start();
+
+        (controller, tempdir, errors)
+    }
+
+    fn read_dead_letter(tempdir: &TempDir) -> Vec<JsonValue> {
+        read_to_string(tempdir.path().join("dead_letter.json"))
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_dead_letter() {
+        let (controller, tempdir, errors) = dead_letter_pipeline(
+            "1,true,,foo\nx,true,,bar\n2,false,,baz\n3,maybe,,qux\n",
+            None,
+        );
+
+        wait(|| controller.pipeline_complete(), DEFAULT_TIMEOUT_MS).unwrap();
+
+        {
+            let input_status = controller.status().input_status();
+            let input_status = input_status.get(&0).unwrap();
+            assert_eq!(
+                input_status
+                    .metrics
+                    .num_parse_errors
+                    .load(Ordering::Acquire),
+                2
+            );
+            assert!(input_status.fatal_error.lock().unwrap().is_none());
+        }
+        assert_eq!(
+            controller
+                .status()
+                .output_status()
+                .get(&0)
+                .unwrap()
+                .transmitted_records(),
+            2
+        );
+        controller.stop().unwrap();
+        assert_eq!(errors.lock().unwrap().len(), 2);
+
+        let records = read_dead_letter(&tempdir);
+        assert_eq!(records.len(), 2);
+        for (record, (event_number, text)) in records
+            .iter()
+            .zip([(2, "x,true,,bar"), (4, "3,maybe,,qux")])
+        {
+            assert_eq!(record["endpoint"], "test_input1");
+            assert_eq!(record["event_number"], event_number);
+            assert!(record["invalid_text"].as_str().unwrap().starts_with(text));
+            assert!(!record["description"].as_str().unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_max_parse_errors() {
+        let (controller, tempdir, errors) =
+            dead_letter_pipeline("1,true,,foo\nx,true,,bar\ny,false,,baz\n", Some(1));
+
+        wait(|| controller.pipeline_complete(), DEFAULT_TIMEOUT_MS).unwrap();
+
+        {
+            let input_status = controller.status().input_status();
+            let input_status = input_status.get(&0).unwrap();
+            assert_eq!(
+                input_status
+                    .metrics
+                    .num_parse_errors
+                    .load(Ordering::Acquire),
+                2
+            );
+            assert!(input_status
+                .fatal_error
+                .lock()
+                .unwrap()
+                .as_ref()
+                .unwrap()
+                .contains("max_parse_errors"));
+        }
+        controller.stop().unwrap();
+
+        // Two parse errors plus the fatal error.
+        assert_eq!(errors.lock().unwrap().len(), 3);
+        assert_eq!(read_dead_letter(&tempdir).len(), 2);
+    }
+
+    #[test]
+    fn test_dead_letter_http() {
+        let tempdir = TempDir::new().unwrap();
+        let input_path = tempdir.path().join("input.csv");
+        std::fs::File::create(&input_path).unwrap();
+
+        let config_str = format!(
+            r#"
+name: test
+workers: 1
+inputs:
+    test_input1:
+        stream: test_input1
+        transport:
+            name: file
+            config:
+                path: {input_path:?}
+        format:
+            name: csv
+        dead_letter:
+            transport:
+                name: http
+outputs: {{}}
+        "#
+        );
+        let config: PipelineConfig = serde_yaml::from_str(&config_str).unwrap();
+
+        let Err(error) = Controller::with_config(
+            |workers| Ok(test_circuit(workers)),
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        ) else {
+            panic!("HTTP dead-letter output should be rejected");
+        };
+        assert!(matches!(
+            error,
+            ControllerError::Config {
+                config_error: ConfigError::UnsupportedDeadLetterTransport { .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let tempdir = TempDir::new().unwrap();
+        let input_path = tempdir.path().join("input.csv");
+        let output_path = tempdir.path().join("output.csv");
+        let mut input = std::fs::File::create(&input_path).unwrap();
+        for id in 0..3000 {
+            writeln!(input, "{id},true,,foo").unwrap();
+        }
+        drop(input);
+
+        let config_str = format!(
+            r#"
+name: test
+workers: 1
+inputs:
+    test_input1:
+        stream: test_input1
+        transport:
+            name: file
+            config:
+                path: {input_path:?}
+                buffer_size_bytes: 1000
+        format:
+            name: csv
+        max_records_per_second: 1000
+outputs:
+    test_output1:
+        stream: test_output1
+        transport:
+            name: file
+            config:
+                path: {output_path:?}
+        format:
+            name: csv
+        "#
+        );
+        let config: PipelineConfig = serde_yaml::from_str(&config_str).unwrap();
+
+        let controller = Controller::with_config(
+            |workers| Ok(test_circuit(workers)),
+            &config,
+            Box::new(|e| panic!("error: {e}")),
+        )
+        .unwrap();
+        let start = Instant::now();
+        controller.start();
+
+        wait(|| controller.pipeline_complete(), DEFAULT_TIMEOUT_MS).unwrap();
+
+        // The first 1000 records are admitted immediately; the remaining 2000
+        // take about two seconds.
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(
+            controller
+                .status()
+                .output_status()
+                .get(&0)
+                .unwrap()
+                .transmitted_records(),
+            3000
+        );
+        assert!(
+            controller
+                .status()
+                .input_status()
+                .get(&0)
+                .unwrap()
+                .metrics
+                .throttled_usecs
+                .load(Ordering::Acquire)
+                > 0
+        );
+        controller.stop().unwrap();
+    }
 }
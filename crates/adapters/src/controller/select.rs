@@ -0,0 +1,448 @@
+//! Evaluation of ad hoc [`SelectQuery`]s over output batches.
+//!
+//! The circuit outputs a snapshot of the queried collection (see
+//! [`OutputQuery::Select`](`pipeline_types::query::OutputQuery::Select`)).
+//! [`SelectEncoder`] wraps the encoder of the endpoint and evaluates the query
+//! over the snapshot, so that the program doesn't need to be recompiled for
+//! each query.
+//!
+//! [`SerCursor`] can't seek to a key given as JSON, so evaluating a query
+//! scans every key in the snapshot and serializes it to JSON to evaluate the
+//! filter, even for predicates over the leading column of the key.
+
+use crate::{
+    catalog::{RecordFormat, SerBatch, SerCursor},
+    ControllerError, Encoder, OutputConsumer,
+};
+use anyhow::{anyhow, Result as AnyResult};
+use pipeline_types::{
+    format::json::JsonFlavor,
+    query::{Predicate, PredicateOp, SelectQuery},
+};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use std::{cmp::Ordering, sync::Arc};
+
+/// Encoder that only encodes the records selected by a [`SelectQuery`].
+pub(crate) struct SelectEncoder {
+    encoder: Box<dyn Encoder>,
+    query: Arc<SelectQuery>,
+
+    /// Number of records that can still be output under the query's
+    /// `limit`.
+    remaining: Option<u64>,
+}
+
+impl SelectEncoder {
+    pub(crate) fn new(encoder: Box<dyn Encoder>, query: SelectQuery) -> Self {
+        Self {
+            encoder,
+            remaining: query.limit,
+            query: Arc::new(query),
+        }
+    }
+}
+
+impl Encoder for SelectEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.encoder.consumer()
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        let batches = batches
+            .iter()
+            .map(|batch| {
+                SelectBatch::new(batch.clone(), self.query.clone(), &mut self.remaining)
+                    .map(|batch| Arc::new(batch) as Arc<dyn SerBatch>)
+            })
+            .collect::<AnyResult<Vec<_>>>()?;
+        self.encoder.encode(&batches)
+    }
+}
+
+/// A batch that only contains the records of another batch that are
+/// selected by a query.
+struct SelectBatch {
+    batch: Arc<dyn SerBatch>,
+    query: Arc<SelectQuery>,
+
+    /// For each key in `batch`, whether it is selected by the query.
+    selected: Vec<bool>,
+
+    key_count: usize,
+    len: usize,
+}
+
+impl SelectBatch {
+    /// Evaluates `query` over `batch`, selecting at most `remaining` keys and
+    /// decrementing `remaining` by the number of selected keys.
+    fn new(
+        batch: Arc<dyn SerBatch>,
+        query: Arc<SelectQuery>,
+        remaining: &mut Option<u64>,
+    ) -> AnyResult<Self> {
+        let mut selected = Vec::with_capacity(batch.key_count());
+        let mut key_count = 0;
+        let mut len = 0;
+
+        let mut cursor = batch.cursor(RecordFormat::Json(JsonFlavor::Default))?;
+        let mut buffer = Vec::new();
+        while cursor.key_valid() {
+            let select = if *remaining == Some(0) {
+                false
+            } else {
+                buffer.clear();
+                cursor.serialize_key(&mut buffer)?;
+                let record = parse_record(&buffer)?;
+                evaluate(&query.filter, &record)?
+            };
+
+            if select {
+                key_count += 1;
+                while cursor.val_valid() {
+                    len += 1;
+                    cursor.step_val();
+                }
+                if let Some(remaining) = remaining.as_mut() {
+                    *remaining -= 1;
+                }
+            }
+            selected.push(select);
+            cursor.step_key();
+        }
+        drop(cursor);
+
+        Ok(Self {
+            batch,
+            query,
+            selected,
+            key_count,
+            len,
+        })
+    }
+}
+
+impl SerBatch for SelectBatch {
+    fn key_count(&self) -> usize {
+        self.key_count
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn cursor<'a>(
+        &'a self,
+        record_format: RecordFormat,
+    ) -> Result<Box<dyn SerCursor + 'a>, ControllerError> {
+        // Projection only applies to JSON; the HTTP server rejects queries
+        // that project columns with other formats.
+        let columns = match record_format {
+            RecordFormat::Json(_) => self.query.columns.as_deref(),
+            _ => None,
+        };
+        Ok(Box::new(SelectCursor::new(
+            self.batch.cursor(record_format)?,
+            &self.selected,
+            columns,
+        )))
+    }
+}
+
+/// Cursor that skips the keys that are not selected by a query and
+/// projects the selected keys onto the query's columns.
+struct SelectCursor<'a> {
+    cursor: Box<dyn SerCursor + 'a>,
+    selected: &'a [bool],
+    columns: Option<&'a [String]>,
+
+    /// Index of the current key in the underlying batch.
+    index: usize,
+
+    buffer: Vec<u8>,
+}
+
+impl<'a> SelectCursor<'a> {
+    fn new(
+        cursor: Box<dyn SerCursor + 'a>,
+        selected: &'a [bool],
+        columns: Option<&'a [String]>,
+    ) -> Self {
+        let mut result = Self {
+            cursor,
+            selected,
+            columns,
+            index: 0,
+            buffer: Vec::new(),
+        };
+        result.skip_unselected();
+        result
+    }
+
+    fn skip_unselected(&mut self) {
+        while self.cursor.key_valid() && !self.selected[self.index] {
+            self.cursor.step_key();
+            self.index += 1;
+        }
+    }
+}
+
+impl<'a> SerCursor for SelectCursor<'a> {
+    fn key_valid(&self) -> bool {
+        self.cursor.key_valid()
+    }
+
+    fn val_valid(&self) -> bool {
+        self.cursor.val_valid()
+    }
+
+    fn serialize_key(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        let Some(columns) = self.columns else {
+            return self.cursor.serialize_key(dst);
+        };
+
+        self.buffer.clear();
+        self.cursor.serialize_key(&mut self.buffer)?;
+        let record = parse_record(&self.buffer)?;
+
+        dst.push(b'{');
+        for (i, column) in columns.iter().enumerate() {
+            let (name, value) = lookup_column(&record, column)?;
+            if i > 0 {
+                dst.push(b',');
+            }
+            serde_json::to_writer(&mut *dst, name)?;
+            dst.push(b':');
+            serde_json::to_writer(&mut *dst, value)?;
+        }
+        dst.push(b'}');
+        Ok(())
+    }
+
+    fn serialize_key_weight(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_key_weight(dst)
+    }
+
+    fn serialize_val(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_val(dst)
+    }
+
+    fn weight(&mut self) -> i64 {
+        self.cursor.weight()
+    }
+
+    fn step_key(&mut self) {
+        self.cursor.step_key();
+        self.index += 1;
+        self.skip_unselected();
+    }
+
+    fn step_val(&mut self) {
+        self.cursor.step_val();
+    }
+
+    fn rewind_keys(&mut self) {
+        self.cursor.rewind_keys();
+        self.index = 0;
+        self.skip_unselected();
+    }
+
+    fn rewind_vals(&mut self) {
+        self.cursor.rewind_vals();
+    }
+}
+
+fn parse_record(json: &[u8]) -> AnyResult<JsonMap<String, JsonValue>> {
+    match serde_json::from_slice(json)? {
+        JsonValue::Object(record) => Ok(record),
+        value => Err(anyhow!("expected a JSON object, found '{value}'")),
+    }
+}
+
+/// Looks up a column by name, ignoring case.
+fn lookup_column<'a>(
+    record: &'a JsonMap<String, JsonValue>,
+    column: &str,
+) -> AnyResult<(&'a String, &'a JsonValue)> {
+    record
+        .get_key_value(column)
+        .or_else(|| {
+            record
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(column))
+        })
+        .ok_or_else(|| anyhow!("unknown column '{column}'"))
+}
+
+/// Returns true if `record` satisfies all predicates in `filter`.
+fn evaluate(filter: &[Predicate], record: &JsonMap<String, JsonValue>) -> AnyResult<bool> {
+    for predicate in filter {
+        let (_, value) = lookup_column(record, &predicate.column)?;
+        if !evaluate_predicate(predicate.op, value, &predicate.value) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn evaluate_predicate(op: PredicateOp, value: &JsonValue, constant: &JsonValue) -> bool {
+    match (op, value, constant) {
+        // `IS NULL` and `IS NOT NULL`.
+        (PredicateOp::Eq, _, JsonValue::Null) => value.is_null(),
+        (PredicateOp::Ne, _, JsonValue::Null) => !value.is_null(),
+        // Arrays and objects are only compared for equality.
+        (PredicateOp::Eq, JsonValue::Array(_) | JsonValue::Object(_), _) => value == constant,
+        (PredicateOp::Ne, JsonValue::Array(_) | JsonValue::Object(_), _) => value != constant,
+        _ => match compare(value, constant) {
+            None => false,
+            Some(ordering) => match op {
+                PredicateOp::Eq => ordering == Ordering::Equal,
+                PredicateOp::Ne => ordering != Ordering::Equal,
+                PredicateOp::Lt => ordering == Ordering::Less,
+                PredicateOp::Le => ordering != Ordering::Greater,
+                PredicateOp::Gt => ordering == Ordering::Greater,
+                PredicateOp::Ge => ordering != Ordering::Less,
+            },
+        },
+    }
+}
+
+/// Compares a column value with a constant.  Returns `None` if either one is
+/// `NULL` or if they can't be compared.
+fn compare(value: &JsonValue, constant: &JsonValue) -> Option<Ordering> {
+    match (value, constant) {
+        (JsonValue::Null, _) | (_, JsonValue::Null) => None,
+        (JsonValue::Bool(x), JsonValue::Bool(y)) => Some(x.cmp(y)),
+        (JsonValue::Number(x), JsonValue::Number(y)) => {
+            if let (Some(x), Some(y)) = (x.as_i64(), y.as_i64()) {
+                Some(x.cmp(&y))
+            } else if let (Some(x), Some(y)) = (x.as_u64(), y.as_u64()) {
+                Some(x.cmp(&y))
+            } else {
+                x.as_f64()?.partial_cmp(&y.as_f64()?)
+            }
+        }
+        // Decimals may be serialized as strings.
+        (JsonValue::Number(x), JsonValue::String(y)) => {
+            x.as_f64()?.partial_cmp(&y.trim().parse::<f64>().ok()?)
+        }
+        (JsonValue::String(x), JsonValue::Number(y)) => {
+            x.trim().parse::<f64>().ok()?.partial_cmp(&y.as_f64()?)
+        }
+        (JsonValue::String(x), JsonValue::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SelectBatch;
+    use crate::{
+        catalog::{RecordFormat, SerBatch},
+        static_compile::seroutput::SerBatchImpl,
+        test::TestStruct,
+    };
+    use dbsp::{trace::Batch, OrdZSet};
+    use pipeline_types::{format::json::JsonFlavor, query::SelectQuery};
+    use std::sync::Arc;
+
+    fn test_batch() -> Arc<dyn SerBatch> {
+        let records = (0..10)
+            .map(|id| {
+                (
+                    TestStruct {
+                        id,
+                        b: id % 2 == 0,
+                        i: if id % 3 == 0 { None } else { Some(id as i64) },
+                        s: format!("s{id}"),
+                    },
+                    1,
+                )
+            })
+            .collect::<Vec<_>>();
+        let zset = OrdZSet::from_keys((), records);
+        Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>
+    }
+
+    /// Evaluates `query` over `test_batch()`, returning the selected records
+    /// in JSON format.
+    fn select(query: &str) -> Vec<String> {
+        let query: SelectQuery = serde_json::from_str(query).unwrap();
+        let mut remaining = query.limit;
+        let batch = SelectBatch::new(test_batch(), Arc::new(query), &mut remaining).unwrap();
+
+        let mut cursor = batch
+            .cursor(RecordFormat::Json(JsonFlavor::Default))
+            .unwrap();
+        let mut result = Vec::new();
+        while cursor.key_valid() {
+            let mut buffer = Vec::new();
+            cursor.serialize_key(&mut buffer).unwrap();
+            result.push(String::from_utf8(buffer).unwrap());
+            cursor.step_key();
+        }
+        assert_eq!(batch.key_count(), result.len());
+        result
+    }
+
+    #[test]
+    fn test_select_all() {
+        assert_eq!(select("{}").len(), 10);
+        assert_eq!(select(r#"{"limit": 3}"#).len(), 3);
+    }
+
+    #[test]
+    fn test_select_filter() {
+        // Key equality.
+        assert_eq!(
+            select(r#"{"filter": [{"column": "id", "op": "eq", "value": 4}]}"#),
+            [r#"{"id":4,"b":true,"i":4,"s":"s4"}"#]
+        );
+
+        // Key range.
+        assert_eq!(
+            select(
+                r#"{"filter": [{"column": "ID", "op": "ge", "value": 7},
+                               {"column": "id", "op": "lt", "value": 9}]}"#
+            ),
+            [
+                r#"{"id":7,"b":false,"i":7,"s":"s7"}"#,
+                r#"{"id":8,"b":true,"i":8,"s":"s8"}"#
+            ]
+        );
+
+        // NULL tests.
+        assert_eq!(
+            select(r#"{"filter": [{"column": "i", "op": "eq", "value": null}]}"#).len(),
+            4
+        );
+        assert_eq!(
+            select(r#"{"filter": [{"column": "i", "op": "ne", "value": null}]}"#).len(),
+            6
+        );
+
+        // Comparisons with NULL are false.
+        assert_eq!(
+            select(r#"{"filter": [{"column": "i", "op": "ne", "value": 1}]}"#).len(),
+            5
+        );
+
+        // Column predicates, limit and projection.
+        assert_eq!(
+            select(
+                r#"{"filter": [{"column": "b", "op": "eq", "value": false},
+                               {"column": "s", "op": "gt", "value": "s2"}],
+                    "columns": ["s", "id"],
+                    "limit": 2}"#
+            ),
+            [r#"{"s":"s3","id":3}"#, r#"{"s":"s5","id":5}"#]
+        );
+    }
+
+    #[test]
+    fn test_select_unknown_column() {
+        let query: SelectQuery =
+            serde_json::from_str(r#"{"filter": [{"column": "x", "op": "eq", "value": 1}]}"#)
+                .unwrap();
+        assert!(SelectBatch::new(test_batch(), Arc::new(query), &mut None).is_err());
+    }
+}
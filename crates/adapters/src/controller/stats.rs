@@ -43,12 +43,13 @@ use num_traits::FromPrimitive;
 use psutil::process::{Process, ProcessError};
 use serde::{Serialize, Serializer};
 use std::{
-    cmp::min,
+    cmp::{max, min},
     collections::BTreeMap,
     sync::{
         atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
         Mutex,
     },
+    time::{Duration, Instant},
 };
 
 #[derive(Default, Serialize)]
@@ -323,16 +324,50 @@ impl ControllerStatus {
     }
 
     /// True if the number of records buffered by the endpoint exceeds
-    /// its `max_buffered_records` config parameter.
+    /// its `max_buffered_records` config parameter, scaled by its `weight`
+    /// while other endpoints also have buffered records.
     pub fn input_endpoint_full(&self, endpoint_id: &EndpointId) -> bool {
-        let buffered_records = self.num_input_endpoint_buffered_records(endpoint_id);
+        let inputs = self.inputs.read().unwrap();
 
-        let max_buffered_records = match self.inputs.read().unwrap().get(endpoint_id) {
+        let max_buffered_records = match inputs.get(endpoint_id) {
             None => return false,
-            Some(endpoint) => endpoint.config.connector_config.max_buffered_records,
+            Some(endpoint) => Self::max_buffered_records(&inputs, endpoint),
         };
 
-        buffered_records >= max_buffered_records
+        self.num_input_endpoint_buffered_records(endpoint_id) >= max_buffered_records
+    }
+
+    /// Returns the number of records that `endpoint` may buffer: its
+    /// `max_buffered_records`, scaled by the ratio of its `weight` to the
+    /// largest weight among the endpoints that have buffered records.
+    fn max_buffered_records(
+        inputs: &BTreeMap<EndpointId, InputEndpointStatus>,
+        endpoint: &InputEndpointStatus,
+    ) -> u64 {
+        let max_buffered_records = endpoint.config.connector_config.max_buffered_records;
+        let weight = endpoint.weight();
+        let max_weight = inputs
+            .values()
+            .filter(|status| status.metrics.buffered_records.load(Ordering::Acquire) > 0)
+            .map(InputEndpointStatus::weight)
+            .max()
+            .unwrap_or(weight);
+        if weight >= max_weight {
+            max_buffered_records
+        } else {
+            (max_buffered_records as u128 * weight as u128 / max_weight as u128).max(1) as u64
+        }
+    }
+
+    /// Returns how long the endpoint must be paused to stay within its
+    /// `max_records_per_second` and `max_bytes_per_second` limits, or `None`
+    /// if it may ingest more data now.
+    pub fn input_endpoint_throttle(
+        &self,
+        endpoint_id: &EndpointId,
+        now: Instant,
+    ) -> Option<Duration> {
+        self.inputs.read().unwrap().get(endpoint_id)?.throttle(now)
     }
 
     /// Update counters after receiving a new input batch.
@@ -380,9 +415,15 @@ impl ControllerStatus {
         // removed. In this case, it's safe to ignore this operation.
         if let Some(endpoint_stats) = inputs.get(&endpoint_id) {
             let old = endpoint_stats.add_buffered(num_bytes, num_records);
-
-            if old < endpoint_stats.config.connector_config.max_buffered_records
-                && old + num_records >= endpoint_stats.config.connector_config.max_buffered_records
+            let max_buffered_records = Self::max_buffered_records(&inputs, endpoint_stats);
+
+            // Also wake up the backpressure thread when the endpoint starts
+            // competing with other endpoints, which can reduce their share,
+            // or when it exceeds its rate limit.
+            if (old < max_buffered_records && old + num_records >= max_buffered_records)
+                || (old == 0 && num_records > 0 && inputs.len() > 1)
+                || (endpoint_stats.is_rate_limited()
+                    && endpoint_stats.throttle(Instant::now()).is_some())
             {
                 backpressure_thread_unparker.unpark();
             }
@@ -506,9 +547,25 @@ impl ControllerStatus {
         })
     }
 
-    pub fn parse_error(&self, endpoint_id: EndpointId) {
+    /// Increment the parse error counter of the endpoint.
+    ///
+    /// Returns `true` if this error caused the number of parse errors to
+    /// exceed the `max_parse_errors` threshold of the endpoint.
+    pub fn parse_error(&self, endpoint_id: EndpointId) -> bool {
         if let Some(endpoint_stats) = self.input_status().get(&endpoint_id) {
-            endpoint_stats.parse_error();
+            let num_parse_errors = endpoint_stats.parse_error();
+            endpoint_stats.config.connector_config.max_parse_errors == Some(num_parse_errors - 1)
+        } else {
+            false
+        }
+    }
+
+    /// True if the number of parse errors at the endpoint exceeds its
+    /// `max_parse_errors` threshold.
+    pub fn parse_errors_exceeded(&self, endpoint_id: &EndpointId) -> bool {
+        match self.input_status().get(endpoint_id) {
+            Some(endpoint_stats) => endpoint_stats.parse_errors_exceeded(),
+            None => false,
         }
     }
 
@@ -613,6 +670,63 @@ pub struct InputEndpointMetrics {
 
     /// The first step known not to have committed yet.
     pub uncommitted: AtomicStep,
+
+    /// Total time, in microseconds, for which the endpoint was paused to
+    /// enforce its `max_records_per_second` and `max_bytes_per_second`
+    /// limits.
+    pub throttled_usecs: AtomicU64,
+}
+
+/// Token bucket that enforces a maximal rate on a monotonically increasing
+/// counter, such as the number of records received by an endpoint.
+struct TokenBucket {
+    /// Tokens added per second, which is also the bucket's capacity.
+    rate: f64,
+
+    /// Available tokens.  This becomes negative when the endpoint gets ahead
+    /// of the rate, because the endpoint is only paused after the fact.
+    tokens: f64,
+
+    /// The time and value of the counter as of the last update.
+    updated: Instant,
+    counter: u64,
+}
+
+impl TokenBucket {
+    fn new(rate: u64) -> Self {
+        let rate = rate.max(1) as f64;
+        Self {
+            rate,
+            tokens: rate,
+            updated: Instant::now(),
+            counter: 0,
+        }
+    }
+
+    /// Takes tokens for the counter's increase to `counter` as of `now`, and
+    /// returns how long it takes to pay back any deficit.
+    fn update(&mut self, counter: u64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.tokens -= counter.saturating_sub(self.counter) as f64;
+        self.updated = now;
+        self.counter = counter;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// Rate limiting state of an input endpoint.
+struct Throttle {
+    records: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+
+    /// When the endpoint started exceeding its rate limit, if it is exceeding
+    /// it now.
+    since: Option<Instant>,
 }
 
 /// Input endpoint status information.
@@ -631,16 +745,78 @@ pub struct InputEndpointStatus {
 
     /// Whether this input endpoint is [fault tolerant](crate#fault-tolerance).
     pub is_fault_tolerant: bool,
+
+    #[serde(skip)]
+    throttle: Mutex<Throttle>,
 }
 
 impl InputEndpointStatus {
     fn new(endpoint_name: &str, config: InputEndpointConfig, is_fault_tolerant: bool) -> Self {
+        let throttle = Throttle {
+            records: config
+                .connector_config
+                .max_records_per_second
+                .map(TokenBucket::new),
+            bytes: config
+                .connector_config
+                .max_bytes_per_second
+                .map(TokenBucket::new),
+            since: None,
+        };
         Self {
             endpoint_name: endpoint_name.to_string(),
             config,
             metrics: Default::default(),
             fatal_error: Mutex::new(None),
             is_fault_tolerant,
+            throttle: Mutex::new(throttle),
+        }
+    }
+
+    fn weight(&self) -> u64 {
+        self.config.connector_config.weight.max(1)
+    }
+
+    fn is_rate_limited(&self) -> bool {
+        self.config
+            .connector_config
+            .max_records_per_second
+            .is_some()
+            || self.config.connector_config.max_bytes_per_second.is_some()
+    }
+
+    /// Returns how long the endpoint must be paused to stay within its rate
+    /// limits, or `None` if it may ingest more data now.  Tracks the time
+    /// for which the endpoint exceeds its limits in `throttled_usecs`.
+    fn throttle(&self, now: Instant) -> Option<Duration> {
+        if !self.is_rate_limited() {
+            return None;
+        }
+
+        let mut throttle = self.throttle.lock().unwrap();
+        let records = self.metrics.total_records.load(Ordering::Acquire);
+        let bytes = self.metrics.total_bytes.load(Ordering::Acquire);
+        let delay = max(
+            throttle
+                .records
+                .as_mut()
+                .map_or(Duration::ZERO, |bucket| bucket.update(records, now)),
+            throttle
+                .bytes
+                .as_mut()
+                .map_or(Duration::ZERO, |bucket| bucket.update(bytes, now)),
+        );
+        if delay.is_zero() {
+            if let Some(since) = throttle.since.take() {
+                self.metrics.throttled_usecs.fetch_add(
+                    now.saturating_duration_since(since).as_micros() as u64,
+                    Ordering::AcqRel,
+                );
+            }
+            None
+        } else {
+            throttle.since.get_or_insert(now);
+            Some(delay)
         }
     }
 
@@ -680,9 +856,18 @@ impl InputEndpointStatus {
         self.metrics.end_of_input.load(Ordering::Acquire)
     }
 
-    /// Increment parser error counter.
-    fn parse_error(&self) {
-        self.metrics.num_parse_errors.fetch_add(1, Ordering::AcqRel);
+    /// Increment parser error counter; return the new number of parse errors.
+    fn parse_error(&self) -> u64 {
+        self.metrics.num_parse_errors.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    fn parse_errors_exceeded(&self) -> bool {
+        self.config
+            .connector_config
+            .max_parse_errors
+            .is_some_and(|max_parse_errors| {
+                self.metrics.num_parse_errors.load(Ordering::Acquire) > max_parse_errors
+            })
     }
 
     /// Increment transport error counter.  If this is the first fatal error,
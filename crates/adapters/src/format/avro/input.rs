@@ -0,0 +1,703 @@
+use super::{
+    schema_registry::{split_header, SchemaRegistryClient, HEADER_LEN},
+    UNIX_EPOCH_DAYS_FROM_CE,
+};
+use crate::{
+    catalog::{DeCollectionStream, RecordFormat},
+    format::{InputFormat, ParseError, Parser},
+    util::truncate_ellipse,
+    ControllerError, DeCollectionHandle,
+};
+use actix_web::HttpRequest;
+use apache_avro::{from_avro_datum, schema::DecimalSchema, types::Value, Schema};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use erased_serde::Serialize as ErasedSerialize;
+use pipeline_types::format::{avro::AvroParserConfig, json::JsonFlavor};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use serde_urlencoded::Deserializer as UrlDeserializer;
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, mem::take, sync::Arc};
+
+/// Maximal number of bytes of an invalid message to include in a parse
+/// error.
+const MAX_INVALID_BYTES: usize = 64;
+
+/// The largest absolute value of the `__weight` field.  A record with weight
+/// `w` is pushed to the input stream as `|w|` copies of the record.
+const MAX_DUPLICATES: i64 = 1_000_000;
+
+/// Avro format parser.
+pub struct AvroInputFormat;
+
+impl InputFormat for AvroInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("avro")
+    }
+
+    fn config_from_http_request(
+        &self,
+        endpoint_name: &str,
+        request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        Ok(Box::new(
+            AvroParserConfig::deserialize(UrlDeserializer::new(form_urlencoded::parse(
+                request.query_string().as_bytes(),
+            )))
+            .map_err(|e| {
+                ControllerError::parser_config_parse_error(
+                    endpoint_name,
+                    &e,
+                    request.query_string(),
+                )
+            })?,
+        ))
+    }
+
+    fn new_parser(
+        &self,
+        endpoint_name: &str,
+        input_stream: &dyn DeCollectionHandle,
+        config: &YamlValue,
+    ) -> Result<Box<dyn Parser>, ControllerError> {
+        let config = AvroParserConfig::deserialize(config).map_err(|e| {
+            ControllerError::parser_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+
+        let schema_source = match (&config.registry_url, &config.schema) {
+            (Some(registry_url), None) => {
+                SchemaSource::Registry(Arc::new(SchemaRegistryClient::new(registry_url)))
+            }
+            (None, Some(schema)) => {
+                SchemaSource::Fixed(Arc::new(Schema::parse_str(schema).map_err(|e| {
+                    ControllerError::input_format_not_supported(
+                        endpoint_name,
+                        &format!("invalid Avro schema: {e}"),
+                    )
+                })?))
+            }
+            _ => {
+                return Err(ControllerError::input_format_not_supported(
+                    endpoint_name,
+                    "exactly one of 'registry_url' and 'schema' must be specified",
+                ))
+            }
+        };
+
+        let input_stream =
+            input_stream.configure_deserializer(RecordFormat::Json(JsonFlavor::Default))?;
+        Ok(Box::new(AvroParser::new(input_stream, schema_source)) as Box<dyn Parser>)
+    }
+}
+
+/// Where the parser gets the writer's schema of each message from.
+#[derive(Clone)]
+enum SchemaSource {
+    /// Messages start with a header that identifies their schema in a schema
+    /// registry.
+    Registry(Arc<SchemaRegistryClient>),
+
+    /// All messages use the same schema and have no header.
+    Fixed(Arc<Schema>),
+}
+
+struct AvroParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionStream>,
+
+    schema_source: SchemaSource,
+
+    /// Avro messages are not delimited, so a stream of messages can only be
+    /// decoded once it has been received in full; we accumulate fragments
+    /// here until the end of input.
+    buffer: Vec<u8>,
+
+    last_event_number: u64,
+}
+
+impl AvroParser {
+    fn new(input_stream: Box<dyn DeCollectionStream>, schema_source: SchemaSource) -> Self {
+        Self {
+            input_stream,
+            schema_source,
+            buffer: Vec::new(),
+            last_event_number: 0,
+        }
+    }
+
+    /// Decodes a sequence of messages and pushes the records to the input
+    /// stream.
+    fn parse_messages(&mut self, mut data: &[u8]) -> (usize, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        let mut num_records = 0;
+
+        while !data.is_empty() {
+            self.last_event_number += 1;
+            match self.decode(&mut data) {
+                Ok((value, schema)) => {
+                    num_records += self.push_record(&value, &schema, &mut errors)
+                }
+                Err(error) => {
+                    // Avro messages are not self-delimiting, so we cannot find
+                    // the start of the next message after a decoding error.
+                    errors.push(error);
+                    break;
+                }
+            }
+        }
+
+        self.input_stream.flush();
+        (num_records, errors)
+    }
+
+    /// Decodes the message at the start of `data` and advances `data` past
+    /// it.
+    fn decode(&self, data: &mut &[u8]) -> Result<(Value, Arc<Schema>), ParseError> {
+        let schema = match &self.schema_source {
+            SchemaSource::Fixed(schema) => schema.clone(),
+            SchemaSource::Registry(registry) => {
+                let Some((id, payload)) = split_header(data) else {
+                    return Err(ParseError::bin_event_error(
+                        format!("Avro message does not start with a valid {HEADER_LEN}-byte schema registry header"),
+                        self.last_event_number,
+                        &data[..data.len().min(HEADER_LEN)],
+                        Some(Cow::Borrowed("Configure the 'schema' property of the parser instead of 'registry_url' to decode messages without a header")),
+                    ));
+                };
+                *data = payload;
+                registry.schema(id).map_err(|e| {
+                    ParseError::new(
+                        format!("failed to retrieve Avro schema {id}: {e}"),
+                        Some(self.last_event_number),
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                })?
+            }
+        };
+
+        let message = *data;
+        let value = from_avro_datum(&schema, data, None).map_err(|e| {
+            ParseError::new(
+                format!("failed to decode Avro message: {e}"),
+                Some(self.last_event_number),
+                None,
+                None,
+                Some(&message[..message.len().min(MAX_INVALID_BYTES)]),
+                None,
+            )
+        })?;
+        Ok((value, schema))
+    }
+
+    /// Pushes a decoded record to the input stream, returning the number of
+    /// updates pushed.
+    fn push_record(
+        &mut self,
+        value: &Value,
+        schema: &Schema,
+        errors: &mut Vec<ParseError>,
+    ) -> usize {
+        let (Value::Record(fields), Schema::Record(record_schema)) = (value, schema) else {
+            errors.push(ParseError::new(
+                format!(
+                    "Avro message is not a record: {}",
+                    truncate_ellipse(&format!("{value:?}"), 100, "...")
+                ),
+                Some(self.last_event_number),
+                None,
+                None,
+                None,
+                None,
+            ));
+            return 0;
+        };
+
+        let mut record = JsonMap::new();
+        let mut weight = 1;
+        for ((name, value), field_schema) in fields.iter().zip(record_schema.fields.iter()) {
+            let error = match (name.as_str(), value) {
+                ("__action", Value::String(action)) if action == "insert" => continue,
+                ("__action", Value::String(action)) if action == "delete" => {
+                    weight = -1;
+                    continue;
+                }
+                ("__action", _) => {
+                    "invalid '__action' value; expected \"insert\" or \"delete\"".to_string()
+                }
+                ("__weight", Value::Int(w)) => {
+                    weight = *w as i64;
+                    continue;
+                }
+                ("__weight", Value::Long(w)) => {
+                    weight = *w;
+                    continue;
+                }
+                ("__weight", _) => "invalid '__weight' value; expected an integer".to_string(),
+                _ => match avro_to_json(value, Some(&field_schema.schema)) {
+                    Ok(value) => {
+                        record.insert(name.clone(), value);
+                        continue;
+                    }
+                    Err(e) => format!("failed to convert Avro value: {e}"),
+                },
+            };
+            errors.push(ParseError::new(
+                error,
+                Some(self.last_event_number),
+                Some(name.clone()),
+                None,
+                None,
+                None,
+            ));
+            return 0;
+        }
+
+        if !(-MAX_DUPLICATES..=MAX_DUPLICATES).contains(&weight) {
+            errors.push(ParseError::new(
+                format!("'__weight' value {weight} is out of range; its absolute value must not exceed {MAX_DUPLICATES}"),
+                Some(self.last_event_number),
+                Some("__weight".to_string()),
+                None,
+                None,
+                None,
+            ));
+            return 0;
+        }
+
+        let record = JsonValue::Object(record).to_string();
+        let mut num_records = 0;
+        for _ in 0..weight.unsigned_abs() {
+            let result = if weight > 0 {
+                self.input_stream.insert(record.as_bytes())
+            } else {
+                self.input_stream.delete(record.as_bytes())
+            };
+            if let Err(e) = result {
+                errors.push(ParseError::text_event_error(
+                    "failed to deserialize Avro record",
+                    e,
+                    self.last_event_number,
+                    Some(&record),
+                    None,
+                ));
+                break;
+            }
+            num_records += 1;
+        }
+        num_records
+    }
+}
+
+impl Parser for AvroParser {
+    fn input_fragment(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
+        self.buffer.extend_from_slice(data);
+        (0, Vec::new())
+    }
+
+    /// A chunk contains one or more complete messages, e.g., the payload of
+    /// a Kafka message.
+    fn input_chunk(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
+        self.parse_messages(data)
+    }
+
+    fn eoi(&mut self) -> (usize, Vec<ParseError>) {
+        let data = take(&mut self.buffer);
+        self.parse_messages(&data)
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self::new(
+            self.input_stream.fork(),
+            self.schema_source.clone(),
+        ))
+    }
+}
+
+/// Converts an Avro value to JSON in the encoding expected by the default
+/// JSON deserializer of input tables.
+///
+/// `schema` is the schema of the value, if known; it is needed to decode
+/// decimals, whose scale is only stored in the schema.
+fn avro_to_json(value: &Value, schema: Option<&Schema>) -> Result<JsonValue, String> {
+    let out_of_range = || format!("value out of range: {value:?}");
+
+    Ok(match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(b) => JsonValue::from(*b),
+        Value::Int(i) => JsonValue::from(*i),
+        Value::Long(l) => JsonValue::from(*l),
+        Value::Float(f) => JsonValue::from(*f),
+        Value::Double(d) => JsonValue::from(*d),
+        Value::String(s) | Value::Enum(_, s) => JsonValue::from(s.as_str()),
+        Value::Uuid(uuid) => JsonValue::from(uuid.to_string()),
+        Value::Union(index, value) => {
+            let variant = match schema {
+                Some(Schema::Union(union)) => union.variants().get(*index as usize),
+                _ => None,
+            };
+            avro_to_json(value, variant)?
+        }
+        Value::Array(items) => {
+            let items_schema = match schema {
+                Some(Schema::Array(items_schema)) => Some(items_schema.as_ref()),
+                _ => None,
+            };
+            JsonValue::Array(
+                items
+                    .iter()
+                    .map(|item| avro_to_json(item, items_schema))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        Value::Map(entries) => {
+            let values_schema = match schema {
+                Some(Schema::Map(values_schema)) => Some(values_schema.as_ref()),
+                _ => None,
+            };
+            JsonValue::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), avro_to_json(value, values_schema)?)))
+                    .collect::<Result<_, String>>()?,
+            )
+        }
+        Value::Record(fields) => {
+            let field_schemas = match schema {
+                Some(Schema::Record(record_schema)) => Some(&record_schema.fields),
+                _ => None,
+            };
+            JsonValue::Object(
+                fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, value))| {
+                        let field_schema = field_schemas
+                            .and_then(|fields| fields.get(i))
+                            .map(|field| &field.schema);
+                        Ok((name.clone(), avro_to_json(value, field_schema)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+            )
+        }
+        Value::Decimal(decimal) => {
+            let Some(Schema::Decimal(DecimalSchema { scale, .. })) = schema else {
+                return Err("cannot decode a decimal value without its schema".to_string());
+            };
+            let bytes = Vec::<u8>::try_from(decimal).map_err(|e| e.to_string())?;
+            JsonValue::from(decimal_from_bytes(&bytes, *scale as u32)?.to_string())
+        }
+        Value::Date(days) => {
+            let date = days
+                .checked_add(UNIX_EPOCH_DAYS_FROM_CE)
+                .and_then(NaiveDate::from_num_days_from_ce_opt)
+                .ok_or_else(out_of_range)?;
+            JsonValue::from(date.format("%Y-%m-%d").to_string())
+        }
+        Value::TimeMillis(millis) => {
+            let time = time_from_micros(*millis as i64 * 1_000).ok_or_else(out_of_range)?;
+            JsonValue::from(time.format("%H:%M:%S%.f").to_string())
+        }
+        Value::TimeMicros(micros) => {
+            let time = time_from_micros(*micros).ok_or_else(out_of_range)?;
+            JsonValue::from(time.format("%H:%M:%S%.f").to_string())
+        }
+        Value::TimestampMillis(millis) | Value::LocalTimestampMillis(millis) => {
+            let timestamp =
+                NaiveDateTime::from_timestamp_millis(*millis).ok_or_else(out_of_range)?;
+            JsonValue::from(timestamp.format("%F %T%.f").to_string())
+        }
+        Value::TimestampMicros(micros) | Value::LocalTimestampMicros(micros) => {
+            let timestamp =
+                NaiveDateTime::from_timestamp_micros(*micros).ok_or_else(out_of_range)?;
+            JsonValue::from(timestamp.format("%F %T%.f").to_string())
+        }
+        value => return Err(format!("Avro value {value:?} is not supported")),
+    })
+}
+
+/// Returns the time of day `micros` microseconds after midnight.
+fn time_from_micros(micros: i64) -> Option<NaiveTime> {
+    let secs = u32::try_from(micros.div_euclid(1_000_000)).ok()?;
+    let nanos = micros.rem_euclid(1_000_000) as u32 * 1_000;
+    NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos)
+}
+
+/// Decodes an Avro decimal: a big-endian two's complement integer scaled by
+/// `10^-scale`.
+fn decimal_from_bytes(bytes: &[u8], scale: u32) -> Result<Decimal, String> {
+    if bytes.len() > 16 {
+        return Err(format!("{}-byte decimal value is too large", bytes.len()));
+    }
+    let negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let mut buffer = if negative { [0xff; 16] } else { [0; 16] };
+    buffer[16 - bytes.len()..].copy_from_slice(bytes);
+    Decimal::try_from_i128_with_scale(i128::from_be_bytes(buffer), scale)
+        .map_err(|e| format!("decimal value out of range: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        deserialize_table_record,
+        format::avro::schema_registry::header,
+        test::{mock_parser_pipeline, MockSchemaRegistry},
+        InputConsumer, ParseError,
+    };
+    use apache_avro::{to_avro_datum, types::Value, Decimal as AvroDecimal, Schema};
+    use pipeline_types::{config::FormatConfig, format::avro::AvroParserConfig};
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use std::borrow::Cow;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[allow(non_snake_case)]
+    struct TestRecord {
+        ID: i64,
+        AMOUNT: Decimal,
+        DAY: String,
+        TS: String,
+        TAGS: Vec<i64>,
+        NAME: Option<String>,
+    }
+
+    deserialize_table_record!(TestRecord["TestRecord", 6] {
+        (ID, "ID", false, i64, None),
+        (AMOUNT, "AMOUNT", false, Decimal, None),
+        (DAY, "DAY", false, String, None),
+        (TS, "TS", false, String, None),
+        (TAGS, "TAGS", false, Vec<i64>, None),
+        (NAME, "NAME", false, Option<String>, Some(None))
+    });
+
+    const SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "test_record",
+        "fields": [
+            {"name": "id", "type": "long"},
+            {"name": "amount", "type": {"type": "bytes", "logicalType": "decimal", "precision": 10, "scale": 2}},
+            {"name": "day", "type": {"type": "int", "logicalType": "date"}},
+            {"name": "ts", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+            {"name": "tags", "type": {"type": "array", "items": "long"}},
+            {"name": "name", "type": ["null", "string"]},
+            {"name": "__action", "type": "string"}
+        ]
+    }"#;
+
+    fn test_value(id: i64, action: &str) -> Value {
+        let (amount, day, ts, tags, name) = if id == 1 {
+            // 123.45, 2023-01-01.
+            (
+                vec![0x30, 0x39],
+                19358,
+                1672531200123,
+                vec![Value::Long(1), Value::Long(2)],
+                Value::Union(1, Box::new(Value::String("foo".to_string()))),
+            )
+        } else {
+            // -5.00, 2024-02-29.
+            (
+                vec![0xfe, 0x0c],
+                19782,
+                1709208000000,
+                vec![],
+                Value::Union(0, Box::new(Value::Null)),
+            )
+        };
+        Value::Record(vec![
+            ("id".to_string(), Value::Long(id)),
+            (
+                "amount".to_string(),
+                Value::Decimal(AvroDecimal::from(amount)),
+            ),
+            ("day".to_string(), Value::Date(day)),
+            ("ts".to_string(), Value::TimestampMillis(ts)),
+            ("tags".to_string(), Value::Array(tags)),
+            ("name".to_string(), name),
+            ("__action".to_string(), Value::String(action.to_string())),
+        ])
+    }
+
+    fn expected_records() -> Vec<TestRecord> {
+        vec![
+            TestRecord {
+                ID: 1,
+                AMOUNT: dec!(123.45),
+                DAY: "2023-01-01".to_string(),
+                TS: "2023-01-01 00:00:00.123".to_string(),
+                TAGS: vec![1, 2],
+                NAME: Some("foo".to_string()),
+            },
+            TestRecord {
+                ID: 2,
+                AMOUNT: dec!(-5.00),
+                DAY: "2024-02-29".to_string(),
+                TS: "2024-02-29 12:00:00".to_string(),
+                TAGS: vec![],
+                NAME: None,
+            },
+        ]
+    }
+
+    /// Encodes `value` as a message, with a schema registry header if
+    /// `schema_id` is specified.
+    fn message(value: Value, schema_id: Option<u32>) -> Vec<u8> {
+        let schema = Schema::parse_str(SCHEMA).unwrap();
+        let mut message = schema_id.map(|id| header(id).to_vec()).unwrap_or_default();
+        message.extend(to_avro_datum(&schema, value).unwrap());
+        message
+    }
+
+    fn format_config(config: AvroParserConfig) -> FormatConfig {
+        FormatConfig {
+            name: Cow::Borrowed("avro"),
+            config: serde_yaml::to_value(config).unwrap(),
+        }
+    }
+
+    fn registry_config(registry: &MockSchemaRegistry) -> FormatConfig {
+        format_config(AvroParserConfig {
+            registry_url: Some(registry.url()),
+            schema: None,
+        })
+    }
+
+    /// Parses `messages` and returns the parse errors as JSON values.
+    fn parse_errors(config: &FormatConfig, messages: &[Vec<u8>]) -> Vec<serde_json::Value> {
+        let (mut consumer, _zset) = mock_parser_pipeline::<TestRecord, TestRecord>(config).unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+        messages
+            .iter()
+            .flat_map(|message| consumer.input_chunk(message))
+            .map(|error: ParseError| serde_json::to_value(error).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_avro_schema() {
+        let config = format_config(AvroParserConfig {
+            registry_url: None,
+            schema: Some(SCHEMA.to_string()),
+        });
+        let (mut consumer, zset) = mock_parser_pipeline::<TestRecord, TestRecord>(&config).unwrap();
+
+        // Messages without a header can be concatenated, e.g., in a file.
+        let mut data = message(test_value(1, "insert"), None);
+        data.extend(message(test_value(2, "delete"), None));
+        consumer.input_fragment(&data);
+        assert!(consumer.eoi().is_empty());
+
+        let expected = expected_records();
+        let flushed = &zset.state().flushed;
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].unwrap_insert(), &expected[0]);
+        assert_eq!(flushed[1].unwrap_delete(), &expected[1]);
+    }
+
+    #[actix_web::test]
+    async fn test_avro_registry() {
+        let registry = MockSchemaRegistry::start();
+        let id = registry.state().add_schema(SCHEMA);
+
+        let (mut consumer, zset) =
+            mock_parser_pipeline::<TestRecord, TestRecord>(&registry_config(&registry)).unwrap();
+        assert!(consumer
+            .input_chunk(&message(test_value(1, "insert"), Some(id)))
+            .is_empty());
+        assert!(consumer
+            .input_chunk(&message(test_value(2, "insert"), Some(id)))
+            .is_empty());
+
+        let expected = expected_records();
+        let flushed = &zset.state().flushed;
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].unwrap_insert(), &expected[0]);
+        assert_eq!(flushed[1].unwrap_insert(), &expected[1]);
+
+        // The schema is retrieved once and then cached.
+        assert_eq!(registry.state().lookups, 1);
+    }
+
+    #[test]
+    fn test_avro_weights() {
+        let schema_str = SCHEMA.replace(
+            r#"{"name": "__action", "type": "string"}"#,
+            r#"{"name": "__weight", "type": "long"}"#,
+        );
+        let schema = Schema::parse_str(&schema_str).unwrap();
+        let weighted_message = |weight: i64| {
+            let Value::Record(mut fields) = test_value(1, "insert") else {
+                unreachable!()
+            };
+            *fields.last_mut().unwrap() = ("__weight".to_string(), Value::Long(weight));
+            to_avro_datum(&schema, Value::Record(fields)).unwrap()
+        };
+
+        let config = format_config(AvroParserConfig {
+            registry_url: None,
+            schema: Some(schema_str.clone()),
+        });
+        let (mut consumer, zset) = mock_parser_pipeline::<TestRecord, TestRecord>(&config).unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+        assert!(consumer.input_chunk(&weighted_message(2)).is_empty());
+
+        // A weight that is too large to expand into copies of the record is
+        // rejected.
+        let errors = consumer.input_chunk(&weighted_message(i64::MAX));
+        assert_eq!(errors.len(), 1);
+        let error = serde_json::to_value(&errors[0]).unwrap();
+        assert_eq!(error["event_number"], 2);
+        assert_eq!(error["field"], "__weight");
+
+        let expected = expected_records();
+        let flushed = &zset.state().flushed;
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].unwrap_insert(), &expected[0]);
+        assert_eq!(flushed[1].unwrap_insert(), &expected[0]);
+    }
+
+    #[actix_web::test]
+    async fn test_avro_errors() {
+        let registry = MockSchemaRegistry::start();
+        let id = registry.state().add_schema(SCHEMA);
+
+        let errors = parse_errors(
+            &registry_config(&registry),
+            &[
+                // No header.
+                message(test_value(1, "insert"), None),
+                // Unknown schema id.
+                message(test_value(1, "insert"), Some(id + 1)),
+                // Truncated message.
+                message(test_value(1, "insert"), Some(id))[..10].to_vec(),
+                // Invalid `__action`.
+                message(test_value(1, "upsert"), Some(id)),
+            ],
+        );
+
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0]["description"]
+            .as_str()
+            .unwrap()
+            .contains("schema registry header"));
+        assert!(errors[1]["description"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("failed to retrieve Avro schema {}", id + 1)));
+        assert!(errors[2]["description"]
+            .as_str()
+            .unwrap()
+            .contains("failed to decode Avro message"));
+        assert_eq!(errors[3]["field"], "__action");
+        for (i, error) in errors.iter().enumerate() {
+            assert_eq!(error["event_number"], i + 1);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use super::DEFAULT_DECIMAL_PRECISION;
+use anyhow::{anyhow, bail, Result as AnyResult};
+use apache_avro::Schema;
+use pipeline_types::{
+    format::UpdateFormat,
+    program_schema::{ColumnType, Relation},
+};
+use serde_json::{json, Value as JsonValue};
+
+mod input;
+mod output;
+mod schema_registry;
+
+pub use input::AvroInputFormat;
+pub use output::AvroOutputFormat;
+
+/// Number of days from 0001-01-01 (day 1 of the common era) to 1970-01-01,
+/// the epoch of Avro dates.
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Derives the Avro schema of output records from the schema of a SQL
+/// relation: a record with a field for each column, followed by the
+/// `__action` or `__weight` field.
+pub(crate) fn relation_to_avro_schema(
+    relation: &Relation,
+    update_format: &UpdateFormat,
+) -> AnyResult<Schema> {
+    let mut fields = relation
+        .fields
+        .iter()
+        .map(|field| {
+            let typ = columntype_to_avro(&field.columntype)
+                .map_err(|e| anyhow!("column '{}': {e}", field.name))?;
+            Ok(if field.columntype.nullable {
+                json!({"name": field.name, "type": typ, "default": null})
+            } else {
+                json!({"name": field.name, "type": typ})
+            })
+        })
+        .collect::<AnyResult<Vec<_>>>()?;
+
+    fields.push(match update_format {
+        UpdateFormat::InsertDelete => json!({"name": "__action", "type": "string"}),
+        UpdateFormat::Weighted => json!({"name": "__weight", "type": "long"}),
+    });
+
+    let schema = json!({
+        "type": "record",
+        "name": relation.name,
+        "fields": fields,
+    });
+    Schema::parse(&schema).map_err(|e| anyhow!("invalid Avro schema {schema}: {e}"))
+}
+
+/// Maps a SQL column type, as it appears in the program schema, to an Avro
+/// type.
+fn columntype_to_avro(columntype: &ColumnType) -> AnyResult<JsonValue> {
+    let typ = match columntype.typ.to_ascii_uppercase().as_str() {
+        "BOOLEAN" => json!("boolean"),
+        "TINYINT" | "SMALLINT" | "INTEGER" => json!("int"),
+        "BIGINT" => json!("long"),
+        "REAL" => json!("float"),
+        "FLOAT" | "DOUBLE" => json!("double"),
+        "DECIMAL" => {
+            let precision = match columntype.precision {
+                Some(precision) if precision > 0 => precision,
+                _ => DEFAULT_DECIMAL_PRECISION.into(),
+            };
+            json!({
+                "type": "bytes",
+                "logicalType": "decimal",
+                "precision": precision,
+                "scale": columntype.scale.unwrap_or(0),
+            })
+        }
+        "CHAR" | "VARCHAR" => json!("string"),
+        "DATE" => json!({"type": "int", "logicalType": "date"}),
+        "TIME" => json!({"type": "long", "logicalType": "time-micros"}),
+        "TIMESTAMP" => json!({"type": "long", "logicalType": "timestamp-millis"}),
+        "ARRAY" => {
+            let Some(component) = &columntype.component else {
+                bail!("ARRAY type without a component type");
+            };
+            json!({"type": "array", "items": columntype_to_avro(component)?})
+        }
+        typ => bail!("SQL type '{typ}' is not supported by the Avro format"),
+    };
+
+    Ok(if columntype.nullable {
+        json!(["null", typ])
+    } else {
+        typ
+    })
+}
@@ -0,0 +1,490 @@
+use super::{
+    relation_to_avro_schema,
+    schema_registry::{header, SchemaRegistryClient, HEADER_LEN},
+    UNIX_EPOCH_DAYS_FROM_CE,
+};
+use crate::{
+    catalog::{CursorWithPolarity, RecordFormat, SerBatch, SerCursor},
+    format::reject_keyed_consumer,
+    ControllerError, Encoder, OutputConsumer, OutputFormat,
+};
+use actix_web::HttpRequest;
+use anyhow::{anyhow, bail, Result as AnyResult};
+use apache_avro::{
+    schema::{DecimalSchema, RecordSchema},
+    to_avro_datum,
+    types::Value,
+    Decimal as AvroDecimal, Schema,
+};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use erased_serde::Serialize as ErasedSerialize;
+use pipeline_types::format::{avro::AvroEncoderConfig, json::JsonFlavor, UpdateFormat};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, str::FromStr, sync::Arc};
+
+/// The largest weight of a record that can be output using the
+/// `insert_delete` update format, which requires duplicating the record `w`
+/// times.
+const MAX_DUPLICATES: i64 = 1_000_000;
+
+/// Avro format encoder.
+pub struct AvroOutputFormat;
+
+impl OutputFormat for AvroOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("avro")
+    }
+
+    fn config_from_http_request(
+        &self,
+        endpoint_name: &str,
+        _request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        // The encoder requires the schema of the output relation, which
+        // cannot be passed in a URL query string.
+        Err(ControllerError::output_format_not_supported(
+            endpoint_name,
+            "the Avro format is not supported for HTTP output endpoints",
+        ))
+    }
+
+    fn new_encoder(
+        &self,
+        endpoint_name: &str,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> Result<Box<dyn Encoder>, ControllerError> {
+        reject_keyed_consumer(endpoint_name, "Avro", consumer.as_ref())?;
+
+        let config = AvroEncoderConfig::deserialize(config).map_err(|e| {
+            ControllerError::encoder_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+
+        Ok(Box::new(AvroEncoder::new(consumer, config).map_err(
+            |e| ControllerError::output_format_not_supported(endpoint_name, &e.to_string()),
+        )?))
+    }
+}
+
+struct AvroEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    update_format: UpdateFormat,
+
+    /// Avro schema of output records, derived from the schema of the output
+    /// relation.
+    schema: Schema,
+
+    /// Schema registry header of each message, which contains the id of
+    /// `schema` in the registry.
+    header: [u8; HEADER_LEN],
+
+    /// Buffer used to serialize records as JSON.
+    buffer: Vec<u8>,
+
+    max_buffer_size: usize,
+}
+
+impl AvroEncoder {
+    fn new(output_consumer: Box<dyn OutputConsumer>, config: AvroEncoderConfig) -> AnyResult<Self> {
+        let max_buffer_size = output_consumer.max_buffer_size_bytes();
+
+        let schema = relation_to_avro_schema(&config.relation_schema, &config.update_format)?;
+        let subject = config
+            .subject
+            .clone()
+            .unwrap_or_else(|| format!("{}-value", config.relation_schema.name));
+        let id = SchemaRegistryClient::new(&config.registry_url)
+            .register_schema(&subject, &schema)
+            .map_err(|e| {
+                anyhow!("failed to register Avro schema under subject '{subject}': {e}")
+            })?;
+
+        Ok(Self {
+            output_consumer,
+            update_format: config.update_format,
+            schema,
+            header: header(id),
+            buffer: Vec::new(),
+            max_buffer_size,
+        })
+    }
+
+    /// Encodes a record, serialized as a JSON object, as an Avro message with
+    /// the `__action` or `__weight` field set according to weight `w`.
+    fn encode_record(&self, record: &JsonValue, w: i64) -> AnyResult<Vec<u8>> {
+        let (Schema::Record(RecordSchema { fields, .. }), JsonValue::Object(columns)) =
+            (&self.schema, record)
+        else {
+            bail!("cannot encode JSON value {record} as an Avro record");
+        };
+
+        let mut values = Vec::with_capacity(fields.len());
+        for field in fields.iter() {
+            let value = match field.name.as_str() {
+                "__action" => Value::String(if w > 0 { "insert" } else { "delete" }.to_string()),
+                "__weight" => Value::Long(w),
+                name => {
+                    let column = columns
+                        .get(name)
+                        .or_else(|| {
+                            columns
+                                .iter()
+                                .find(|(column, _)| column.eq_ignore_ascii_case(name))
+                                .map(|(_, value)| value)
+                        })
+                        .unwrap_or(&JsonValue::Null);
+                    json_to_avro(column, &field.schema)
+                        .map_err(|e| anyhow!("column '{name}': {e}"))?
+                }
+            };
+            values.push((field.name.clone(), value));
+        }
+
+        let mut message = self.header.to_vec();
+        message.extend(to_avro_datum(&self.schema, Value::Record(values))?);
+        Ok(message)
+    }
+}
+
+impl Encoder for AvroEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        for batch in batches.iter() {
+            let mut cursor =
+                CursorWithPolarity::new(batch.cursor(RecordFormat::Json(JsonFlavor::Default))?);
+
+            while cursor.key_valid() {
+                if !cursor.val_valid() {
+                    cursor.step_key();
+                    continue;
+                }
+                let w = cursor.weight();
+
+                self.buffer.clear();
+                cursor.serialize_key(&mut self.buffer)?;
+                let record: JsonValue = serde_json::from_slice(&self.buffer)?;
+
+                let (message, copies) = match self.update_format {
+                    UpdateFormat::InsertDelete => {
+                        if !(-MAX_DUPLICATES..=MAX_DUPLICATES).contains(&w) {
+                            bail!(
+                                "Unable to output record '{}' with very large weight {w}. Consider adjusting your SQL queries to avoid duplicate output records, e.g., using 'SELECT DISTINCT', or use the 'weighted' update format.",
+                                &record
+                            );
+                        }
+                        (self.encode_record(&record, w.signum())?, w.abs())
+                    }
+                    UpdateFormat::Weighted => (self.encode_record(&record, w)?, 1),
+                };
+
+                if message.len() > self.max_buffer_size {
+                    bail!("Avro message exceeds maximum buffer size supported by the output transport. Max supported buffer size is {} bytes, but the message requires {} bytes.",
+                          self.max_buffer_size,
+                          message.len());
+                }
+                // Each message is pushed as a separate buffer, so that the
+                // Kafka transport writes it as a separate Kafka message.
+                for _ in 0..copies {
+                    self.output_consumer.push_buffer(&message);
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts a JSON value produced by the default JSON serializer of output
+/// relations to an Avro value of type `schema`.
+fn json_to_avro(json: &JsonValue, schema: &Schema) -> AnyResult<Value> {
+    let invalid = || anyhow!("cannot encode JSON value {json} as Avro type {schema:?}");
+
+    Ok(match (schema, json) {
+        (Schema::Union(union), json) => {
+            let (index, variant) = union
+                .variants()
+                .iter()
+                .enumerate()
+                .find(|(_, variant)| matches!(variant, Schema::Null) == json.is_null())
+                .ok_or_else(invalid)?;
+            Value::Union(index as u32, Box::new(json_to_avro(json, variant)?))
+        }
+        (Schema::Null, JsonValue::Null) => Value::Null,
+        (Schema::Boolean, JsonValue::Bool(b)) => Value::Boolean(*b),
+        (Schema::Int, JsonValue::Number(n)) => Value::Int(
+            n.as_i64()
+                .and_then(|n| i32::try_from(n).ok())
+                .ok_or_else(invalid)?,
+        ),
+        (Schema::Long, JsonValue::Number(n)) => Value::Long(n.as_i64().ok_or_else(invalid)?),
+        (Schema::Float, JsonValue::Number(n)) => {
+            Value::Float(n.as_f64().ok_or_else(invalid)? as f32)
+        }
+        (Schema::Double, JsonValue::Number(n)) => Value::Double(n.as_f64().ok_or_else(invalid)?),
+        (Schema::String, JsonValue::String(s)) => Value::String(s.clone()),
+        (
+            Schema::Decimal(DecimalSchema { scale, .. }),
+            JsonValue::String(_) | JsonValue::Number(_),
+        ) => {
+            let decimal = match json {
+                JsonValue::String(s) => Decimal::from_str(s),
+                json => Decimal::from_str(&json.to_string()),
+            };
+            let mut decimal = decimal.map_err(|_| invalid())?;
+            decimal.rescale(*scale as u32);
+            Value::Decimal(AvroDecimal::from(decimal_to_bytes(decimal.mantissa())))
+        }
+        (Schema::Date, JsonValue::String(s)) => {
+            let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| invalid())?;
+            Value::Date(date.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE)
+        }
+        (Schema::TimeMicros, JsonValue::String(s)) => {
+            let time = NaiveTime::parse_from_str(s, "%H:%M:%S%.f").map_err(|_| invalid())?;
+            Value::TimeMicros(
+                time.num_seconds_from_midnight() as i64 * 1_000_000
+                    + time.nanosecond() as i64 / 1_000,
+            )
+        }
+        (Schema::TimestampMillis, JsonValue::String(s)) => {
+            let timestamp = NaiveDateTime::parse_from_str(s, "%F %T%.f").map_err(|_| invalid())?;
+            Value::TimestampMillis(timestamp.timestamp_millis())
+        }
+        (Schema::Array(items), JsonValue::Array(values)) => Value::Array(
+            values
+                .iter()
+                .map(|value| json_to_avro(value, items))
+                .collect::<AnyResult<_>>()?,
+        ),
+        _ => return Err(invalid()),
+    })
+}
+
+/// Encodes the unscaled value of a decimal as the shortest big-endian two's
+/// complement integer.
+fn decimal_to_bytes(mantissa: i128) -> Vec<u8> {
+    let bytes = mantissa.to_be_bytes();
+    let sign = if mantissa < 0 { 0xff } else { 0 };
+    let mut start = 0;
+    while start < bytes.len() - 1
+        && bytes[start] == sign
+        && (bytes[start + 1] & 0x80) == (sign & 0x80)
+    {
+        start += 1;
+    }
+    bytes[start..].to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::AvroEncoder;
+    use crate::{
+        catalog::SerBatch,
+        format::Encoder,
+        static_compile::seroutput::SerBatchImpl,
+        test::{
+            generate_test_batches_with_weights, mock_parser_pipeline, MockOutputConsumer,
+            MockSchemaRegistry, MockUpdate, TestStruct,
+        },
+        transport::InputConsumer,
+    };
+    use dbsp::{trace::Batch, OrdZSet};
+    use pipeline_types::{
+        config::FormatConfig,
+        format::{avro::AvroEncoderConfig, UpdateFormat},
+        program_schema::{ColumnType, Field, Relation},
+    };
+    use proptest::prelude::*;
+    use std::sync::Arc;
+
+    fn column(name: &str, typ: &str, nullable: bool) -> Field {
+        Field {
+            name: name.to_string(),
+            columntype: ColumnType {
+                typ: typ.to_string(),
+                nullable,
+                precision: None,
+                scale: None,
+                component: None,
+            },
+        }
+    }
+
+    fn test_struct_schema() -> Relation {
+        Relation {
+            name: "test_output".to_string(),
+            fields: vec![
+                column("id", "INTEGER", false),
+                column("b", "BOOLEAN", false),
+                column("i", "BIGINT", true),
+                column("s", "VARCHAR", false),
+            ],
+        }
+    }
+
+    /// Encodes `batches` as Avro messages, registering the schema with
+    /// `registry`, and decodes the messages back with the Avro parser.
+    fn roundtrip(
+        registry: &MockSchemaRegistry,
+        update_format: UpdateFormat,
+        batches: &[Vec<(TestStruct, i64)>],
+    ) -> Vec<MockUpdate<TestStruct, TestStruct>> {
+        let config = AvroEncoderConfig {
+            registry_url: registry.url(),
+            subject: None,
+            update_format,
+            relation_schema: test_struct_schema(),
+        };
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let mut encoder = AvroEncoder::new(Box::new(consumer), config).unwrap();
+        let zsets = batches
+            .iter()
+            .map(|batch| {
+                let zset = OrdZSet::from_keys((), batch.clone());
+                Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>
+            })
+            .collect::<Vec<_>>();
+        encoder.encode(zsets.as_slice()).unwrap();
+
+        let format_config: FormatConfig = serde_yaml::from_str(&format!(
+            r#"
+name: avro
+config:
+    registry_url: "{}"
+"#,
+            registry.url()
+        ))
+        .unwrap();
+        let (mut consumer, zset) =
+            mock_parser_pipeline::<TestStruct, TestStruct>(&format_config).unwrap();
+        consumer.input_fragment(&consumer_data.lock().unwrap());
+        assert!(consumer.eoi().is_empty());
+
+        let state = zset.state();
+        state.flushed.clone()
+    }
+
+    fn test_avro(update_format: UpdateFormat, batches: Vec<Vec<(TestStruct, i64)>>) {
+        let mut expected = Vec::new();
+        for (record, w) in batches.iter().flatten().filter(|(_, w)| *w != 0) {
+            for _ in 0..w.abs() {
+                expected.push(MockUpdate::with_polarity(record.clone(), *w > 0));
+            }
+        }
+        expected.sort();
+
+        let registry = MockSchemaRegistry::start();
+        let mut actual = roundtrip(&registry, update_format, &batches);
+        actual.sort();
+
+        assert_eq!(actual, expected);
+
+        let state = registry.state();
+        assert_eq!(
+            state.subjects.keys().collect::<Vec<_>>(),
+            ["test_output-value"]
+        );
+        // The parser caches the schema.
+        assert!(state.lookups <= 1);
+    }
+
+    fn test_data() -> Vec<Vec<(TestStruct, i64)>> {
+        vec![
+            vec![
+                (
+                    TestStruct {
+                        id: 0,
+                        b: true,
+                        i: None,
+                        s: "foo".to_string(),
+                    },
+                    1,
+                ),
+                (
+                    TestStruct {
+                        id: 1,
+                        b: false,
+                        i: Some(10),
+                        s: "bar".to_string(),
+                    },
+                    -1,
+                ),
+            ],
+            vec![
+                (
+                    TestStruct {
+                        id: 2,
+                        b: true,
+                        i: Some(-5),
+                        s: "".to_string(),
+                    },
+                    2,
+                ),
+                (
+                    TestStruct {
+                        id: 3,
+                        b: false,
+                        i: None,
+                        s: "buzz".to_string(),
+                    },
+                    -3,
+                ),
+            ],
+        ]
+    }
+
+    #[actix_web::test]
+    async fn test_avro_insert_delete() {
+        test_avro(UpdateFormat::InsertDelete, test_data());
+    }
+
+    #[actix_web::test]
+    async fn test_avro_weighted() {
+        test_avro(UpdateFormat::Weighted, test_data());
+    }
+
+    #[actix_web::test]
+    async fn test_avro_register_once() {
+        let registry = MockSchemaRegistry::start();
+        roundtrip(&registry, UpdateFormat::Weighted, &test_data());
+        roundtrip(&registry, UpdateFormat::Weighted, &test_data());
+
+        let state = registry.state();
+        assert_eq!(state.schemas.len(), 1);
+        assert_eq!(state.subjects["test_output-value"], [1]);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(10))]
+
+        #[test]
+        fn proptest_avro_insert_delete(data in generate_test_batches_with_weights(10, 20))
+        {
+            actix_web::rt::System::new().block_on(async {
+                test_avro(UpdateFormat::InsertDelete, data)
+            })
+        }
+
+        #[test]
+        fn proptest_avro_weighted(data in generate_test_batches_with_weights(10, 20))
+        {
+            actix_web::rt::System::new().block_on(async {
+                test_avro(UpdateFormat::Weighted, data)
+            })
+        }
+    }
+}
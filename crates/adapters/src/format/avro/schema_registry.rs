@@ -0,0 +1,129 @@
+//! A minimal client for the REST API of the Confluent schema registry.
+//!
+//! Parsers and encoders run outside of any async runtime, so the client uses
+//! a blocking HTTP client.
+
+use anyhow::{anyhow, bail, Result as AnyResult};
+use apache_avro::Schema;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Content type of schema registry requests.
+const CONTENT_TYPE: &str = "application/vnd.schemaregistry.v1+json";
+
+/// The first byte of the header of messages written by Confluent
+/// serializers.
+pub(crate) const MAGIC_BYTE: u8 = 0;
+
+/// Length of the header: the magic byte followed by the big-endian 32-bit
+/// schema id.
+pub(crate) const HEADER_LEN: usize = 5;
+
+/// Splits a message into the id of its schema and its payload, or returns
+/// `None` if the message does not start with a valid header.
+pub(crate) fn split_header(message: &[u8]) -> Option<(u32, &[u8])> {
+    if message.len() < HEADER_LEN || message[0] != MAGIC_BYTE {
+        return None;
+    }
+    let id = u32::from_be_bytes(message[1..HEADER_LEN].try_into().unwrap());
+    Some((id, &message[HEADER_LEN..]))
+}
+
+/// Returns the header of a message whose schema has id `id`.
+pub(crate) fn header(id: u32) -> [u8; HEADER_LEN] {
+    let mut header = [MAGIC_BYTE; HEADER_LEN];
+    header[1..].copy_from_slice(&id.to_be_bytes());
+    header
+}
+
+#[derive(Deserialize)]
+struct SchemaResponse {
+    schema: String,
+    #[serde(rename = "schemaType")]
+    schema_type: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RegisterResponse {
+    id: u32,
+}
+
+pub(crate) struct SchemaRegistryClient {
+    /// Base URL of the registry, without a trailing slash.
+    url: String,
+
+    agent: ureq::Agent,
+
+    /// Schemas retrieved from the registry so far, by id.  Schemas are
+    /// immutable, so they never need to be refreshed.
+    schemas: Mutex<HashMap<u32, Arc<Schema>>>,
+}
+
+impl SchemaRegistryClient {
+    pub(crate) fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::Agent::new(),
+            schemas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the schema with the specified id, retrieving it from the
+    /// registry unless it is already cached.
+    pub(crate) fn schema(&self, id: u32) -> AnyResult<Arc<Schema>> {
+        if let Some(schema) = self.schemas.lock().unwrap().get(&id) {
+            return Ok(schema.clone());
+        }
+
+        let response: SchemaResponse = self
+            .agent
+            .get(&format!("{}/schemas/ids/{id}", self.url))
+            .set("Accept", CONTENT_TYPE)
+            .call()
+            .map_err(request_error)?
+            .into_json()?;
+        if let Some(schema_type) = &response.schema_type {
+            if schema_type != "AVRO" {
+                bail!("schema {id} is a {schema_type} schema, not an Avro schema");
+            }
+        }
+        let schema = Arc::new(
+            Schema::parse_str(&response.schema)
+                .map_err(|e| anyhow!("schema {id} is not a valid Avro schema: {e}"))?,
+        );
+
+        self.schemas.lock().unwrap().insert(id, schema.clone());
+        Ok(schema)
+    }
+
+    /// Registers `schema` under `subject` and returns its id.  Registering a
+    /// schema that already exists under the subject returns the id of the
+    /// existing schema.
+    pub(crate) fn register_schema(&self, subject: &str, schema: &Schema) -> AnyResult<u32> {
+        let response: RegisterResponse = self
+            .agent
+            .post(&format!("{}/subjects/{subject}/versions", self.url))
+            .set("Content-Type", CONTENT_TYPE)
+            .send_json(json!({"schema": serde_json::to_string(schema)?}))
+            .map_err(request_error)?
+            .into_json()?;
+        Ok(response.id)
+    }
+}
+
+/// Converts an HTTP error into an error that includes the response of the
+/// registry, which explains the failure.
+fn request_error(error: ureq::Error) -> anyhow::Error {
+    match error {
+        ureq::Error::Status(status, response) => {
+            let url = response.get_url().to_string();
+            let body = response.into_string().unwrap_or_default();
+            anyhow!("schema registry request '{url}' failed with status {status}: {body}")
+        }
+        error => anyhow!("schema registry request failed: {error}"),
+    }
+}
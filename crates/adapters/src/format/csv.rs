@@ -1,18 +1,38 @@
 use crate::{
     catalog::{CursorWithPolarity, DeCollectionStream, RecordFormat, SerBatch, SerCursor},
-    format::{Encoder, InputFormat, OutputFormat, ParseError, Parser},
+    format::{reject_keyed_consumer, Encoder, InputFormat, OutputFormat, ParseError, Parser},
+    static_compile::seroutput::SwappableWrite,
     util::{split_on_newline, truncate_ellipse},
     ControllerError, DeCollectionHandle, OutputConsumer,
 };
 use actix_web::HttpRequest;
-use anyhow::{bail, Result as AnyResult};
-use csv_core::{ReadRecordResult, Reader as CsvReader};
+use anyhow::{anyhow, bail, Result as AnyResult};
+use csv::{
+    ByteRecord, Reader as CsvRecordReader, ReaderBuilder as CsvReaderBuilder, Writer as CsvWriter,
+    WriterBuilder as CsvWriterBuilder,
+};
+use csv_core::{ReadRecordResult, Reader as CsvReader, ReaderBuilder as CsvCoreReaderBuilder};
 use erased_serde::Serialize as ErasedSerialize;
-use pipeline_types::format::csv::{CsvEncoderConfig, CsvParserConfig};
-use serde::Deserialize;
+use pipeline_types::{
+    format::{
+        csv::{CsvEncoderConfig, CsvParserConfig},
+        json::JsonFlavor,
+    },
+    program_schema::Field,
+};
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 use serde_urlencoded::Deserializer as UrlDeserializer;
 use serde_yaml::Value as YamlValue;
-use std::{borrow::Cow, mem::take, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::{Formatter, Result as FmtResult},
+    mem::take,
+    sync::Arc,
+};
 
 pub(crate) mod deserializer;
 pub use deserializer::byte_record_deserializer;
@@ -22,6 +42,163 @@ pub use deserializer::string_record_deserializer;
 /// truncate it to `MAX_RECORD_LEN_IN_ERRMSG` bytes.
 static MAX_RECORD_LEN_IN_ERRMSG: usize = 4096;
 
+/// Name of the header of the weight column written by the encoder.
+const WEIGHT_COLUMN: &str = "__weight";
+
+/// CSV dialect options shared by the parser and the encoder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CsvDialect {
+    delimiter: u8,
+    quote: u8,
+    escape: Option<u8>,
+    comment: Option<u8>,
+    null_string: Option<String>,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            comment: None,
+            null_string: None,
+        }
+    }
+}
+
+impl CsvDialect {
+    fn new(
+        delimiter: char,
+        quote: char,
+        escape: Option<char>,
+        comment: Option<char>,
+        null_string: &Option<String>,
+    ) -> AnyResult<Self> {
+        fn ascii(option: &str, c: char) -> AnyResult<u8> {
+            if c.is_ascii() {
+                Ok(c as u8)
+            } else {
+                bail!("'{option}' must be an ASCII character, but '{c}' is not")
+            }
+        }
+
+        Ok(Self {
+            delimiter: ascii("delimiter", delimiter)?,
+            quote: ascii("quote", quote)?,
+            escape: escape.map(|c| ascii("escape", c)).transpose()?,
+            comment: comment.map(|c| ascii("comment", c)).transpose()?,
+            null_string: null_string.clone(),
+        })
+    }
+
+    /// Returns true if this is the dialect used to serialize and deserialize
+    /// records internally, in which case records don't need to be
+    /// transcoded.
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns a reader that establishes record boundaries.
+    fn record_splitter(&self) -> CsvReader {
+        CsvCoreReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .double_quote(self.escape.is_none())
+            .comment(self.comment)
+            .build()
+    }
+
+    fn reader_builder(&self) -> CsvReaderBuilder {
+        let mut builder = CsvReaderBuilder::new();
+        builder
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape)
+            .double_quote(self.escape.is_none())
+            .comment(self.comment);
+        builder
+    }
+
+    fn writer_builder(&self) -> CsvWriterBuilder {
+        let mut builder = CsvWriterBuilder::new();
+        builder
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .escape(self.escape.unwrap_or(b'\\'))
+            .double_quote(self.escape.is_none())
+            .comment(self.comment);
+        builder
+    }
+}
+
+/// Reads one record at a time from byte slices.
+struct RecordReader {
+    builder: CsvReaderBuilder,
+    reader: CsvRecordReader<VecDeque<u8>>,
+    record: ByteRecord,
+}
+
+impl RecordReader {
+    fn new(dialect: &CsvDialect) -> Self {
+        let builder = dialect.reader_builder();
+        Self {
+            reader: builder.from_reader(VecDeque::new()),
+            builder,
+            record: ByteRecord::new(),
+        }
+    }
+
+    /// Parses a single record.  Returns `None` if `data` only contains
+    /// comments.
+    ///
+    /// The last record of an input stream may lack a line terminator, in
+    /// which case the reader only finds its end when it runs out of input.
+    fn read(&mut self, data: &[u8]) -> AnyResult<Option<&ByteRecord>> {
+        self.reader.get_mut().extend(data.iter());
+        let result = self.reader.read_byte_record(&mut self.record);
+        if !matches!(result, Ok(true)) || self.reader.is_done() {
+            // The reader stops at the end of its input or after an error;
+            // start over with a new one.
+            self.reader = self.builder.from_reader(VecDeque::new());
+        }
+        match result {
+            Ok(true) => Ok(Some(&self.record)),
+            result => {
+                result?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Writes one record at a time to a byte vector.
+struct RecordWriter(CsvWriter<SwappableWrite<Vec<u8>>>);
+
+impl RecordWriter {
+    fn new(builder: &CsvWriterBuilder) -> Self {
+        Self(builder.from_writer(SwappableWrite::new()))
+    }
+
+    /// Appends a record to `buffer`.
+    fn write<I, T>(&mut self, record: I, buffer: &mut Vec<u8>) -> AnyResult<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.0.get_ref().swap(Some(take(buffer)));
+        let result = self.0.write_record(record);
+        let _ = self.0.flush();
+        *buffer = self.0.get_ref().swap(None).unwrap();
+        Ok(result?)
+    }
+}
+
 /// CSV format parser.
 pub struct CsvInputFormat;
 
@@ -35,20 +212,47 @@ impl InputFormat for CsvInputFormat {
     // HTTP query, but a specialized method gives us more flexibility.
     fn config_from_http_request(
         &self,
-        _endpoint_name: &str,
-        _request: &HttpRequest,
+        endpoint_name: &str,
+        request: &HttpRequest,
     ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
-        Ok(Box::new(CsvParserConfig {}))
+        Ok(Box::new(
+            CsvParserConfig::deserialize(UrlDeserializer::new(form_urlencoded::parse(
+                request.query_string().as_bytes(),
+            )))
+            .map_err(|e| {
+                ControllerError::parser_config_parse_error(
+                    endpoint_name,
+                    &e,
+                    request.query_string(),
+                )
+            })?,
+        ))
     }
 
     fn new_parser(
         &self,
-        _endpoint_name: &str,
+        endpoint_name: &str,
         input_stream: &dyn DeCollectionHandle,
-        _config: &YamlValue,
+        config: &YamlValue,
     ) -> Result<Box<dyn Parser>, ControllerError> {
+        let config = CsvParserConfig::deserialize(config).map_err(|e| {
+            ControllerError::parser_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+        let dialect = CsvDialect::new(
+            config.delimiter,
+            config.quote,
+            config.escape,
+            config.comment,
+            &config.null_string,
+        )
+        .map_err(|e| ControllerError::input_format_not_supported(endpoint_name, &e.to_string()))?;
+
         let input_stream = input_stream.configure_deserializer(RecordFormat::Csv)?;
-        Ok(Box::new(CsvParser::new(input_stream)) as Box<dyn Parser>)
+        Ok(Box::new(CsvParser::new(input_stream, Arc::new(config), dialect)) as Box<dyn Parser>)
     }
 }
 
@@ -56,6 +260,28 @@ struct CsvParser {
     /// Input handle to push parsed data to.
     input_stream: Box<dyn DeCollectionStream>,
 
+    config: Arc<CsvParserConfig>,
+
+    dialect: CsvDialect,
+
+    /// Parses records in the configured dialect, so that they can be
+    /// transcoded to the dialect expected by `input_stream`.
+    reader: RecordReader,
+
+    /// Writes transcoded records.
+    writer: RecordWriter,
+
+    /// Buffer to transcode records into.
+    transcoded: Vec<u8>,
+
+    /// `true` until the header row has been read.
+    expect_headers: bool,
+
+    /// For each column of the input table, its position in input records,
+    /// or `None` if it is missing from the header row.  Only set when
+    /// columns are mapped by header name.
+    column_map: Option<Vec<Option<usize>>>,
+
     /// Since we cannot assume that the input buffer ends on line end,
     /// we save the "leftover" part of the buffer after the last new-line
     /// character and prepend it to the next input buffer.
@@ -65,19 +291,110 @@ struct CsvParser {
 }
 
 impl CsvParser {
-    fn new(input_stream: Box<dyn DeCollectionStream>) -> Self {
+    fn new(
+        input_stream: Box<dyn DeCollectionStream>,
+        config: Arc<CsvParserConfig>,
+        dialect: CsvDialect,
+    ) -> Self {
         Self {
             input_stream,
+            expect_headers: config.has_headers,
+            config,
+            reader: RecordReader::new(&dialect),
+            writer: RecordWriter::new(CsvWriterBuilder::new().has_headers(false).flexible(true)),
+            dialect,
+            transcoded: Vec::new(),
+            column_map: None,
             leftover: Vec::new(),
             last_event_number: 0,
         }
     }
 
+    /// Maps the columns of the input table to positions in input records
+    /// based on the header row.  Returns an error for each non-nullable
+    /// column missing from the header.
+    fn read_headers(&mut self, record: &[u8]) -> Vec<ParseError> {
+        let Some(columns) = &self.config.relation_schema else {
+            return Vec::new();
+        };
+
+        let headers = match self.reader.read(record) {
+            Ok(Some(headers)) => headers
+                .iter()
+                .map(|header| String::from_utf8_lossy(header).trim().to_string())
+                .collect::<Vec<_>>(),
+            Ok(None) => return Vec::new(),
+            Err(e) => {
+                return vec![ParseError::text_envelope_error(
+                    format!("failed to parse CSV header: {e}"),
+                    &String::from_utf8_lossy(record),
+                    None,
+                )]
+            }
+        };
+
+        let mut errors = Vec::new();
+        let column_map = columns
+            .fields
+            .iter()
+            .map(|column: &Field| {
+                let position = headers
+                    .iter()
+                    .position(|header| header.eq_ignore_ascii_case(&column.name));
+                if position.is_none() && !column.columntype.nullable {
+                    errors.push(ParseError::new(
+                        format!("column '{}' is missing from the CSV header", column.name),
+                        None,
+                        Some(column.name.clone()),
+                        Some(&String::from_utf8_lossy(record)),
+                        None,
+                        None,
+                    ));
+                }
+                position
+            })
+            .collect();
+        self.column_map = Some(column_map);
+        errors
+    }
+
+    /// Transcodes a record from the configured dialect to the dialect
+    /// expected by `input_stream` and pushes it to the stream.
+    fn insert(&mut self, record: &[u8]) -> AnyResult<()> {
+        if self.dialect.is_default() && self.column_map.is_none() {
+            return self.input_stream.insert(record);
+        }
+
+        let Some(fields) = self.reader.read(record)? else {
+            // A comment.
+            return Ok(());
+        };
+        let null_string = self.dialect.null_string.as_ref().map(String::as_bytes);
+        let field = |i: usize| match fields.get(i) {
+            Some(field) if Some(field) != null_string => field,
+            _ => &[][..],
+        };
+
+        self.transcoded.clear();
+        match &self.column_map {
+            Some(column_map) => self.writer.write(
+                column_map
+                    .iter()
+                    .map(|position| position.map_or(&[][..], field)),
+                &mut self.transcoded,
+            )?,
+            None => self
+                .writer
+                .write((0..fields.len()).map(field), &mut self.transcoded)?,
+        }
+        self.input_stream.insert(&self.transcoded)
+    }
+
     fn parse_from_buffer(&mut self, mut buffer: &[u8]) -> (usize, Vec<ParseError>) {
         let mut errors = Vec::new();
         let mut num_records = 0;
 
-        let mut csv_reader = CsvReader::new();
+        let mut csv_reader = self.dialect.record_splitter();
 
         // println!("parse_from_buffer:{}", std::str::from_utf8(buffer).unwrap());
 
@@ -103,15 +420,22 @@ impl CsvParser {
                             .unwrap_or("invalid utf-8"),
                         &record_buffer[0..total_bytes_read],
                     );*/
-                    match self
-                        .input_stream
-                        .insert(&record_buffer[0..total_bytes_read])
-                    {
-                        Err(e) => {
+                    let record = &record_buffer[0..total_bytes_read];
+                    let inserted = if self.expect_headers {
+                        self.expect_headers = false;
+                        errors.extend(self.read_headers(record));
+                        None
+                    } else {
+                        Some(self.insert(record))
+                    };
+                    match inserted {
+                        None => {}
+                        Some(Err(e)) => {
+                            self.last_event_number += 1;
                             errors.push(ParseError::text_event_error(
                                 "failed to deserialize CSV record",
                                 e,
-                                self.last_event_number + 1,
+                                self.last_event_number,
                                 Some(
                                     &std::str::from_utf8(&record_buffer[0..total_bytes_read])
                                         .map(|s| s.to_string())
@@ -123,8 +447,9 @@ impl CsvParser {
                                 None,
                             ));
                         }
-                        Ok(()) => {
+                        Some(Ok(())) => {
                             num_records += 1;
+                            self.last_event_number += 1;
                         }
                     }
                     // Lines ending in "\r\n" get broken up after `\r` by the parser.
@@ -134,7 +459,6 @@ impl CsvParser {
                         bytes_read += 1;
                     }
                     record_buffer = &buffer[bytes_read..];
-                    self.last_event_number += 1;
                     total_bytes_read = 0;
                     if result == ReadRecordResult::InputEmpty {
                         break;
@@ -200,7 +524,14 @@ impl Parser for CsvParser {
     }
 
     fn fork(&self) -> Box<dyn Parser> {
-        Box::new(Self::new(self.input_stream.fork()))
+        let mut parser = Self::new(
+            self.input_stream.fork(),
+            self.config.clone(),
+            self.dialect.clone(),
+        );
+        parser.expect_headers = self.expect_headers;
+        parser.column_map = self.column_map.clone();
+        Box::new(parser)
     }
 }
 
@@ -237,6 +568,8 @@ impl OutputFormat for CsvOutputFormat {
         config: &YamlValue,
         consumer: Box<dyn OutputConsumer>,
     ) -> Result<Box<dyn Encoder>, ControllerError> {
+        reject_keyed_consumer(endpoint_name, "CSV", consumer.as_ref())?;
+
         let config = CsvEncoderConfig::deserialize(config).map_err(|e| {
             ControllerError::encoder_config_parse_error(
                 endpoint_name,
@@ -245,30 +578,164 @@ impl OutputFormat for CsvOutputFormat {
             )
         })?;
 
-        Ok(Box::new(CsvEncoder::new(consumer, config)))
+        let dialect = CsvDialect::new(
+            config.delimiter,
+            config.quote,
+            config.escape,
+            config.comment,
+            &config.null_string,
+        )
+        .map_err(|e| ControllerError::output_format_not_supported(endpoint_name, &e.to_string()))?;
+
+        Ok(Box::new(CsvEncoder::new(consumer, config, dialect)))
+    }
+}
+
+/// Collects the field names of a serialized JSON object.
+struct FieldNames;
+
+impl<'de> Visitor<'de> for FieldNames {
+    type Value = Vec<String>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+        formatter.write_str("a JSON object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut names = Vec::new();
+        while let Some((name, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+            names.push(name);
+        }
+        Ok(names)
+    }
+}
+
+/// Returns the column names of the records in `batch`, or `None` if `batch`
+/// is empty.
+fn column_names(batch: &dyn SerBatch) -> AnyResult<Option<Vec<String>>> {
+    let mut cursor = batch.cursor(RecordFormat::Json(JsonFlavor::Default))?;
+    if !cursor.key_valid() {
+        return Ok(None);
+    }
+
+    let mut buffer = Vec::new();
+    cursor.serialize_key(&mut buffer)?;
+    let names = serde_json::Deserializer::from_slice(&buffer)
+        .deserialize_map(FieldNames)
+        .map_err(|e| anyhow!("failed to retrieve CSV column names: {e}"))?;
+    Ok(Some(names))
+}
+
+/// Records which fields of a serialized JSON object are `null`.
+struct NullFields;
+
+impl<'de> Visitor<'de> for NullFields {
+    type Value = Vec<bool>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> FmtResult {
+        formatter.write_str("a JSON object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut nulls = Vec::new();
+        while let Some((IgnoredAny, value)) = map.next_entry::<IgnoredAny, Option<IgnoredAny>>()? {
+            nulls.push(value.is_none());
+        }
+        Ok(nulls)
     }
 }
 
+/// For each column of the record under `cursor`, returns `true` if its value
+/// is NULL.
+fn null_fields(cursor: &mut dyn SerCursor, buffer: &mut Vec<u8>) -> AnyResult<Vec<bool>> {
+    buffer.clear();
+    cursor.serialize_key(buffer)?;
+    serde_json::Deserializer::from_slice(buffer)
+        .deserialize_map(NullFields)
+        .map_err(|e| anyhow!("failed to retrieve NULL columns of CSV record: {e}"))
+}
+
 struct CsvEncoder {
     /// Input handle to push serialized data to.
     output_consumer: Box<dyn OutputConsumer>,
 
     config: CsvEncoderConfig,
+    dialect: CsvDialect,
+
+    /// Parses records serialized in the default dialect, so that they can
+    /// be transcoded to `dialect`.
+    reader: RecordReader,
+
+    /// Writes records in `dialect`.
+    writer: RecordWriter,
+
+    /// `true` until the header row has been written.
+    write_headers: bool,
+
+    /// Buffer to serialize records into to locate NULL fields.
+    json_buffer: Vec<u8>,
+
     buffer: Vec<u8>,
     max_buffer_size: usize,
 }
 
 impl CsvEncoder {
-    fn new(output_consumer: Box<dyn OutputConsumer>, config: CsvEncoderConfig) -> Self {
+    fn new(
+        output_consumer: Box<dyn OutputConsumer>,
+        config: CsvEncoderConfig,
+        dialect: CsvDialect,
+    ) -> Self {
         let max_buffer_size = output_consumer.max_buffer_size_bytes();
 
         Self {
             output_consumer,
+            write_headers: config.has_headers,
             config,
+            reader: RecordReader::new(&CsvDialect::default()),
+            writer: RecordWriter::new(&dialect.writer_builder()),
+            dialect,
+            json_buffer: Vec::new(),
             buffer: Vec::new(),
             max_buffer_size,
         }
     }
+
+    /// Transcodes the record at the end of `buffer`, starting at `offset`,
+    /// from the default dialect to the configured dialect.  `nulls` flags
+    /// the fields of the record that are NULL.
+    fn transcode(&mut self, buffer: &mut Vec<u8>, offset: usize, nulls: &[bool]) -> AnyResult<()> {
+        if self.dialect.is_default() {
+            return Ok(());
+        }
+
+        let fields = self
+            .reader
+            .read(&buffer[offset..])?
+            .ok_or_else(|| anyhow!("failed to transcode CSV record"))?;
+        buffer.truncate(offset);
+
+        let null_string = self
+            .dialect
+            .null_string
+            .as_ref()
+            .map_or(&[][..], String::as_bytes);
+        self.writer.write(
+            fields.iter().enumerate().map(|(i, field)| {
+                if nulls.get(i) == Some(&true) {
+                    null_string
+                } else {
+                    field
+                }
+            }),
+            buffer,
+        )
+    }
 }
 
 impl Encoder for CsvEncoder {
@@ -281,18 +748,48 @@ impl Encoder for CsvEncoder {
         //let mut writer = self.builder.from_writer(buffer);
         let mut num_records = 0;
 
+        if self.write_headers {
+            for batch in batches.iter() {
+                if let Some(mut names) = column_names(batch.as_ref())? {
+                    names.push(WEIGHT_COLUMN.to_string());
+                    self.writer.write(&names, &mut buffer)?;
+                    self.write_headers = false;
+                    break;
+                }
+            }
+        }
+
         for batch in batches.iter() {
             let mut cursor = CursorWithPolarity::new(batch.cursor(RecordFormat::Csv)?);
 
+            // The default dialect encodes NULLs and empty strings identically.
+            // When NULLs must be written as `null_string`, we locate them using
+            // the JSON encoding of the same records.
+            let mut json_cursor = if self.dialect.null_string.is_some() {
+                Some(CursorWithPolarity::new(
+                    batch.cursor(RecordFormat::Json(JsonFlavor::Default))?,
+                ))
+            } else {
+                None
+            };
+
             while cursor.key_valid() {
                 if !cursor.val_valid() {
                     cursor.step_key();
+                    if let Some(json_cursor) = &mut json_cursor {
+                        json_cursor.step_key();
+                    }
                     continue;
                 }
                 let prev_len = buffer.len();
 
                 // `serialize_key_weight`
                 cursor.serialize_key_weight(&mut buffer)?;
+                let nulls = match &mut json_cursor {
+                    Some(json_cursor) => null_fields(json_cursor, &mut self.json_buffer)?,
+                    None => Vec::new(),
+                };
+                self.transcode(&mut buffer, prev_len, &nulls)?;
 
                 // Drop the last encoded record if it exceeds max_buffer_size.
                 // The record will be included in the next buffer.
@@ -326,6 +823,9 @@ impl Encoder for CsvEncoder {
 
                 if !overflow {
                     cursor.step_key();
+                    if let Some(json_cursor) = &mut json_cursor {
+                        json_cursor.step_key();
+                    }
                 }
             }
         }
@@ -340,3 +840,251 @@ impl Encoder for CsvEncoder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::CsvOutputFormat;
+    use crate::{
+        catalog::SerBatch,
+        format::OutputFormat,
+        static_compile::seroutput::SerBatchImpl,
+        test::{mock_parser_pipeline, MockOutputConsumer, MockUpdate, TestStruct},
+        transport::InputConsumer,
+        ParseError,
+    };
+    use dbsp::{trace::Batch, OrdZSet};
+    use pipeline_types::{
+        config::FormatConfig,
+        format::csv::{CsvEncoderConfig, CsvParserConfig},
+        program_schema::{ColumnType, Field, Relation},
+    };
+    use std::{borrow::Cow, sync::Arc};
+
+    fn column(name: &str, typ: &str, nullable: bool) -> Field {
+        Field {
+            name: name.to_string(),
+            columntype: ColumnType {
+                typ: typ.to_string(),
+                nullable,
+                precision: None,
+                scale: None,
+                component: None,
+            },
+        }
+    }
+
+    fn test_struct_schema() -> Relation {
+        Relation {
+            name: "test_input".to_string(),
+            fields: vec![
+                column("id", "INTEGER", false),
+                column("b", "BOOLEAN", false),
+                column("i", "BIGINT", true),
+                column("s", "VARCHAR", false),
+            ],
+        }
+    }
+
+    fn test_struct(id: u32, b: bool, i: Option<i64>, s: &str) -> TestStruct {
+        TestStruct {
+            id,
+            b,
+            i,
+            s: s.to_string(),
+        }
+    }
+
+    /// Parses `input` with `config`, returning the parsed records and the
+    /// parse errors.
+    fn parse(
+        config: CsvParserConfig,
+        input: &str,
+    ) -> (Vec<MockUpdate<TestStruct, TestStruct>>, Vec<ParseError>) {
+        let format_config = FormatConfig {
+            name: Cow::from("csv"),
+            config: serde_yaml::to_value(config).unwrap(),
+        };
+        let (mut consumer, zset) =
+            mock_parser_pipeline::<TestStruct, TestStruct>(&format_config).unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+
+        let mut errors = consumer.input_fragment(input.as_bytes());
+        errors.extend(consumer.eoi());
+
+        let state = zset.state();
+        (state.flushed.clone(), errors)
+    }
+
+    /// Encodes `records` with `config`.
+    fn encode(config: CsvEncoderConfig, records: Vec<(TestStruct, i64)>) -> String {
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let format_config = FormatConfig {
+            name: Cow::from("csv"),
+            config: serde_yaml::to_value(config).unwrap(),
+        };
+        let mut encoder = CsvOutputFormat
+            .new_encoder("test", &format_config.config, Box::new(consumer))
+            .unwrap();
+
+        let zset = OrdZSet::from_keys((), records);
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        encoder.encode(&[batch]).unwrap();
+
+        let data = consumer_data.lock().unwrap();
+        String::from_utf8(data.clone()).unwrap()
+    }
+
+    #[test]
+    fn test_csv_dialect() {
+        let config = CsvParserConfig {
+            delimiter: '\t',
+            null_string: Some("\\N".to_string()),
+            comment: Some('#'),
+            ..Default::default()
+        };
+        let (records, errors) = parse(
+            config,
+            "# comment\n0\ttrue\t\\N\tfoo\n# comment\n1\tfalse\t5\t\"b\tr\"\n",
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            records,
+            [
+                MockUpdate::Insert(test_struct(0, true, None, "foo")),
+                MockUpdate::Insert(test_struct(1, false, Some(5), "b\tr")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_no_trailing_newline() {
+        let config = CsvParserConfig {
+            delimiter: '\t',
+            null_string: Some("\\N".to_string()),
+            ..Default::default()
+        };
+        let (records, errors) = parse(config, "0\ttrue\t\\N\tfoo\n1\tfalse\t5\tbar");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            records,
+            [
+                MockUpdate::Insert(test_struct(0, true, None, "foo")),
+                MockUpdate::Insert(test_struct(1, false, Some(5), "bar")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        let config = CsvParserConfig {
+            quote: '\'',
+            escape: Some('\\'),
+            ..Default::default()
+        };
+        let (records, errors) = parse(config, "0,true,1,'a\\'b'\n");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            records,
+            [MockUpdate::Insert(test_struct(0, true, Some(1), "a'b"))]
+        );
+    }
+
+    #[test]
+    fn test_csv_headers() {
+        // Without a schema, the header is skipped and columns are mapped by
+        // position.
+        let config = CsvParserConfig {
+            delimiter: '|',
+            has_headers: true,
+            ..Default::default()
+        };
+        let (records, errors) = parse(config, "id|b|i|s\n0|true|1|foo\n");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            records,
+            [MockUpdate::Insert(test_struct(0, true, Some(1), "foo"))]
+        );
+
+        // With a schema, columns are mapped by name.
+        let config = CsvParserConfig {
+            delimiter: '|',
+            has_headers: true,
+            relation_schema: Some(test_struct_schema()),
+            ..Default::default()
+        };
+        let (records, errors) = parse(config, "S|extra|ID|B\nfoo|x|0|true\nbar|y|1|false\n");
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(
+            records,
+            [
+                MockUpdate::Insert(test_struct(0, true, None, "foo")),
+                MockUpdate::Insert(test_struct(1, false, None, "bar")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_csv_missing_column() {
+        let config = CsvParserConfig {
+            has_headers: true,
+            relation_schema: Some(test_struct_schema()),
+            ..Default::default()
+        };
+        let (_records, errors) = parse(config, "id,b,i\n");
+        assert_eq!(
+            errors,
+            [ParseError::new(
+                "column 's' is missing from the CSV header".to_string(),
+                None,
+                Some("s".to_string()),
+                Some("id,b,i\n"),
+                None,
+                None,
+            )]
+        );
+    }
+
+    #[test]
+    fn test_csv_encoder() {
+        let records = vec![
+            (test_struct(0, true, None, "foo"), 1),
+            (test_struct(1, false, Some(5), "b|r"), -1),
+            (test_struct(2, true, Some(6), ""), 1),
+        ];
+
+        // Default dialect.
+        assert_eq!(
+            encode(CsvEncoderConfig::default(), records.clone()),
+            "1,false,5,b|r,-1\n0,true,,foo,1\n2,true,6,,1\n"
+        );
+
+        // Only NULLs, not empty strings, are written as `null_string`.
+        let config = CsvEncoderConfig {
+            delimiter: '|',
+            has_headers: true,
+            null_string: Some("\\N".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            encode(config, records),
+            "id|b|i|s|__weight\n1|false|5|\"b|r\"|-1\n0|true|\\N|foo|1\n2|true|6||1\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_non_ascii() {
+        let config = CsvEncoderConfig {
+            delimiter: '¦',
+            ..Default::default()
+        };
+        let consumer = MockOutputConsumer::new();
+        assert!(CsvOutputFormat
+            .new_encoder(
+                "test",
+                &serde_yaml::to_value(config).unwrap(),
+                Box::new(consumer)
+            )
+            .is_err());
+    }
+}
@@ -4,11 +4,12 @@ use crate::{
     ControllerError, Encoder, OutputConsumer, OutputFormat,
 };
 use actix_web::HttpRequest;
-use anyhow::{bail, Result as AnyResult};
+use anyhow::{anyhow, bail, Result as AnyResult};
 use erased_serde::Serialize as ErasedSerialize;
 use pipeline_types::format::json::{JsonEncoderConfig, JsonFlavor, JsonUpdateFormat};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
 use serde_urlencoded::Deserializer as UrlDeserializer;
 use serde_yaml::Value as YamlValue;
 use std::io::Write;
@@ -68,7 +69,7 @@ impl OutputFormat for JsonOutputFormat {
             )
         })?;
 
-        validate(&config, endpoint_name)?;
+        validate(&config, endpoint_name, consumer.key_fields().is_some())?;
 
         // Snowflake requires one record per message.
         if config.update_format == JsonUpdateFormat::Snowflake {
@@ -79,7 +80,11 @@ impl OutputFormat for JsonOutputFormat {
     }
 }
 
-fn validate(config: &JsonEncoderConfig, endpoint_name: &str) -> Result<(), ControllerError> {
+fn validate(
+    config: &JsonEncoderConfig,
+    endpoint_name: &str,
+    keyed: bool,
+) -> Result<(), ControllerError> {
     if !matches!(
         config.update_format,
         JsonUpdateFormat::InsertDelete | JsonUpdateFormat::Snowflake
@@ -93,6 +98,18 @@ fn validate(config: &JsonEncoderConfig, endpoint_name: &str) -> Result<(), Contr
         ));
     }
 
+    // In the one-message-per-record mode, each message holds a plain record,
+    // so no other update format can be honored.
+    if keyed && config.update_format != JsonUpdateFormat::InsertDelete {
+        return Err(ControllerError::output_format_not_supported(
+            endpoint_name,
+            &format!(
+                "{:?} update format is not supported with one message per record",
+                config.update_format
+            ),
+        ));
+    }
+
     Ok(())
 }
 
@@ -106,11 +123,15 @@ struct JsonEncoder {
     stream_id: u64,
     /// Sequence number of the last record produced by this encoder.
     seq_number: u64,
+    /// Columns that key each message, if the consumer expects one message
+    /// per record.
+    key_fields: Option<Vec<String>>,
 }
 
 impl JsonEncoder {
     fn new(output_consumer: Box<dyn OutputConsumer>, mut config: JsonEncoderConfig) -> Self {
         let max_buffer_size = output_consumer.max_buffer_size_bytes();
+        let key_fields = output_consumer.key_fields().map(<[String]>::to_vec);
 
         if config.json_flavor.is_none() {
             config.json_flavor = Some(match config.update_format {
@@ -128,16 +149,91 @@ impl JsonEncoder {
             // id into a negative number.
             stream_id: StdRng::from_entropy().gen_range(0..i64::MAX) as u64,
             seq_number: 0,
+            key_fields,
+        }
+    }
+
+    /// Encodes `batches` as one message per record, keyed by the values of
+    /// the key fields.  The message value is the record itself, or `None`
+    /// for a deleted record.
+    ///
+    /// All deletions are output before all insertions, so that the tombstone
+    /// for a deleted record does not supersede an inserted record with the
+    /// same key.
+    fn encode_keyed(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        let key_fields = self.key_fields.as_deref().unwrap();
+        let mut record = take(&mut self.buffer);
+
+        for insert in [false, true] {
+            for batch in batches.iter() {
+                let mut cursor =
+                    batch.cursor(RecordFormat::Json(self.config.json_flavor.clone().unwrap()))?;
+
+                while cursor.key_valid() {
+                    let mut w = 0;
+                    while cursor.val_valid() {
+                        w += cursor.weight();
+                        cursor.step_val();
+                    }
+
+                    if w != 0 && (w > 0) == insert {
+                        record.clear();
+                        cursor.serialize_key(&mut record)?;
+                        if record.len() > self.max_buffer_size {
+                            let record = std::str::from_utf8(&record).unwrap_or_default();
+                            bail!("JSON record exceeds maximum buffer size supported by the output transport. Max supported buffer size is {} bytes, but the following record requires {} bytes: '{}'.",
+                                  self.max_buffer_size,
+                                  record.len(),
+                                  truncate_ellipse(record, MAX_RECORD_LEN_IN_ERRMSG, "..."));
+                        }
+
+                        let key = record_key(&record, key_fields)?;
+                        self.output_consumer
+                            .push_key(&key, insert.then_some(record.as_slice()));
+                    }
+
+                    cursor.step_key();
+                }
+            }
         }
+
+        record.clear();
+        self.buffer = record;
+
+        Ok(())
     }
 }
 
+/// Extracts the values of `key_fields` from the JSON-encoded `record` and
+/// encodes them as a JSON object, which serves as the record's message key.
+fn record_key(record: &[u8], key_fields: &[String]) -> AnyResult<Vec<u8>> {
+    let JsonValue::Object(mut record) = serde_json::from_slice(record)? else {
+        bail!("output record is not a JSON object");
+    };
+
+    let key = key_fields
+        .iter()
+        .map(|field| {
+            let value = record.remove(field).ok_or_else(|| {
+                anyhow!("output record has no column '{field}' listed in 'key_fields'")
+            })?;
+            Ok((field.clone(), value))
+        })
+        .collect::<AnyResult<JsonMap<String, JsonValue>>>()?;
+
+    Ok(serde_json::to_vec(&key)?)
+}
+
 impl Encoder for JsonEncoder {
     fn consumer(&mut self) -> &mut dyn OutputConsumer {
         self.output_consumer.as_mut()
     }
 
     fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        if self.key_fields.is_some() {
+            return self.encode_keyed(batches);
+        }
+
         let mut buffer = take(&mut self.buffer);
 
         // Reserve one extra byte for the closing bracket `]`.
@@ -286,13 +382,13 @@ impl Encoder for JsonEncoder {
 
 #[cfg(test)]
 mod test {
-    use super::{JsonEncoder, JsonEncoderConfig};
+    use super::{JsonEncoder, JsonEncoderConfig, JsonOutputFormat};
     use crate::test::generate_test_batches_with_weights;
     use crate::{
         catalog::SerBatch,
         format::{
             json::{InsDelUpdate, SnowflakeAction, SnowflakeUpdate},
-            Encoder,
+            Encoder, OutputFormat,
         },
         static_compile::seroutput::SerBatchImpl,
         test::{MockOutputConsumer, TestStruct},
@@ -557,6 +653,74 @@ mod test {
         assert_eq!(format!("{err}"), "JSON record exceeds maximum buffer size supported by the output transport. Max supported buffer size is 32 bytes, but the following record requires 46 bytes: '{\"delete\":{\"id\":1,\"b\":false,\"i\":10,\"s\":\"bar\"}}'.");
     }
 
+    #[test]
+    fn test_keyed() {
+        let config = JsonEncoderConfig {
+            update_format: JsonUpdateFormat::InsertDelete,
+            json_flavor: None,
+            buffer_size_records: 3,
+            array: false,
+        };
+
+        let consumer = MockOutputConsumer::with_key_fields(&["id"]);
+        let messages = consumer.messages.clone();
+        let mut encoder = JsonEncoder::new(Box::new(consumer), config);
+
+        let record = |id, s: &str| TestStruct {
+            id,
+            b: true,
+            i: None,
+            s: s.to_string(),
+        };
+        let zset = OrdZSet::from_keys(
+            (),
+            vec![
+                // Update of record 1.
+                (record(1, "old"), -1),
+                (record(1, "new"), 1),
+                (record(2, "foo"), 2),
+                (record(3, "bar"), -1),
+            ],
+        );
+        encoder
+            .encode(&[Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>])
+            .unwrap();
+
+        let messages = messages
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, val)| {
+                (
+                    String::from_utf8(key.clone()).unwrap(),
+                    val.as_ref()
+                        .map(|val| serde_json::from_slice::<TestStruct>(val).unwrap()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                (r#"{"id":1}"#.to_string(), None),
+                (r#"{"id":3}"#.to_string(), None),
+                (r#"{"id":1}"#.to_string(), Some(record(1, "new"))),
+                (r#"{"id":2}"#.to_string(), Some(record(2, "foo"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keyed_update_format() {
+        let config = serde_yaml::from_str("update_format: snowflake").unwrap();
+        let consumer = MockOutputConsumer::with_key_fields(&["id"]);
+        let err = JsonOutputFormat
+            .new_encoder("test", &config, Box::new(consumer))
+            .err()
+            .unwrap();
+        assert!(format!("{err}")
+            .contains("Snowflake update format is not supported with one message per record"));
+    }
+
     #[test]
     fn test_ndjson_insdel() {
         test_json::<InsDelUpdate<TestStruct>>(false, test_data());
@@ -15,15 +15,23 @@ use std::{
     sync::Arc,
 };
 
+mod avro;
 pub(crate) mod csv;
 mod json;
+mod parquet;
 
 pub use self::csv::{byte_record_deserializer, string_record_deserializer};
 use self::{
+    avro::{AvroInputFormat, AvroOutputFormat},
     csv::{CsvInputFormat, CsvOutputFormat},
     json::{JsonInputFormat, JsonOutputFormat},
+    parquet::{ParquetInputFormat, ParquetOutputFormat},
 };
 
+/// Precision used for `DECIMAL` columns declared without one, which matches
+/// the largest precision supported by the SQL runtime.
+const DEFAULT_DECIMAL_PRECISION: u8 = 28;
+
 /// Error parsing input data.
 #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
 #[serde(transparent)]
@@ -304,16 +312,26 @@ impl ParseErrorInner {
 // external crates to implement new formats.
 static INPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn InputFormat>>> = Lazy::new(|| {
     BTreeMap::from([
+        ("avro", Box::new(AvroInputFormat) as Box<dyn InputFormat>),
         ("csv", Box::new(CsvInputFormat) as Box<dyn InputFormat>),
         ("json", Box::new(JsonInputFormat) as Box<dyn InputFormat>),
+        (
+            "parquet",
+            Box::new(ParquetInputFormat) as Box<dyn InputFormat>,
+        ),
     ])
 });
 
 /// Static map of supported output formats.
 static OUTPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn OutputFormat>>> = Lazy::new(|| {
     BTreeMap::from([
+        ("avro", Box::new(AvroOutputFormat) as Box<dyn OutputFormat>),
         ("csv", Box::new(CsvOutputFormat) as Box<dyn OutputFormat>),
         ("json", Box::new(JsonOutputFormat) as Box<dyn OutputFormat>),
+        (
+            "parquet",
+            Box::new(ParquetOutputFormat) as Box<dyn OutputFormat>,
+        ),
     ])
 });
 
@@ -456,6 +474,23 @@ impl dyn OutputFormat {
     }
 }
 
+/// Fails if `consumer` expects one message per record (see
+/// [`OutputConsumer::key_fields`]), which the `format` encoder does not
+/// support.
+pub(crate) fn reject_keyed_consumer(
+    endpoint_name: &str,
+    format: &str,
+    consumer: &dyn OutputConsumer,
+) -> Result<(), ControllerError> {
+    if consumer.key_fields().is_some() {
+        return Err(ControllerError::output_format_not_supported(
+            endpoint_name,
+            &format!("the {format} format does not support one message per record"),
+        ));
+    }
+    Ok(())
+}
+
 pub trait Encoder: Send {
     /// Returns a reference to the consumer that the encoder is connected to.
     fn consumer(&mut self) -> &mut dyn OutputConsumer;
@@ -472,5 +507,27 @@ pub trait OutputConsumer: Send {
 
     fn batch_start(&mut self, step: Step);
     fn push_buffer(&mut self, buffer: &[u8]);
+
+    /// Returns the names of the columns that form the key of each message if
+    /// the consumer expects one message per record, pushed with
+    /// [`OutputConsumer::push_key`], instead of buffers of records.
+    ///
+    /// Encoders that do not support this mode must reject such consumers.
+    fn key_fields(&self) -> Option<&[String]> {
+        None
+    }
+
+    /// Pushes a single record with message key `key`.  `val` is `None` for a
+    /// deleted record.
+    ///
+    /// Only called if [`OutputConsumer::key_fields`] returns `Some`.  The
+    /// default implementation, meant for consumers that never do, pushes `val`
+    /// as a buffer and drops the key.
+    fn push_key(&mut self, _key: &[u8], val: Option<&[u8]>) {
+        if let Some(val) = val {
+            self.push_buffer(val);
+        }
+    }
+
     fn batch_end(&mut self);
 }
@@ -0,0 +1,805 @@
+use super::relation_to_arrow_fields;
+use crate::{
+    catalog::{DeCollectionStream, RecordFormat},
+    format::{InputFormat, ParseError, Parser},
+    ControllerError, DeCollectionHandle,
+};
+use actix_web::HttpRequest;
+use arrow::{
+    array::{Array, AsArray},
+    datatypes::{
+        DataType, Date32Type, Date64Type, Decimal128Type, Decimal256Type, Field, Float32Type,
+        Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, Schema, Time32MillisecondType,
+        Time32SecondType, Time64MicrosecondType, Time64NanosecondType, TimeUnit,
+        TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    },
+    record_batch::RecordBatch,
+};
+use bytes::Bytes;
+use erased_serde::Serialize as ErasedSerialize;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use pipeline_types::format::{json::JsonFlavor, parquet::ParquetParserConfig};
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, mem::take, sync::Arc};
+
+/// The largest absolute value of the `__weight` column.  A record with weight
+/// `w` is pushed to the input stream as `|w|` copies of the record.
+const MAX_DUPLICATES: i64 = 1_000_000;
+
+/// Parquet format parser.
+pub struct ParquetInputFormat;
+
+impl InputFormat for ParquetInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("parquet")
+    }
+
+    fn config_from_http_request(
+        &self,
+        _endpoint_name: &str,
+        _request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        Ok(Box::new(ParquetParserConfig::default()))
+    }
+
+    fn new_parser(
+        &self,
+        endpoint_name: &str,
+        input_stream: &dyn DeCollectionHandle,
+        config: &YamlValue,
+    ) -> Result<Box<dyn Parser>, ControllerError> {
+        let config = ParquetParserConfig::deserialize(config).map_err(|e| {
+            ControllerError::parser_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+        let expected_fields = config
+            .relation_schema
+            .as_ref()
+            .map(relation_to_arrow_fields)
+            .transpose()
+            .map_err(|e| {
+                ControllerError::input_format_not_supported(endpoint_name, &e.to_string())
+            })?;
+
+        let input_stream =
+            input_stream.configure_deserializer(RecordFormat::Json(JsonFlavor::Default))?;
+        Ok(Box::new(ParquetParser::new(input_stream, expected_fields)) as Box<dyn Parser>)
+    }
+}
+
+struct ParquetParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionStream>,
+
+    /// Columns of the input table, if the schema of the table is known.
+    expected_fields: Option<Arc<Vec<Field>>>,
+
+    /// A Parquet file can only be decoded once it has been received in full,
+    /// so we accumulate fragments here until the end of input.
+    buffer: Vec<u8>,
+
+    last_event_number: u64,
+}
+
+impl ParquetParser {
+    fn new(input_stream: Box<dyn DeCollectionStream>, expected_fields: Option<Vec<Field>>) -> Self {
+        Self {
+            input_stream,
+            expected_fields: expected_fields.map(Arc::new),
+            buffer: Vec::new(),
+            last_event_number: 0,
+        }
+    }
+
+    /// Decodes a complete Parquet file and pushes its rows to the input
+    /// stream.
+    fn parse_file(&mut self, data: Bytes) -> (usize, Vec<ParseError>) {
+        let builder = match ParquetRecordBatchReaderBuilder::try_new(data) {
+            Ok(builder) => builder,
+            Err(e) => {
+                return (
+                    0,
+                    vec![ParseError::new(
+                        format!("failed to read Parquet file: {e}"),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )],
+                )
+            }
+        };
+
+        if let Some(expected_fields) = &self.expected_fields {
+            let errors = check_schema(builder.schema(), expected_fields);
+            if !errors.is_empty() {
+                return (0, errors);
+            }
+        }
+
+        let reader = match builder.build() {
+            Ok(reader) => reader,
+            Err(e) => {
+                return (
+                    0,
+                    vec![ParseError::new(
+                        format!("failed to read Parquet file: {e}"),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )],
+                )
+            }
+        };
+
+        let mut errors = Vec::new();
+        let mut num_records = 0;
+        for batch in reader {
+            match batch {
+                Ok(batch) => num_records += self.parse_batch(&batch, &mut errors),
+                Err(e) => {
+                    errors.push(ParseError::new(
+                        format!("failed to decode Parquet row group: {e}"),
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                    ));
+                    break;
+                }
+            }
+        }
+
+        self.input_stream.flush();
+        (num_records, errors)
+    }
+
+    /// Pushes the rows of `batch` to the input stream, returning the number of
+    /// updates pushed.
+    fn parse_batch(&mut self, batch: &RecordBatch, errors: &mut Vec<ParseError>) -> usize {
+        let schema = batch.schema();
+        let action_column = schema.index_of("__action").ok();
+        let weight_column = schema.index_of("__weight").ok();
+
+        let mut num_records = 0;
+        'rows: for row in 0..batch.num_rows() {
+            self.last_event_number += 1;
+
+            let mut record = JsonMap::new();
+            for (field, column) in schema.fields().iter().zip(batch.columns()) {
+                if field.name() == "__action" || field.name() == "__weight" {
+                    continue;
+                }
+                match arrow_to_json(column.as_ref(), row) {
+                    Ok(value) => {
+                        record.insert(field.name().clone(), value);
+                    }
+                    Err(e) => {
+                        errors.push(ParseError::new(
+                            format!("failed to convert Parquet value: {e}"),
+                            Some(self.last_event_number),
+                            Some(field.name().clone()),
+                            None,
+                            None,
+                            None,
+                        ));
+                        continue 'rows;
+                    }
+                }
+            }
+
+            let weight = match update_weight(batch, row, action_column, weight_column) {
+                Ok(weight) => weight,
+                Err((field, e)) => {
+                    errors.push(ParseError::new(
+                        e,
+                        Some(self.last_event_number),
+                        Some(field.to_string()),
+                        None,
+                        None,
+                        None,
+                    ));
+                    continue;
+                }
+            };
+
+            let record = JsonValue::Object(record).to_string();
+            for _ in 0..weight.unsigned_abs() {
+                let result = if weight > 0 {
+                    self.input_stream.insert(record.as_bytes())
+                } else {
+                    self.input_stream.delete(record.as_bytes())
+                };
+                if let Err(e) = result {
+                    errors.push(ParseError::text_event_error(
+                        "failed to deserialize Parquet record",
+                        e,
+                        self.last_event_number,
+                        Some(&record),
+                        None,
+                    ));
+                    break;
+                }
+                num_records += 1;
+            }
+        }
+
+        num_records
+    }
+}
+
+impl Parser for ParquetParser {
+    fn input_fragment(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
+        self.buffer.extend_from_slice(data);
+        (0, Vec::new())
+    }
+
+    /// A chunk is a complete Parquet file, e.g., the contents of a message.
+    fn input_chunk(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
+        self.parse_file(Bytes::copy_from_slice(data))
+    }
+
+    fn eoi(&mut self) -> (usize, Vec<ParseError>) {
+        if self.buffer.is_empty() {
+            return (0, Vec::new());
+        }
+        let data = Bytes::from(take(&mut self.buffer));
+        self.parse_file(data)
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self {
+            input_stream: self.input_stream.fork(),
+            expected_fields: self.expected_fields.clone(),
+            buffer: Vec::new(),
+            last_event_number: 0,
+        })
+    }
+}
+
+/// Returns the weight of the update in `row`: `1` or `-1` as specified by the
+/// `__action` column, the value of the `__weight` column, or `1` if the file
+/// has neither.
+///
+/// On error, returns the name of the offending column and an error message.
+fn update_weight(
+    batch: &RecordBatch,
+    row: usize,
+    action_column: Option<usize>,
+    weight_column: Option<usize>,
+) -> Result<i64, (&'static str, String)> {
+    if let Some(index) = action_column {
+        let column = batch.column(index);
+        let action = match column.data_type() {
+            DataType::Utf8 if !column.is_null(row) => column.as_string::<i32>().value(row),
+            DataType::LargeUtf8 if !column.is_null(row) => column.as_string::<i64>().value(row),
+            _ => "",
+        };
+        match action {
+            "insert" => Ok(1),
+            "delete" => Ok(-1),
+            _ => Err((
+                "__action",
+                "invalid '__action' value; expected \"insert\" or \"delete\"".to_string(),
+            )),
+        }
+    } else if let Some(index) = weight_column {
+        match arrow_to_json(batch.column(index).as_ref(), row) {
+            Ok(JsonValue::Number(weight)) if weight.is_i64() => {
+                let weight = weight.as_i64().unwrap();
+                if (-MAX_DUPLICATES..=MAX_DUPLICATES).contains(&weight) {
+                    Ok(weight)
+                } else {
+                    Err((
+                        "__weight",
+                        format!("'__weight' value {weight} is out of range; its absolute value must not exceed {MAX_DUPLICATES}"),
+                    ))
+                }
+            }
+            _ => Err((
+                "__weight",
+                "invalid '__weight' value; expected an integer".to_string(),
+            )),
+        }
+    } else {
+        Ok(1)
+    }
+}
+
+/// Checks that the schema of a Parquet file is compatible with the columns
+/// of the input table and returns an error for each column that is not.
+fn check_schema(schema: &Schema, expected_fields: &[Field]) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    for expected in expected_fields {
+        let actual = schema
+            .fields()
+            .iter()
+            .find(|field| field.name().eq_ignore_ascii_case(expected.name()));
+        let error = match actual {
+            None if !expected.is_nullable() => {
+                format!(
+                    "column '{}' is missing from the Parquet file",
+                    expected.name()
+                )
+            }
+            Some(actual) if !compatible(actual.data_type(), expected.data_type()) => {
+                format!(
+                    "column '{}' has Arrow type {} in the Parquet file, which cannot be converted to Arrow type {} of the table column",
+                    expected.name(),
+                    actual.data_type(),
+                    expected.data_type()
+                )
+            }
+            _ => continue,
+        };
+        errors.push(ParseError::new(
+            error,
+            None,
+            Some(expected.name().clone()),
+            None,
+            None,
+            None,
+        ));
+    }
+    errors
+}
+
+/// Returns true if values of Arrow type `actual` can be ingested into a
+/// column whose SQL type maps to Arrow type `expected`.
+fn compatible(actual: &DataType, expected: &DataType) -> bool {
+    match (actual, expected) {
+        (DataType::Null, _) => true,
+        (DataType::List(actual) | DataType::LargeList(actual), DataType::List(expected)) => {
+            compatible(actual.data_type(), expected.data_type())
+        }
+        (actual, expected) if expected.is_integer() => actual.is_integer(),
+        (actual, expected) if expected.is_floating() => actual.is_numeric(),
+        (actual, DataType::Decimal128(_, _)) => actual.is_numeric(),
+        (DataType::Utf8 | DataType::LargeUtf8, DataType::Utf8) => true,
+        (DataType::Date32 | DataType::Date64, DataType::Date32) => true,
+        (DataType::Time32(_) | DataType::Time64(_), DataType::Time64(_)) => true,
+        (DataType::Timestamp(_, _), DataType::Timestamp(_, _)) => true,
+        (actual, expected) => actual == expected,
+    }
+}
+
+/// Converts the value in `row` of `array` to JSON in the encoding expected
+/// by the default JSON deserializer of input tables.
+fn arrow_to_json(array: &dyn Array, row: usize) -> Result<JsonValue, String> {
+    if array.is_null(row) {
+        return Ok(JsonValue::Null);
+    }
+
+    let out_of_range = || format!("value out of range for Arrow type {}", array.data_type());
+
+    Ok(match array.data_type() {
+        DataType::Null => JsonValue::Null,
+        DataType::Boolean => JsonValue::from(array.as_boolean().value(row)),
+        DataType::Int8 => JsonValue::from(array.as_primitive::<Int8Type>().value(row)),
+        DataType::Int16 => JsonValue::from(array.as_primitive::<Int16Type>().value(row)),
+        DataType::Int32 => JsonValue::from(array.as_primitive::<Int32Type>().value(row)),
+        DataType::Int64 => JsonValue::from(array.as_primitive::<Int64Type>().value(row)),
+        DataType::UInt8 => JsonValue::from(array.as_primitive::<UInt8Type>().value(row)),
+        DataType::UInt16 => JsonValue::from(array.as_primitive::<UInt16Type>().value(row)),
+        DataType::UInt32 => JsonValue::from(array.as_primitive::<UInt32Type>().value(row)),
+        DataType::UInt64 => JsonValue::from(array.as_primitive::<UInt64Type>().value(row)),
+        DataType::Float32 => JsonValue::from(array.as_primitive::<Float32Type>().value(row)),
+        DataType::Float64 => JsonValue::from(array.as_primitive::<Float64Type>().value(row)),
+        DataType::Decimal128(_, _) => {
+            JsonValue::from(array.as_primitive::<Decimal128Type>().value_as_string(row))
+        }
+        DataType::Decimal256(_, _) => {
+            JsonValue::from(array.as_primitive::<Decimal256Type>().value_as_string(row))
+        }
+        DataType::Utf8 => JsonValue::from(array.as_string::<i32>().value(row)),
+        DataType::LargeUtf8 => JsonValue::from(array.as_string::<i64>().value(row)),
+        DataType::Date32 => {
+            let date = array.as_primitive::<Date32Type>().value_as_date(row);
+            JsonValue::from(
+                date.ok_or_else(out_of_range)?
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            )
+        }
+        DataType::Date64 => {
+            let date = array.as_primitive::<Date64Type>().value_as_date(row);
+            JsonValue::from(
+                date.ok_or_else(out_of_range)?
+                    .format("%Y-%m-%d")
+                    .to_string(),
+            )
+        }
+        DataType::Time32(TimeUnit::Second) => {
+            let time = array.as_primitive::<Time32SecondType>().value_as_time(row);
+            JsonValue::from(
+                time.ok_or_else(out_of_range)?
+                    .format("%H:%M:%S")
+                    .to_string(),
+            )
+        }
+        DataType::Time32(TimeUnit::Millisecond) => {
+            let time = array
+                .as_primitive::<Time32MillisecondType>()
+                .value_as_time(row);
+            JsonValue::from(
+                time.ok_or_else(out_of_range)?
+                    .format("%H:%M:%S%.f")
+                    .to_string(),
+            )
+        }
+        DataType::Time64(TimeUnit::Microsecond) => {
+            let time = array
+                .as_primitive::<Time64MicrosecondType>()
+                .value_as_time(row);
+            JsonValue::from(
+                time.ok_or_else(out_of_range)?
+                    .format("%H:%M:%S%.f")
+                    .to_string(),
+            )
+        }
+        DataType::Time64(TimeUnit::Nanosecond) => {
+            let time = array
+                .as_primitive::<Time64NanosecondType>()
+                .value_as_time(row);
+            JsonValue::from(
+                time.ok_or_else(out_of_range)?
+                    .format("%H:%M:%S%.f")
+                    .to_string(),
+            )
+        }
+        DataType::Timestamp(unit, _) => {
+            let timestamp = match unit {
+                TimeUnit::Second => array
+                    .as_primitive::<TimestampSecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Millisecond => array
+                    .as_primitive::<TimestampMillisecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Microsecond => array
+                    .as_primitive::<TimestampMicrosecondType>()
+                    .value_as_datetime(row),
+                TimeUnit::Nanosecond => array
+                    .as_primitive::<TimestampNanosecondType>()
+                    .value_as_datetime(row),
+            };
+            JsonValue::from(
+                timestamp
+                    .ok_or_else(out_of_range)?
+                    .format("%F %T%.f")
+                    .to_string(),
+            )
+        }
+        DataType::List(_) => {
+            let list = array.as_list::<i32>().value(row);
+            JsonValue::Array(
+                (0..list.len())
+                    .map(|i| arrow_to_json(list.as_ref(), i))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        DataType::LargeList(_) => {
+            let list = array.as_list::<i64>().value(row);
+            JsonValue::Array(
+                (0..list.len())
+                    .map(|i| arrow_to_json(list.as_ref(), i))
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        data_type => return Err(format!("Arrow type {data_type} is not supported")),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        deserialize_table_record,
+        test::{mock_input_pipeline, mock_parser_pipeline, wait, DEFAULT_TIMEOUT_MS},
+        InputConsumer, ParseError,
+    };
+    use arrow::{
+        array::{
+            ArrayRef, Date32Array, Decimal128Array, Int64Array, ListArray, StringArray,
+            TimestampMillisecondArray,
+        },
+        datatypes::Int64Type,
+        record_batch::RecordBatch,
+    };
+    use parquet::arrow::ArrowWriter;
+    use pipeline_types::config::FormatConfig;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use std::{io::Write, sync::Arc};
+    use tempfile::NamedTempFile;
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[allow(non_snake_case)]
+    struct TestRecord {
+        ID: i64,
+        AMOUNT: Decimal,
+        DAY: String,
+        TS: String,
+        TAGS: Vec<i64>,
+        NAME: Option<String>,
+    }
+
+    deserialize_table_record!(TestRecord["TestRecord", 6] {
+        (ID, "ID", false, i64, None),
+        (AMOUNT, "AMOUNT", false, Decimal, None),
+        (DAY, "DAY", false, String, None),
+        (TS, "TS", false, String, None),
+        (TAGS, "TAGS", false, Vec<i64>, None),
+        (NAME, "NAME", false, Option<String>, Some(None))
+    });
+
+    fn test_batch() -> RecordBatch {
+        RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+            (
+                "amount",
+                Arc::new(
+                    Decimal128Array::from(vec![12345, -500])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ) as ArrayRef,
+            ),
+            // 2023-01-01 and 2024-02-29.
+            (
+                "day",
+                Arc::new(Date32Array::from(vec![19358, 19782])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMillisecondArray::from(vec![
+                    1672531200123,
+                    1709208000000,
+                ])) as ArrayRef,
+            ),
+            (
+                "tags",
+                Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                    Some(vec![Some(1), Some(2)]),
+                    Some(vec![]),
+                ])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![Some("foo"), None])) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    fn expected_records() -> Vec<TestRecord> {
+        vec![
+            TestRecord {
+                ID: 1,
+                AMOUNT: dec!(123.45),
+                DAY: "2023-01-01".to_string(),
+                TS: "2023-01-01 00:00:00.123".to_string(),
+                TAGS: vec![1, 2],
+                NAME: Some("foo".to_string()),
+            },
+            TestRecord {
+                ID: 2,
+                AMOUNT: dec!(-5.00),
+                DAY: "2024-02-29".to_string(),
+                TS: "2024-02-29 12:00:00".to_string(),
+                TAGS: vec![],
+                NAME: None,
+            },
+        ]
+    }
+
+    fn write_parquet(batch: &RecordBatch) -> Vec<u8> {
+        let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), None).unwrap();
+        writer.write(batch).unwrap();
+        writer.into_inner().unwrap()
+    }
+
+    /// Parses `file` and returns the parse errors as JSON values.
+    fn parse_errors(config: &str, file: &[u8]) -> Vec<serde_json::Value> {
+        let format_config: FormatConfig = serde_yaml::from_str(config).unwrap();
+        let (mut consumer, _zset) =
+            mock_parser_pipeline::<TestRecord, TestRecord>(&format_config).unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+        consumer.input_fragment(file);
+        consumer
+            .eoi()
+            .iter()
+            .map(|error: &ParseError| serde_json::to_value(error).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parquet_file() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&write_parquet(&test_batch())).unwrap();
+        temp_file.flush().unwrap();
+
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: file
+    config:
+        path: {:?}
+        buffer_size_bytes: 100
+format:
+    name: parquet
+"#,
+            temp_file.path().to_str().unwrap()
+        );
+
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestRecord, TestRecord>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+
+        endpoint.start(0).unwrap();
+        let expected = expected_records();
+        wait(
+            || zset.state().flushed.len() == expected.len(),
+            DEFAULT_TIMEOUT_MS,
+        );
+        assert!(consumer.state().eoi);
+        for (upd, expected) in zset.state().flushed.iter().zip(expected.iter()) {
+            assert_eq!(upd.unwrap_insert(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parquet_actions() {
+        let batch = test_batch();
+        let mut columns = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .zip(batch.columns().iter().cloned())
+            .collect::<Vec<_>>();
+        columns.push((
+            "__action".to_string(),
+            Arc::new(StringArray::from(vec!["delete", "insert"])) as ArrayRef,
+        ));
+        let file = write_parquet(&RecordBatch::try_from_iter(columns).unwrap());
+
+        let format_config: FormatConfig = serde_yaml::from_str("name: parquet").unwrap();
+        let (mut consumer, zset) =
+            mock_parser_pipeline::<TestRecord, TestRecord>(&format_config).unwrap();
+        consumer.input_fragment(&file);
+        assert!(consumer.eoi().is_empty());
+
+        let expected = expected_records();
+        let flushed = &zset.state().flushed;
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].unwrap_delete(), &expected[0]);
+        assert_eq!(flushed[1].unwrap_insert(), &expected[1]);
+    }
+
+    #[test]
+    fn test_parquet_weights() {
+        let batch = test_batch();
+        let mut columns = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .zip(batch.columns().iter().cloned())
+            .collect::<Vec<_>>();
+        columns.push((
+            "__weight".to_string(),
+            Arc::new(Int64Array::from(vec![2, i64::MAX])) as ArrayRef,
+        ));
+        let file = write_parquet(&RecordBatch::try_from_iter(columns).unwrap());
+
+        let format_config: FormatConfig = serde_yaml::from_str("name: parquet").unwrap();
+        let (mut consumer, zset) =
+            mock_parser_pipeline::<TestRecord, TestRecord>(&format_config).unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+        consumer.input_fragment(&file);
+        let errors = consumer.eoi();
+
+        // A weight that is too large to expand into copies of the record is
+        // rejected.
+        assert_eq!(errors.len(), 1);
+        let error = serde_json::to_value(&errors[0]).unwrap();
+        assert_eq!(error["event_number"], 2);
+        assert_eq!(error["field"], "__weight");
+
+        let expected = expected_records();
+        let flushed = &zset.state().flushed;
+        assert_eq!(flushed.len(), 2);
+        assert_eq!(flushed[0].unwrap_insert(), &expected[0]);
+        assert_eq!(flushed[1].unwrap_insert(), &expected[0]);
+    }
+
+    #[test]
+    fn test_parquet_row_errors() {
+        // `id` cannot be parsed as a BIGINT in either row.
+        let batch = RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(StringArray::from(vec!["abc", "def"])) as ArrayRef,
+            ),
+            (
+                "amount",
+                Arc::new(StringArray::from(vec!["1.5", "2.5"])) as ArrayRef,
+            ),
+            (
+                "day",
+                Arc::new(StringArray::from(vec!["2023-01-01", "x"])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(StringArray::from(vec!["x", "y"])) as ArrayRef,
+            ),
+            (
+                "tags",
+                Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+                    Some(vec![]),
+                    Some(vec![]),
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let errors = parse_errors("name: parquet", &write_parquet(&batch));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["event_number"], 1);
+        assert_eq!(errors[0]["field"], "ID");
+        assert_eq!(errors[1]["event_number"], 2);
+        assert_eq!(errors[1]["field"], "ID");
+    }
+
+    #[test]
+    fn test_parquet_schema_check() {
+        let config = r#"
+name: parquet
+config:
+    relation_schema:
+        name: test_input
+        fields:
+            - name: ID
+              columntype: { type: BIGINT, nullable: false }
+            - name: DAY
+              columntype: { type: DATE, nullable: false }
+            - name: NAME
+              columntype: { type: VARCHAR, nullable: true }
+            - name: MISSING
+              columntype: { type: INTEGER, nullable: false }
+            - name: OPTIONAL
+              columntype: { type: INTEGER, nullable: true }
+"#;
+        let batch = RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![1])) as ArrayRef),
+            ("day", Arc::new(Int64Array::from(vec![19358])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec!["foo"])) as ArrayRef),
+        ])
+        .unwrap();
+
+        let errors = parse_errors(config, &write_parquet(&batch));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0]["field"], "DAY");
+        assert_eq!(errors[1]["field"], "MISSING");
+    }
+
+    #[test]
+    fn test_parquet_invalid_file() {
+        let errors = parse_errors("name: parquet", b"not a parquet file");
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("failed to read Parquet file"));
+    }
+}
@@ -0,0 +1,103 @@
+use super::DEFAULT_DECIMAL_PRECISION;
+use anyhow::{anyhow, bail, Result as AnyResult};
+use arrow::datatypes::{DataType, Field, TimeUnit, DECIMAL128_MAX_PRECISION};
+use pipeline_types::program_schema::{ColumnType, Relation};
+use std::sync::Arc;
+
+mod input;
+mod output;
+
+pub use input::ParquetInputFormat;
+pub use output::ParquetOutputFormat;
+
+/// Derives the Arrow schema of a Parquet file from the schema of a SQL
+/// relation.
+pub(crate) fn relation_to_arrow_fields(relation: &Relation) -> AnyResult<Vec<Field>> {
+    relation
+        .fields
+        .iter()
+        .map(|field| {
+            let data_type = columntype_to_datatype(&field.columntype)
+                .map_err(|e| anyhow!("column '{}': {e}", field.name))?;
+            Ok(Field::new(
+                &field.name,
+                data_type,
+                field.columntype.nullable,
+            ))
+        })
+        .collect()
+}
+
+/// Maps a SQL column type, as it appears in the program schema, to an Arrow
+/// data type.
+fn columntype_to_datatype(columntype: &ColumnType) -> AnyResult<DataType> {
+    Ok(match columntype.typ.to_ascii_uppercase().as_str() {
+        "BOOLEAN" => DataType::Boolean,
+        "TINYINT" => DataType::Int8,
+        "SMALLINT" => DataType::Int16,
+        "INTEGER" => DataType::Int32,
+        "BIGINT" => DataType::Int64,
+        "REAL" => DataType::Float32,
+        "FLOAT" | "DOUBLE" => DataType::Float64,
+        "DECIMAL" => {
+            let precision = match columntype.precision {
+                Some(precision) if precision > 0 => precision,
+                _ => DEFAULT_DECIMAL_PRECISION.into(),
+            };
+            if precision > DECIMAL128_MAX_PRECISION.into() {
+                bail!("DECIMAL precision {precision} exceeds the maximum precision {DECIMAL128_MAX_PRECISION} supported by the Parquet format");
+            }
+            let scale = columntype.scale.unwrap_or(0) as i8;
+            DataType::Decimal128(precision as u8, scale)
+        }
+        "CHAR" | "VARCHAR" => DataType::Utf8,
+        "DATE" => DataType::Date32,
+        "TIME" => DataType::Time64(TimeUnit::Nanosecond),
+        "TIMESTAMP" => DataType::Timestamp(TimeUnit::Millisecond, None),
+        "ARRAY" => {
+            let Some(component) = &columntype.component else {
+                bail!("ARRAY type without a component type");
+            };
+            DataType::List(Arc::new(Field::new(
+                "item",
+                columntype_to_datatype(component)?,
+                component.nullable,
+            )))
+        }
+        typ => bail!("SQL type '{typ}' is not supported by the Parquet format"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::columntype_to_datatype;
+    use arrow::datatypes::DataType;
+    use pipeline_types::program_schema::ColumnType;
+
+    fn decimal(precision: Option<i64>, scale: Option<i64>) -> ColumnType {
+        ColumnType {
+            typ: "DECIMAL".to_string(),
+            nullable: false,
+            precision,
+            scale,
+            component: None,
+        }
+    }
+
+    #[test]
+    fn test_decimal_precision() {
+        assert_eq!(
+            columntype_to_datatype(&decimal(None, None)).unwrap(),
+            DataType::Decimal128(28, 0)
+        );
+        assert_eq!(
+            columntype_to_datatype(&decimal(Some(38), Some(2))).unwrap(),
+            DataType::Decimal128(38, 2)
+        );
+        assert!(columntype_to_datatype(&decimal(Some(39), Some(2)))
+            .unwrap_err()
+            .to_string()
+            .contains("exceeds the maximum precision 38"));
+        assert!(columntype_to_datatype(&decimal(Some(300), None)).is_err());
+    }
+}
@@ -0,0 +1,479 @@
+use super::relation_to_arrow_fields;
+use crate::{
+    catalog::{CursorWithPolarity, RecordFormat, SerBatch, SerCursor},
+    format::reject_keyed_consumer,
+    ControllerError, Encoder, OutputConsumer, OutputFormat,
+};
+use actix_web::HttpRequest;
+use anyhow::{bail, Result as AnyResult};
+use arrow::{
+    array::{ArrayRef, Int64Array, StringArray},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    json::{reader::Decoder as JsonDecoder, ReaderBuilder as JsonReaderBuilder},
+    record_batch::RecordBatch,
+};
+use erased_serde::Serialize as ErasedSerialize;
+use parquet::arrow::ArrowWriter;
+use pipeline_types::format::{json::JsonFlavor, parquet::ParquetEncoderConfig, UpdateFormat};
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, sync::Arc};
+
+/// The largest weight of a record that can be output using the
+/// `insert_delete` update format, which requires duplicating the record `w`
+/// times.
+const MAX_DUPLICATES: i64 = 1_000_000;
+
+/// Parquet format encoder.
+pub struct ParquetOutputFormat;
+
+impl OutputFormat for ParquetOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("parquet")
+    }
+
+    fn config_from_http_request(
+        &self,
+        endpoint_name: &str,
+        _request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        // The encoder requires the schema of the output relation, which
+        // cannot be passed in a URL query string.
+        Err(ControllerError::output_format_not_supported(
+            endpoint_name,
+            "the Parquet format is not supported for HTTP output endpoints",
+        ))
+    }
+
+    fn new_encoder(
+        &self,
+        endpoint_name: &str,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> Result<Box<dyn Encoder>, ControllerError> {
+        reject_keyed_consumer(endpoint_name, "Parquet", consumer.as_ref())?;
+
+        let config = ParquetEncoderConfig::deserialize(config).map_err(|e| {
+            ControllerError::encoder_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+
+        Ok(Box::new(ParquetEncoder::new(consumer, config).map_err(
+            |e| ControllerError::output_format_not_supported(endpoint_name, &e.to_string()),
+        )?))
+    }
+}
+
+struct ParquetEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    config: ParquetEncoderConfig,
+
+    /// Arrow schema of the Parquet file: the columns of the output relation
+    /// followed by the `__action` or `__weight` column.
+    schema: SchemaRef,
+
+    /// Decodes the records of the current row group, which the cursor
+    /// serializes as JSON, into Arrow arrays.
+    decoder: JsonDecoder,
+
+    /// Records in the current row group, as newline-delimited JSON.
+    buffer: Vec<u8>,
+
+    /// Weights of the records in `buffer`.
+    weights: Vec<i64>,
+
+    max_buffer_size: usize,
+}
+
+impl ParquetEncoder {
+    fn new(
+        output_consumer: Box<dyn OutputConsumer>,
+        config: ParquetEncoderConfig,
+    ) -> AnyResult<Self> {
+        let max_buffer_size = output_consumer.max_buffer_size_bytes();
+
+        let mut fields = relation_to_arrow_fields(&config.relation_schema)?;
+        let decoder = JsonReaderBuilder::new(Arc::new(Schema::new(fields.clone())))
+            .with_batch_size(config.buffer_size_records.max(1))
+            .build_decoder()?;
+
+        fields.push(match config.update_format {
+            UpdateFormat::InsertDelete => Field::new("__action", DataType::Utf8, false),
+            UpdateFormat::Weighted => Field::new("__weight", DataType::Int64, false),
+        });
+
+        Ok(Self {
+            output_consumer,
+            config,
+            schema: Arc::new(Schema::new(fields)),
+            decoder,
+            buffer: Vec::new(),
+            weights: Vec::new(),
+            max_buffer_size,
+        })
+    }
+
+    /// Writes the buffered records to `writer` as a row group, creating the
+    /// writer if this is the first row group in the file.
+    fn write_row_group(&mut self, writer: &mut Option<ArrowWriter<Vec<u8>>>) -> AnyResult<()> {
+        self.decoder.decode(&self.buffer)?;
+        let Some(batch) = self.decoder.flush()? else {
+            return Ok(());
+        };
+
+        let mut columns = batch.columns().to_vec();
+        columns.push(match self.config.update_format {
+            UpdateFormat::InsertDelete => Arc::new(StringArray::from_iter_values(
+                self.weights
+                    .iter()
+                    .map(|&w| if w > 0 { "insert" } else { "delete" }),
+            )) as ArrayRef,
+            UpdateFormat::Weighted => Arc::new(Int64Array::from(self.weights.clone())),
+        });
+        let batch = RecordBatch::try_new(self.schema.clone(), columns)?;
+
+        if writer.is_none() {
+            *writer = Some(ArrowWriter::try_new(Vec::new(), self.schema.clone(), None)?);
+        }
+        let writer = writer.as_mut().unwrap();
+        writer.write(&batch)?;
+        writer.flush()?;
+
+        self.buffer.clear();
+        self.weights.clear();
+        Ok(())
+    }
+}
+
+impl Encoder for ParquetEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        // Each call produces a complete Parquet file, so that every buffer
+        // pushed to the transport can be read on its own.
+        let mut writer = None;
+        self.buffer.clear();
+        self.weights.clear();
+
+        for batch in batches.iter() {
+            let mut cursor =
+                CursorWithPolarity::new(batch.cursor(RecordFormat::Json(JsonFlavor::Default))?);
+
+            while cursor.key_valid() {
+                if !cursor.val_valid() {
+                    cursor.step_key();
+                    continue;
+                }
+                let w = cursor.weight();
+
+                let copies = match self.config.update_format {
+                    UpdateFormat::InsertDelete => {
+                        if !(-MAX_DUPLICATES..=MAX_DUPLICATES).contains(&w) {
+                            let mut key_str = String::new();
+                            let _ = cursor.serialize_key(unsafe { key_str.as_mut_vec() });
+                            bail!(
+                                "Unable to output record '{}' with very large weight {w}. Consider adjusting your SQL queries to avoid duplicate output records, e.g., using 'SELECT DISTINCT', or use the 'weighted' update format.",
+                                &key_str
+                            );
+                        }
+                        w.abs()
+                    }
+                    UpdateFormat::Weighted => 1,
+                };
+
+                for _ in 0..copies {
+                    cursor.serialize_key(&mut self.buffer)?;
+                    self.buffer.push(b'\n');
+                    self.weights.push(w);
+
+                    if self.weights.len() >= self.config.buffer_size_records {
+                        self.write_row_group(&mut writer)?;
+                    }
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        if !self.weights.is_empty() {
+            self.write_row_group(&mut writer)?;
+        }
+
+        if let Some(writer) = writer {
+            let file = writer.into_inner()?;
+            if file.len() > self.max_buffer_size {
+                bail!("Parquet file exceeds maximum buffer size supported by the output transport. Max supported buffer size is {} bytes, but the file requires {} bytes.",
+                      self.max_buffer_size,
+                      file.len());
+            }
+            self.output_consumer.push_buffer(&file);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ParquetEncoder;
+    use crate::{
+        catalog::SerBatch,
+        format::Encoder,
+        static_compile::seroutput::SerBatchImpl,
+        test::{generate_test_batches_with_weights, TestStruct},
+        transport::{OutputEndpoint, OutputTransport, Step},
+        OutputConsumer,
+    };
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Int32Type, Int64Type};
+    use dbsp::{trace::Batch, OrdZSet};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use pipeline_types::{
+        config::OutputEndpointConfig,
+        format::{parquet::ParquetEncoderConfig, UpdateFormat},
+        program_schema::{ColumnType, Field, Relation},
+    };
+    use proptest::prelude::*;
+    use std::{fs::File, sync::Arc};
+    use tempfile::NamedTempFile;
+
+    /// Passes encoded buffers to an output transport endpoint.
+    struct EndpointConsumer(Box<dyn OutputEndpoint>);
+
+    impl OutputConsumer for EndpointConsumer {
+        fn max_buffer_size_bytes(&self) -> usize {
+            self.0.max_buffer_size_bytes()
+        }
+
+        fn batch_start(&mut self, step: Step) {
+            self.0.batch_start(step).unwrap();
+        }
+
+        fn push_buffer(&mut self, buffer: &[u8]) {
+            self.0.push_buffer(buffer).unwrap();
+        }
+
+        fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) {
+            self.0.push_key(key, val).unwrap();
+        }
+
+        fn batch_end(&mut self) {
+            self.0.batch_end().unwrap();
+        }
+    }
+
+    fn column(name: &str, typ: &str, nullable: bool) -> Field {
+        Field {
+            name: name.to_string(),
+            columntype: ColumnType {
+                typ: typ.to_string(),
+                nullable,
+                precision: None,
+                scale: None,
+                component: None,
+            },
+        }
+    }
+
+    fn test_struct_schema() -> Relation {
+        Relation {
+            name: "test_output".to_string(),
+            fields: vec![
+                column("id", "INTEGER", false),
+                column("b", "BOOLEAN", false),
+                column("i", "BIGINT", true),
+                column("s", "VARCHAR", false),
+            ],
+        }
+    }
+
+    /// Encodes `batches` into a file using the `file` output transport and
+    /// reads the file back as a list of records and their `__action` or
+    /// `__weight` column values.
+    fn encode_to_file(
+        update_format: UpdateFormat,
+        buffer_size_records: usize,
+        batches: &[Vec<(TestStruct, i64)>],
+    ) -> Vec<(TestStruct, i64)> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config_str = format!(
+            r#"
+stream: test_output
+transport:
+    name: file
+    config:
+        path: {:?}
+format:
+    name: parquet
+"#,
+            temp_file.path().to_str().unwrap(),
+        );
+        let endpoint_config: OutputEndpointConfig = serde_yaml::from_str(&config_str).unwrap();
+        let endpoint = <dyn OutputTransport>::get_transport("file")
+            .unwrap()
+            .new_endpoint(&endpoint_config)
+            .unwrap();
+
+        let config = ParquetEncoderConfig {
+            buffer_size_records,
+            update_format: update_format.clone(),
+            relation_schema: test_struct_schema(),
+        };
+        let mut encoder =
+            ParquetEncoder::new(Box::new(EndpointConsumer(endpoint)), config).unwrap();
+        let zsets = batches
+            .iter()
+            .map(|batch| {
+                let zset = OrdZSet::from_keys((), batch.clone());
+                Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>
+            })
+            .collect::<Vec<_>>();
+        encoder.encode(zsets.as_slice()).unwrap();
+        drop(encoder);
+
+        let file = File::open(temp_file.path()).unwrap();
+        if file.metadata().unwrap().len() == 0 {
+            return Vec::new();
+        }
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert!(reader
+            .metadata()
+            .row_groups()
+            .iter()
+            .all(|row_group| row_group.num_rows() as usize <= buffer_size_records));
+
+        let mut result = Vec::new();
+        for batch in reader.build().unwrap() {
+            let batch = batch.unwrap();
+            let id = batch.column(0).as_primitive::<Int32Type>();
+            let b = batch.column(1).as_boolean();
+            let i = batch.column(2).as_primitive::<Int64Type>();
+            let s = batch.column(3).as_string::<i32>();
+            for row in 0..batch.num_rows() {
+                let record = TestStruct {
+                    id: id.value(row) as u32,
+                    b: b.value(row),
+                    i: (!i.is_null(row)).then(|| i.value(row)),
+                    s: s.value(row).to_string(),
+                };
+                let weight = match update_format {
+                    UpdateFormat::InsertDelete => {
+                        match batch.column(4).as_string::<i32>().value(row) {
+                            "insert" => 1,
+                            "delete" => -1,
+                            action => panic!("unexpected action '{action}'"),
+                        }
+                    }
+                    UpdateFormat::Weighted => {
+                        batch.column(4).as_primitive::<Int64Type>().value(row)
+                    }
+                };
+                result.push((record, weight));
+            }
+        }
+        result
+    }
+
+    fn test_parquet(update_format: UpdateFormat, batches: Vec<Vec<(TestStruct, i64)>>) {
+        let mut expected = Vec::new();
+        for (record, w) in batches.iter().flatten().filter(|(_, w)| *w != 0) {
+            match update_format {
+                UpdateFormat::InsertDelete => {
+                    for _ in 0..w.abs() {
+                        expected.push((record.clone(), w.signum()));
+                    }
+                }
+                UpdateFormat::Weighted => expected.push((record.clone(), *w)),
+            }
+        }
+        expected.sort();
+
+        let mut actual = encode_to_file(update_format, 3, &batches);
+        actual.sort();
+
+        assert_eq!(actual, expected);
+    }
+
+    fn test_data() -> Vec<Vec<(TestStruct, i64)>> {
+        vec![
+            vec![
+                (
+                    TestStruct {
+                        id: 0,
+                        b: true,
+                        i: None,
+                        s: "foo".to_string(),
+                    },
+                    1,
+                ),
+                (
+                    TestStruct {
+                        id: 1,
+                        b: false,
+                        i: Some(10),
+                        s: "bar".to_string(),
+                    },
+                    -1,
+                ),
+            ],
+            vec![
+                (
+                    TestStruct {
+                        id: 2,
+                        b: true,
+                        i: Some(-5),
+                        s: "".to_string(),
+                    },
+                    2,
+                ),
+                (
+                    TestStruct {
+                        id: 3,
+                        b: false,
+                        i: None,
+                        s: "buzz".to_string(),
+                    },
+                    -3,
+                ),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_parquet_insert_delete() {
+        test_parquet(UpdateFormat::InsertDelete, test_data());
+    }
+
+    #[test]
+    fn test_parquet_weighted() {
+        test_parquet(UpdateFormat::Weighted, test_data());
+    }
+
+    #[test]
+    fn test_parquet_empty() {
+        assert!(encode_to_file(UpdateFormat::Weighted, 3, &[vec![]]).is_empty());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(30))]
+
+        #[test]
+        fn proptest_parquet_insert_delete(data in generate_test_batches_with_weights(10, 20))
+        {
+            test_parquet(UpdateFormat::InsertDelete, data)
+        }
+
+        #[test]
+        fn proptest_parquet_weighted(data in generate_test_batches_with_weights(10, 20))
+        {
+            test_parquet(UpdateFormat::Weighted, data)
+        }
+    }
+}
@@ -192,14 +192,19 @@ pub use catalog::{
 pub use format::{Encoder, InputFormat, OutputConsumer, OutputFormat, ParseError, Parser};
 
 pub use controller::{
-    ConfigError, ConnectorConfig, Controller, ControllerError, ControllerStatus, FormatConfig,
-    InputEndpointConfig, OutputEndpointConfig, PipelineConfig, RuntimeConfig, TransportConfig,
+    ConfigError, ConnectorConfig, Controller, ControllerError, ControllerStatus, DeadLetterConfig,
+    FormatConfig, InputEndpointConfig, OutputEndpointConfig, PipelineConfig, RuntimeConfig,
+    TransportConfig,
 };
 pub use transport::{
-    AsyncErrorCallback, FileInputTransport, InputConsumer, InputEndpoint, InputReader,
-    InputTransport, OutputEndpoint, OutputTransport,
+    AsyncErrorCallback, DirectoryInputTransport, FileInputTransport, InputConsumer, InputEndpoint,
+    InputReader, InputTransport, OutputEndpoint, OutputTransport, S3InputTransport,
+    S3OutputTransport,
 };
 
+#[cfg(feature = "with-postgres")]
+pub use transport::{PostgresCdcInputTransport, PostgresOutputTransport};
+
 pub use static_compile::{
     DateFormat, DeserializationContext, DeserializeWithContext, FieldParseError,
     SerializationContext, SerializeWithContext, SqlSerdeConfig, TimeFormat, TimestampFormat,
@@ -14,9 +14,9 @@
 //!   variants, it embeds:
 //!
 //!    * [`controller::ControllerError`](ControllerError), for errors that arise
-//!    from operating a streaming pipeline consisting of input adapters, output
-//!    adapters, and a DBSP circuit, via the controller API.  In some variants,
-//!    it embeds:
+//!      from operating a streaming pipeline consisting of input adapters,
+//!      output adapters, and a DBSP circuit, via the controller API.  In some
+//!      variants, it embeds:
 //!
 //!      * [`dbsp::Error`], which indicates invalid pipeline or endpoint
 //!        configuration.
@@ -166,6 +166,16 @@ pub enum PipelineError {
         parse_error: String,
     },
     NeighborhoodNotSupported,
+    SelectStreamingNotSupported,
+    MissingSelectQuery,
+    InvalidSelectQuery {
+        query: JsonValue,
+        error: String,
+    },
+    SelectNotSupported,
+    WebSocketHandshake {
+        error: String,
+    },
     ControllerError {
         // Fold `ControllerError` directly into `PipelineError` to simplify
         // the error hierarchy from the user's pespective.
@@ -230,6 +240,21 @@ impl Display for PipelineError {
             Self::NeighborhoodNotSupported => {
                 f.write_str("Neighborhood queries are not supported for this table.")
             }
+            Self::SelectStreamingNotSupported => {
+                f.write_str("Continuous monitoring is not supported for select queries. Use '?mode=snapshot' to evaluate the query over the current contents of the table.")
+            }
+            Self::MissingSelectQuery => {
+                f.write_str(r#"Select request must specify the query in the body of the request: '{"filter": [{"column": "id", "op": "eq", "value": 1}], "columns": ["id", "name"], "limit": 100}'."#)
+            }
+            Self::InvalidSelectQuery{query, error} => {
+                write!(f, "Invalid select query '{query}': '{error}'.")
+            }
+            Self::SelectNotSupported => {
+                f.write_str("Select queries are not supported for this table.")
+            }
+            Self::WebSocketHandshake{error} => {
+                write!(f, "WebSocket handshake failed: '{error}'.")
+            }
             Self::ControllerError{ error } => {
                 error.fmt(f)
             }
@@ -266,6 +291,11 @@ impl DetailedError for PipelineError {
             Self::TableSnapshotNotImplemented => Cow::from("TableSnapshotNotImplemented"),
             Self::MissingNeighborhoodSpec => Cow::from("MissingNeighborhoodSpec"),
             Self::NeighborhoodNotSupported => Cow::from("NeighborhoodNotSupported"),
+            Self::SelectStreamingNotSupported => Cow::from("SelectStreamingNotSupported"),
+            Self::MissingSelectQuery => Cow::from("MissingSelectQuery"),
+            Self::InvalidSelectQuery { .. } => Cow::from("InvalidSelectQuery"),
+            Self::SelectNotSupported => Cow::from("SelectNotSupported"),
+            Self::WebSocketHandshake { .. } => Cow::from("WebSocketHandshake"),
             Self::NumQuantilesOutOfRange { .. } => Cow::from("NumQuantilesOutOfRange"),
             Self::InvalidNeighborhoodSpec { .. } => Cow::from("InvalidNeighborhoodSpec"),
             Self::ParseErrors { .. } => Cow::from("ParseErrors"),
@@ -319,6 +349,11 @@ impl ResponseError for PipelineError {
             Self::TableSnapshotNotImplemented => StatusCode::NOT_IMPLEMENTED,
             Self::MissingNeighborhoodSpec => StatusCode::BAD_REQUEST,
             Self::NeighborhoodNotSupported => StatusCode::METHOD_NOT_ALLOWED,
+            Self::SelectStreamingNotSupported => StatusCode::METHOD_NOT_ALLOWED,
+            Self::MissingSelectQuery => StatusCode::BAD_REQUEST,
+            Self::InvalidSelectQuery { .. } => StatusCode::BAD_REQUEST,
+            Self::SelectNotSupported => StatusCode::METHOD_NOT_ALLOWED,
+            Self::WebSocketHandshake { .. } => StatusCode::BAD_REQUEST,
             Self::NumQuantilesOutOfRange { .. } => StatusCode::RANGE_NOT_SATISFIABLE,
             Self::InvalidNeighborhoodSpec { .. } => StatusCode::BAD_REQUEST,
             Self::ParseErrors { .. } => StatusCode::BAD_REQUEST,
@@ -1,6 +1,6 @@
 use crate::{
     catalog::RecordFormat,
-    controller::ConnectorConfig,
+    controller::{ConnectorConfig, EndpointId},
     transport::http::{
         HttpInputEndpoint, HttpInputTransport, HttpOutputEndpoint, HttpOutputTransport,
     },
@@ -16,13 +16,17 @@ use actix_web::{
     web::{Data as WebData, Json, Payload, Query},
     App, Error as ActixError, HttpRequest, HttpResponse, HttpServer, Responder,
 };
+use actix_ws::{MessageStream, Session};
 use clap::Parser;
 use colored::Colorize;
 use dbsp::operator::sample::MAX_QUANTILES;
 use env_logger::Env;
 use log::{debug, error, info, warn};
 use pipeline_types::{format::json::JsonFlavor, transport::http::EgressMode};
-use pipeline_types::{query::OutputQuery, transport::http::SERVER_PORT_FILE};
+use pipeline_types::{
+    query::{OutputQuery, SelectQuery},
+    transport::http::SERVER_PORT_FILE,
+};
 use serde::Deserialize;
 use serde_json::{json, Value as JsonValue};
 use std::io::Write;
@@ -145,8 +149,7 @@ pub struct ServerArgs {
 /// # Arguments
 ///
 /// * `circuit_factory` - a function that creates a circuit and builds an
-///   input/output stream
-/// catalog.
+///   input/output stream catalog.
 pub fn server_main<F>(circuit_factory: F) -> Result<(), ControllerError>
 where
     F: FnOnce(
@@ -417,6 +420,8 @@ where
         .service(dump_profile)
         .service(input_endpoint)
         .service(output_endpoint)
+        .service(input_websocket)
+        .service(output_websocket)
 }
 
 #[get("/start")]
@@ -536,6 +541,73 @@ async fn input_endpoint(
     payload: Payload,
 ) -> impl Responder {
     debug!("{req:?}");
+    let (endpoint, endpoint_id) = connect_input_endpoint(&state, &req, &args)?;
+
+    // Call endpoint to complete request.
+    let response = endpoint.complete_request(payload).await;
+    drop(endpoint);
+
+    // Delete endpoint on completion/error.
+    disconnect_input_endpoint(&state, &endpoint_id);
+
+    response
+}
+
+/// Streams input to a table over a WebSocket.
+///
+/// Each message carries complete records in the format selected by the
+/// `format` argument.  The server acknowledges each message with a JSON
+/// object that holds its `sequence_number`, the `step` that includes it, and
+/// any `parse_errors`.
+#[get("/ws/ingress/{table_name}")]
+async fn input_websocket(
+    state: WebData<ServerState>,
+    req: HttpRequest,
+    args: Query<IngressArgs>,
+    payload: Payload,
+) -> impl Responder {
+    debug!("{req:?}");
+    let (response, session, messages) =
+        actix_ws::handle(&req, payload).map_err(|e| PipelineError::WebSocketHandshake {
+            error: e.to_string(),
+        })?;
+    let (endpoint, endpoint_id) = connect_input_endpoint(&state, &req, &args)?;
+
+    // Use a downgraded reference to `state`, so the session doesn't prevent
+    // the controller from shutting down.
+    let weak_state = Arc::downgrade(&state.into_inner());
+    let request_step_state = weak_state.clone();
+    rt::spawn(async move {
+        endpoint
+            .complete_websocket(
+                session,
+                messages,
+                Box::new(move || {
+                    if let Some(state) = request_step_state.upgrade() {
+                        if let Some(controller) = state.controller.lock().unwrap().as_ref() {
+                            controller.request_step();
+                        }
+                    }
+                }),
+            )
+            .await;
+        drop(endpoint);
+
+        if let Some(state) = weak_state.upgrade() {
+            disconnect_input_endpoint(&state, &endpoint_id);
+        }
+    });
+
+    Ok::<_, PipelineError>(response)
+}
+
+/// Creates an HTTP input endpoint for the table named in `req` and connects
+/// it to the controller.
+fn connect_input_endpoint(
+    state: &ServerState,
+    req: &HttpRequest,
+    args: &IngressArgs,
+) -> Result<(HttpInputEndpoint, EndpointId), PipelineError> {
     let table_name = match req.match_info().get("table_name") {
         None => {
             return Err(PipelineError::MissingUrlEncodedParam {
@@ -557,8 +629,13 @@ async fn input_endpoint(
         stream: Cow::from(table_name),
         connector_config: ConnectorConfig {
             transport: HttpInputTransport::config(),
-            format: parser_config_from_http_request(&endpoint_name, &args.format, &req)?,
+            format: parser_config_from_http_request(&endpoint_name, &args.format, req)?,
             max_buffered_records: HttpInputTransport::default_max_buffered_records(),
+            dead_letter: None,
+            max_parse_errors: None,
+            max_records_per_second: None,
+            max_bytes_per_second: None,
+            weight: 1,
         },
     };
 
@@ -583,21 +660,19 @@ async fn input_endpoint(
             }
         }
         None => {
-            return Err(missing_controller_error(&state));
+            return Err(missing_controller_error(state));
         }
     };
 
-    // Call endpoint to complete request.
-    let response = endpoint.complete_request(payload).await;
-    drop(endpoint);
+    Ok((endpoint, endpoint_id))
+}
 
-    // Delete endpoint on completion/error.
+/// Disconnects an input endpoint created by [`connect_input_endpoint`].
+fn disconnect_input_endpoint(state: &ServerState, endpoint_id: &EndpointId) {
     if let Some(controller) = state.controller.lock().unwrap().as_ref() {
-        controller.disconnect_input(&endpoint_id);
+        controller.disconnect_input(endpoint_id);
         controller.unregister_api_connection();
     }
-
-    response
 }
 
 /// Create an instance of `FormatConfig` from format name and
@@ -672,9 +747,41 @@ async fn output_endpoint(
     body: Option<Json<JsonValue>>,
 ) -> impl Responder {
     debug!("/egress request:{req:?}");
+    connect_output_endpoint(state.into_inner(), &req, &args, body, None)
+}
 
-    let state = state.into_inner();
+/// Streams the output of a query over a WebSocket.
+///
+/// Each output chunk is sent as a text message.  The client controls the
+/// flow of chunks by sending `{"credit": N}` messages, each of which permits
+/// the server to send `N` more chunks.  While the client withholds credit,
+/// the pipeline stalls rather than dropping output.
+#[get("/ws/egress/{table_name}")]
+async fn output_websocket(
+    state: WebData<ServerState>,
+    req: HttpRequest,
+    args: Query<EgressArgs>,
+    payload: Payload,
+) -> impl Responder {
+    debug!("/ws/egress request:{req:?}");
+    let websocket =
+        actix_ws::handle(&req, payload).map_err(|e| PipelineError::WebSocketHandshake {
+            error: e.to_string(),
+        })?;
+    connect_output_endpoint(state.into_inner(), &req, &args, None, Some(websocket))
+}
 
+/// Creates an HTTP output endpoint for the query described by `req`, `args`
+/// and `body`, connects it to the controller, and returns the response that
+/// streams its output, either in the body of the response or, if
+/// `websocket` is specified, over the WebSocket.
+fn connect_output_endpoint(
+    state: Arc<ServerState>,
+    req: &HttpRequest,
+    args: &EgressArgs,
+    body: Option<Json<JsonValue>>,
+    websocket: Option<(HttpResponse, Session, MessageStream)>,
+) -> Result<HttpResponse, PipelineError> {
     let table_name = match req.match_info().get("table_name") {
         None => {
             return Err(PipelineError::MissingUrlEncodedParam {
@@ -689,6 +796,9 @@ async fn output_endpoint(
         (EgressMode::Watch, OutputQuery::Quantiles) => {
             return Err(PipelineError::QuantileStreamingNotSupported);
         }
+        (EgressMode::Watch, OutputQuery::Select) => {
+            return Err(PipelineError::SelectStreamingNotSupported);
+        }
         (EgressMode::Snapshot, OutputQuery::Table) => {
             return Err(PipelineError::TableSnapshotNotImplemented);
         }
@@ -707,6 +817,31 @@ async fn output_endpoint(
         });
     }
 
+    // Parse the ad hoc query.
+    let select = if args.query == OutputQuery::Select {
+        let body = body
+            .as_ref()
+            .map(|body| body.0.clone())
+            .ok_or(PipelineError::MissingSelectQuery)?;
+        let select =
+            SelectQuery::deserialize(&body).map_err(|e| PipelineError::InvalidSelectQuery {
+                query: body.clone(),
+                error: e.to_string(),
+            })?;
+        if select.columns.is_some() && args.format != "json" {
+            return Err(PipelineError::InvalidSelectQuery {
+                query: body,
+                error: format!(
+                    "column projection is only supported with the 'json' format, not '{}'",
+                    args.format
+                ),
+            });
+        }
+        Some(select)
+    } else {
+        None
+    };
+
     // Generate endpoint name depending on the query and output mode.
     let endpoint_name = format!(
         "api-{}-{table_name}-{}{}",
@@ -718,6 +853,7 @@ async fn output_endpoint(
             OutputQuery::Table => "",
             OutputQuery::Neighborhood => "neighborhood-",
             OutputQuery::Quantiles => "quantiles-",
+            OutputQuery::Select => "select-",
         },
         Uuid::new_v4()
     );
@@ -730,19 +866,26 @@ async fn output_endpoint(
         &args.format,
         matches!(
             args.query,
-            OutputQuery::Neighborhood | OutputQuery::Quantiles
+            OutputQuery::Neighborhood | OutputQuery::Quantiles | OutputQuery::Select
         ),
         args.mode == EgressMode::Watch,
+        websocket.is_some(),
     );
 
     // Create endpoint config.
     let config = OutputEndpointConfig {
         stream: Cow::from(table_name),
         query: args.query,
+        select,
         connector_config: ConnectorConfig {
             transport: HttpOutputTransport::config(),
-            format: encoder_config_from_http_request(&endpoint_name, &args.format, &req)?,
+            format: encoder_config_from_http_request(&endpoint_name, &args.format, req)?,
             max_buffered_records: HttpOutputTransport::default_max_buffered_records(),
+            dead_letter: None,
+            max_parse_errors: None,
+            max_records_per_second: None,
+            max_bytes_per_second: None,
+            weight: 1,
         },
     };
 
@@ -776,9 +919,7 @@ async fn output_endpoint(
             // this closure doesn't prevent the controller from shutting down.
             let weak_state = Arc::downgrade(&state);
 
-            // Call endpoint to create a response with a streaming body, which will be
-            // evaluated after we return the response object to actix.
-            response = endpoint.request(Box::new(move || {
+            let finalizer = Box::new(move || {
                 // Delete endpoint on completion/error.
                 // We don't control the lifetime of the reponse object after
                 // returning it to actix, so the only way to run cleanup code
@@ -795,7 +936,22 @@ async fn output_endpoint(
                         }
                     }
                 }
-            }));
+            });
+
+            response = match websocket {
+                // Call endpoint to create a response with a streaming body, which will be
+                // evaluated after we return the response object to actix.
+                None => endpoint.request(finalizer),
+                // Serve the WebSocket session in a separate task, which runs the
+                // finalizer when the session ends.
+                Some((response, session, messages)) => {
+                    let endpoint = endpoint.clone();
+                    rt::spawn(
+                        async move { endpoint.websocket(session, messages, finalizer).await },
+                    );
+                    response
+                }
+            };
 
             // The endpoint is ready to receive data from the pipeline.
             match args.query {
@@ -847,6 +1003,20 @@ async fn output_endpoint(
                         .set_for_all(args.quantiles as usize);
                     controller.request_step();
                 }
+                // Request a snapshot of the table to evaluate the query over.
+                OutputQuery::Select => {
+                    controller
+                        .catalog()
+                        .lock()
+                        .unwrap()
+                        .output_handles(&config.stream)
+                        .unwrap()
+                        .snapshot_trigger_handle
+                        .as_ref()
+                        .ok_or(PipelineError::SelectNotSupported)?
+                        .set_for_all(true);
+                    controller.request_step();
+                }
                 OutputQuery::Table => {}
             }
         }
@@ -856,6 +1026,128 @@ async fn output_endpoint(
     Ok(response)
 }
 
+#[cfg(test)]
+mod test {
+    use super::{bootstrap, build_app, ServerArgs, ServerState};
+    use crate::test::test_circuit;
+    use actix_web::{http::StatusCode, web::Data as WebData, App};
+    use awc::ws::{Frame, Message};
+    use futures_util::{SinkExt, Stream, StreamExt};
+    use serde_json::{json, Value as JsonValue};
+    use std::{
+        fmt::Debug,
+        io::Write,
+        thread,
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+    use tempfile::NamedTempFile;
+    use tokio::time::timeout;
+
+    /// Returns the next text message received over `socket`, parsed as JSON.
+    async fn next_json<S, E>(socket: &mut S) -> JsonValue
+    where
+        S: Stream<Item = Result<Frame, E>> + Unpin,
+        E: Debug,
+    {
+        loop {
+            if let Frame::Text(bytes) = socket.next().await.unwrap().unwrap() {
+                return serde_json::from_slice(&bytes).unwrap();
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_websocket() {
+        let mut config_file = NamedTempFile::new().unwrap();
+        config_file.write_all(b"name: test\ninputs: {}\n").unwrap();
+
+        let state = WebData::new(ServerState::new(None));
+        let state_clone = state.clone();
+        let args = ServerArgs {
+            config_file: config_file.path().display().to_string(),
+            metadata_file: None,
+            bind_address: "127.0.0.1".to_string(),
+            default_port: None,
+        };
+        thread::spawn(move || {
+            bootstrap(
+                args,
+                |workers| Ok(test_circuit(workers)),
+                state_clone,
+                std::sync::mpsc::channel().0,
+            )
+        });
+
+        let mut server = actix_test::start(move || build_app(App::new(), state.clone()));
+        let start = Instant::now();
+        while server.get("/stats").send().await.unwrap().status() == StatusCode::SERVICE_UNAVAILABLE
+        {
+            assert!(start.elapsed() < Duration::from_millis(20_000));
+            sleep(Duration::from_millis(200));
+        }
+        assert!(server
+            .get("/start")
+            .send()
+            .await
+            .unwrap()
+            .status()
+            .is_success());
+
+        let mut output = server
+            .ws_at("/ws/egress/test_output1?format=csv")
+            .await
+            .unwrap();
+        let mut input = server
+            .ws_at("/ws/ingress/test_input1?format=csv")
+            .await
+            .unwrap();
+
+        // Each input message is acknowledged with the step that includes it.
+        input
+            .send(Message::Text("1,true,,foo\n".into()))
+            .await
+            .unwrap();
+        let ack = next_json(&mut input).await;
+        assert_eq!(ack["sequence_number"], 0);
+        assert!(ack.get("parse_errors").is_none());
+        let step = ack["step"].as_u64().unwrap();
+
+        input
+            .send(Message::Text("2,false,,bar\ninvalid\n".into()))
+            .await
+            .unwrap();
+        let ack = next_json(&mut input).await;
+        assert_eq!(ack["sequence_number"], 1);
+        assert!(ack["step"].as_u64().unwrap() > step);
+        assert_eq!(ack["parse_errors"].as_array().unwrap().len(), 1);
+
+        // Output only flows once the client grants credit.
+        assert!(timeout(Duration::from_millis(1_000), output.next())
+            .await
+            .is_err());
+        output
+            .send(Message::Text(json!({"credit": 100}).to_string().into()))
+            .await
+            .unwrap();
+        let mut text = String::new();
+        while !(text.contains("foo") && text.contains("bar")) {
+            let chunk = timeout(Duration::from_millis(10_000), next_json(&mut output))
+                .await
+                .unwrap();
+            text += chunk["text_data"].as_str().unwrap_or_default();
+        }
+
+        assert!(server
+            .get("/shutdown")
+            .send()
+            .await
+            .unwrap()
+            .status()
+            .is_success());
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "with-kafka")]
 mod test_with_kafka {
@@ -4,8 +4,9 @@ use crate::{
     Catalog, DeserializeWithContext, SerializeWithContext,
 };
 use dbsp::{
-    algebra::ZRingValue,
+    algebra::{HasZero, ZRingValue},
     operator::{DelayedFeedback, FilterMap, NeighborhoodDescr, Update},
+    trace::{Batch, Builder, Cursor, Trace},
     utils::Tup2,
     CollectionHandle, DBData, DBWeight, OrdIndexedZSet, RootCircuit, Stream, UpsertHandle, ZSet,
 };
@@ -205,6 +206,17 @@ impl Catalog {
         let quantiles_handle = quantiles_stream
             .output_guarded(&num_quantiles_stream.apply(|num_quantiles| *num_quantiles > 0));
 
+        // Handle for the snapshot query.
+        let (snapshot_trigger_stream, snapshot_trigger_handle) = circuit.add_input_stream::<bool>();
+
+        // Snapshot of the collection, only produced when the trigger is set.
+        let snapshot_handle = stream
+            .integrate_trace()
+            .apply2(&snapshot_trigger_stream, |trace, trigger| {
+                trace_snapshot(trace, *trigger)
+            })
+            .output_guarded(&snapshot_trigger_stream.apply(|trigger| *trigger));
+
         let handles = OutputCollectionHandles {
             delta_handle: Box::new(<SerCollectionHandleImpl<_, D, ()>>::new(delta_handle))
                 as Box<dyn SerCollectionHandle>,
@@ -228,6 +240,11 @@ impl Catalog {
             quantiles_handle: Some(Box::new(<SerCollectionHandleImpl<_, D, ()>>::new(
                 quantiles_handle,
             )) as Box<dyn SerCollectionHandle>),
+
+            snapshot_trigger_handle: Some(snapshot_trigger_handle),
+            snapshot_handle: Some(Box::new(<SerCollectionHandleImpl<_, D, ()>>::new(
+                snapshot_handle,
+            )) as Box<dyn SerCollectionHandle>),
         };
 
         self.output_batch_handles.insert(name.to_owned(), handles);
@@ -331,6 +348,18 @@ impl Catalog {
             .map(|Tup2(_k, v)| v.clone())
             .output_guarded(&num_quantiles_stream.apply(|num_quantiles| *num_quantiles > 0));
 
+        // Handle for the snapshot query.
+        let (snapshot_trigger_stream, snapshot_trigger_handle) = circuit.add_input_stream::<bool>();
+
+        // Snapshot of the collection, only produced when the trigger is set.
+        let snapshot_handle = stream
+            .integrate_trace()
+            .apply2(&snapshot_trigger_stream, |trace, trigger| {
+                trace_snapshot(trace, *trigger)
+            })
+            .map(|(_k, v)| v.clone())
+            .output_guarded(&snapshot_trigger_stream.apply(|trigger| *trigger));
+
         let handles = OutputCollectionHandles {
             delta_handle: Box::new(<SerCollectionHandleImpl<_, VD, ()>>::new(delta_handle))
                 as Box<dyn SerCollectionHandle>,
@@ -357,12 +386,45 @@ impl Catalog {
             quantiles_handle: Some(Box::new(<SerCollectionHandleImpl<_, VD, ()>>::new(
                 quantiles_handle,
             )) as Box<dyn SerCollectionHandle>),
+
+            snapshot_trigger_handle: Some(snapshot_trigger_handle),
+            snapshot_handle: Some(Box::new(<SerCollectionHandleImpl<_, VD, ()>>::new(
+                snapshot_handle,
+            )) as Box<dyn SerCollectionHandle>),
         };
 
         self.output_batch_handles.insert(name.to_owned(), handles);
     }
 }
 
+/// Returns the contents of `trace` as a single batch if `trigger` is set, or
+/// an empty batch otherwise.
+fn trace_snapshot<T>(trace: &T, trigger: bool) -> T::Batch
+where
+    T: Trace<Time = ()>,
+{
+    if !trigger {
+        return T::Batch::empty(());
+    }
+
+    let mut builder = <T::Batch as Batch>::Builder::with_capacity((), trace.len());
+    let mut cursor = trace.cursor();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let weight = cursor.weight();
+            if !weight.is_zero() {
+                builder.push((
+                    T::Batch::item_from(cursor.key().clone(), cursor.val().clone()),
+                    weight,
+                ));
+            }
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    builder.done()
+}
+
 #[cfg(test)]
 mod test {
     use std::{io::Write, ops::Deref};
@@ -551,6 +613,30 @@ mod test {
         assert_eq!(
             quantiles,
             r#"1: {"id":1,"b":true,"i":null,"s":"1-modified"}
+"#
+        );
+
+        // Step 4: take a snapshot.
+
+        output_stream_handles
+            .snapshot_trigger_handle
+            .as_ref()
+            .unwrap()
+            .set_for_all(true);
+
+        circuit.step().unwrap();
+
+        let snapshot = batch_to_json(
+            output_stream_handles
+                .snapshot_handle
+                .as_ref()
+                .unwrap()
+                .consolidate()
+                .deref(),
+        );
+        assert_eq!(
+            snapshot,
+            r#"1: {"id":1,"b":true,"i":null,"s":"1-modified"}
 "#
         );
     }
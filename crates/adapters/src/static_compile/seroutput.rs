@@ -15,18 +15,18 @@ use std::{cell::RefCell, io, io::Write, marker::PhantomData, ops::DerefMut, sync
 ///
 /// This is used in CSV serialization to serialize to a different buffer on
 /// every invocation.
-struct SwappableWrite<W> {
+pub(crate) struct SwappableWrite<W> {
     writer: RefCell<Option<W>>,
 }
 
 impl<W> SwappableWrite<W> {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             writer: RefCell::new(None),
         }
     }
 
-    fn swap(&self, mut writer: Option<W>) -> Option<W> {
+    pub(crate) fn swap(&self, mut writer: Option<W>) -> Option<W> {
         std::mem::swap(self.writer.borrow_mut().deref_mut(), &mut writer);
         writer
     }
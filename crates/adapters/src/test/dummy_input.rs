@@ -0,0 +1,168 @@
+//! An [`InputConsumer`] that records the calls made to it, for testing
+//! fault-tolerant input transports.
+
+use crate::{transport::Step, InputConsumer, ParseError};
+use anyhow::Error as AnyError;
+use crossbeam::sync::{Parker, Unparker};
+use log::info;
+use std::{
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// A call made to a [`DummyInputConsumer`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConsumerCall {
+    StartStep(Step),
+    InputFragment(String),
+    InputChunk(String),
+    Error(bool),
+    Eoi,
+}
+
+/// Receives and records the calls made to the [`InputConsumer`]s returned by
+/// [`DummyInputReceiver::consumer`].
+pub struct DummyInputReceiver {
+    inner: Arc<DummyInputReceiverInner>,
+    parker: Parker,
+}
+
+struct DummyInputReceiverInner {
+    unparker: Unparker,
+    calls: Mutex<Vec<ConsumerCall>>,
+    committed: Mutex<Option<Step>>,
+}
+
+impl Default for DummyInputReceiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DummyInputReceiver {
+    pub fn new() -> Self {
+        let parker = Parker::new();
+        let unparker = parker.unparker().clone();
+        Self {
+            inner: Arc::new(DummyInputReceiverInner {
+                unparker,
+                calls: Mutex::new(Vec::new()),
+                committed: Mutex::new(None),
+            }),
+            parker,
+        }
+    }
+
+    /// Wait some time for the input consumer to report that `committed` was
+    /// called.  However, we don't expect it to have been called, so we panic
+    /// with an error if it has.
+    ///
+    /// The waiting time here is arbitrary, since we expect that we could wait
+    /// forever.
+    #[track_caller]
+    pub fn expect_eof(&self) {
+        sleep(Duration::from_millis(100));
+
+        let actual: Vec<_> = self.inner.calls.lock().unwrap().drain(..).collect();
+        assert_eq!(Vec::<ConsumerCall>::new(), actual);
+    }
+
+    /// Wait until the input consumer receives `expected`. Panics if it receives
+    /// something else or if it doesn't receive it within a reasonable amount of
+    /// time.  It is not an error for the consumer to receive more following
+    /// `expected`; any data received afterward is left for later calls to
+    /// check.
+    #[track_caller]
+    pub fn expect(&self, expected: Vec<ConsumerCall>) {
+        let mut last_change = Instant::now();
+        let mut last_len = 0;
+        loop {
+            let mut current = self.inner.calls.lock().unwrap();
+            if current.len() >= expected.len()
+                || Instant::now().duration_since(last_change) > Duration::from_secs(10)
+            {
+                let len = current.len().min(expected.len());
+                let actual: Vec<_> = current.drain(0..len).collect();
+
+                // Without this, sometimes we get SIGSEGV in librdkafka.
+                drop(current);
+
+                assert_eq!(expected, actual);
+                return;
+            }
+            if current.len() != last_len {
+                last_len = current.len();
+                last_change = Instant::now();
+            }
+            drop(current);
+
+            self.parker.park_timeout(Duration::from_millis(100));
+        }
+    }
+
+    pub fn wait_to_complete(&self, step: Step) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            assert!(Instant::now() < deadline);
+            if let Some(committed) = *self.inner.committed.lock().unwrap() {
+                if committed >= step {
+                    return;
+                }
+            }
+            self.parker.park_deadline(deadline);
+        }
+    }
+
+    pub fn consumer(&self) -> Box<dyn InputConsumer> {
+        Box::new(DummyInputConsumer(self.inner.clone()))
+    }
+}
+
+pub struct DummyInputConsumer(Arc<DummyInputReceiverInner>);
+
+impl DummyInputConsumer {
+    fn called(&mut self, call: ConsumerCall) {
+        info!("{call:?}");
+        self.0.calls.lock().unwrap().push(call);
+        self.0.unparker.unpark();
+    }
+}
+
+impl InputConsumer for DummyInputConsumer {
+    fn start_step(&mut self, step: Step) {
+        self.called(ConsumerCall::StartStep(step));
+    }
+    fn input_fragment(&mut self, data: &[u8]) -> Vec<ParseError> {
+        self.called(ConsumerCall::InputFragment(
+            String::from_utf8(data.into()).unwrap(),
+        ));
+        vec![]
+    }
+    fn input_chunk(&mut self, data: &[u8]) -> Vec<ParseError> {
+        self.called(ConsumerCall::InputChunk(
+            String::from_utf8(data.into()).unwrap(),
+        ));
+        vec![]
+    }
+    fn error(&mut self, fatal: bool, error: AnyError) {
+        info!("error: {error}");
+        self.called(ConsumerCall::Error(fatal));
+    }
+    fn eoi(&mut self) -> Vec<ParseError> {
+        self.called(ConsumerCall::Eoi);
+        vec![]
+    }
+    fn fork(&self) -> Box<dyn InputConsumer> {
+        unreachable!()
+    }
+    fn committed(&mut self, step: Step) {
+        info!("step {step} committed");
+        let mut completed = self.0.committed.lock().unwrap();
+        if let Some(committed) = *completed {
+            assert_eq!(committed + 1, step);
+        }
+        *completed = Some(step);
+        self.0.unparker.unpark();
+    }
+}
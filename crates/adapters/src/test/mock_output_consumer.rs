@@ -1,9 +1,16 @@
 use crate::{transport::Step, OutputConsumer};
 use std::sync::{Arc, Mutex};
 
+/// Key and optional value of a message pushed with `push_key`.
+pub type KeyedMessage = (Vec<u8>, Option<Vec<u8>>);
+
 pub struct MockOutputConsumer {
     pub data: Arc<Mutex<Vec<u8>>>,
+
+    /// Keyed messages pushed with `push_key`.
+    pub messages: Arc<Mutex<Vec<KeyedMessage>>>,
     max_buffer_size_bytes: usize,
+    key_fields: Option<Vec<String>>,
 }
 
 impl Default for MockOutputConsumer {
@@ -20,7 +27,18 @@ impl MockOutputConsumer {
     pub fn with_max_buffer_size_bytes(bytes: usize) -> Self {
         Self {
             data: Arc::new(Mutex::new(Vec::new())),
+            messages: Arc::new(Mutex::new(Vec::new())),
             max_buffer_size_bytes: bytes,
+            key_fields: None,
+        }
+    }
+
+    /// Creates a consumer that expects one message per record, keyed by
+    /// `key_fields`.
+    pub fn with_key_fields(key_fields: &[&str]) -> Self {
+        Self {
+            key_fields: Some(key_fields.iter().map(|field| field.to_string()).collect()),
+            ..Self::new()
         }
     }
 }
@@ -34,5 +52,14 @@ impl OutputConsumer for MockOutputConsumer {
     fn push_buffer(&mut self, buffer: &[u8]) {
         self.data.lock().unwrap().extend_from_slice(buffer)
     }
+    fn key_fields(&self) -> Option<&[String]> {
+        self.key_fields.as_deref()
+    }
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push((key.to_vec(), val.map(<[u8]>::to_vec)))
+    }
     fn batch_end(&mut self) {}
 }
@@ -13,6 +13,8 @@ use std::{
 };
 
 mod data;
+#[cfg(test)]
+mod dummy_input;
 
 #[cfg(feature = "with-kafka")]
 pub mod kafka;
@@ -22,13 +24,19 @@ pub mod http;
 mod mock_dezset;
 mod mock_input_consumer;
 mod mock_output_consumer;
+#[cfg(test)]
+mod schema_registry;
 
 pub use data::{
     generate_test_batch, generate_test_batches, generate_test_batches_with_weights, TestStruct,
 };
+#[cfg(test)]
+pub use dummy_input::{ConsumerCall, DummyInputConsumer, DummyInputReceiver};
 pub use mock_dezset::{MockDeZSet, MockUpdate};
 pub use mock_input_consumer::MockInputConsumer;
 pub use mock_output_consumer::MockOutputConsumer;
+#[cfg(test)]
+pub use schema_registry::MockSchemaRegistry;
 
 pub struct TestLogger;
 pub static TEST_LOGGER: TestLogger = TestLogger;
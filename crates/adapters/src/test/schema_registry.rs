@@ -0,0 +1,106 @@
+//! A mock Confluent schema registry for testing the Avro format.
+
+use actix_test::TestServer;
+use actix_web::{
+    web::{self, Data, Json, Path},
+    App, HttpResponse,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Inner state of `MockSchemaRegistry`.
+#[derive(Default)]
+pub struct MockSchemaRegistryState {
+    /// Registered schemas.  The id of a schema is its index plus one.
+    pub schemas: Vec<String>,
+
+    /// Ids of the schemas registered under each subject.
+    pub subjects: BTreeMap<String, Vec<u32>>,
+
+    /// Number of schema lookups by id.
+    pub lookups: usize,
+}
+
+impl MockSchemaRegistryState {
+    /// Adds `schema` to the registry unless it is already there and returns
+    /// its id.
+    pub fn add_schema(&mut self, schema: &str) -> u32 {
+        match self.schemas.iter().position(|s| s == schema) {
+            Some(index) => index as u32 + 1,
+            None => {
+                self.schemas.push(schema.to_string());
+                self.schemas.len() as u32
+            }
+        }
+    }
+}
+
+/// Serves the subset of the schema registry REST API used by the Avro parser
+/// and encoder.
+pub struct MockSchemaRegistry {
+    state: Arc<Mutex<MockSchemaRegistryState>>,
+    server: TestServer,
+}
+
+impl MockSchemaRegistry {
+    pub fn start() -> Self {
+        let state = Arc::new(Mutex::new(MockSchemaRegistryState::default()));
+        let data = Data::from(state.clone());
+        let server = actix_test::start(move || {
+            App::new()
+                .app_data(data.clone())
+                .route("/schemas/ids/{id}", web::get().to(get_schema))
+                .route(
+                    "/subjects/{subject}/versions",
+                    web::post().to(register_schema),
+                )
+        });
+
+        Self { state, server }
+    }
+
+    /// URL of the registry.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.server.addr())
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, MockSchemaRegistryState> {
+        self.state.lock().unwrap()
+    }
+}
+
+async fn get_schema(state: Data<Mutex<MockSchemaRegistryState>>, id: Path<u32>) -> HttpResponse {
+    let mut state = state.lock().unwrap();
+    state.lookups += 1;
+    match (*id as usize)
+        .checked_sub(1)
+        .and_then(|index| state.schemas.get(index))
+    {
+        Some(schema) => HttpResponse::Ok().json(json!({ "schema": schema })),
+        None => HttpResponse::NotFound()
+            .json(json!({ "error_code": 40403, "message": "Schema not found" })),
+    }
+}
+
+#[derive(Deserialize)]
+struct RegisterRequest {
+    schema: String,
+}
+
+async fn register_schema(
+    state: Data<Mutex<MockSchemaRegistryState>>,
+    subject: Path<String>,
+    request: Json<RegisterRequest>,
+) -> HttpResponse {
+    let mut state = state.lock().unwrap();
+    let id = state.add_schema(&request.schema);
+    let versions = state.subjects.entry(subject.into_inner()).or_default();
+    if !versions.contains(&id) {
+        versions.push(id);
+    }
+    HttpResponse::Ok().json(json!({ "id": id }))
+}
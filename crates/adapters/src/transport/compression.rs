@@ -0,0 +1,426 @@
+//! Transparent decompression of input data.
+//!
+//! [`decompress`] wraps an [`InputConsumer`] in another one that decompresses
+//! the data passed to it, so that an input transport can support compressed
+//! input without changing how it reads data.  Decompression is streaming: each
+//! fragment of compressed input is decompressed and passed along as soon as it
+//! arrives.
+
+use super::{InputConsumer, Step};
+use crate::format::ParseError;
+use anyhow::{Error as AnyError, Result as AnyResult};
+use bzip2::write::BzDecoder;
+use flate2::write::MultiGzDecoder;
+use pipeline_types::transport::compression::Compression;
+use std::{
+    io::{Result as IoResult, Write},
+    mem::take,
+};
+use zstd::stream::write::Decoder as ZstdDecoder;
+
+/// Longest magic number that [`Codec::from_magic`] looks for.
+const MAX_MAGIC_LEN: usize = 4;
+
+/// Returns a consumer that decompresses data according to `compression`
+/// before passing it to `consumer`.
+///
+/// `name` is the name of the input, such as a file name or a URL, which is
+/// used to detect the codec in [`Compression::Auto`] mode.
+pub(crate) fn decompress(
+    compression: Compression,
+    name: &str,
+    consumer: Box<dyn InputConsumer>,
+) -> AnyResult<Box<dyn InputConsumer>> {
+    if compression == Compression::None {
+        Ok(consumer)
+    } else {
+        Ok(Box::new(DecompressingConsumer::new(
+            compression,
+            name,
+            consumer,
+        )?))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Codec {
+    Identity,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Codec {
+    fn from_extension(name: &str) -> Option<Self> {
+        // Ignore the query and fragment in a URL.
+        let name = name.split(['?', '#']).next().unwrap_or_default();
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".gz") || name.ends_with(".gzip") {
+            Some(Self::Gzip)
+        } else if name.ends_with(".zst") || name.ends_with(".zstd") {
+            Some(Self::Zstd)
+        } else if name.ends_with(".bz2") {
+            Some(Self::Bzip2)
+        } else {
+            None
+        }
+    }
+
+    fn from_magic(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Self::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Self::Zstd
+        } else if header.starts_with(b"BZh") {
+            Self::Bzip2
+        } else {
+            Self::Identity
+        }
+    }
+}
+
+/// A streaming decoder that writes decompressed data into a `Vec<u8>`.
+enum Decoder {
+    Identity,
+    Gzip(MultiGzDecoder<Vec<u8>>),
+    Zstd(ZstdDecoder<'static, Vec<u8>>),
+    Bzip2(BzDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    fn new(codec: Codec) -> IoResult<Self> {
+        Ok(match codec {
+            Codec::Identity => Self::Identity,
+            Codec::Gzip => Self::Gzip(MultiGzDecoder::new(Vec::new())),
+            Codec::Zstd => Self::Zstd(ZstdDecoder::new(Vec::new())?),
+            Codec::Bzip2 => Self::Bzip2(BzDecoder::new(Vec::new())),
+        })
+    }
+
+    /// Decompresses `data` and returns as much decompressed data as is
+    /// available.
+    fn decode(&mut self, data: &[u8]) -> IoResult<Vec<u8>> {
+        match self {
+            Self::Identity => Ok(data.to_vec()),
+            Self::Gzip(decoder) => {
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                Ok(take(decoder.get_mut()))
+            }
+            Self::Zstd(decoder) => {
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                Ok(take(decoder.get_mut()))
+            }
+            Self::Bzip2(decoder) => {
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                Ok(take(decoder.get_mut()))
+            }
+        }
+    }
+
+    /// Finishes decompression and returns any remaining decompressed data.
+    fn finish(self) -> IoResult<Vec<u8>> {
+        match self {
+            Self::Identity => Ok(Vec::new()),
+            Self::Gzip(decoder) => decoder.finish(),
+            Self::Zstd(mut decoder) => {
+                decoder.flush()?;
+                Ok(decoder.into_inner())
+            }
+            Self::Bzip2(mut decoder) => decoder.finish(),
+        }
+    }
+}
+
+#[derive(Default)]
+enum State {
+    /// In [`Compression::Auto`] mode, the data received so far, which is not
+    /// yet enough to detect the codec.
+    Detecting(Vec<u8>),
+
+    /// Decompressing.
+    Decoding(Decoder),
+
+    /// Decompression failed or the input ended.  Further input is discarded.
+    #[default]
+    Failed,
+}
+
+/// An [`InputConsumer`] that decompresses its input and passes it along to
+/// another consumer.
+struct DecompressingConsumer {
+    compression: Compression,
+    name: String,
+    inner: Box<dyn InputConsumer>,
+    state: State,
+}
+
+impl DecompressingConsumer {
+    fn new(compression: Compression, name: &str, inner: Box<dyn InputConsumer>) -> AnyResult<Self> {
+        let codec = match compression {
+            Compression::None => Some(Codec::Identity),
+            Compression::Auto => Codec::from_extension(name),
+            Compression::Gzip => Some(Codec::Gzip),
+            Compression::Zstd => Some(Codec::Zstd),
+            Compression::Bzip2 => Some(Codec::Bzip2),
+        };
+        let state = match codec {
+            Some(codec) => State::Decoding(Decoder::new(codec)?),
+            None => State::Detecting(Vec::new()),
+        };
+        Ok(Self {
+            compression,
+            name: name.into(),
+            inner,
+            state,
+        })
+    }
+
+    /// Passes the result of decompression to the inner consumer, or reports an
+    /// error.
+    fn forward(&mut self, result: IoResult<Vec<u8>>) -> Vec<ParseError> {
+        match result {
+            Ok(data) if data.is_empty() => Vec::new(),
+            Ok(data) => self.inner.input_fragment(&data),
+            Err(error) => {
+                self.state = State::Failed;
+                self.inner.error(
+                    true,
+                    AnyError::from(error)
+                        .context(format!("Failed to decompress input '{}'", self.name)),
+                );
+                Vec::new()
+            }
+        }
+    }
+
+    /// Starts decoding with the codec detected from `header`, and then feeds
+    /// it `header`.
+    fn start_decoding(&mut self, header: Vec<u8>) -> Vec<ParseError> {
+        match Decoder::new(Codec::from_magic(&header)) {
+            Ok(decoder) => {
+                self.state = State::Decoding(decoder);
+                self.decode(&header)
+            }
+            Err(error) => self.forward(Err(error)),
+        }
+    }
+
+    fn decode(&mut self, data: &[u8]) -> Vec<ParseError> {
+        match &mut self.state {
+            State::Detecting(header) => {
+                header.extend_from_slice(data);
+                if header.len() >= MAX_MAGIC_LEN {
+                    let header = take(header);
+                    self.start_decoding(header)
+                } else {
+                    Vec::new()
+                }
+            }
+            State::Decoding(Decoder::Identity) => self.inner.input_fragment(data),
+            State::Decoding(decoder) => {
+                let result = decoder.decode(data);
+                self.forward(result)
+            }
+            State::Failed => Vec::new(),
+        }
+    }
+}
+
+impl InputConsumer for DecompressingConsumer {
+    fn start_step(&mut self, step: Step) {
+        self.inner.start_step(step);
+    }
+
+    fn input_fragment(&mut self, data: &[u8]) -> Vec<ParseError> {
+        self.decode(data)
+    }
+
+    fn input_chunk(&mut self, data: &[u8]) -> Vec<ParseError> {
+        // A chunk of compressed data need not decompress to whole records.
+        self.decode(data)
+    }
+
+    fn committed(&mut self, step: Step) {
+        self.inner.committed(step);
+    }
+
+    fn error(&mut self, fatal: bool, error: AnyError) {
+        self.inner.error(fatal, error);
+    }
+
+    fn eoi(&mut self) -> Vec<ParseError> {
+        let mut errors = match take(&mut self.state) {
+            State::Detecting(header) => {
+                let mut errors = self.start_decoding(header);
+                if let State::Decoding(decoder) = take(&mut self.state) {
+                    errors.extend(self.forward(decoder.finish()));
+                }
+                errors
+            }
+            State::Decoding(decoder) => self.forward(decoder.finish()),
+            State::Failed => Vec::new(),
+        };
+        errors.extend(self.inner.eoi());
+        errors
+    }
+
+    fn fork(&self) -> Box<dyn InputConsumer> {
+        Box::new(
+            Self::new(self.compression, &self.name, self.inner.fork())
+                .expect("creating a decoder succeeded before"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{mock_input_pipeline, wait, TestStruct, DEFAULT_TIMEOUT_MS};
+    use bzip2::write::BzEncoder;
+    use flate2::write::GzEncoder;
+    use std::{fs::write, io::Write};
+    use tempfile::TempDir;
+
+    fn test_data() -> (Vec<TestStruct>, String) {
+        let records = (0..100)
+            .map(|id| TestStruct {
+                id,
+                b: id % 2 == 0,
+                i: None,
+                s: format!("record {id}"),
+            })
+            .collect::<Vec<_>>();
+        let csv = records
+            .iter()
+            .map(|r| format!("{},{},,{}\n", r.id, r.b, r.s))
+            .collect::<Vec<_>>()
+            .concat();
+        (records, csv)
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        // Use two gzip members to check that both are decompressed.
+        let (first, second) = data.split_at(data.len() / 2);
+        let mut result = Vec::new();
+        for member in [first, second] {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(member).unwrap();
+            result.extend(encoder.finish().unwrap());
+        }
+        result
+    }
+
+    fn zstd(data: &[u8]) -> Vec<u8> {
+        zstd::stream::encode_all(data, 0).unwrap()
+    }
+
+    fn bzip2(data: &[u8]) -> Vec<u8> {
+        let mut encoder = BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Reads `contents` from a file named `file_name` with the given
+    /// `compression` setting and checks that it decompresses to `expected`.
+    fn test_file(file_name: &str, compression: &str, contents: &[u8], expected: &[TestStruct]) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(file_name);
+        write(&path, contents).unwrap();
+
+        // Use a very small buffer size to exercise streaming decompression.
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: file
+    config:
+        path: {:?}
+        buffer_size_bytes: 3
+        compression: {compression}
+format:
+    name: csv
+"#,
+            path.to_str().unwrap()
+        );
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        endpoint.start(0).unwrap();
+        wait(|| consumer.state().eoi, DEFAULT_TIMEOUT_MS).unwrap();
+        let actual = zset
+            .state()
+            .flushed
+            .iter()
+            .map(|upd| upd.unwrap_insert().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_explicit_compression() {
+        let (records, csv) = test_data();
+        test_file("data.csv", "none", csv.as_bytes(), &records);
+        test_file("data", "gzip", &gzip(csv.as_bytes()), &records);
+        test_file("data", "zstd", &zstd(csv.as_bytes()), &records);
+        test_file("data", "bzip2", &bzip2(csv.as_bytes()), &records);
+    }
+
+    #[test]
+    fn test_auto_compression() {
+        let (records, csv) = test_data();
+
+        // Detect from the extension.
+        test_file("data.csv.gz", "auto", &gzip(csv.as_bytes()), &records);
+        test_file("data.csv.zst", "auto", &zstd(csv.as_bytes()), &records);
+        test_file("data.csv.bz2", "auto", &bzip2(csv.as_bytes()), &records);
+
+        // Detect from the magic bytes.
+        test_file("data1", "auto", &gzip(csv.as_bytes()), &records);
+        test_file("data2", "auto", &zstd(csv.as_bytes()), &records);
+        test_file("data3", "auto", &bzip2(csv.as_bytes()), &records);
+
+        // Uncompressed data is passed through, even if it is shorter than the
+        // longest magic number.
+        test_file("data.csv", "auto", csv.as_bytes(), &records);
+        test_file("empty.csv", "auto", b"", &[]);
+    }
+
+    #[test]
+    fn test_corrupt_input() {
+        let (_records, csv) = test_data();
+        let mut data = gzip(csv.as_bytes());
+        data.truncate(data.len() / 2);
+        data.extend_from_slice(b"garbage");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("data.csv.gz");
+        write(&path, data).unwrap();
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: file
+    config:
+        path: {:?}
+        compression: auto
+format:
+    name: csv
+"#,
+            path.to_str().unwrap()
+        );
+        let (endpoint, consumer, _zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        consumer.on_error(Some(Box::new(|_, _| {})));
+        endpoint.start(0).unwrap();
+        wait(
+            || consumer.state().endpoint_error.is_some(),
+            DEFAULT_TIMEOUT_MS,
+        )
+        .unwrap();
+    }
+}
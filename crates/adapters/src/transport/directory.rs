@@ -0,0 +1,681 @@
+use super::{InputConsumer, InputEndpoint, InputReader, InputTransport, Step};
+use crate::PipelineState;
+use anyhow::{bail, Context, Error as AnyError, Result as AnyResult};
+use crossbeam::sync::{Parker, Unparker};
+use glob::{glob, Pattern};
+use num_traits::FromPrimitive;
+use pipeline_types::transport::directory::{AfterRead, DirectoryInputConfig, FileOrder};
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::{read, remove_file, rename, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    mem::{replace, take},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread::spawn,
+    time::{Duration, SystemTime},
+};
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// [`InputTransport`] implementation that reads data from the files in a
+/// directory that match a glob pattern, watching for new files.
+///
+/// The input transport factory gives this transport the name `directory`.
+pub struct DirectoryInputTransport;
+
+impl InputTransport for DirectoryInputTransport {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("directory")
+    }
+
+    /// Creates a new [`InputEndpoint`] for reading files, interpreting
+    /// `config` as a [`DirectoryInputConfig`].
+    ///
+    /// See [`InputTransport::new_endpoint()`] for more information.
+    fn new_endpoint(&self, config: &YamlValue) -> AnyResult<Box<dyn InputEndpoint>> {
+        let config = DirectoryInputConfig::deserialize(config)?;
+        Pattern::new(&config.path)
+            .with_context(|| format!("Invalid glob pattern '{}'", config.path))?;
+        if config.after_read == AfterRead::Move && config.move_to.is_none() {
+            bail!("'after_read: move' requires 'move_to' to specify a directory");
+        }
+        let ep = DirectoryInputEndpoint {
+            config: Arc::new(config),
+        };
+        Ok(Box::new(ep))
+    }
+}
+
+struct DirectoryInputEndpoint {
+    config: Arc<DirectoryInputConfig>,
+}
+
+impl InputEndpoint for DirectoryInputEndpoint {
+    fn open(
+        &self,
+        consumer: Box<dyn InputConsumer>,
+        _start_step: Step,
+    ) -> AnyResult<Box<dyn InputReader>> {
+        Ok(Box::new(DirectoryInputReader::new(&self.config, consumer)?))
+    }
+
+    fn is_fault_tolerant(&self) -> bool {
+        false
+    }
+}
+
+struct DirectoryInputReader {
+    status: Arc<AtomicU32>,
+    unparker: Unparker,
+    files: Arc<Mutex<ReadFiles>>,
+}
+
+/// Files that the worker has read completely, shared between the worker and
+/// [`DirectoryInputReader::complete`].
+#[derive(Default)]
+struct ReadFiles {
+    /// Files read since the last call to `complete`.
+    read: Vec<PathBuf>,
+
+    /// Files whose data is part of a step, to be recorded as completed and
+    /// disposed of by the worker.
+    completed: Vec<PathBuf>,
+}
+
+impl DirectoryInputReader {
+    fn new(
+        config: &Arc<DirectoryInputConfig>,
+        consumer: Box<dyn InputConsumer>,
+    ) -> AnyResult<Self> {
+        let completed = CompletedFiles::open(config.state_path.as_ref().map(PathBuf::from))?;
+
+        let parker = Parker::new();
+        let unparker = parker.unparker().clone();
+        let status = Arc::new(AtomicU32::new(PipelineState::Paused as u32));
+        let files = Arc::new(Mutex::new(ReadFiles::default()));
+        let mut worker = Worker {
+            config: config.clone(),
+            consumer,
+            parker,
+            status: status.clone(),
+            files: files.clone(),
+            completed,
+            unfinished: HashSet::new(),
+            seen: HashMap::new(),
+        };
+        let _worker = spawn(move || {
+            if let Err(error) = worker.run() {
+                worker.consumer.error(true, error);
+            }
+        });
+
+        Ok(Self {
+            status,
+            unparker,
+            files,
+        })
+    }
+}
+
+impl InputReader for DirectoryInputReader {
+    fn pause(&self) -> AnyResult<()> {
+        // Notify worker thread via the status flag.  The worker may
+        // send another buffer downstream before the flag takes effect.
+        self.status
+            .store(PipelineState::Paused as u32, Ordering::Release);
+        Ok(())
+    }
+
+    fn start(&self, _step: Step) -> AnyResult<()> {
+        self.status
+            .store(PipelineState::Running as u32, Ordering::Release);
+
+        // Wake up the worker if it's paused.
+        self.unparker.unpark();
+        Ok(())
+    }
+
+    fn complete(&self, _step: Step) {
+        // The worker passed the contents of the files read so far to the
+        // consumer before it reported them, so their data is part of `step`.
+        {
+            let mut files = self.files.lock().unwrap();
+            let files = &mut *files;
+            files.completed.append(&mut files.read);
+        }
+
+        // Wake up the worker to record the completed files.
+        self.unparker.unpark();
+    }
+
+    fn disconnect(&self) {
+        self.status
+            .store(PipelineState::Terminated as u32, Ordering::Release);
+
+        // Wake up the worker if it's paused.
+        self.unparker.unpark();
+    }
+}
+
+impl Drop for DirectoryInputReader {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+struct Worker {
+    config: Arc<DirectoryInputConfig>,
+    consumer: Box<dyn InputConsumer>,
+    parker: Parker,
+    status: Arc<AtomicU32>,
+    files: Arc<Mutex<ReadFiles>>,
+    completed: CompletedFiles,
+
+    /// Files that have been read completely but not yet completed.
+    unfinished: HashSet<PathBuf>,
+
+    /// Size and modification time of the files that matched the pattern at
+    /// the previous poll.
+    seen: HashMap<PathBuf, (u64, SystemTime)>,
+}
+
+impl Worker {
+    /// Waits while the endpoint is paused.  Returns `false` if the endpoint
+    /// was disconnected.
+    fn wait_for_running(&self) -> bool {
+        loop {
+            match PipelineState::from_u32(self.status.load(Ordering::Acquire)) {
+                Some(PipelineState::Paused) => self.parker.park(),
+                Some(PipelineState::Running) => return true,
+                Some(PipelineState::Terminated) => return false,
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn run(&mut self) -> AnyResult<()> {
+        let poll_interval = Duration::from_millis(
+            self.config
+                .poll_interval_ms
+                .unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+        );
+        let mut eoi = false;
+        while self.wait_for_running() {
+            self.complete_files()?;
+            if eoi {
+                // Wait for the files read so far to be completed.
+                if self.unfinished.is_empty() {
+                    break;
+                }
+                self.parker.park();
+                continue;
+            }
+            match self.next_file()? {
+                Some(path) => {
+                    if !self.read_file(&path)? {
+                        break;
+                    }
+                    self.unfinished.insert(path.clone());
+                    self.files.lock().unwrap().read.push(path);
+                }
+                // Wait for new files or for a file that is still being
+                // written to stabilize.
+                None if self.config.watch || !self.seen.is_empty() => {
+                    self.parker.park_timeout(poll_interval)
+                }
+                None => {
+                    let _ = self.consumer.eoi();
+                    eoi = true;
+                }
+            }
+        }
+        self.complete_files()
+    }
+
+    /// Records the files whose data has become part of a step as completed.
+    fn complete_files(&mut self) -> AnyResult<()> {
+        let completed = take(&mut self.files.lock().unwrap().completed);
+        for path in completed {
+            self.unfinished.remove(&path);
+            self.complete_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the next file to read, if there is one.
+    ///
+    /// A file is ready to be read only if its size and modification time
+    /// haven't changed since the previous poll, so that we don't read a file
+    /// that is still being written.  If the next file in order isn't ready,
+    /// this returns `None` and leaves it in `self.seen`.
+    fn next_file(&mut self) -> AnyResult<Option<PathBuf>> {
+        let mut seen = HashMap::new();
+        let mut next: Option<(SystemTime, PathBuf)> = None;
+        for entry in glob(&self.config.path)? {
+            let path = match entry {
+                Ok(path) => path,
+                Err(error) => {
+                    // Unreadable directories are not fatal: the files
+                    // in them might become readable later.
+                    self.consumer.error(false, AnyError::from(error));
+                    continue;
+                }
+            };
+            if self.completed.contains(&path) || self.unfinished.contains(&path) {
+                continue;
+            }
+            let metadata = match path.metadata() {
+                Ok(metadata) if metadata.is_file() => metadata,
+                // The file might have been deleted since `glob` listed it.
+                _ => continue,
+            };
+            let modified = metadata.modified()?;
+            let key = match self.config.order {
+                FileOrder::Name => SystemTime::UNIX_EPOCH,
+                FileOrder::Mtime => modified,
+            };
+            if next
+                .as_ref()
+                .map_or(true, |next| (&key, &path) < (&next.0, &next.1))
+            {
+                next = Some((key, path.clone()));
+            }
+            seen.insert(path, (metadata.len(), modified));
+        }
+
+        let previous = replace(&mut self.seen, seen);
+        Ok(next
+            .map(|(_, path)| path)
+            .filter(|path| previous.get(path) == self.seen.get(path)))
+    }
+
+    /// Passes the contents of the file at `path` to the consumer.  Returns
+    /// `false` if the endpoint was disconnected before the whole file was
+    /// read.
+    fn read_file(&mut self, path: &Path) -> AnyResult<bool> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open input file '{}'", path.display()))?;
+        let mut reader = match self.config.buffer_size_bytes {
+            Some(buffer_size) if buffer_size > 0 => BufReader::with_capacity(buffer_size, file),
+            _ => BufReader::new(file),
+        };
+
+        let mut last_byte = None;
+        loop {
+            if !self.wait_for_running() {
+                return Ok(false);
+            }
+            let data = reader
+                .fill_buf()
+                .with_context(|| format!("Failed to read input file '{}'", path.display()))?;
+            if data.is_empty() {
+                break;
+            }
+
+            // Leave it to the controller to handle errors.  There is noone we can
+            // forward the error to upstream.
+            let _ = self.consumer.input_fragment(data);
+            last_byte = data.last().copied();
+            let len = data.len();
+            reader.consume(len);
+        }
+
+        // Don't let the last record in this file run into the first record in
+        // the next one.
+        if last_byte.is_some_and(|c| c != b'\n') {
+            let _ = self.consumer.input_fragment(b"\n");
+        }
+        Ok(true)
+    }
+
+    /// Records that the file at `path` has been read completely and its data
+    /// is part of a step, and then disposes of it according to `after_read`.
+    fn complete_file(&mut self, path: PathBuf) -> AnyResult<()> {
+        self.completed.insert(&path)?;
+
+        let result = match self.config.after_read {
+            AfterRead::Keep => return Ok(()),
+            AfterRead::Delete => remove_file(&path)
+                .with_context(|| format!("Failed to delete input file '{}'", path.display())),
+            AfterRead::Move => {
+                let directory = Path::new(self.config.move_to.as_ref().unwrap());
+                let destination = directory.join(path.file_name().unwrap_or_default());
+                rename(&path, &destination).with_context(|| {
+                    format!(
+                        "Failed to move input file '{}' to '{}'",
+                        path.display(),
+                        destination.display()
+                    )
+                })
+            }
+        };
+        match result {
+            // Now that the file is gone, a new file with the same name should
+            // be read.
+            Ok(()) => self.completed.remove(&path),
+
+            // The file is still recorded as completed, so we won't read it
+            // again.
+            Err(error) => {
+                self.consumer.error(false, error);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The set of files that have been read completely.
+///
+/// If a state file is specified, the set is recorded there with one
+/// JSON-encoded path per line, so that it survives restarts.
+struct CompletedFiles {
+    state_path: Option<PathBuf>,
+    files: HashSet<PathBuf>,
+}
+
+impl CompletedFiles {
+    /// Reads the set of completed files from `state_path`, if it exists.
+    fn open(state_path: Option<PathBuf>) -> AnyResult<Self> {
+        let mut completed = Self {
+            state_path,
+            files: HashSet::new(),
+        };
+        let Some(state_path) = &completed.state_path else {
+            return Ok(completed);
+        };
+        let contents = match read(state_path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(completed),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read state file '{}'", state_path.display())
+                })
+            }
+        };
+
+        let mut n_lines = 0;
+        for line in contents.split_inclusive(|&c| c == b'\n') {
+            if !line.ends_with(b"\n") {
+                // Partial write at the time of a crash.
+                break;
+            }
+            let path: PathBuf = serde_json::from_slice(line).with_context(|| {
+                format!("Failed to parse state file '{}'", state_path.display())
+            })?;
+            completed.files.insert(path);
+            n_lines += 1;
+        }
+
+        // Forget about files that have been deleted or moved away, so that the
+        // state file doesn't grow without bound.
+        completed.files.retain(|path| path.exists());
+        if completed.files.len() < n_lines || contents.last().is_some_and(|&c| c != b'\n') {
+            completed.rewrite()?;
+        }
+        Ok(completed)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    /// Durably adds `path` to the set.
+    fn insert(&mut self, path: &Path) -> AnyResult<()> {
+        if let Some(state_path) = &self.state_path {
+            let mut line = serde_json::to_vec(path)?;
+            line.push(b'\n');
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(state_path)
+                .and_then(|mut file| {
+                    file.write_all(&line)?;
+                    file.sync_data()
+                })
+                .with_context(|| {
+                    format!("Failed to update state file '{}'", state_path.display())
+                })?;
+        }
+        self.files.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Durably removes `path` from the set.
+    fn remove(&mut self, path: &Path) -> AnyResult<()> {
+        if self.files.remove(path) {
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    /// Atomically replaces the state file by the current contents of the set.
+    fn rewrite(&self) -> AnyResult<()> {
+        let Some(state_path) = &self.state_path else {
+            return Ok(());
+        };
+        let mut contents = Vec::new();
+        for path in &self.files {
+            serde_json::to_writer(&mut contents, path)?;
+            contents.push(b'\n');
+        }
+
+        let mut tmp_path = state_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&contents)?;
+                file.sync_all()
+            })
+            .and_then(|_| rename(&tmp_path, state_path))
+            .with_context(|| format!("Failed to rewrite state file '{}'", state_path.display()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::{mock_input_pipeline, wait, TestStruct, DEFAULT_TIMEOUT_MS};
+    use std::{
+        fs::{create_dir, read_dir, read_to_string, write, File, OpenOptions},
+        io::Write,
+        path::Path,
+        thread::sleep,
+        time::{Duration, SystemTime},
+    };
+    use tempfile::TempDir;
+
+    fn test_struct(id: u32) -> TestStruct {
+        TestStruct {
+            id,
+            b: true,
+            i: None,
+            s: format!("s{id}"),
+        }
+    }
+
+    fn write_records(path: &Path, ids: &[u32]) {
+        let contents = ids
+            .iter()
+            .map(|id| format!("{id},true,,s{id}\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_directory_watch() {
+        let dir = TempDir::new().unwrap();
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: directory
+    config:
+        path: {:?}
+        state_path: {:?}
+        poll_interval_ms: 10
+format:
+    name: csv
+"#,
+            dir.path().join("*.csv").to_str().unwrap(),
+            dir.path().join("state.json").to_str().unwrap(),
+        );
+
+        // Files are read in name order, not in the order they were written.
+        // A missing newline at the end of a file must not merge records.
+        write_records(&dir.path().join("b.csv"), &[3, 4]);
+        write(dir.path().join("a.csv"), "1,true,,s1\n2,true,,s2").unwrap();
+        write_records(&dir.path().join("ignored.txt"), &[100]);
+
+        let (endpoint, _consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        endpoint.start(0).unwrap();
+        wait(|| zset.state().flushed.len() == 4, DEFAULT_TIMEOUT_MS).unwrap();
+
+        // New files are discovered.
+        write_records(&dir.path().join("c.csv"), &[5]);
+        wait(|| zset.state().flushed.len() == 5, DEFAULT_TIMEOUT_MS).unwrap();
+        for (i, upd) in zset.state().flushed.iter().enumerate() {
+            assert_eq!(upd.unwrap_insert(), &test_struct(i as u32 + 1));
+        }
+
+        // Files are recorded as completed once their data is part of a step.
+        let state_path = dir.path().join("state.json");
+        assert!(!state_path.exists());
+        endpoint.complete(0);
+        wait(
+            || read_to_string(&state_path).is_ok_and(|state| state.lines().count() == 3),
+            DEFAULT_TIMEOUT_MS,
+        )
+        .unwrap();
+        endpoint.disconnect();
+
+        // After a restart, only new files are read.
+        let (endpoint, _consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        write_records(&dir.path().join("0.csv"), &[6]);
+        endpoint.start(0).unwrap();
+        wait(|| zset.state().flushed.len() == 1, DEFAULT_TIMEOUT_MS).unwrap();
+        sleep(Duration::from_millis(100));
+        assert_eq!(zset.state().flushed.len(), 1);
+        assert_eq!(zset.state().flushed[0].unwrap_insert(), &test_struct(6));
+        endpoint.disconnect();
+    }
+
+    #[test]
+    fn test_directory_move() {
+        let dir = TempDir::new().unwrap();
+        let input_dir = dir.path().join("input");
+        let done_dir = dir.path().join("done");
+        create_dir(&input_dir).unwrap();
+        create_dir(&done_dir).unwrap();
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: directory
+    config:
+        path: {:?}
+        order: mtime
+        watch: false
+        poll_interval_ms: 10
+        after_read: move
+        move_to: {:?}
+format:
+    name: csv
+"#,
+            input_dir.join("*").to_str().unwrap(),
+            done_dir.to_str().unwrap(),
+        );
+
+        // Files are read in modification time order.
+        let now = SystemTime::now();
+        for (name, id, age) in [("a.csv", 2, 10), ("b.csv", 1, 20), ("c.csv", 3, 0)] {
+            let path = input_dir.join(name);
+            write_records(&path, &[id]);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(now - Duration::from_secs(age))
+                .unwrap();
+        }
+
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        endpoint.start(0).unwrap();
+        wait(|| consumer.state().eoi, DEFAULT_TIMEOUT_MS).unwrap();
+        for (i, upd) in zset.state().flushed.iter().enumerate() {
+            assert_eq!(upd.unwrap_insert(), &test_struct(i as u32 + 1));
+        }
+        assert_eq!(zset.state().flushed.len(), 3);
+
+        // Files are only moved once their data is part of a step.
+        assert_eq!(read_dir(&input_dir).unwrap().count(), 3);
+        endpoint.complete(0);
+        wait(
+            || read_dir(&done_dir).unwrap().count() == 3,
+            DEFAULT_TIMEOUT_MS,
+        )
+        .unwrap();
+        assert_eq!(read_dir(&input_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_directory_stability() {
+        let dir = TempDir::new().unwrap();
+        let config_str = format!(
+            r#"
+stream: test_input
+transport:
+    name: directory
+    config:
+        path: {:?}
+        poll_interval_ms: 1000
+format:
+    name: csv
+"#,
+            dir.path().join("*.csv").to_str().unwrap(),
+        );
+
+        let (endpoint, _consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&config_str).unwrap(),
+        )
+        .unwrap();
+        endpoint.start(0).unwrap();
+        sleep(Duration::from_millis(100));
+
+        // A file that is still being written is not read until its size and
+        // modification time stay the same across two polls.
+        let path = dir.path().join("a.csv");
+        write(&path, "1,true,,s1\n2,tr").unwrap();
+        sleep(Duration::from_millis(1500));
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"ue,,s2\n")
+            .unwrap();
+        sleep(Duration::from_millis(1000));
+        assert_eq!(zset.state().flushed.len(), 0);
+
+        wait(|| zset.state().flushed.len() == 2, DEFAULT_TIMEOUT_MS).unwrap();
+        for (i, upd) in zset.state().flushed.iter().enumerate() {
+            assert_eq!(upd.unwrap_insert(), &test_struct(i as u32 + 1));
+        }
+        endpoint.disconnect();
+    }
+}
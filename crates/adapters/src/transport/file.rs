@@ -1,28 +1,39 @@
 use super::{
-    InputConsumer, InputEndpoint, InputReader, InputTransport, OutputEndpoint, OutputTransport,
-    Step,
+    compression::decompress, AtomicStep, InputConsumer, InputEndpoint, InputReader, InputTransport,
+    OutputEndpoint, OutputTransport, Step,
 };
 use crate::{OutputEndpointConfig, PipelineState};
-use anyhow::{Error as AnyError, Result as AnyResult};
+use anyhow::{anyhow, bail, Context, Error as AnyError, Result as AnyResult};
 use crossbeam::sync::{Parker, Unparker};
+use log::{error, warn};
 use num_traits::FromPrimitive;
-use pipeline_types::transport::file::{FileInputConfig, FileOutputConfig};
-use serde::Deserialize;
+use pipeline_types::transport::{
+    compression::Compression,
+    file::{FileInputConfig, FileOutputConfig},
+};
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
-    fs::File,
-    io::{BufRead, BufReader, Write},
+    collections::VecDeque,
+    fs::{read, rename, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::PathBuf,
     sync::{
         atomic::{AtomicU32, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    thread::{sleep, spawn},
+    thread::{self, sleep, spawn, Builder, JoinHandle},
     time::Duration,
 };
 
 const SLEEP_MS: u64 = 200;
 
+/// Read size used by the fault-tolerant reader when `buffer_size_bytes` is not
+/// specified.
+const DEFAULT_BUFFER_SIZE: usize = 8192;
+
 /// [`InputTransport`] implementation that reads data from a file.
 ///
 /// The input transport factory gives this transport the name `file`.
@@ -38,28 +49,62 @@ impl InputTransport for FileInputTransport {
     ///
     /// See [`InputTransport::new_endpoint()`] for more information.
     fn new_endpoint(&self, config: &YamlValue) -> AnyResult<Box<dyn InputEndpoint>> {
-        let ep = FileInputEndpoint {
-            config: FileInputConfig::deserialize(config)?,
+        let config = FileInputConfig::deserialize(config)?;
+        let journal = match &config.fault_tolerance {
+            None => None,
+            Some(_) if config.compression != Compression::None => {
+                bail!("fault tolerance is not supported for compressed input files")
+            }
+            Some(ft) => {
+                let path = match &ft.journal_path {
+                    Some(path) => PathBuf::from(path),
+                    None => PathBuf::from(format!("{}.journal", config.path)),
+                };
+                Some(Arc::new(Mutex::new(Journal::open(path)?)))
+            }
         };
-        Ok(Box::new(ep))
+        Ok(Box::new(FileInputEndpoint { config, journal }))
     }
 }
 
 struct FileInputEndpoint {
     config: FileInputConfig,
+
+    /// The journal of steps, if fault tolerance is enabled.
+    journal: Option<Arc<Mutex<Journal>>>,
 }
 
 impl InputEndpoint for FileInputEndpoint {
     fn open(
         &self,
         consumer: Box<dyn InputConsumer>,
-        _start_step: Step,
+        start_step: Step,
     ) -> AnyResult<Box<dyn InputReader>> {
-        Ok(Box::new(FileInputReader::new(&self.config, consumer)?))
+        match &self.journal {
+            None => Ok(Box::new(FileInputReader::new(&self.config, consumer)?)),
+            Some(journal) => Ok(Box::new(FtFileInputReader::new(
+                &self.config,
+                journal,
+                start_step,
+                consumer,
+            )?)),
+        }
     }
 
     fn is_fault_tolerant(&self) -> bool {
-        false
+        self.journal.is_some()
+    }
+
+    fn expire(&self, step: Step) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.lock().unwrap().expire(step) {
+                warn!("{e:#}");
+            }
+        }
+    }
+
+    fn steps(&self) -> AnyResult<Range<Step>> {
+        Ok(self.journal.as_ref().unwrap().lock().unwrap().steps())
     }
 }
 
@@ -77,6 +122,7 @@ impl FileInputReader {
             Some(buffer_size) if buffer_size > 0 => BufReader::with_capacity(buffer_size, file),
             _ => BufReader::new(file),
         };
+        let consumer = decompress(config.compression, &config.path, consumer)?;
 
         let parker = Parker::new();
         let unparker = Some(parker.unparker().clone());
@@ -112,7 +158,7 @@ impl FileInputReader {
                             consumer.error(true, AnyError::from(e));
                             return;
                         }
-                        Ok(data) if data.is_empty() => {
+                        Ok([]) => {
                             if !follow {
                                 let _ = consumer.eoi();
                                 return;
@@ -171,6 +217,470 @@ impl Drop for FileInputReader {
     }
 }
 
+/// One entry in a [`Journal`]: the range of byte offsets in the input file
+/// that make up `step`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct JournalEntry {
+    step: Step,
+    offsets: Range<u64>,
+}
+
+/// Journal that records the division of an input file into steps.
+///
+/// The journal is a file with one JSON-encoded [`JournalEntry`] per line.  It
+/// is only ever appended to, except by [`Journal::expire`], which atomically
+/// replaces it by a shorter version.  A crash in the middle of an append can
+/// leave a partial line at the end of the file, which [`Journal::open`]
+/// discards.
+struct Journal {
+    path: PathBuf,
+
+    /// Steps recorded in the journal, in order.  Each entry starts at the
+    /// offset where the previous one ends.
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    /// Reads the journal at `path`, or starts a new one if it does not exist.
+    fn open(path: PathBuf) -> AnyResult<Self> {
+        let mut journal = Self {
+            path,
+            entries: VecDeque::new(),
+        };
+        let contents = match read(&journal.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(journal),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read journal '{}'", journal.path.display())
+                })
+            }
+        };
+
+        let mut valid_len = 0;
+        for line in contents.split_inclusive(|&c| c == b'\n') {
+            if !line.ends_with(b"\n") {
+                // Partial write at the time of a crash.
+                break;
+            }
+            let entry: JournalEntry = serde_json::from_slice(line).with_context(|| {
+                format!(
+                    "Failed to parse entry at byte offset {valid_len} in journal '{}'",
+                    journal.path.display()
+                )
+            })?;
+            journal.check_next(&entry)?;
+            journal.entries.push_back(entry);
+            valid_len += line.len();
+        }
+
+        if valid_len < contents.len() {
+            warn!(
+                "discarding partial entry at the end of journal '{}'",
+                journal.path.display()
+            );
+            OpenOptions::new()
+                .write(true)
+                .open(&journal.path)
+                .and_then(|file| file.set_len(valid_len as u64))
+                .with_context(|| {
+                    format!("Failed to truncate journal '{}'", journal.path.display())
+                })?;
+        }
+        Ok(journal)
+    }
+
+    /// Checks that `entry` can be appended to the journal.
+    fn check_next(&self, entry: &JournalEntry) -> AnyResult<()> {
+        if let Some(last) = self.entries.back() {
+            if entry.step != last.step + 1 || entry.offsets.start != last.offsets.end {
+                bail!(
+                    "journal '{}' entry {entry:?} does not follow {last:?}",
+                    self.path.display()
+                );
+            }
+        }
+        if entry.offsets.start > entry.offsets.end {
+            bail!(
+                "journal '{}' entry {entry:?} has invalid offsets",
+                self.path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the range of steps recorded in the journal.
+    fn steps(&self) -> Range<Step> {
+        match (self.entries.front(), self.entries.back()) {
+            (Some(first), Some(last)) => first.step..last.step + 1,
+            _ => 0..0,
+        }
+    }
+
+    /// Returns the entry for `step`, if the journal has one.
+    fn find(&self, step: Step) -> Option<&JournalEntry> {
+        let first = self.entries.front()?;
+        self.entries
+            .get(step.checked_sub(first.step)?.try_into().ok()?)
+    }
+
+    /// Returns the byte offset at which `step` starts, which must be a step
+    /// recorded in the journal or the one right after it.
+    fn start_offset(&self, step: Step) -> AnyResult<u64> {
+        let steps = self.steps();
+        if let Some(entry) = self.find(step) {
+            Ok(entry.offsets.start)
+        } else if step == steps.end {
+            Ok(self.entries.back().map_or(0, |last| last.offsets.end))
+        } else {
+            Err(anyhow!(
+                "cannot start reading at step {step} because journal '{}' records steps {steps:?}",
+                self.path.display()
+            ))
+        }
+    }
+
+    /// Durably appends `entry` to the journal.
+    fn append(&mut self, entry: JournalEntry) -> AnyResult<()> {
+        if self.entries.is_empty() && entry.step != 0 {
+            bail!(
+                "journal '{}' must start at step 0, not {}",
+                self.path.display(),
+                entry.step
+            );
+        }
+        self.check_next(&entry)?;
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                file.write_all(&line)?;
+                file.sync_data()
+            })
+            .with_context(|| format!("Failed to append to journal '{}'", self.path.display()))?;
+        self.entries.push_back(entry);
+        Ok(())
+    }
+
+    /// Discards entries for steps less than `step`, except that the last entry
+    /// is always kept because it records where the next step starts.
+    fn expire(&mut self, step: Step) -> AnyResult<()> {
+        let mut changed = false;
+        while self.entries.len() > 1 && self.entries.front().unwrap().step < step {
+            self.entries.pop_front();
+            changed = true;
+        }
+        if !changed {
+            return Ok(());
+        }
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let mut contents = Vec::new();
+        for entry in &self.entries {
+            serde_json::to_writer(&mut contents, entry)?;
+            contents.push(b'\n');
+        }
+        File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&contents)?;
+                file.sync_all()
+            })
+            .and_then(|_| rename(&tmp_path, &self.path))
+            .with_context(|| format!("Failed to rewrite journal '{}'", self.path.display()))
+    }
+}
+
+/// A reader for fault-tolerant file input.
+struct FtFileInputReader {
+    status: Arc<AtomicU32>,
+
+    /// The greatest step that the reader may read, as passed to
+    /// [`InputReader::start`].
+    run_step: Arc<AtomicStep>,
+
+    /// The greatest step whose completion has been requested.
+    complete_step: Arc<Mutex<Option<Step>>>,
+
+    unparker: Unparker,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl FtFileInputReader {
+    fn new(
+        config: &FileInputConfig,
+        journal: &Arc<Mutex<Journal>>,
+        start_step: Step,
+        mut consumer: Box<dyn InputConsumer>,
+    ) -> AnyResult<Self> {
+        let offset = journal.lock().unwrap().start_offset(start_step)?;
+        let mut file = File::open(&config.path).map_err(|e| {
+            AnyError::msg(format!("Failed to open input file '{}': {e}", config.path))
+        })?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        // Without `follow`, the input ends at the current end of the file.
+        let eof_offset = if config.follow {
+            None
+        } else {
+            Some(file.metadata()?.len())
+        };
+
+        let parker = Parker::new();
+        let unparker = parker.unparker().clone();
+        let status = Arc::new(AtomicU32::new(PipelineState::Paused as u32));
+        let run_step = Arc::new(AtomicStep::new(0));
+        let complete_step = Arc::new(Mutex::new(None));
+        let ft = config.fault_tolerance.as_ref().unwrap();
+        let mut worker = FtWorker {
+            status: status.clone(),
+            run_step: run_step.clone(),
+            complete_step: complete_step.clone(),
+            parker,
+            journal: journal.clone(),
+            file,
+            buffer_size: config
+                .buffer_size_bytes
+                .filter(|&size| size > 0)
+                .unwrap_or(DEFAULT_BUFFER_SIZE),
+            max_step_bytes: ft.max_step_bytes.unwrap_or(u64::MAX).max(1),
+            eof_offset,
+            offset,
+            pending: Vec::new(),
+        };
+        let join_handle = Builder::new().name("file-ft-input".into()).spawn(move || {
+            if let Err(error) = worker.run(start_step, consumer.as_mut()) {
+                error!("Fault-tolerant file input endpoint failed due to: {error:#}");
+                consumer.error(true, error);
+            }
+        })?;
+
+        Ok(Self {
+            status,
+            run_step,
+            complete_step,
+            unparker,
+            join_handle: Some(join_handle),
+        })
+    }
+}
+
+impl InputReader for FtFileInputReader {
+    fn start(&self, step: Step) -> AnyResult<()> {
+        self.run_step.store(step, Ordering::Release);
+        self.status
+            .store(PipelineState::Running as u32, Ordering::Release);
+        self.unparker.unpark();
+        Ok(())
+    }
+
+    fn pause(&self) -> AnyResult<()> {
+        self.status
+            .store(PipelineState::Paused as u32, Ordering::Release);
+        Ok(())
+    }
+
+    fn complete(&self, new_step: Step) {
+        let mut complete_step = self.complete_step.lock().unwrap();
+        match *complete_step {
+            Some(step) if new_step <= step => (),
+            _ => {
+                *complete_step = Some(new_step);
+                self.unparker.unpark();
+            }
+        }
+    }
+
+    fn disconnect(&self) {
+        self.status
+            .store(PipelineState::Terminated as u32, Ordering::Release);
+        self.unparker.unpark();
+    }
+}
+
+impl Drop for FtFileInputReader {
+    fn drop(&mut self) {
+        self.disconnect();
+        if let Some(join_handle) = self.join_handle.take() {
+            // The reader might get dropped from a callback executed from the
+            // worker thread.  We must not join ourselves.
+            if join_handle.thread().id() != thread::current().id() {
+                let _ = join_handle.join();
+            }
+        }
+    }
+}
+
+/// Worker thread state for [`FtFileInputReader`].
+struct FtWorker {
+    status: Arc<AtomicU32>,
+    run_step: Arc<AtomicStep>,
+    complete_step: Arc<Mutex<Option<Step>>>,
+    parker: Parker,
+    journal: Arc<Mutex<Journal>>,
+    file: File,
+    buffer_size: usize,
+    max_step_bytes: u64,
+
+    /// Offset of the end of input, if the file isn't being followed.
+    eof_offset: Option<u64>,
+
+    /// Offset in the file of the first byte not yet passed to the consumer.
+    offset: u64,
+
+    /// Data read from the file starting at `offset`, not yet passed to the
+    /// consumer.
+    pending: Vec<u8>,
+}
+
+impl FtWorker {
+    /// Waits until the reader may push data for `step`.  Returns `false` if
+    /// the reader was disconnected.
+    fn wait_for_start(&self, step: Step) -> bool {
+        loop {
+            match PipelineState::from_u32(self.status.load(Ordering::Acquire)) {
+                Some(PipelineState::Running) if step <= self.run_step.load(Ordering::Acquire) => {
+                    return true
+                }
+                Some(PipelineState::Terminated) => return false,
+                _ => self.parker.park_timeout(Duration::from_millis(SLEEP_MS)),
+            }
+        }
+    }
+
+    fn is_completion_requested(&self, step: Step) -> bool {
+        match *self.complete_step.lock().unwrap() {
+            Some(complete_step) => step <= complete_step,
+            None => false,
+        }
+    }
+
+    /// Reads more data from the file into `pending`.  Returns the number of
+    /// bytes read, which is zero at the end of the file.
+    fn fill(&mut self) -> AnyResult<usize> {
+        let mut limit = self.buffer_size as u64;
+        if let Some(eof_offset) = self.eof_offset {
+            let read_offset = self.offset + self.pending.len() as u64;
+            limit = limit.min(eof_offset.saturating_sub(read_offset));
+        }
+        let n = (&mut self.file)
+            .take(limit)
+            .read_to_end(&mut self.pending)?;
+        Ok(n)
+    }
+
+    /// Passes the first `n` bytes in `pending` to `consumer`, followed by an
+    /// end-of-input notification if that reaches the end of the input.
+    fn deliver(&mut self, n: usize, consumer: &mut dyn InputConsumer) {
+        let _ = consumer.input_fragment(&self.pending[..n]);
+        self.pending.drain(..n);
+        self.offset += n as u64;
+        if self.eof_offset == Some(self.offset) {
+            let _ = consumer.eoi();
+        }
+    }
+
+    /// Returns the number of bytes at the start of `pending` that may be
+    /// added to a step that already contains `step_bytes` bytes.  Steps only
+    /// end after a newline, or at the end of the input.
+    fn deliverable(&self, step_bytes: u64) -> usize {
+        let at_eof = self
+            .eof_offset
+            .is_some_and(|eof_offset| self.offset + self.pending.len() as u64 >= eof_offset);
+        let budget = self
+            .max_step_bytes
+            .saturating_sub(step_bytes)
+            .try_into()
+            .unwrap_or(usize::MAX);
+        if at_eof && self.pending.len() <= budget {
+            return self.pending.len();
+        }
+        let window = &self.pending[..self.pending.len().min(budget)];
+        match window.iter().rposition(|&c| c == b'\n') {
+            Some(pos) => pos + 1,
+            None if step_bytes == 0 && self.pending.len() > budget => {
+                // A line bigger than `max_step_bytes` gets a step of its own.
+                self.pending
+                    .iter()
+                    .position(|&c| c == b'\n')
+                    .map_or(if at_eof { self.pending.len() } else { 0 }, |pos| pos + 1)
+            }
+            None => 0,
+        }
+    }
+
+    fn run(&mut self, start_step: Step, consumer: &mut dyn InputConsumer) -> AnyResult<()> {
+        for step in start_step.. {
+            consumer.start_step(step);
+            if !self.wait_for_start(step) {
+                return Ok(());
+            }
+
+            // We might be starting out at the end of the input, in which case
+            // `deliver` will never report it.
+            if step == start_step && self.eof_offset == Some(self.offset) {
+                let _ = consumer.eoi();
+            }
+
+            let entry = self.journal.lock().unwrap().find(step).cloned();
+            if let Some(entry) = entry {
+                // Replay a step recorded in the journal.
+                while self.offset < entry.offsets.end {
+                    if !self.wait_for_start(step) {
+                        return Ok(());
+                    }
+                    if self.pending.is_empty() && self.fill()? == 0 {
+                        bail!(
+                            "input file ends at byte offset {} but journal records step {step} as ending at {}",
+                            self.offset,
+                            entry.offsets.end
+                        );
+                    }
+                    let n = self
+                        .pending
+                        .len()
+                        .min((entry.offsets.end - self.offset) as usize);
+                    self.deliver(n, consumer);
+                }
+                consumer.committed(step);
+                continue;
+            }
+
+            // Add data to a new step.
+            let start_offset = self.offset;
+            while !self.is_completion_requested(step) {
+                if !self.wait_for_start(step) {
+                    return Ok(());
+                }
+                let step_bytes = self.offset - start_offset;
+                let n = self.deliverable(step_bytes);
+                if n > 0 {
+                    self.deliver(n, consumer);
+                } else if step_bytes > 0
+                    && step_bytes + self.pending.len() as u64 >= self.max_step_bytes
+                {
+                    // The step is full.
+                    break;
+                } else if self.fill()? == 0 {
+                    self.parker.park_timeout(Duration::from_millis(SLEEP_MS));
+                }
+            }
+
+            self.journal.lock().unwrap().append(JournalEntry {
+                step,
+                offsets: start_offset..self.offset,
+            })?;
+            consumer.committed(step);
+        }
+        unreachable!()
+    }
+}
+
 /// [`OutputTransport`] implementation that writes data to a file.
 ///
 /// The output transport factory gives this transport the name `file`.
@@ -233,14 +743,23 @@ impl OutputEndpoint for FileOutputEndpoint {
 
 #[cfg(test)]
 mod test {
+    use super::FileInputTransport;
     use crate::{
         deserialize_without_context,
-        test::{mock_input_pipeline, wait, DEFAULT_TIMEOUT_MS},
+        test::{mock_input_pipeline, wait, ConsumerCall, DummyInputReceiver, DEFAULT_TIMEOUT_MS},
+        transport::{InputEndpoint, Step},
+        InputTransport,
     };
     use csv::WriterBuilder as CsvWriterBuilder;
     use serde::{Deserialize, Serialize};
-    use std::{io::Write, thread::sleep, time::Duration};
-    use tempfile::NamedTempFile;
+    use std::{
+        fs::{File, OpenOptions},
+        io::Write,
+        path::Path,
+        thread::sleep,
+        time::Duration,
+    };
+    use tempfile::{NamedTempFile, TempDir};
 
     #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
     struct TestStruct {
@@ -398,4 +917,163 @@ format:
 
         endpoint.disconnect();
     }
+
+    fn ft_endpoint(path: &Path, follow: bool, ft_config: &str) -> Box<dyn InputEndpoint> {
+        let config_str = format!(
+            r#"
+path: {:?}
+follow: {follow}
+fault_tolerance: {{{ft_config}}}
+"#,
+            path.to_str().unwrap()
+        );
+        let endpoint = FileInputTransport
+            .new_endpoint(&serde_yaml::from_str(&config_str).unwrap())
+            .unwrap();
+        assert!(endpoint.is_fault_tolerant());
+        endpoint
+    }
+
+    fn append(path: &Path, data: &str) {
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    fn fragment(data: &str) -> ConsumerCall {
+        ConsumerCall::InputFragment(data.into())
+    }
+
+    /// Reads some steps, "crashes", and then checks that the steps are
+    /// replayed identically after a restart.
+    #[test]
+    fn test_ft_file_restart() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("input.csv");
+        let journal_path = dir.path().join("input.csv.journal");
+        File::create(&path).unwrap();
+        append(&path, "0\n1\n");
+
+        let endpoint = ft_endpoint(&path, true, "");
+        assert_eq!(endpoint.steps().unwrap(), 0..0);
+
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 0).unwrap();
+        reader.start(0).unwrap();
+        receiver.expect(vec![ConsumerCall::StartStep(0), fragment("0\n1\n")]);
+
+        // Complete step 0.
+        reader.complete(0);
+        receiver.expect(vec![ConsumerCall::StartStep(1)]);
+        receiver.wait_to_complete(0);
+        assert_eq!(endpoint.steps().unwrap(), 0..1);
+
+        // We shouldn't get more data until we ask for step 1.  A partial line
+        // must be held back until it is complete.
+        append(&path, "2\n3");
+        receiver.expect_eof();
+        reader.start(10).unwrap();
+        receiver.expect(vec![fragment("2\n")]);
+        append(&path, "\n4\n");
+        receiver.expect(vec![fragment("3\n4\n")]);
+
+        // Complete step 1.
+        reader.complete(1);
+        receiver.expect(vec![ConsumerCall::StartStep(2)]);
+        receiver.wait_to_complete(1);
+        assert_eq!(endpoint.steps().unwrap(), 0..2);
+
+        // Crash while appending to the journal.
+        append(&path, "5\n");
+        drop(reader);
+        drop(endpoint);
+        append(&journal_path, r#"{"step":2,"offs"#);
+
+        // After restarting, step 1 should be replayed exactly, followed by new
+        // data in step 2.
+        let endpoint = ft_endpoint(&path, true, "");
+        assert_eq!(endpoint.steps().unwrap(), 0..2);
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 1).unwrap();
+        reader.start(1).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(1),
+            fragment("2\n3\n4\n"),
+            ConsumerCall::StartStep(2),
+        ]);
+        receiver.wait_to_complete(1);
+        receiver.expect_eof();
+
+        reader.start(2).unwrap();
+        receiver.expect(vec![fragment("5\n")]);
+        reader.complete(2);
+        receiver.expect(vec![ConsumerCall::StartStep(3)]);
+        receiver.wait_to_complete(2);
+        assert_eq!(endpoint.steps().unwrap(), 0..3);
+        drop(reader);
+
+        // Expired steps can no longer be read.
+        endpoint.expire(2);
+        assert_eq!(endpoint.steps().unwrap(), 2..3);
+        assert!(endpoint
+            .open(DummyInputReceiver::new().consumer(), 1)
+            .is_err());
+        let endpoint = ft_endpoint(&path, true, "");
+        assert_eq!(endpoint.steps().unwrap(), 2..3);
+
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 2).unwrap();
+        reader.start(2).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(2),
+            fragment("5\n"),
+            ConsumerCall::StartStep(3),
+        ]);
+        receiver.expect_eof();
+    }
+
+    /// Checks that `max_step_bytes` divides input into steps at line
+    /// boundaries, and that end of input is reported without `follow`.
+    #[test]
+    fn test_ft_file_max_step_bytes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("input.csv");
+        File::create(&path).unwrap();
+        append(&path, "aa\nbb\nccccccc\nd");
+
+        let endpoint = ft_endpoint(&path, false, "max_step_bytes: 5");
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 0).unwrap();
+        reader.start(Step::MAX).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(0),
+            fragment("aa\n"),
+            ConsumerCall::StartStep(1),
+            fragment("bb\n"),
+            ConsumerCall::StartStep(2),
+            fragment("ccccccc\n"),
+            ConsumerCall::StartStep(3),
+            fragment("d"),
+            ConsumerCall::Eoi,
+        ]);
+        receiver.wait_to_complete(2);
+        receiver.expect_eof();
+
+        reader.complete(3);
+        receiver.expect(vec![ConsumerCall::StartStep(4)]);
+        receiver.wait_to_complete(3);
+        assert_eq!(endpoint.steps().unwrap(), 0..4);
+        drop(reader);
+
+        // Replaying step 3 reports the end of input again.
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 3).unwrap();
+        reader.start(3).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(3),
+            fragment("d"),
+            ConsumerCall::Eoi,
+            ConsumerCall::StartStep(4),
+        ]);
+        receiver.expect_eof();
+    }
 }
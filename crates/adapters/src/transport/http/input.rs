@@ -3,24 +3,29 @@ use crate::{
     transport::{InputReader, Step},
     ControllerError, InputConsumer, InputEndpoint, ParseError, PipelineState, TransportConfig,
 };
-use actix::Message;
 use actix_web::{web::Payload, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message as WsMessage, MessageStream, Session};
 use anyhow::{anyhow, Error as AnyError, Result as AnyResult};
 use circular_queue::CircularQueue;
 use futures_util::StreamExt;
 use log::debug;
 use num_traits::FromPrimitive;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
+    collections::VecDeque,
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc, Mutex,
     },
     time::Duration,
 };
-use tokio::{sync::watch, time::timeout};
+use tokio::{
+    select,
+    sync::watch,
+    time::{sleep, timeout},
+};
 
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) enum HttpIngressMode {
@@ -60,6 +65,21 @@ impl HttpInputTransport {
     }
 }
 
+/// Acknowledgement sent to a WebSocket client for each input message.
+#[derive(Serialize)]
+struct InputAck {
+    /// Sequence number of the message, counting from 0.
+    sequence_number: u64,
+
+    /// The step of the pipeline that includes the message.  The message's
+    /// effects are visible in the outputs for this step.
+    step: Step,
+
+    /// Errors parsing the message.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parse_errors: Vec<ParseError>,
+}
+
 struct HttpInputEndpointInner {
     name: String,
     state: AtomicU32,
@@ -67,6 +87,13 @@ struct HttpInputEndpointInner {
     consumer: Mutex<Option<Box<dyn InputConsumer>>>,
     /// Ingest data even if the pipeline is paused.
     force: bool,
+    /// Number of WebSocket messages pushed to the consumer.  The lock is held
+    /// while pushing a message, so that [`InputReader::complete`] can tell
+    /// which messages precede a step.
+    num_messages: Mutex<u64>,
+    /// The most recent step whose completion was requested, with the number
+    /// of WebSocket messages pushed before the request.
+    step_notifier: watch::Sender<Option<(Step, u64)>>,
 }
 
 impl HttpInputEndpointInner {
@@ -81,14 +108,12 @@ impl HttpInputEndpointInner {
             status_notifier: watch::channel(()).0,
             consumer: Mutex::new(None),
             force,
+            num_messages: Mutex::new(0),
+            step_notifier: watch::channel(None).0,
         }
     }
 }
 
-#[derive(Message)]
-#[rtype(result = "()")]
-struct EndpointStateNotification;
-
 /// Input endpoint that streams input data via HTTP.
 #[derive(Clone)]
 pub(crate) struct HttpInputEndpoint {
@@ -124,6 +149,22 @@ impl HttpInputEndpoint {
             .input_fragment(bytes)
     }
 
+    /// Pushes a WebSocket message, which must consist of complete records.
+    /// Returns the number of messages pushed so far, including this one.
+    fn push_message(&self, bytes: &[u8]) -> (u64, Vec<ParseError>) {
+        let mut num_messages = self.inner.num_messages.lock().unwrap();
+        let errors = self
+            .inner
+            .consumer
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .input_chunk(bytes);
+        *num_messages += 1;
+        (*num_messages, errors)
+    }
+
     fn eoi(&self) -> Vec<ParseError> {
         self.inner.consumer.lock().unwrap().as_mut().unwrap().eoi()
     }
@@ -204,6 +245,109 @@ impl HttpInputEndpoint {
             Err(PipelineError::parse_errors(num_errors, errors.asc_iter()))
         }
     }
+
+    /// Read input messages from a WebSocket and push them to the pipeline.
+    ///
+    /// Each text or binary message must contain complete records.  Once the
+    /// pipeline has included a message in a step, the client receives an
+    /// [`InputAck`] for it.  While the endpoint is paused, no messages are
+    /// read, which applies backpressure to the client.
+    ///
+    /// Returns when the client closes the connection or when the pipeline
+    /// terminates.  `request_step` is invoked if acknowledgements are
+    /// outstanding but the pipeline has not started a new step in a while.
+    pub(crate) async fn complete_websocket(
+        &self,
+        mut session: Session,
+        mut messages: MessageStream,
+        request_step: Box<dyn Fn() + Send>,
+    ) {
+        debug!("HTTP input endpoint '{}': WebSocket connected", self.name());
+
+        let mut status_watch = self.inner.status_notifier.subscribe();
+        let mut step_watch = self.inner.step_notifier.subscribe();
+        let mut sequence_number = 0;
+
+        // Messages that have not been acknowledged yet, as the number of
+        // messages pushed up to and including each one, its sequence number,
+        // and its parse errors.
+        let mut unacked = VecDeque::new();
+
+        loop {
+            let latest_step = *step_watch.borrow_and_update();
+            if let Some((step, num_messages)) = latest_step {
+                while unacked.front().is_some_and(|(n, _, _)| *n <= num_messages) {
+                    let (_, sequence_number, parse_errors) = unacked.pop_front().unwrap();
+                    let ack = InputAck {
+                        sequence_number,
+                        step,
+                        parse_errors,
+                    };
+                    if session
+                        .text(serde_json::to_string(&ack).unwrap())
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+
+            let running = match self.state() {
+                PipelineState::Paused => false,
+                PipelineState::Running => true,
+                PipelineState::Terminated => {
+                    let _ = session
+                        .close(Some(CloseReason {
+                            code: CloseCode::Away,
+                            description: Some("pipeline is shutting down".to_string()),
+                        }))
+                        .await;
+                    return;
+                }
+            };
+
+            select! {
+                _ = status_watch.changed() => (),
+                _ = step_watch.changed() => (),
+                message = messages.next(), if running => match message {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        let (n, errors) = self.push_message(text.as_bytes());
+                        unacked.push_back((n, sequence_number, errors));
+                        sequence_number += 1;
+                    }
+                    Some(Ok(WsMessage::Binary(bytes))) => {
+                        let (n, errors) = self.push_message(&bytes);
+                        unacked.push_back((n, sequence_number, errors));
+                        sequence_number += 1;
+                    }
+                    Some(Ok(WsMessage::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | None => break,
+                    Some(Ok(_)) => (),
+                    Some(Err(e)) => {
+                        self.error(false, anyhow!("WebSocket protocol error: {e}"));
+                        break;
+                    }
+                },
+                _ = sleep(Duration::from_millis(1_000)) => {
+                    if !unacked.is_empty() {
+                        request_step();
+                    }
+                }
+            }
+        }
+
+        self.eoi();
+        let _ = session.close(None).await;
+        debug!(
+            "HTTP input endpoint '{}': WebSocket closed after {sequence_number} messages",
+            self.name()
+        );
+    }
 }
 
 impl InputEndpoint for HttpInputEndpoint {
@@ -242,6 +386,13 @@ impl InputReader for HttpInputEndpoint {
         Ok(())
     }
 
+    fn complete(&self, step: Step) {
+        let num_messages = *self.inner.num_messages.lock().unwrap();
+        self.inner
+            .step_notifier
+            .send_replace(Some((step, num_messages)));
+    }
+
     fn disconnect(&self) {
         self.inner
             .state
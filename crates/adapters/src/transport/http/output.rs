@@ -1,11 +1,13 @@
 use crate::{AsyncErrorCallback, OutputEndpoint, TransportConfig};
 use actix_web::{http::header::ContentType, web::Bytes, HttpResponse};
+use actix_ws::{Message as WsMessage, MessageStream, Session};
 use anyhow::{anyhow, Result as AnyResult};
 use async_stream::stream;
 use crossbeam::sync::ShardedLock;
+use futures_util::StreamExt;
 use log::debug;
 use log::error;
-use serde::{ser::SerializeStruct, Serializer};
+use serde::{ser::SerializeStruct, Deserialize, Serializer};
 use serde_json::value::RawValue;
 use serde_yaml::Value as YamlValue;
 use std::{
@@ -17,7 +19,11 @@ use std::{
     time::Duration,
 };
 use tokio::{
-    sync::broadcast::{self, error::RecvError},
+    select,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, Mutex as AsyncMutex,
+    },
     time::timeout,
 };
 
@@ -70,12 +76,35 @@ impl Buffer {
     }
 }
 
+/// Message from a WebSocket client that grants the endpoint permission to
+/// send `credit` more chunks.
+#[derive(Deserialize)]
+struct FlowControl {
+    credit: u64,
+}
+
+/// Channel that carries output chunks to the client.
+#[derive(Clone)]
+enum ChunkSender {
+    /// Chunks are broadcast to HTTP responses, which drop chunks if the client
+    /// falls behind.
+    Broadcast(broadcast::Sender<Buffer>),
+
+    /// Chunks are sent to a WebSocket session, which only forwards them to
+    /// the client as it grants credit.  When the channel is full, the
+    /// endpoint blocks, which applies backpressure to the pipeline.
+    Bounded(mpsc::Sender<Buffer>),
+}
+
 struct HttpOutputEndpointInner {
     name: String,
     format: Format,
 
     total_buffers: AtomicU64,
-    sender: ShardedLock<Option<broadcast::Sender<Buffer>>>,
+    sender: ShardedLock<Option<ChunkSender>>,
+    /// Receiving end of a [`ChunkSender::Bounded`] channel, taken by the
+    /// WebSocket session.
+    receiver: AsyncMutex<Option<mpsc::Receiver<Buffer>>>,
     // This endpoint starts with sending a snapshot of a relation.
     snapshot: bool,
     stream: bool,
@@ -83,12 +112,28 @@ struct HttpOutputEndpointInner {
 }
 
 impl HttpOutputEndpointInner {
-    pub(crate) fn new(name: &str, format: Format, snapshot: bool, stream: bool) -> Self {
+    pub(crate) fn new(
+        name: &str,
+        format: Format,
+        snapshot: bool,
+        stream: bool,
+        flow_control: bool,
+    ) -> Self {
+        let (sender, receiver) = if flow_control {
+            let (sender, receiver) = mpsc::channel(MAX_BUFFERS);
+            (ChunkSender::Bounded(sender), Some(receiver))
+        } else {
+            (
+                ChunkSender::Broadcast(broadcast::channel(MAX_BUFFERS).0),
+                None,
+            )
+        };
         Self {
             name: name.to_string(),
             format,
             total_buffers: AtomicU64::new(0),
-            sender: ShardedLock::new(Some(broadcast::channel(MAX_BUFFERS).0)),
+            sender: ShardedLock::new(Some(sender)),
+            receiver: AsyncMutex::new(receiver),
             snapshot,
             stream,
             // async_error_callback: RwLock::new(None),
@@ -142,12 +187,17 @@ impl HttpOutputEndpointInner {
         json_buf.push(b'\n');
 
         // A failure simply means that there are no receivers.
-        let _ = self
-            .sender
-            .read()
-            .unwrap()
-            .as_ref()
-            .map(|sender| sender.send(Buffer::new(seq_number, Bytes::from(json_buf))));
+        let sender = self.sender.read().unwrap().clone();
+        let buffer = Buffer::new(seq_number, Bytes::from(json_buf));
+        match sender {
+            Some(ChunkSender::Broadcast(sender)) => {
+                let _ = sender.send(buffer);
+            }
+            Some(ChunkSender::Bounded(sender)) => {
+                let _ = sender.blocking_send(buffer);
+            }
+            None => (),
+        }
         Ok(())
     }
 }
@@ -179,14 +229,29 @@ pub(crate) struct HttpOutputEndpoint {
 }
 
 impl HttpOutputEndpoint {
-    pub(crate) fn new(name: &str, format: &str, snapshot: bool, stream: bool) -> Self {
+    /// Creates an endpoint.  With `flow_control`, the endpoint can only be
+    /// used with [`Self::websocket`], and it blocks when the client stops
+    /// granting credit, instead of dropping chunks.
+    pub(crate) fn new(
+        name: &str,
+        format: &str,
+        snapshot: bool,
+        stream: bool,
+        flow_control: bool,
+    ) -> Self {
         let format = match format {
             "csv" => Format::Text,
             "json" => Format::Json,
             _ => Format::Binary,
         };
         Self {
-            inner: Arc::new(HttpOutputEndpointInner::new(name, format, snapshot, stream)),
+            inner: Arc::new(HttpOutputEndpointInner::new(
+                name,
+                format,
+                snapshot,
+                stream,
+                flow_control,
+            )),
         }
     }
 
@@ -195,13 +260,10 @@ impl HttpOutputEndpoint {
     }
 
     fn connect(&self) -> broadcast::Receiver<Buffer> {
-        self.inner
-            .sender
-            .read()
-            .unwrap()
-            .as_ref()
-            .unwrap()
-            .subscribe()
+        match self.inner.sender.read().unwrap().as_ref().unwrap() {
+            ChunkSender::Broadcast(sender) => sender.subscribe(),
+            ChunkSender::Bounded(_) => panic!("HTTP output endpoint uses flow control"),
+        }
     }
 
     /// Create an HTTP response object with a streaming body that
@@ -250,6 +312,75 @@ impl HttpOutputEndpoint {
                 }
             })
     }
+
+    /// Sends output chunks to a WebSocket client as text messages, until the
+    /// circuit terminates or the client disconnects.
+    ///
+    /// The client controls the flow of chunks by sending `{"credit": N}`
+    /// messages, each of which permits the endpoint to send `N` more chunks.
+    /// The session starts with no credit.  `finalizer` is invoked when the
+    /// session ends.
+    pub(crate) async fn websocket(
+        &self,
+        mut session: Session,
+        mut messages: MessageStream,
+        finalizer: Box<dyn FnMut()>,
+    ) {
+        let _guard = RequestGuard::new(finalizer);
+        let Some(mut receiver) = self.inner.receiver.lock().await.take() else {
+            return;
+        };
+        debug!(
+            "HTTP output endpoint '{}': WebSocket connected",
+            self.name()
+        );
+
+        let mut credit: u64 = 0;
+        loop {
+            select! {
+                message = messages.next() => match message {
+                    Some(Ok(WsMessage::Text(text))) => {
+                        match serde_json::from_str::<FlowControl>(&text) {
+                            Ok(flow_control) => credit = credit.saturating_add(flow_control.credit),
+                            Err(e) => debug!(
+                                "HTTP output endpoint '{}': invalid message from client: {e}",
+                                self.name()
+                            ),
+                        }
+                    }
+                    Some(Ok(WsMessage::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => (),
+                },
+                buffer = receiver.recv(), if credit > 0 => match buffer {
+                    Some(buffer) => {
+                        debug!(
+                            "HTTP output endpoint '{}': sending chunk #{} ({} bytes)",
+                            self.name(),
+                            buffer.sequence_number,
+                            buffer.data.len(),
+                        );
+                        // Trim the chunk separator, which WebSocket framing
+                        // makes redundant.
+                        let text = String::from_utf8_lossy(&buffer.data);
+                        if session.text(text.trim_end().to_string()).await.is_err() {
+                            return;
+                        }
+                        credit -= 1;
+                    }
+                    None => break,
+                },
+            }
+        }
+
+        // Unblock the pipeline if it is waiting for space in the channel.
+        drop(receiver);
+        let _ = session.close(None).await;
+    }
 }
 
 impl OutputEndpoint for HttpOutputEndpoint {
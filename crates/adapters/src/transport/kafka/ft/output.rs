@@ -9,6 +9,7 @@ use rdkafka::{
     config::FromClientConfigAndContext,
     consumer::BaseConsumer,
     error::KafkaError,
+    message::{Header, Headers, OwnedHeaders},
     producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer},
     types::RDKafkaErrorCode,
     ClientConfig, ClientContext, Message,
@@ -29,6 +30,10 @@ const DEFAULT_MAX_MESSAGE_SIZE: usize = 1_000_000;
 // This value was established empirically.
 const MAX_MESSAGE_OVERHEAD: usize = 64;
 
+/// Name of the message header that stores the [`OutputPosition`] in the
+/// `record` message mode, in which the message key identifies the record.
+const POSITION_HEADER: &str = "feldera_position";
+
 /// State of the `KafkaOutputEndpoint`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum State {
@@ -48,7 +53,8 @@ enum State {
 
 /// A position in the output partition.
 ///
-/// This is stored as the Kafka message key.
+/// This is stored as the Kafka message key, or in the [`POSITION_HEADER`]
+/// message header in the `record` message mode.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 struct OutputPosition {
     /// The step number.
@@ -70,7 +76,13 @@ impl OutputPosition {
     where
         M: Message,
     {
-        Ok(serde_json::from_slice(msg.key().unwrap_or(&[]))?)
+        let header = msg.headers().and_then(|headers| {
+            headers
+                .iter()
+                .find(|header| header.key == POSITION_HEADER)
+                .and_then(|header| header.value)
+        });
+        Ok(serde_json::from_slice(header.or(msg.key()).unwrap_or(&[]))?)
     }
 }
 
@@ -82,10 +94,14 @@ pub struct KafkaOutputEndpoint {
     max_message_size: usize,
     next_step: Step,
     state: State,
+
+    /// Columns that key each message in the `record` message mode.
+    key_fields: Option<Vec<String>>,
 }
 
 impl KafkaOutputEndpoint {
-    pub fn new(config: KafkaOutputConfig) -> AnyResult<Self> {
+    pub fn new(mut config: KafkaOutputConfig) -> AnyResult<Self> {
+        config.validate()?;
         let ft = config.fault_tolerance.as_ref().unwrap();
         let mut common = CommonConfig::new(
             &config.kafka_options,
@@ -144,9 +160,28 @@ impl KafkaOutputEndpoint {
             max_message_size,
             next_step,
             state: State::New,
+            key_fields: config.record_key_fields().map(<[String]>::to_vec),
         })
     }
 
+    /// Advances to the next message within the open batch.  Returns the
+    /// position of the message, or `None` if its step was already output in a
+    /// previous run and the message should be discarded.
+    fn next_position(&mut self, method: &str) -> Option<OutputPosition> {
+        let State::BatchOpen(position) = self.state else {
+            unreachable!(
+                "state should be BatchOpen (not {:?}) in `{method}()`",
+                self.state
+            )
+        };
+        self.state = State::BatchOpen(OutputPosition {
+            step: position.step,
+            substep: position.substep + 1,
+        });
+
+        (position.step >= self.next_step).then_some(position)
+    }
+
     /// Reads the tail of `topic` using `seekable_consumer_config`. Returns the
     /// number of partitions in `topic` and the step number for the next step to
     /// be written.
@@ -165,7 +200,7 @@ impl KafkaOutputEndpoint {
                 if let Some(msg) = ctp.read_last_message(&watermarks)? {
                     let key = OutputPosition::from_message(&msg).with_context(|| {
                         format!(
-                            "message at offset {} in {ctp} should have step and substep as key",
+                            "message at offset {} in {ctp} should have step and substep as key or header",
                             msg.offset()
                         )
                     })?;
@@ -212,20 +247,12 @@ impl OutputEndpoint for KafkaOutputEndpoint {
     }
 
     fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
-        let State::BatchOpen(OutputPosition { step, substep }) = self.state else {
-            unreachable!(
-                "state should be BatchOpen (not {:?}) in `push_buffer()`",
-                self.state
-            )
-        };
-        self.state = State::BatchOpen(OutputPosition {
-            step,
-            substep: substep + 1,
-        });
+        if self.key_fields.is_some() {
+            bail!("the Kafka output endpoint is configured with 'message_mode: record', which requires one message per record");
+        }
 
-        if step >= self.next_step {
-            let key = OutputPosition { step, substep };
-            let key = serde_json::to_string(&key).unwrap();
+        if let Some(position) = self.next_position("push_buffer") {
+            let key = serde_json::to_string(&position).unwrap();
             let record = BaseRecord::to(&self.topic)
                 .key(&key)
                 .partition(self.next_partition as i32)
@@ -243,6 +270,35 @@ impl OutputEndpoint for KafkaOutputEndpoint {
         Ok(())
     }
 
+    fn key_fields(&self) -> Option<&[String]> {
+        self.key_fields.as_deref()
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) -> AnyResult<()> {
+        if let Some(position) = self.next_position("push_key") {
+            let position = serde_json::to_vec(&position).unwrap();
+            let headers = OwnedHeaders::new().insert(Header {
+                key: POSITION_HEADER,
+                value: Some(&position),
+            });
+
+            // Let Kafka choose the partition based on the key, so that all
+            // changes to a record go to the same partition.  A message without
+            // a payload is a tombstone.
+            let mut record = <BaseRecord<[u8], [u8], ()>>::to(&self.topic)
+                .key(key)
+                .headers(headers);
+            if let Some(val) = val {
+                record = record.payload(val);
+            }
+            self.kafka_producer
+                .send(record)
+                .map_err(|(err, _record)| err)?;
+            self.kafka_producer.context().take_delivery_slot();
+        }
+        Ok(())
+    }
+
     fn batch_end(&mut self) -> AnyResult<()> {
         let State::BatchOpen(position) = self.state else {
             unreachable!(
@@ -2,13 +2,12 @@ use crate::{
     test::{
         generate_test_batches,
         kafka::{BufferConsumer, KafkaResources, TestProducer},
-        mock_input_pipeline, test_circuit, wait, MockDeZSet, TestStruct, DEFAULT_TIMEOUT_MS,
+        mock_input_pipeline, test_circuit, wait, ConsumerCall, DummyInputReceiver, MockDeZSet,
+        TestStruct, DEFAULT_TIMEOUT_MS,
     },
     transport::Step,
-    Controller, InputConsumer, InputTransport, OutputTransport, ParseError, PipelineConfig,
+    Controller, InputTransport, OutputTransport, PipelineConfig,
 };
-use anyhow::Error as AnyError;
-use crossbeam::sync::{Parker, Unparker};
 use env_logger::Env;
 use log::info;
 use proptest::prelude::*;
@@ -17,10 +16,10 @@ use std::{
     io::Write,
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc, Mutex,
+        Arc,
     },
     thread::sleep,
-    time::{Duration, Instant},
+    time::Duration,
 };
 use uuid::Uuid;
 
@@ -386,153 +385,6 @@ fault_tolerance:
     receiver.expect_eof();
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum ConsumerCall {
-    StartStep(Step),
-    InputFragment(String),
-    InputChunk(String),
-    Error(bool),
-    Eoi,
-}
-
-struct DummyInputReceiver {
-    inner: Arc<DummyInputReceiverInner>,
-    parker: Parker,
-}
-
-struct DummyInputReceiverInner {
-    unparker: Unparker,
-    calls: Mutex<Vec<ConsumerCall>>,
-    committed: Mutex<Option<Step>>,
-}
-
-impl DummyInputReceiver {
-    pub fn new() -> Self {
-        let parker = Parker::new();
-        let unparker = parker.unparker().clone();
-        Self {
-            inner: Arc::new(DummyInputReceiverInner {
-                unparker,
-                calls: Mutex::new(Vec::new()),
-                committed: Mutex::new(None),
-            }),
-            parker,
-        }
-    }
-
-    /// Wait some time for the input consumer to report that `committed` was
-    /// called.  However, we don't expect it to have been called, so we panic
-    /// with an error if it has.
-    ///
-    /// The waiting time here is arbitrary, since we expect that we could wait
-    /// forever.
-    #[track_caller]
-    pub fn expect_eof(&self) {
-        sleep(Duration::from_millis(100));
-
-        let actual: Vec<_> = self.inner.calls.lock().unwrap().drain(..).collect();
-        assert_eq!(Vec::<ConsumerCall>::new(), actual);
-    }
-
-    /// Wait until the input consumer receives `expected`. Panics if it receives
-    /// something else or if it doesn't receive it within a reasonable amount of
-    /// time.  It is not an error for the consumer to receive more following
-    /// `expected`; any data received afterward is left for later calls to
-    /// check.
-    #[track_caller]
-    pub fn expect(&self, expected: Vec<ConsumerCall>) {
-        let mut last_change = Instant::now();
-        let mut last_len = 0;
-        loop {
-            let mut current = self.inner.calls.lock().unwrap();
-            if current.len() >= expected.len()
-                || Instant::now().duration_since(last_change) > Duration::from_secs(10)
-            {
-                let len = current.len().min(expected.len());
-                let actual: Vec<_> = current.drain(0..len).collect();
-
-                // Without this, sometimes we get SIGSEGV in librdkafka.
-                drop(current);
-
-                assert_eq!(expected, actual);
-                return;
-            }
-            if current.len() != last_len {
-                last_len = current.len();
-                last_change = Instant::now();
-            }
-            drop(current);
-
-            self.parker.park_timeout(Duration::from_millis(100));
-        }
-    }
-
-    pub fn wait_to_complete(&self, step: Step) {
-        let deadline = Instant::now() + Duration::from_secs(10);
-        loop {
-            assert!(Instant::now() < deadline);
-            if let Some(committed) = *self.inner.committed.lock().unwrap() {
-                if committed >= step {
-                    return;
-                }
-            }
-            self.parker.park_deadline(deadline);
-        }
-    }
-
-    pub fn consumer(&self) -> Box<dyn InputConsumer> {
-        Box::new(DummyInputConsumer(self.inner.clone()))
-    }
-}
-
-struct DummyInputConsumer(Arc<DummyInputReceiverInner>);
-
-impl DummyInputConsumer {
-    fn called(&mut self, call: ConsumerCall) {
-        info!("{call:?}");
-        self.0.calls.lock().unwrap().push(call);
-        self.0.unparker.unpark();
-    }
-}
-
-impl InputConsumer for DummyInputConsumer {
-    fn start_step(&mut self, step: Step) {
-        self.called(ConsumerCall::StartStep(step));
-    }
-    fn input_fragment(&mut self, data: &[u8]) -> Vec<ParseError> {
-        self.called(ConsumerCall::InputFragment(
-            String::from_utf8(data.into()).unwrap(),
-        ));
-        vec![]
-    }
-    fn input_chunk(&mut self, data: &[u8]) -> Vec<ParseError> {
-        self.called(ConsumerCall::InputChunk(
-            String::from_utf8(data.into()).unwrap(),
-        ));
-        vec![]
-    }
-    fn error(&mut self, fatal: bool, error: AnyError) {
-        info!("error: {error}");
-        self.called(ConsumerCall::Error(fatal));
-    }
-    fn eoi(&mut self) -> Vec<ParseError> {
-        self.called(ConsumerCall::Eoi);
-        vec![]
-    }
-    fn fork(&self) -> Box<dyn InputConsumer> {
-        unreachable!()
-    }
-    fn committed(&mut self, step: Step) {
-        info!("step {step} committed");
-        let mut completed = self.0.committed.lock().unwrap();
-        if let Some(committed) = *completed {
-            assert_eq!(committed + 1, step);
-        }
-        *completed = Some(step);
-        self.0.unparker.unpark();
-    }
-}
-
 #[test]
 fn output_test() {
     kafka_output_test(
@@ -588,6 +440,58 @@ format:
     }
 }
 
+#[test]
+fn keyed_output_test() {
+    init_test_logger();
+    let output_topic = "ft_kafka_keyed_output_topic";
+
+    // Create topics.
+    let _kafka_resources = KafkaResources::create_topics(&[(output_topic, 2)]);
+
+    let transport = <dyn OutputTransport>::get_transport("kafka").unwrap();
+
+    let config_str = format!(
+        r#"
+stream: test_output1
+transport:
+    name: kafka
+    config:
+        topic: {output_topic}
+        fault_tolerance: {{}}
+        message_mode: record
+        key_fields: [id]
+format:
+    name: json
+"#
+    );
+    let config = serde_yaml::from_str(&config_str).unwrap();
+
+    let mut endpoint = transport.new_endpoint(&config).unwrap();
+    assert_eq!(endpoint.key_fields(), Some(&["id".to_string()][..]));
+    endpoint
+        .connect(Box::new(|fatal, error| info!("({fatal:?}, {error:?})")))
+        .unwrap();
+    for step in 0..5 {
+        endpoint.batch_start(step).unwrap();
+        let key = format!(r#"{{"id":{step}}}"#);
+        endpoint.push_key(key.as_bytes(), Some(b"{}")).unwrap();
+        endpoint.push_key(key.as_bytes(), None).unwrap();
+        assert!(endpoint.push_buffer(b"{}").is_err());
+        endpoint.batch_end().unwrap();
+    }
+    drop(endpoint);
+
+    // The message keys identify records, so the new endpoint must recover the
+    // step number from the message headers.
+    let mut endpoint = transport.new_endpoint(&config).unwrap();
+    endpoint
+        .connect(Box::new(|fatal, error| info!("({fatal:?}, {error:?})")))
+        .unwrap();
+    endpoint.batch_start(5).unwrap();
+    endpoint.push_key(br#"{"id":5}"#, Some(b"{}")).unwrap();
+    endpoint.batch_end().unwrap();
+}
+
 fn _test() {
     let transport = <dyn OutputTransport>::get_transport("kafka").unwrap();
 
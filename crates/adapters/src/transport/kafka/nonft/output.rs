@@ -145,6 +145,22 @@ impl KafkaOutputEndpoint {
             max_message_size,
         })
     }
+
+    /// Waits for the number of unacknowledged messages to drop below
+    /// `max_inflight_messages`.
+    fn wait_for_inflight_messages(&self) {
+        while self.kafka_producer.in_flight_count() as i64
+            > self.config.max_inflight_messages as i64
+        {
+            // FIXME: It appears that the delivery callback can be invoked before the
+            // in-flight counter is decremented, in which case we may never get
+            // unparked and may need to poll the in-flight counter.  This
+            // shouldn't cause performance issues in practice, but
+            // it would still be nice to have a more reliable way to wake up the endpoint
+            // thread _after_ the in-flight counter has been decremented.
+            self.parker.park_timeout(OUTPUT_POLLING_INTERVAL);
+        }
+    }
 }
 
 impl OutputEndpoint for KafkaOutputEndpoint {
@@ -178,20 +194,12 @@ impl OutputEndpoint for KafkaOutputEndpoint {
     }
 
     fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
-        // Wait for the number of unacknowledged messages to drop
-        // below `max_inflight_messages`.
-        while self.kafka_producer.in_flight_count() as i64
-            > self.config.max_inflight_messages as i64
-        {
-            // FIXME: It appears that the delivery callback can be invoked before the
-            // in-flight counter is decremented, in which case we may never get
-            // unparked and may need to poll the in-flight counter.  This
-            // shouldn't cause performance issues in practice, but
-            // it would still be nice to have a more reliable way to wake up the endpoint
-            // thread _after_ the in-flight counter has been decremented.
-            self.parker.park_timeout(OUTPUT_POLLING_INTERVAL);
+        if self.config.record_key_fields().is_some() {
+            bail!("the Kafka output endpoint is configured with 'message_mode: record', which requires one message per record");
         }
 
+        self.wait_for_inflight_messages();
+
         let record = <BaseRecord<(), [u8], ()>>::to(&self.config.topic).payload(buffer);
         self.kafka_producer
             .send(record)
@@ -199,6 +207,24 @@ impl OutputEndpoint for KafkaOutputEndpoint {
         Ok(())
     }
 
+    fn key_fields(&self) -> Option<&[String]> {
+        self.config.record_key_fields()
+    }
+
+    fn push_key(&mut self, key: &[u8], val: Option<&[u8]>) -> AnyResult<()> {
+        self.wait_for_inflight_messages();
+
+        // A message without a payload is a tombstone.
+        let mut record = <BaseRecord<[u8], [u8], ()>>::to(&self.config.topic).key(key);
+        if let Some(val) = val {
+            record = record.payload(val);
+        }
+        self.kafka_producer
+            .send(record)
+            .map_err(|(err, _record)| err)?;
+        Ok(())
+    }
+
     fn is_fault_tolerant(&self) -> bool {
         false
     }
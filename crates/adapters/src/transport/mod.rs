@@ -18,8 +18,22 @@
 //!   * `file`, for input from a file via [`FileInputTransport`] or output to a
 //!     file via [`FileOutputTransport`].
 //!
+//!   * `directory`, for input from the files that match a glob pattern, in
+//!     order, via [`DirectoryInputTransport`].
+//!
 //!   * `url`, for input from an HTTP or HTTPS url via [`UrlInputTransport`].
 //!
+//!   * `postgres`, for output to a PostgreSQL table via
+//!     [`PostgresOutputTransport`], if the `with-postgres` feature is enabled.
+//!
+//!   * `postgres_cdc`, for input of the changes to a PostgreSQL table from a
+//!     logical replication slot via [`PostgresCdcInputTransport`], if the
+//!     `with-postgres` feature is enabled.
+//!
+//!   * `s3`, for input from the objects in an S3 bucket via
+//!     [`S3InputTransport`] or output to objects in an S3 bucket via
+//!     [`S3OutputTransport`].
+//!
 //!   * `kafka`, for input from [Kafka](https://kafka.apache.org/) via
 //!     [`KafkaInputTransport`] or output to Kafka via [`KafkaOutputTransport`],
 //!     if the `with-kafka` feature is enabled.
@@ -40,8 +54,11 @@ use std::collections::BTreeMap;
 use std::sync::atomic::AtomicU64;
 use std::{borrow::Cow, ops::Range};
 
+mod compression;
+mod directory;
 mod file;
 pub mod http;
+mod s3;
 
 pub mod url;
 
@@ -50,12 +67,24 @@ mod secret_resolver;
 #[cfg(feature = "with-kafka")]
 pub(crate) mod kafka;
 
+#[cfg(feature = "with-postgres")]
+mod postgres;
+#[cfg(feature = "with-postgres")]
+mod postgres_cdc;
+
+pub use directory::DirectoryInputTransport;
 pub use file::{FileInputTransport, FileOutputTransport};
+pub use s3::{S3InputTransport, S3OutputTransport};
 pub use url::UrlInputTransport;
 
 #[cfg(feature = "with-kafka")]
 pub use kafka::{KafkaInputTransport, KafkaOutputTransport};
 
+#[cfg(feature = "with-postgres")]
+pub use postgres::PostgresOutputTransport;
+#[cfg(feature = "with-postgres")]
+pub use postgres_cdc::PostgresCdcInputTransport;
+
 /// Step number for fault-tolerant input and output.
 ///
 /// A [fault-tolerant](crate#fault-tolerance) data transport divides input into
@@ -79,10 +108,20 @@ static INPUT_TRANSPORT: Lazy<BTreeMap<&'static str, Box<dyn InputTransport>>> =
             "file",
             Box::new(FileInputTransport) as Box<dyn InputTransport>,
         ),
+        (
+            "directory",
+            Box::new(DirectoryInputTransport) as Box<dyn InputTransport>,
+        ),
         (
             "url",
             Box::new(UrlInputTransport) as Box<dyn InputTransport>,
         ),
+        #[cfg(feature = "with-postgres")]
+        (
+            "postgres_cdc",
+            Box::new(PostgresCdcInputTransport) as Box<dyn InputTransport>,
+        ),
+        ("s3", Box::new(S3InputTransport) as Box<dyn InputTransport>),
         #[cfg(feature = "with-kafka")]
         (
             "kafka",
@@ -98,6 +137,15 @@ static OUTPUT_TRANSPORT: Lazy<BTreeMap<&'static str, Box<dyn OutputTransport>>>
             "file",
             Box::new(FileOutputTransport) as Box<dyn OutputTransport>,
         ),
+        #[cfg(feature = "with-postgres")]
+        (
+            "postgres",
+            Box::new(PostgresOutputTransport) as Box<dyn OutputTransport>,
+        ),
+        (
+            "s3",
+            Box::new(S3OutputTransport) as Box<dyn OutputTransport>,
+        ),
         #[cfg(feature = "with-kafka")]
         (
             "kafka",
@@ -387,6 +435,23 @@ pub trait OutputEndpoint: Send {
 
     fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()>;
 
+    /// Returns the names of the columns that form the key of each message if
+    /// the endpoint expects one message per record, pushed with
+    /// [`OutputEndpoint::push_key`], instead of buffers of records.
+    fn key_fields(&self) -> Option<&[String]> {
+        None
+    }
+
+    /// Sends a single record as a message with key `key`.  `val` is `None`
+    /// for a deleted record.
+    ///
+    /// Only called if [`OutputEndpoint::key_fields`] returns `Some`.
+    fn push_key(&mut self, _key: &[u8], _val: Option<&[u8]>) -> AnyResult<()> {
+        Err(AnyError::msg(
+            "this endpoint does not support keyed messages",
+        ))
+    }
+
     /// Notifies the output endpoint that output for the current step is
     /// complete.
     ///
@@ -0,0 +1,569 @@
+use super::{OutputEndpoint, OutputTransport, Step};
+use crate::OutputEndpointConfig;
+use anyhow::{anyhow, bail, Context, Error as AnyError, Result as AnyResult};
+use log::info;
+use pipeline_types::{
+    format::json::{JsonEncoderConfig, JsonUpdateFormat},
+    transport::postgres::PostgresOutputConfig,
+};
+use postgres::{Client, NoTls};
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use std::{borrow::Cow, collections::BTreeMap, mem::take};
+
+/// [`OutputTransport`] implementation that writes data to a PostgreSQL table.
+///
+/// The output transport factory gives this transport the name `postgres`.
+pub struct PostgresOutputTransport;
+
+impl OutputTransport for PostgresOutputTransport {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("postgres")
+    }
+
+    /// Creates a new [`OutputEndpoint`] for writing to a PostgreSQL table,
+    /// interpreting `config` as a [`PostgresOutputConfig`].
+    ///
+    /// See [`OutputTransport::new_endpoint()`] for more information.
+    fn new_endpoint(&self, config: &OutputEndpointConfig) -> AnyResult<Box<dyn OutputEndpoint>> {
+        let format = &config.connector_config.format;
+        if format.name != "json" {
+            bail!(
+                "the 'postgres' transport requires the 'json' format, but '{}' is configured",
+                format.name
+            );
+        }
+        let format_config = JsonEncoderConfig::deserialize(&format.config)?;
+        if format_config.update_format != JsonUpdateFormat::InsertDelete || format_config.array {
+            bail!("the 'postgres' transport requires the 'insert_delete' JSON update format without array encoding");
+        }
+
+        let config = PostgresOutputConfig::deserialize(&config.connector_config.transport.config)?;
+        let ep = PostgresOutputEndpoint::new(config)?;
+
+        Ok(Box::new(ep))
+    }
+}
+
+/// Change to a row, identified by its primary key, within an output batch.
+enum Change {
+    Insert(JsonValue),
+    Delete(JsonValue),
+}
+
+/// An output record in the `insert_delete` JSON format.
+#[derive(Deserialize)]
+enum InsDelUpdate {
+    #[serde(rename = "insert")]
+    Insert(Map<String, JsonValue>),
+    #[serde(rename = "delete")]
+    Delete(Map<String, JsonValue>),
+}
+
+struct PostgresOutputEndpoint {
+    config: PostgresOutputConfig,
+    client: Client,
+
+    /// `DELETE` statement that removes the rows in a JSON array from the
+    /// table.
+    delete_sql: String,
+
+    /// Quoted name of the progress table, if fault tolerance is enabled.
+    progress_table: Option<String>,
+
+    /// `INSERT ... ON CONFLICT` statement that upserts the rows in a JSON
+    /// array into the table.
+    upsert_sql: String,
+
+    /// Changes in the current batch, indexed by serialized primary key.  Only
+    /// the last change to each key is kept.
+    changes: BTreeMap<String, Change>,
+
+    /// Step of the current batch.
+    step: Option<Step>,
+
+    /// The first step that has not been committed to the table.  Only
+    /// maintained when fault tolerance is enabled.
+    next_step: Step,
+}
+
+impl PostgresOutputEndpoint {
+    fn new(config: PostgresOutputConfig) -> AnyResult<Self> {
+        if config.primary_key.is_empty() {
+            bail!("'primary_key' must specify at least one column");
+        }
+        let table = quote_table_name(&config.table)?;
+        let progress_table = config
+            .fault_tolerance
+            .as_ref()
+            .map(|ft_config| quote_table_name(&ft_config.progress_table))
+            .transpose()?;
+
+        let mut client = Client::connect(&config.uri, NoTls)
+            .with_context(|| format!("failed to connect to PostgreSQL at '{}'", config.uri))?;
+
+        // Column names of the table, in order.  The text-to-regclass cast
+        // resolves the table name the same way SQL statements do.
+        let columns = client
+            .query(
+                "SELECT attname::text FROM pg_attribute \
+                 WHERE attrelid = $1::text::regclass AND attnum > 0 AND NOT attisdropped \
+                 ORDER BY attnum",
+                &[&table],
+            )
+            .with_context(|| format!("failed to look up table '{}'", config.table))?
+            .iter()
+            .map(|row| row.get::<_, String>(0))
+            .collect::<Vec<_>>();
+        for key in config.primary_key.iter() {
+            if !columns.contains(key) {
+                bail!(
+                    "primary key column '{key}' does not exist in table '{}'",
+                    config.table
+                );
+            }
+        }
+
+        let delete_sql = format!(
+            "DELETE FROM {table} AS t USING json_populate_recordset(NULL::{table}, $1::text::json) AS d WHERE {}",
+            config
+                .primary_key
+                .iter()
+                .map(|key| format!("t.{0} = d.{0}", quote_ident(key)))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        );
+        let updates = columns
+            .iter()
+            .filter(|column| !config.primary_key.contains(column))
+            .map(|column| format!("{0} = EXCLUDED.{0}", quote_ident(column)))
+            .collect::<Vec<_>>();
+        let upsert_sql = format!(
+            "INSERT INTO {table} SELECT * FROM json_populate_recordset(NULL::{table}, $1::text::json) ON CONFLICT ({}) DO {}",
+            config
+                .primary_key
+                .iter()
+                .map(|key| quote_ident(key))
+                .collect::<Vec<_>>()
+                .join(", "),
+            if updates.is_empty() {
+                "NOTHING".to_string()
+            } else {
+                format!("UPDATE SET {}", updates.join(", "))
+            }
+        );
+
+        let mut next_step = 0;
+        if let Some(progress_table) = &progress_table {
+            client.batch_execute(&format!(
+                "CREATE TABLE IF NOT EXISTS {progress_table} (output_table TEXT PRIMARY KEY, step BIGINT NOT NULL)"
+            ))?;
+            if let Some(row) = client.query_opt(
+                &format!("SELECT step FROM {progress_table} WHERE output_table = $1"),
+                &[&config.table],
+            )? {
+                next_step = row.get::<_, i64>(0) as Step + 1;
+            }
+        }
+
+        Ok(Self {
+            config,
+            client,
+            delete_sql,
+            progress_table,
+            upsert_sql,
+            changes: BTreeMap::new(),
+            step: None,
+            next_step,
+        })
+    }
+
+    /// True if the current batch was committed in a previous run and must be
+    /// discarded.
+    fn discard(&self) -> bool {
+        self.config.fault_tolerance.is_some() && self.step.is_some_and(|step| step < self.next_step)
+    }
+
+    fn add_record(&mut self, record: Map<String, JsonValue>, insert: bool) -> AnyResult<()> {
+        let key = self
+            .config
+            .primary_key
+            .iter()
+            .map(|column| {
+                record.get(column).ok_or_else(|| {
+                    anyhow!("output record is missing primary key column '{column}'")
+                })
+            })
+            .collect::<AnyResult<Vec<_>>>()?;
+        let key = serde_json::to_string(&key)?;
+
+        let record = JsonValue::Object(record);
+        let change = if insert {
+            Change::Insert(record)
+        } else {
+            // The encoder outputs deletions before insertions, but a batch
+            // may consist of several such runs.  Deleting the old version of
+            // a row must not undo an insertion of its new version.
+            if let Some(Change::Insert(inserted)) = self.changes.get(&key) {
+                if *inserted != record {
+                    return Ok(());
+                }
+            }
+            Change::Delete(record)
+        };
+        self.changes.insert(key, change);
+        Ok(())
+    }
+}
+
+impl OutputEndpoint for PostgresOutputEndpoint {
+    fn connect(
+        &mut self,
+        _async_error_callback: Box<dyn Fn(bool, AnyError) + Send + Sync>,
+    ) -> AnyResult<()> {
+        Ok(())
+    }
+
+    fn max_buffer_size_bytes(&self) -> usize {
+        usize::MAX
+    }
+
+    fn batch_start(&mut self, step: Step) -> AnyResult<()> {
+        self.step = Some(step);
+        if self.discard() {
+            info!(
+                "dropping step {step} that was already written to '{}' in a previous run",
+                self.config.table
+            );
+        }
+        Ok(())
+    }
+
+    fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
+        if self.discard() {
+            return Ok(());
+        }
+
+        for line in buffer.split(|c| *c == b'\n') {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice(line)
+                .context("failed to parse output record in the 'insert_delete' JSON format")?
+            {
+                InsDelUpdate::Insert(record) => self.add_record(record, true)?,
+                InsDelUpdate::Delete(record) => self.add_record(record, false)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn batch_end(&mut self) -> AnyResult<()> {
+        let changes = take(&mut self.changes);
+        if self.discard() || changes.is_empty() {
+            return Ok(());
+        }
+
+        let mut deletes = Vec::new();
+        let mut inserts = Vec::new();
+        for change in changes.values() {
+            match change {
+                Change::Insert(record) => inserts.push(record),
+                Change::Delete(record) => deletes.push(record),
+            }
+        }
+
+        let mut transaction = self.client.transaction()?;
+        if !deletes.is_empty() {
+            transaction.execute(&self.delete_sql, &[&serde_json::to_string(&deletes)?])?;
+        }
+        if !inserts.is_empty() {
+            transaction.execute(&self.upsert_sql, &[&serde_json::to_string(&inserts)?])?;
+        }
+        if let (Some(progress_table), Some(step)) = (&self.progress_table, self.step) {
+            transaction.execute(
+                &format!(
+                    "INSERT INTO {progress_table} (output_table, step) VALUES ($1, $2) \
+                     ON CONFLICT (output_table) DO UPDATE SET step = EXCLUDED.step"
+                ),
+                &[&self.config.table, &(step as i64)],
+            )?;
+        }
+        transaction
+            .commit()
+            .with_context(|| format!("failed to commit output to '{}'", self.config.table))?;
+
+        if let Some(step) = self.step {
+            self.next_step = step + 1;
+        }
+        Ok(())
+    }
+
+    fn is_fault_tolerant(&self) -> bool {
+        self.config.fault_tolerance.is_some()
+    }
+}
+
+/// Quotes `name` as a PostgreSQL identifier, so that it is used verbatim.
+pub(super) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Parses `name`, a table name optionally qualified with a schema name, the
+/// way PostgreSQL parses names in SQL statements, and returns it with each
+/// part quoted, so that it can be safely interpolated into SQL statements.
+pub(super) fn quote_table_name(name: &str) -> AnyResult<String> {
+    Ok(quote_table_parts(&parse_table_name(name)?))
+}
+
+/// Quotes and joins the parts of a table name returned by
+/// [`parse_table_name`].
+pub(super) fn quote_table_parts(parts: &[String]) -> String {
+    parts
+        .iter()
+        .map(|part| quote_ident(part))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Parses `name`, a table name optionally qualified with a schema name, the
+/// way PostgreSQL parses names in SQL statements, and returns its parts.
+///
+/// A part is either an identifier that consists of letters, digits, `_` and
+/// `$` and doesn't start with a digit or `$`, which is folded to lower case,
+/// or a double-quoted identifier, which is used verbatim.
+pub(super) fn parse_table_name(name: &str) -> AnyResult<Vec<String>> {
+    let mut parts = Vec::new();
+    let mut rest = name;
+    loop {
+        let (part, tail) = if let Some(quoted) = rest.strip_prefix('"') {
+            // A quoted part ends at the first quote that isn't doubled.
+            let mut part = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) if quoted[i + 1..].starts_with('"') => {
+                        part.push('"');
+                        chars.next();
+                    }
+                    Some((i, '"')) => break i + 1,
+                    Some((_, c)) => part.push(c),
+                    None => bail!("unterminated quoted identifier in table name '{name}'"),
+                }
+            };
+            (part, &quoted[end..])
+        } else {
+            let end = rest.find('.').unwrap_or(rest.len());
+            let part = &rest[..end];
+            let mut chars = part.chars();
+            if !chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+            {
+                bail!("invalid table name '{name}': '{part}' is not a valid identifier; enclose names with other characters in double quotes");
+            }
+            (part.to_ascii_lowercase(), &rest[end..])
+        };
+        if part.is_empty() {
+            bail!("invalid table name '{name}': identifiers must not be empty");
+        }
+        parts.push(part);
+
+        match tail.strip_prefix('.') {
+            Some(tail) => rest = tail,
+            None if tail.is_empty() => break,
+            None => bail!("invalid table name '{name}': expected '.' after a quoted identifier"),
+        }
+    }
+    if parts.len() > 2 {
+        bail!("invalid table name '{name}': expected a table name optionally qualified with a schema name");
+    }
+
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::quote_table_name;
+    use crate::{transport::Step, OutputEndpoint, OutputEndpointConfig, OutputTransport};
+    use postgres::{Client, NoTls};
+    use std::env;
+
+    /// Connection string of the PostgreSQL instance used by tests.
+    fn postgres_url() -> String {
+        env::var("POSTGRES_URL").unwrap_or_else(|_| "host=localhost user=postgres".to_string())
+    }
+
+    /// Creates a fresh table `table` with primary key `id` and drops the
+    /// progress table used by its fault-tolerant endpoints.
+    fn create_table(table: &str) -> Client {
+        let mut client = Client::connect(&postgres_url(), NoTls).unwrap();
+        client
+            .batch_execute(&format!(
+                "DROP TABLE IF EXISTS {table}, {table}_progress;
+                 CREATE TABLE {table} (id INT PRIMARY KEY, b BOOLEAN, s TEXT)"
+            ))
+            .unwrap();
+        client
+    }
+
+    fn endpoint(table: &str, fault_tolerance: bool) -> Box<dyn OutputEndpoint> {
+        let config_str = format!(
+            r#"
+stream: test_output
+transport:
+    name: postgres
+    config:
+        uri: "{}"
+        table: {table}
+        primary_key: [id]
+        {}
+format:
+    name: json
+"#,
+            postgres_url(),
+            if fault_tolerance {
+                format!("fault_tolerance: {{progress_table: {table}_progress}}")
+            } else {
+                String::new()
+            }
+        );
+        let config: OutputEndpointConfig = serde_yaml::from_str(&config_str).unwrap();
+        let mut endpoint = <dyn OutputTransport>::get_transport("postgres")
+            .unwrap()
+            .new_endpoint(&config)
+            .unwrap();
+        endpoint.connect(Box::new(|_, _| {})).unwrap();
+        endpoint
+    }
+
+    fn write_batch(endpoint: &mut dyn OutputEndpoint, step: Step, records: &str) {
+        endpoint.batch_start(step).unwrap();
+        endpoint.push_buffer(records.as_bytes()).unwrap();
+        endpoint.batch_end().unwrap();
+    }
+
+    fn contents(client: &mut Client, table: &str) -> Vec<(i32, bool, String)> {
+        client
+            .query(&format!("SELECT id, b, s FROM {table} ORDER BY id"), &[])
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect()
+    }
+
+    #[test]
+    fn test_postgres_upsert() {
+        let table = "test_postgres_upsert";
+        let mut client = create_table(table);
+        let mut endpoint = endpoint(table, false);
+        assert!(!endpoint.is_fault_tolerant());
+
+        write_batch(
+            endpoint.as_mut(),
+            0,
+            r#"{"insert":{"id":1,"b":true,"s":"foo"}}
+{"insert":{"id":2,"b":false,"s":"bar"}}
+{"insert":{"id":3,"b":true,"s":"baz"}}
+"#,
+        );
+        assert_eq!(
+            contents(&mut client, table),
+            vec![
+                (1, true, "foo".to_string()),
+                (2, false, "bar".to_string()),
+                (3, true, "baz".to_string())
+            ]
+        );
+
+        // Update record 2, delete record 3.  The insertion of the new version
+        // of record 1 precedes the deletion of the old one.
+        write_batch(
+            endpoint.as_mut(),
+            1,
+            r#"{"delete":{"id":2,"b":false,"s":"bar"}}
+{"insert":{"id":2,"b":true,"s":"qux"}}
+{"delete":{"id":3,"b":true,"s":"baz"}}
+{"insert":{"id":1,"b":false,"s":"foo"}}
+{"delete":{"id":1,"b":true,"s":"foo"}}
+"#,
+        );
+        assert_eq!(
+            contents(&mut client, table),
+            vec![(1, false, "foo".to_string()), (2, true, "qux".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_postgres_ft() {
+        let table = "test_postgres_ft";
+        let mut client = create_table(table);
+
+        let mut endpoint1 = endpoint(table, true);
+        assert!(endpoint1.is_fault_tolerant());
+        write_batch(
+            endpoint1.as_mut(),
+            0,
+            r#"{"insert":{"id":1,"b":true,"s":"foo"}}"#,
+        );
+        write_batch(
+            endpoint1.as_mut(),
+            1,
+            r#"{"insert":{"id":2,"b":true,"s":"bar"}}"#,
+        );
+        drop(endpoint1);
+
+        // After a restart, steps 0 and 1 are replayed and must be discarded.
+        let mut endpoint2 = endpoint(table, true);
+        write_batch(
+            endpoint2.as_mut(),
+            0,
+            r#"{"delete":{"id":1,"b":true,"s":"foo"}}"#,
+        );
+        write_batch(
+            endpoint2.as_mut(),
+            1,
+            r#"{"insert":{"id":2,"b":false,"s":"baz"}}"#,
+        );
+        write_batch(
+            endpoint2.as_mut(),
+            2,
+            r#"{"insert":{"id":3,"b":true,"s":"qux"}}"#,
+        );
+        assert_eq!(
+            contents(&mut client, table),
+            vec![
+                (1, true, "foo".to_string()),
+                (2, true, "bar".to_string()),
+                (3, true, "qux".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_table_name() {
+        assert_eq!(quote_table_name("orders").unwrap(), r#""orders""#);
+        assert_eq!(
+            quote_table_name("Public.Orders_2$").unwrap(),
+            r#""public"."orders_2$""#
+        );
+        assert_eq!(
+            quote_table_name(r#""My Schema"."a""b""#).unwrap(),
+            r#""My Schema"."a""b""#
+        );
+
+        for name in [
+            "",
+            "orders; DROP TABLE orders",
+            "orders.",
+            "1orders",
+            "a.b.c",
+            r#""orders"#,
+            r#""""#,
+            r#""a"b"#,
+        ] {
+            assert!(quote_table_name(name).is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,1240 @@
+use super::{
+    postgres::{parse_table_name, quote_ident, quote_table_parts},
+    AtomicStep, InputConsumer, InputEndpoint, InputReader, InputTransport, Step,
+};
+use crate::PipelineState;
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+use crossbeam::sync::{Parker, Unparker};
+use log::{error, info, warn};
+use num_traits::FromPrimitive;
+use pipeline_types::transport::postgres::PostgresCdcInputConfig;
+use postgres::{Client, IsolationLevel, NoTls, SimpleQueryMessage};
+use serde::Deserialize;
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    ops::Range,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, Builder, JoinHandle},
+    time::Duration,
+};
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Maximum number of new logical decoding rows to read from the replication
+/// slot at a time.
+const MAX_ROWS_PER_POLL: u64 = 10_000;
+
+/// Number of rows of the initial snapshot fetched and passed to the consumer
+/// at a time.
+const SNAPSHOT_BATCH_ROWS: usize = 1_000;
+
+/// [`InputTransport`] implementation that reads the changes to a PostgreSQL
+/// table from a logical replication slot.
+///
+/// The input transport factory gives this transport the name `postgres_cdc`.
+pub struct PostgresCdcInputTransport;
+
+impl InputTransport for PostgresCdcInputTransport {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("postgres_cdc")
+    }
+
+    /// Creates a new [`InputEndpoint`] for reading changes from PostgreSQL,
+    /// interpreting `config` as a [`PostgresCdcInputConfig`].
+    ///
+    /// See [`InputTransport::new_endpoint()`] for more information.
+    fn new_endpoint(&self, config: &YamlValue) -> AnyResult<Box<dyn InputEndpoint>> {
+        let config = PostgresCdcInputConfig::deserialize(config)?;
+        let journal = Journal::open(&config)?;
+        Ok(Box::new(PostgresCdcInputEndpoint {
+            config,
+            journal: Arc::new(Mutex::new(journal)),
+        }))
+    }
+}
+
+struct PostgresCdcInputEndpoint {
+    config: PostgresCdcInputConfig,
+    journal: Arc<Mutex<Journal>>,
+}
+
+impl InputEndpoint for PostgresCdcInputEndpoint {
+    fn open(
+        &self,
+        consumer: Box<dyn InputConsumer>,
+        start_step: Step,
+    ) -> AnyResult<Box<dyn InputReader>> {
+        Ok(Box::new(PostgresCdcInputReader::new(
+            &self.config,
+            &self.journal,
+            start_step,
+            consumer,
+        )?))
+    }
+
+    fn is_fault_tolerant(&self) -> bool {
+        true
+    }
+
+    fn expire(&self, step: Step) {
+        if let Err(e) = self.journal.lock().unwrap().expire(step) {
+            warn!("{e:#}");
+        }
+    }
+
+    fn steps(&self) -> AnyResult<Range<Step>> {
+        Ok(self.journal.lock().unwrap().steps())
+    }
+}
+
+/// Formats `lsn` the way PostgreSQL does, e.g., `16/B374D848`.
+fn format_lsn(lsn: u64) -> String {
+    format!("{:X}/{:X}", lsn >> 32, lsn & 0xffff_ffff)
+}
+
+/// The transactions visible to a snapshot, as reported by
+/// `txid_current_snapshot()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TxidSnapshot {
+    xmin: u64,
+    xmax: u64,
+    xip: Vec<u64>,
+}
+
+impl TxidSnapshot {
+    /// Parses the `xmin:xmax:xip_list` text form of a snapshot.
+    fn parse(s: &str) -> AnyResult<Self> {
+        let mut fields = s.split(':');
+        let (Some(xmin), Some(xmax), Some(xip), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            bail!("invalid transaction snapshot '{s}'");
+        };
+        Ok(Self {
+            xmin: xmin.parse()?,
+            xmax: xmax.parse()?,
+            xip: xip
+                .split(',')
+                .filter(|xid| !xid.is_empty())
+                .map(|xid| xid.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let xip = self
+            .xip
+            .iter()
+            .map(|xid| xid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!("{}:{}:{xip}", self.xmin, self.xmax)
+    }
+
+    /// Returns true if the changes made by the transaction with 32-bit ID
+    /// `xid` are visible to the snapshot.
+    ///
+    /// The transactions in the replication slot started recently, so `xid` is
+    /// in the same epoch as `xmax`, unless it is greater than `xmax`, in which
+    /// case the transaction started after the snapshot anyway.
+    fn is_visible(&self, xid: u32) -> bool {
+        let xid = (self.xmax & !0xffff_ffff) | xid as u64;
+        xid < self.xmin || (xid < self.xmax && !self.xip.contains(&xid))
+    }
+}
+
+/// One step recorded in the journal.
+#[derive(Clone, Debug)]
+struct JournalEntry {
+    step: Step,
+
+    /// The step consists of the transactions whose commit records end after
+    /// `lsns.start` and no later than `lsns.end`.
+    lsns: Range<u64>,
+
+    /// Whether the step consists of the initial snapshot of the table.
+    snapshot: bool,
+
+    /// The transactions that the initial snapshot reflects, if there was one.
+    visibility: Option<TxidSnapshot>,
+}
+
+/// Journal of the steps read from a replication slot, stored in a table in
+/// the source database.
+struct Journal {
+    client: Client,
+
+    /// Quoted name of the journal table.
+    journal_table: String,
+
+    /// Quoted name of the table that holds the initial snapshot, so that the
+    /// step that consists of it can be replayed.
+    snapshot_table: String,
+
+    slot: String,
+    entries: VecDeque<JournalEntry>,
+}
+
+impl Journal {
+    fn open(config: &PostgresCdcInputConfig) -> AnyResult<Self> {
+        let mut table_parts = parse_table_name(&config.journal_table)?;
+        let journal_table = quote_table_parts(&table_parts);
+        table_parts
+            .last_mut()
+            .unwrap()
+            .push_str(&format!("_{}_snapshot", config.slot));
+        let snapshot_table = quote_table_parts(&table_parts);
+
+        let mut client = Client::connect(&config.uri, NoTls)
+            .with_context(|| format!("failed to connect to PostgreSQL at '{}'", config.uri))?;
+        client.batch_execute(&format!(
+            "CREATE TABLE IF NOT EXISTS {journal_table} (
+                 slot TEXT NOT NULL,
+                 step BIGINT NOT NULL,
+                 start_lsn BIGINT NOT NULL,
+                 end_lsn BIGINT NOT NULL,
+                 snapshot BOOLEAN NOT NULL,
+                 visibility TEXT,
+                 PRIMARY KEY (slot, step))"
+        ))?;
+        let entries = client
+            .query(
+                &format!(
+                    "SELECT step, start_lsn, end_lsn, snapshot, visibility FROM {journal_table} \
+                     WHERE slot = $1 ORDER BY step"
+                ),
+                &[&config.slot],
+            )?
+            .iter()
+            .map(|row| {
+                Ok(JournalEntry {
+                    step: row.get::<_, i64>(0) as Step,
+                    lsns: row.get::<_, i64>(1) as u64..row.get::<_, i64>(2) as u64,
+                    snapshot: row.get(3),
+                    visibility: row
+                        .get::<_, Option<&str>>(4)
+                        .map(TxidSnapshot::parse)
+                        .transpose()?,
+                })
+            })
+            .collect::<AnyResult<VecDeque<_>>>()?;
+
+        Ok(Self {
+            client,
+            journal_table,
+            snapshot_table,
+            slot: config.slot.clone(),
+            entries,
+        })
+    }
+
+    /// Returns the range of steps recorded in the journal.
+    fn steps(&self) -> Range<Step> {
+        match (self.entries.front(), self.entries.back()) {
+            (Some(first), Some(last)) => first.step..last.step + 1,
+            _ => 0..0,
+        }
+    }
+
+    /// Returns the entry for `step`, if the journal has one.
+    fn find(&self, step: Step) -> Option<&JournalEntry> {
+        let first = self.entries.front()?;
+        self.entries
+            .get(step.checked_sub(first.step)?.try_into().ok()?)
+    }
+
+    /// Durably appends `entry` to the journal.
+    fn append(&mut self, entry: JournalEntry) -> AnyResult<()> {
+        if let Some(last) = self.entries.back() {
+            if entry.step != last.step + 1 || entry.lsns.start != last.lsns.end {
+                bail!(
+                    "journal entry for step {} does not follow step {}",
+                    entry.step,
+                    last.step
+                );
+            }
+        }
+        self.client.execute(
+            &format!(
+                "INSERT INTO {} (slot, step, start_lsn, end_lsn, snapshot, visibility) \
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                self.journal_table
+            ),
+            &[
+                &self.slot,
+                &(entry.step as i64),
+                &(entry.lsns.start as i64),
+                &(entry.lsns.end as i64),
+                &entry.snapshot,
+                &entry.visibility.as_ref().map(TxidSnapshot::to_text),
+            ],
+        )?;
+        self.entries.push_back(entry);
+        Ok(())
+    }
+
+    /// Discards entries for steps less than `step`, except that the last entry
+    /// is always kept because it records where the next step starts, and then
+    /// advances the replication slot past the discarded steps and drops the
+    /// initial snapshot if it was discarded.
+    fn expire(&mut self, step: Step) -> AnyResult<()> {
+        let mut changed = false;
+        let mut snapshot = false;
+        while self.entries.len() > 1 && self.entries.front().unwrap().step < step {
+            snapshot |= self.entries.pop_front().unwrap().snapshot;
+            changed = true;
+        }
+        if !changed {
+            return Ok(());
+        }
+
+        // Delete the entries before advancing the slot, so that the journal
+        // never records steps that can no longer be read.
+        let first = self.entries.front().unwrap();
+        self.client.execute(
+            &format!(
+                "DELETE FROM {} WHERE slot = $1 AND step < $2",
+                self.journal_table
+            ),
+            &[&self.slot, &(first.step as i64)],
+        )?;
+        if snapshot {
+            self.client
+                .batch_execute(&format!("DROP TABLE IF EXISTS {}", self.snapshot_table))?;
+        }
+        if first.lsns.start > 0 {
+            self.client
+                .execute(
+                    "SELECT pg_replication_slot_advance($1, $2::text::pg_lsn)",
+                    &[&self.slot, &format_lsn(first.lsns.start)],
+                )
+                .with_context(|| format!("failed to advance replication slot '{}'", self.slot))?;
+        }
+        Ok(())
+    }
+}
+
+/// A column of a relation, as described by a `Relation` message.
+struct Column {
+    name: String,
+    type_oid: u32,
+}
+
+/// A column value in a `TupleData` message part.
+enum TupleValue {
+    Null,
+    UnchangedToast,
+    Text(String),
+}
+
+/// Reads the fields of a `pgoutput` message.
+struct MessageReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> MessageReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, n: usize) -> AnyResult<&'a [u8]> {
+        if self.data.len() < n {
+            bail!("truncated pgoutput message");
+        }
+        let (bytes, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> AnyResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> AnyResult<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> AnyResult<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> AnyResult<u64> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Reads a null-terminated string.
+    fn string(&mut self) -> AnyResult<String> {
+        let len = self
+            .data
+            .iter()
+            .position(|&c| c == 0)
+            .ok_or_else(|| anyhow!("truncated pgoutput message"))?;
+        let s = String::from_utf8(self.bytes(len)?.to_vec())?;
+        self.bytes(1)?;
+        Ok(s)
+    }
+
+    fn tuple(&mut self) -> AnyResult<Vec<TupleValue>> {
+        let n = self.u16()?;
+        (0..n)
+            .map(|_| match self.u8()? {
+                b'n' => Ok(TupleValue::Null),
+                b'u' => Ok(TupleValue::UnchangedToast),
+                b't' => {
+                    let len = self.u32()? as usize;
+                    Ok(TupleValue::Text(String::from_utf8(
+                        self.bytes(len)?.to_vec(),
+                    )?))
+                }
+                kind => bail!("unsupported pgoutput tuple value kind '{}'", kind as char),
+            })
+            .collect()
+    }
+}
+
+/// Converts the PostgreSQL text representation of a value of type `type_oid`
+/// into JSON.
+fn text_to_json(type_oid: u32, text: &str) -> JsonValue {
+    const BOOL: u32 = 16;
+    const INT8: u32 = 20;
+    const INT2: u32 = 21;
+    const INT4: u32 = 23;
+    const FLOAT4: u32 = 700;
+    const FLOAT8: u32 = 701;
+
+    match type_oid {
+        BOOL => JsonValue::Bool(text == "t"),
+        // `NaN` and infinities are not valid JSON numbers.
+        INT8 | INT2 | INT4 | FLOAT4 | FLOAT8 => serde_json::from_str(text)
+            .map(JsonValue::Number)
+            .unwrap_or_else(|_| JsonValue::String(text.to_string())),
+        _ => JsonValue::String(text.to_string()),
+    }
+}
+
+/// Appends `{"<action>":<record>}` and a new-line to `buffer`.
+fn write_update(buffer: &mut Vec<u8>, action: &str, record: Map<String, JsonValue>) {
+    let mut update = Map::new();
+    update.insert(action.to_string(), JsonValue::Object(record));
+    serde_json::to_writer(&mut *buffer, &update).unwrap();
+    buffer.push(b'\n');
+}
+
+/// A transaction read from the replication slot.
+struct Transaction {
+    /// End of the transaction's commit record.
+    end_lsn: u64,
+
+    /// Changes to the table in the `insert_delete` JSON format.
+    buffer: Vec<u8>,
+
+    /// Changes that could not be converted.
+    errors: Vec<String>,
+}
+
+/// A reader for a PostgreSQL table's changes.
+struct PostgresCdcInputReader {
+    status: Arc<AtomicU32>,
+
+    /// The greatest step that the reader may read, as passed to
+    /// [`InputReader::start`].
+    run_step: Arc<AtomicStep>,
+
+    /// The greatest step whose completion has been requested.
+    complete_step: Arc<Mutex<Option<Step>>>,
+
+    unparker: Unparker,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl PostgresCdcInputReader {
+    fn new(
+        config: &PostgresCdcInputConfig,
+        journal: &Arc<Mutex<Journal>>,
+        start_step: Step,
+        mut consumer: Box<dyn InputConsumer>,
+    ) -> AnyResult<Self> {
+        let (delivered_lsn, visibility, fresh_start) = {
+            let journal = journal.lock().unwrap();
+            let steps = journal.steps();
+            if let Some(entry) = journal.find(start_step) {
+                (entry.lsns.start, entry.visibility.clone(), false)
+            } else if start_step == steps.end {
+                match journal.entries.back() {
+                    Some(last) => (last.lsns.end, last.visibility.clone(), false),
+                    None => (0, None, true),
+                }
+            } else {
+                bail!(
+                    "cannot start reading at step {start_step} because the journal for replication slot '{}' records steps {steps:?}",
+                    config.slot
+                );
+            }
+        };
+
+        let mut client = Client::connect(&config.uri, NoTls)
+            .with_context(|| format!("failed to connect to PostgreSQL at '{}'", config.uri))?;
+        // The `regclass` text form of the table is a name that PostgreSQL
+        // quotes and schema-qualifies as needed.
+        let row = client
+            .query_one(
+                "SELECT $1::text::regclass::oid, $1::text::regclass::text",
+                &[&config.table],
+            )
+            .with_context(|| format!("failed to look up table '{}'", config.table))?;
+        let relid: u32 = row.get(0);
+        let table_name: String = row.get(1);
+        let columns = client
+            .query(
+                "SELECT attname::text, atttypid FROM pg_attribute \
+                 WHERE attrelid = $1 AND attnum > 0 AND NOT attisdropped ORDER BY attnum",
+                &[&relid],
+            )?
+            .iter()
+            .map(|row| Column {
+                name: row.get(0),
+                type_oid: row.get(1),
+            })
+            .collect();
+
+        let slot_exists = !client
+            .query(
+                "SELECT 1 FROM pg_replication_slots WHERE slot_name = $1",
+                &[&config.slot],
+            )?
+            .is_empty();
+        let take_snapshot = fresh_start && config.snapshot;
+        if fresh_start {
+            if client
+                .query(
+                    "SELECT 1 FROM pg_publication WHERE pubname = $1",
+                    &[&config.publication],
+                )?
+                .is_empty()
+            {
+                client.batch_execute(&format!(
+                    "CREATE PUBLICATION {} FOR TABLE {table_name}",
+                    quote_ident(&config.publication)
+                ))?;
+            }
+
+            // Without a journal, we start from scratch.  The snapshot is only
+            // consistent with a replication slot created right before it.
+            if slot_exists && take_snapshot {
+                info!(
+                    "dropping replication slot '{}' to take a new snapshot of '{}'",
+                    config.slot, config.table
+                );
+                client.execute("SELECT pg_drop_replication_slot($1)", &[&config.slot])?;
+            }
+            if !slot_exists || take_snapshot {
+                client
+                    .execute(
+                        "SELECT pg_create_logical_replication_slot($1, 'pgoutput')",
+                        &[&config.slot],
+                    )
+                    .with_context(|| {
+                        format!("failed to create replication slot '{}'", config.slot)
+                    })?;
+            }
+        } else if !slot_exists {
+            bail!("replication slot '{}' does not exist", config.slot);
+        }
+
+        // Read changes from a temporary copy of the slot, which we can consume
+        // as we go, instead of peeking at the slot itself, which would return
+        // all of the changes since the first step that hasn't expired on every
+        // poll.  PostgreSQL drops the copy when the connection closes.
+        let pid: i32 = client.query_one("SELECT pg_backend_pid()", &[])?.get(0);
+        let reader_slot = format!("{}_{pid}", config.slot);
+        client
+            .execute(
+                "SELECT pg_copy_logical_replication_slot($1, $2, true)",
+                &[&config.slot, &reader_slot],
+            )
+            .with_context(|| format!("failed to copy replication slot '{}'", config.slot))?;
+        let snapshot_table = journal.lock().unwrap().snapshot_table.clone();
+
+        let parker = Parker::new();
+        let unparker = parker.unparker().clone();
+        let status = Arc::new(AtomicU32::new(PipelineState::Paused as u32));
+        let run_step = Arc::new(AtomicStep::new(0));
+        let complete_step = Arc::new(Mutex::new(None));
+        let mut worker = Worker {
+            config: config.clone(),
+            status: status.clone(),
+            run_step: run_step.clone(),
+            complete_step: complete_step.clone(),
+            parker,
+            journal: journal.clone(),
+            client,
+            reader_slot,
+            relid,
+            table_name,
+            snapshot_table,
+            columns,
+            poll_interval: Duration::from_millis(
+                config.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+            ),
+            take_snapshot,
+            relations: HashMap::new(),
+            delivered_lsn,
+            visibility,
+            pending: VecDeque::new(),
+        };
+        let join_handle = Builder::new()
+            .name("postgres-cdc-input".into())
+            .spawn(move || {
+                if let Err(error) = worker.run(start_step, consumer.as_mut()) {
+                    error!("PostgreSQL CDC input endpoint failed due to: {error:#}");
+                    consumer.error(true, error);
+                }
+            })?;
+
+        Ok(Self {
+            status,
+            run_step,
+            complete_step,
+            unparker,
+            join_handle: Some(join_handle),
+        })
+    }
+}
+
+impl InputReader for PostgresCdcInputReader {
+    fn start(&self, step: Step) -> AnyResult<()> {
+        self.run_step.store(step, Ordering::Release);
+        self.status
+            .store(PipelineState::Running as u32, Ordering::Release);
+        self.unparker.unpark();
+        Ok(())
+    }
+
+    fn pause(&self) -> AnyResult<()> {
+        self.status
+            .store(PipelineState::Paused as u32, Ordering::Release);
+        Ok(())
+    }
+
+    fn complete(&self, new_step: Step) {
+        let mut complete_step = self.complete_step.lock().unwrap();
+        match *complete_step {
+            Some(step) if new_step <= step => (),
+            _ => {
+                *complete_step = Some(new_step);
+                self.unparker.unpark();
+            }
+        }
+    }
+
+    fn disconnect(&self) {
+        self.status
+            .store(PipelineState::Terminated as u32, Ordering::Release);
+        self.unparker.unpark();
+    }
+}
+
+impl Drop for PostgresCdcInputReader {
+    fn drop(&mut self) {
+        self.disconnect();
+        if let Some(join_handle) = self.join_handle.take() {
+            // The reader might get dropped from a callback executed from the
+            // worker thread.  We must not join ourselves.
+            if join_handle.thread().id() != thread::current().id() {
+                let _ = join_handle.join();
+            }
+        }
+    }
+}
+
+/// Worker thread state for [`PostgresCdcInputReader`].
+struct Worker {
+    config: PostgresCdcInputConfig,
+    status: Arc<AtomicU32>,
+    run_step: Arc<AtomicStep>,
+    complete_step: Arc<Mutex<Option<Step>>>,
+    parker: Parker,
+    journal: Arc<Mutex<Journal>>,
+    client: Client,
+
+    /// Temporary copy of the replication slot that this reader consumes.
+    reader_slot: String,
+
+    /// OID of the table.
+    relid: u32,
+
+    /// Name of the table, quoted as needed.
+    table_name: String,
+
+    /// Table that holds the initial snapshot.
+    snapshot_table: String,
+
+    /// Columns of the table, used to convert the initial snapshot.
+    columns: Vec<Column>,
+
+    poll_interval: Duration,
+
+    /// Whether the first new step must consist of the initial snapshot.
+    take_snapshot: bool,
+
+    /// Relations described by `Relation` messages, indexed by OID.
+    relations: HashMap<u32, Vec<Column>>,
+
+    /// End of the commit record of the last transaction passed to the
+    /// consumer.
+    delivered_lsn: u64,
+
+    /// The transactions reflected in the initial snapshot.
+    visibility: Option<TxidSnapshot>,
+
+    /// Transactions read from the replication slot but not yet passed to the
+    /// consumer.
+    pending: VecDeque<Transaction>,
+}
+
+impl Worker {
+    /// Waits until the reader may push data for `step`.  Returns `false` if
+    /// the reader was disconnected.
+    fn wait_for_start(&self, step: Step) -> bool {
+        loop {
+            match PipelineState::from_u32(self.status.load(Ordering::Acquire)) {
+                Some(PipelineState::Running) if step <= self.run_step.load(Ordering::Acquire) => {
+                    return true
+                }
+                Some(PipelineState::Terminated) => return false,
+                _ => self.parker.park_timeout(self.poll_interval),
+            }
+        }
+    }
+
+    fn is_completion_requested(&self, step: Step) -> bool {
+        match *self.complete_step.lock().unwrap() {
+            Some(complete_step) => step <= complete_step,
+            None => false,
+        }
+    }
+
+    /// Copies the current contents of the table into the snapshot table and
+    /// records the transactions that they reflect.
+    fn snapshot(&mut self) -> AnyResult<()> {
+        let mut transaction = self
+            .client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .start()?;
+
+        // The first statement in the transaction fixes its snapshot.
+        let visibility: String = transaction
+            .query_one("SELECT txid_current_snapshot()::text", &[])?
+            .get(0);
+        self.visibility = Some(TxidSnapshot::parse(&visibility)?);
+
+        transaction.batch_execute(&format!(
+            "DROP TABLE IF EXISTS {snapshot_table};
+             CREATE TABLE {snapshot_table} AS SELECT * FROM {}",
+            self.table_name,
+            snapshot_table = self.snapshot_table
+        ))?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Passes the contents of the snapshot table to `consumer` as insertions.
+    fn deliver_snapshot(&mut self, consumer: &mut dyn InputConsumer) -> AnyResult<()> {
+        let mut transaction = self
+            .client
+            .build_transaction()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .read_only(true)
+            .start()?;
+        transaction
+            .batch_execute(&format!(
+                "DECLARE snapshot NO SCROLL CURSOR FOR SELECT * FROM {}",
+                self.snapshot_table
+            ))
+            .with_context(|| {
+                format!(
+                    "failed to read the initial snapshot of '{}' from '{}'",
+                    self.config.table, self.snapshot_table
+                )
+            })?;
+        loop {
+            let mut buffer = Vec::new();
+            for message in
+                transaction.simple_query(&format!("FETCH {SNAPSHOT_BATCH_ROWS} FROM snapshot"))?
+            {
+                if let SimpleQueryMessage::Row(row) = message {
+                    let mut record = Map::new();
+                    for (i, column) in self.columns.iter().enumerate() {
+                        let value = row
+                            .get(i)
+                            .map_or(JsonValue::Null, |text| text_to_json(column.type_oid, text));
+                        record.insert(column.name.clone(), value);
+                    }
+                    write_update(&mut buffer, "insert", record);
+                }
+            }
+            if buffer.is_empty() {
+                break;
+            }
+            let _ = consumer.input_chunk(&buffer);
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Reads transactions that have not yet been passed to the consumer from
+    /// the replication slot into `pending`.  Returns `false` if there are none.
+    ///
+    /// Logical decoding only stops at transaction boundaries, so every
+    /// transaction is read in a single poll.
+    fn poll(&mut self) -> AnyResult<bool> {
+        let rows = self.client.query(
+            "SELECT data FROM pg_logical_slot_get_binary_changes($1, NULL, $2, \
+             'proto_version', '1', 'publication_names', $3)",
+            &[
+                &self.reader_slot,
+                &(MAX_ROWS_PER_POLL as i32),
+                &self.config.publication,
+            ],
+        )?;
+
+        let mut transaction: Option<(Transaction, bool)> = None;
+        for row in rows.iter() {
+            let data: &[u8] = row.get(0);
+            let mut reader = MessageReader::new(data);
+            let Some((current, skip)) = transaction.as_mut() else {
+                if reader.u8()? == b'B' {
+                    let _final_lsn = reader.u64()?;
+                    let _commit_time = reader.u64()?;
+                    let xid = reader.u32()?;
+                    let skip = self
+                        .visibility
+                        .as_ref()
+                        .is_some_and(|visibility| visibility.is_visible(xid));
+                    transaction = Some((
+                        Transaction {
+                            end_lsn: 0,
+                            buffer: Vec::new(),
+                            errors: Vec::new(),
+                        },
+                        skip,
+                    ));
+                }
+                continue;
+            };
+            if let Err(e) = self.decode(&mut reader, current, *skip) {
+                current.errors.push(format!("{e:#}"));
+            }
+            if current.end_lsn != 0 {
+                let (current, skip) = transaction.take().unwrap();
+                if current.end_lsn <= self.delivered_lsn {
+                    // Passed to the consumer before this reader was opened.
+                    continue;
+                }
+                if skip {
+                    // Reflected in the initial snapshot.
+                    self.pending.push_back(Transaction {
+                        buffer: Vec::new(),
+                        errors: Vec::new(),
+                        ..current
+                    });
+                } else {
+                    self.pending.push_back(current);
+                }
+            }
+        }
+        Ok(!self.pending.is_empty())
+    }
+
+    /// Decodes the message in `reader`, which is part of `transaction`, and
+    /// adds any changes to the table to `transaction`.
+    fn decode(
+        &mut self,
+        reader: &mut MessageReader,
+        transaction: &mut Transaction,
+        skip: bool,
+    ) -> AnyResult<()> {
+        match reader.u8()? {
+            b'C' => {
+                let _flags = reader.u8()?;
+                let _commit_lsn = reader.u64()?;
+                transaction.end_lsn = reader.u64()?;
+            }
+            b'R' => {
+                let relid = reader.u32()?;
+                let _namespace = reader.string()?;
+                let _name = reader.string()?;
+                let _replica_identity = reader.u8()?;
+                let n = reader.u16()?;
+                let columns = (0..n)
+                    .map(|_| {
+                        let _flags = reader.u8()?;
+                        let name = reader.string()?;
+                        let type_oid = reader.u32()?;
+                        let _type_modifier = reader.u32()?;
+                        Ok(Column { name, type_oid })
+                    })
+                    .collect::<AnyResult<_>>()?;
+                self.relations.insert(relid, columns);
+            }
+            action @ (b'I' | b'U' | b'D') => {
+                let relid = reader.u32()?;
+                if relid != self.relid || skip {
+                    return Ok(());
+                }
+                let columns = self.relations.get(&relid).ok_or_else(|| {
+                    anyhow!("change to relation {relid} precedes its description")
+                })?;
+                let mut kind = reader.u8()?;
+                let old = if kind == b'O' {
+                    let old = record(columns, reader.tuple()?, None)?;
+                    if action == b'U' {
+                        kind = reader.u8()?;
+                    }
+                    Some(old)
+                } else {
+                    // A key-only (`K`) old tuple cannot be retracted.
+                    None
+                };
+                if action != b'I' && old.is_none() {
+                    bail!(
+                        "change to '{}' does not include the old version of the row; the table must have REPLICA IDENTITY FULL",
+                        self.config.table
+                    );
+                }
+                if let Some(old) = &old {
+                    write_update(&mut transaction.buffer, "delete", old.clone());
+                }
+                if action != b'D' {
+                    if kind != b'N' {
+                        bail!("malformed pgoutput message");
+                    }
+                    let new = record(columns, reader.tuple()?, old.as_ref())?;
+                    write_update(&mut transaction.buffer, "insert", new);
+                }
+            }
+            b'T' => {
+                let n = reader.u32()?;
+                let _options = reader.u8()?;
+                for _ in 0..n {
+                    if reader.u32()? == self.relid && !skip {
+                        bail!("TRUNCATE of '{}' is not supported", self.config.table);
+                    }
+                }
+            }
+            // Other messages, such as `Type` and `Origin`, are not needed.
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Passes `transaction` to `consumer`.
+    fn deliver(&mut self, transaction: Transaction, consumer: &mut dyn InputConsumer) {
+        for error in transaction.errors {
+            consumer.error(false, anyhow!(error));
+        }
+        if !transaction.buffer.is_empty() {
+            let _ = consumer.input_chunk(&transaction.buffer);
+        }
+        self.delivered_lsn = transaction.end_lsn;
+    }
+
+    fn run(&mut self, start_step: Step, consumer: &mut dyn InputConsumer) -> AnyResult<()> {
+        for step in start_step.. {
+            consumer.start_step(step);
+            if !self.wait_for_start(step) {
+                return Ok(());
+            }
+
+            let entry = self.journal.lock().unwrap().find(step).cloned();
+            if let Some(entry) = entry {
+                // Replay a step recorded in the journal.
+                if entry.snapshot {
+                    self.deliver_snapshot(consumer)?;
+                }
+                while self.delivered_lsn < entry.lsns.end {
+                    if !self.wait_for_start(step) {
+                        return Ok(());
+                    }
+                    match self.pending.pop_front() {
+                        Some(transaction) if transaction.end_lsn <= entry.lsns.end => {
+                            self.deliver(transaction, consumer)
+                        }
+                        Some(_) => bail!(
+                            "replication slot '{}' has no transaction ending at LSN {}, which the journal records as the end of step {step}",
+                            self.config.slot,
+                            format_lsn(entry.lsns.end)
+                        ),
+                        None => {
+                            if !self.poll()? {
+                                bail!(
+                                    "replication slot '{}' ends before LSN {}, which the journal records as the end of step {step}",
+                                    self.config.slot,
+                                    format_lsn(entry.lsns.end)
+                                );
+                            }
+                        }
+                    }
+                }
+                consumer.committed(step);
+                continue;
+            }
+
+            // Add data to a new step.  The initial snapshot gets a step of its
+            // own.
+            let start_lsn = self.delivered_lsn;
+            let snapshot = self.take_snapshot;
+            if snapshot {
+                self.take_snapshot = false;
+                self.snapshot()?;
+                self.deliver_snapshot(consumer)?;
+            }
+            while !self.is_completion_requested(step) {
+                if !self.wait_for_start(step) {
+                    return Ok(());
+                }
+                if snapshot {
+                    self.parker.park_timeout(self.poll_interval);
+                } else if let Some(transaction) = self.pending.pop_front() {
+                    self.deliver(transaction, consumer);
+                } else if !self.poll()? {
+                    self.parker.park_timeout(self.poll_interval);
+                }
+            }
+
+            self.journal.lock().unwrap().append(JournalEntry {
+                step,
+                lsns: start_lsn..self.delivered_lsn,
+                snapshot,
+                visibility: self.visibility.clone(),
+            })?;
+            consumer.committed(step);
+        }
+        unreachable!()
+    }
+}
+
+/// Converts the values in `tuple` into a JSON record.  Unchanged TOASTed
+/// values are taken from `old`.
+fn record(
+    columns: &[Column],
+    tuple: Vec<TupleValue>,
+    old: Option<&Map<String, JsonValue>>,
+) -> AnyResult<Map<String, JsonValue>> {
+    if tuple.len() != columns.len() {
+        bail!(
+            "pgoutput tuple has {} values but its relation has {} columns",
+            tuple.len(),
+            columns.len()
+        );
+    }
+    columns
+        .iter()
+        .zip(tuple)
+        .map(|(column, value)| {
+            let value = match value {
+                TupleValue::Null => JsonValue::Null,
+                TupleValue::Text(text) => text_to_json(column.type_oid, &text),
+                TupleValue::UnchangedToast => old
+                    .and_then(|old| old.get(&column.name))
+                    .cloned()
+                    .ok_or_else(|| anyhow!("value of column '{}' is not available", column.name))?,
+            };
+            Ok((column.name.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{ConsumerCall, DummyInputReceiver},
+        transport::InputEndpoint,
+        InputTransport,
+    };
+    use postgres::{Client, NoTls};
+    use serde_json::json;
+    use std::env;
+
+    /// Connection string of the PostgreSQL instance used by tests, which must
+    /// be configured with `wal_level = logical`.
+    fn postgres_url() -> String {
+        env::var("POSTGRES_URL").unwrap_or_else(|_| "host=localhost user=postgres".to_string())
+    }
+
+    /// Creates a fresh table `table` with primary key `id` and drops the
+    /// replication slot, publication, and journal used by its endpoints.
+    fn create_table(table: &str) -> Client {
+        let mut client = Client::connect(&postgres_url(), NoTls).unwrap();
+        client
+            .execute(
+                "SELECT pg_drop_replication_slot(slot_name) FROM pg_replication_slots \
+                 WHERE slot_name = $1",
+                &[&format!("{table}_slot")],
+            )
+            .unwrap();
+        client
+            .batch_execute(&format!(
+                "DROP PUBLICATION IF EXISTS {table}_pub;
+                 DROP TABLE IF EXISTS {table}, {table}_journal, {table}_journal_{table}_slot_snapshot;
+                 CREATE TABLE {table} (id INT PRIMARY KEY, b BOOLEAN, s TEXT);
+                 ALTER TABLE {table} REPLICA IDENTITY FULL"
+            ))
+            .unwrap();
+        client
+    }
+
+    fn cdc_endpoint(table: &str) -> Box<dyn InputEndpoint> {
+        let config_str = format!(
+            r#"
+uri: "{}"
+table: {table}
+slot: {table}_slot
+publication: {table}_pub
+journal_table: {table}_journal
+poll_interval_ms: 10
+"#,
+            postgres_url()
+        );
+        <dyn InputTransport>::get_transport("postgres_cdc")
+            .unwrap()
+            .new_endpoint(&serde_yaml::from_str(&config_str).unwrap())
+            .unwrap()
+    }
+
+    /// Returns the chunk that the connector outputs for `updates`, each of
+    /// which is an action and a record.
+    fn chunk(updates: &[(&str, i32, bool, &str)]) -> ConsumerCall {
+        let mut s = String::new();
+        for (action, id, b, text) in updates {
+            let record = json!({"id": id, "b": b, "s": text});
+            s += &format!("{{\"{action}\":{record}}}\n");
+        }
+        ConsumerCall::InputChunk(s)
+    }
+
+    /// The chunks that the connector outputs in step 1 of
+    /// [`test_postgres_cdc`].
+    fn step1() -> Vec<ConsumerCall> {
+        vec![
+            chunk(&[("insert", 2, false, "bar")]),
+            chunk(&[("delete", 1, true, "foo"), ("insert", 1, true, "baz")]),
+            chunk(&[("delete", 2, false, "bar")]),
+        ]
+    }
+
+    /// Reads the initial snapshot and some changes, "crashes", and then checks
+    /// that the changes are replayed identically after a restart.
+    #[test]
+    fn test_postgres_cdc() {
+        let table = "test_postgres_cdc";
+        let mut client = create_table(table);
+        client
+            .batch_execute(&format!("INSERT INTO {table} VALUES (1, true, 'foo')"))
+            .unwrap();
+
+        let endpoint = cdc_endpoint(table);
+        assert!(endpoint.is_fault_tolerant());
+        assert_eq!(endpoint.steps().unwrap(), 0..0);
+
+        // Step 0 consists of the snapshot.
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 0).unwrap();
+        reader.start(0).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(0),
+            chunk(&[("insert", 1, true, "foo")]),
+        ]);
+        reader.complete(0);
+        receiver.expect(vec![ConsumerCall::StartStep(1)]);
+        receiver.wait_to_complete(0);
+        assert_eq!(endpoint.steps().unwrap(), 0..1);
+
+        // Changes made after the snapshot go into step 1.  An update retracts
+        // the old version of the row.
+        client
+            .batch_execute(&format!(
+                "INSERT INTO {table} VALUES (2, false, 'bar');
+                 UPDATE {table} SET s = 'baz' WHERE id = 1;
+                 DELETE FROM {table} WHERE id = 2"
+            ))
+            .unwrap();
+        receiver.expect_eof();
+        reader.start(1).unwrap();
+        receiver.expect(step1());
+        reader.complete(1);
+        receiver.expect(vec![ConsumerCall::StartStep(2)]);
+        receiver.wait_to_complete(1);
+        assert_eq!(endpoint.steps().unwrap(), 0..2);
+
+        // Crash with more changes in the replication slot.
+        client
+            .batch_execute(&format!("INSERT INTO {table} VALUES (3, true, 'qux')"))
+            .unwrap();
+        drop(reader);
+        drop(endpoint);
+
+        // After restarting, step 1 should be replayed exactly, followed by new
+        // data in step 2.
+        let endpoint = cdc_endpoint(table);
+        assert_eq!(endpoint.steps().unwrap(), 0..2);
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 1).unwrap();
+        reader.start(2).unwrap();
+        let mut expected = vec![ConsumerCall::StartStep(1)];
+        expected.extend(step1());
+        expected.extend([
+            ConsumerCall::StartStep(2),
+            chunk(&[("insert", 3, true, "qux")]),
+        ]);
+        receiver.expect(expected);
+        reader.complete(2);
+        receiver.expect(vec![ConsumerCall::StartStep(3)]);
+        receiver.wait_to_complete(2);
+        drop(reader);
+
+        // The snapshot is replayed as it was taken, even though the table has
+        // changed since.
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 0).unwrap();
+        reader.start(0).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(0),
+            chunk(&[("insert", 1, true, "foo")]),
+            ConsumerCall::StartStep(1),
+        ]);
+        drop(reader);
+
+        // Expiring steps advances the replication slot, after which the
+        // remaining steps can still be replayed, and drops the snapshot.
+        endpoint.expire(2);
+        assert_eq!(endpoint.steps().unwrap(), 2..3);
+        let snapshot_table: Option<String> = client
+            .query_one(
+                "SELECT to_regclass($1)::text",
+                &[&format!("{table}_journal_{table}_slot_snapshot")],
+            )
+            .unwrap()
+            .get(0);
+        assert_eq!(snapshot_table, None);
+        assert!(endpoint
+            .open(DummyInputReceiver::new().consumer(), 1)
+            .is_err());
+        let receiver = DummyInputReceiver::new();
+        let reader = endpoint.open(receiver.consumer(), 2).unwrap();
+        reader.start(2).unwrap();
+        receiver.expect(vec![
+            ConsumerCall::StartStep(2),
+            chunk(&[("insert", 3, true, "qux")]),
+            ConsumerCall::StartStep(3),
+        ]);
+        receiver.expect_eof();
+    }
+}
@@ -0,0 +1,1187 @@
+use super::{
+    secret_resolver::MaybeSecret, InputConsumer, InputEndpoint, InputReader, InputTransport,
+    OutputEndpoint, OutputTransport, Step,
+};
+use crate::{AsyncErrorCallback, OutputEndpointConfig, PipelineState};
+use actix_web::http::header::ContentRangeSpec;
+use anyhow::{anyhow, bail, Context, Result as AnyResult};
+use aws_sdk_s3::{
+    config::{Builder as S3ConfigBuilder, Credentials, Region},
+    error::DisplayErrorContext,
+    primitives::ByteStream,
+    Client,
+};
+use futures::StreamExt;
+use log::error;
+use pipeline_types::{
+    secret_ref::MaybeSecretRef,
+    transport::s3::{S3InputConfig, S3OutputConfig},
+};
+use serde::Deserialize;
+use serde_yaml::Value as YamlValue;
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::VecDeque,
+    fs::{read, rename, File},
+    io::{ErrorKind, Write},
+    mem::take,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread::spawn,
+    time::Duration,
+};
+use tokio::{
+    runtime::{Builder as RuntimeBuilder, Runtime},
+    select,
+    sync::{
+        watch::{channel, Receiver, Sender},
+        Notify,
+    },
+    time::sleep,
+};
+
+const DEFAULT_POLL_INTERVAL_MS: u64 = 10_000;
+
+/// [`InputTransport`] implementation that reads data from the objects in an
+/// S3 bucket, or in a bucket of an S3-compatible service.
+///
+/// The input transport factory gives this transport the name `s3`.
+pub struct S3InputTransport;
+
+impl InputTransport for S3InputTransport {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("s3")
+    }
+
+    /// Creates a new [`InputEndpoint`] for reading objects from a bucket,
+    /// interpreting `config` as an [`S3InputConfig`].
+    ///
+    /// See [`InputTransport::new_endpoint()`] for more information.
+    fn new_endpoint(&self, config: &YamlValue) -> AnyResult<Box<dyn InputEndpoint>> {
+        let config = S3InputConfig::deserialize(config)?;
+        check_credentials(&config.access_key_id, &config.secret_access_key)?;
+        let ep = S3InputEndpoint {
+            config: Arc::new(config),
+        };
+        Ok(Box::new(ep))
+    }
+}
+
+/// [`OutputTransport`] implementation that writes data to objects in an S3
+/// bucket, or in a bucket of an S3-compatible service.
+///
+/// The output transport factory gives this transport the name `s3`.
+pub struct S3OutputTransport;
+
+impl OutputTransport for S3OutputTransport {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("s3")
+    }
+
+    /// Creates a new [`OutputEndpoint`] for writing objects to a bucket,
+    /// interpreting `config` as an [`S3OutputConfig`].
+    ///
+    /// See [`OutputTransport::new_endpoint()`] for more information.
+    fn new_endpoint(&self, config: &OutputEndpointConfig) -> AnyResult<Box<dyn OutputEndpoint>> {
+        let config = S3OutputConfig::deserialize(&config.connector_config.transport.config)?;
+        check_credentials(&config.access_key_id, &config.secret_access_key)?;
+        if config.steps_per_object == 0 {
+            bail!("'steps_per_object' must be at least 1");
+        }
+        Ok(Box::new(S3OutputEndpoint::new(config)?))
+    }
+}
+
+fn check_credentials(
+    access_key_id: &Option<String>,
+    secret_access_key: &Option<String>,
+) -> AnyResult<()> {
+    if access_key_id.is_some() != secret_access_key.is_some() {
+        bail!("'access_key_id' and 'secret_access_key' must be specified together");
+    }
+    Ok(())
+}
+
+/// Resolves `value`, which may be a secret reference, to a string.
+fn resolve_secret(value: &str) -> AnyResult<String> {
+    match MaybeSecret::new_using_default_directory(MaybeSecretRef::new_using_pattern_match(
+        value.to_string(),
+    ))? {
+        MaybeSecret::String(simple_string) => Ok(simple_string),
+        MaybeSecret::Secret(secret_string) => Ok(secret_string),
+    }
+}
+
+/// Creates a client for the buckets in `region`, at the S3-compatible service
+/// at `endpoint_url` if there is one.  Credentials that are not specified
+/// explicitly are obtained from the environment.
+async fn new_client(
+    region: &str,
+    endpoint_url: Option<&str>,
+    access_key_id: Option<&str>,
+    secret_access_key: Option<&str>,
+) -> AnyResult<Client> {
+    let sdk_config = aws_config::from_env()
+        .region(Region::new(region.to_string()))
+        .load()
+        .await;
+    let mut builder = S3ConfigBuilder::from(&sdk_config);
+    if let Some(endpoint_url) = endpoint_url {
+        // S3-compatible services generally do not support virtual-hosted
+        // style addressing.
+        builder = builder.endpoint_url(endpoint_url).force_path_style(true);
+    }
+    if let (Some(access_key_id), Some(secret_access_key)) = (access_key_id, secret_access_key) {
+        builder = builder.credentials_provider(Credentials::new(
+            resolve_secret(access_key_id)?,
+            resolve_secret(secret_access_key)?,
+            None,
+            None,
+            "feldera",
+        ));
+    }
+    Ok(Client::from_conf(builder.build()))
+}
+
+/// Returns the offset of the first byte in an HTTP `Content-Range` header.
+fn content_range_start(range: &str) -> AnyResult<u64> {
+    match ContentRangeSpec::from_str(range)? {
+        ContentRangeSpec::Bytes {
+            range: Some((start, _)),
+            ..
+        } => Ok(start),
+        ContentRangeSpec::Bytes { range: None, .. } => Ok(0),
+        other => bail!("expected byte range in S3 response, instead received {other}"),
+    }
+}
+
+struct S3InputEndpoint {
+    config: Arc<S3InputConfig>,
+}
+
+impl InputEndpoint for S3InputEndpoint {
+    fn open(
+        &self,
+        consumer: Box<dyn InputConsumer>,
+        _start_step: Step,
+    ) -> AnyResult<Box<dyn InputReader>> {
+        Ok(Box::new(S3InputReader::new(&self.config, consumer)?))
+    }
+
+    fn is_fault_tolerant(&self) -> bool {
+        false
+    }
+}
+
+struct S3InputReader {
+    sender: Sender<PipelineState>,
+    objects: Arc<Mutex<ReadObjects>>,
+    notify: Arc<Notify>,
+}
+
+/// Objects that the worker has read completely, shared between the worker
+/// and [`S3InputReader::complete`].
+#[derive(Default)]
+struct ReadObjects {
+    /// Key of the last object read since the last call to `complete`.
+    read: Option<String>,
+
+    /// Key of the last object whose data is part of a step, to be recorded
+    /// as completed by the worker.
+    completed: Option<String>,
+}
+
+impl S3InputReader {
+    fn new(config: &Arc<S3InputConfig>, consumer: Box<dyn InputConsumer>) -> AnyResult<Self> {
+        let runtime = RuntimeBuilder::new_current_thread().enable_all().build()?;
+        let completed = LastCompleted::open(config.state_path.as_ref().map(PathBuf::from))?;
+        let (sender, receiver) = channel(PipelineState::Paused);
+        let objects = Arc::new(Mutex::new(ReadObjects::default()));
+        let notify = Arc::new(Notify::new());
+        let mut worker = Worker {
+            config: config.clone(),
+            consumer,
+            receiver,
+            objects: objects.clone(),
+            notify: notify.clone(),
+            last_read: completed.key.clone(),
+            completed,
+            pending: VecDeque::new(),
+        };
+        let _worker = spawn(move || {
+            if let Err(error) = runtime.block_on(worker.run()) {
+                worker.consumer.error(true, error);
+            }
+        });
+
+        Ok(Self {
+            sender,
+            objects,
+            notify,
+        })
+    }
+}
+
+impl InputReader for S3InputReader {
+    fn pause(&self) -> AnyResult<()> {
+        // Use `send_replace`, instead of `send`, to make it a no-op if the
+        // worker thread has died.
+        self.sender.send_replace(PipelineState::Paused);
+        Ok(())
+    }
+
+    fn start(&self, _step: Step) -> AnyResult<()> {
+        self.sender.send_replace(PipelineState::Running);
+        Ok(())
+    }
+
+    fn complete(&self, _step: Step) {
+        // The worker passed the contents of the objects read so far to the
+        // consumer before it reported them, so their data is part of `step`.
+        {
+            let mut objects = self.objects.lock().unwrap();
+            if let Some(key) = objects.read.take() {
+                objects.completed = Some(key);
+            }
+        }
+
+        // Wake up the worker to record the completed object.
+        self.notify.notify_one();
+    }
+
+    fn disconnect(&self) {
+        self.sender.send_replace(PipelineState::Terminated);
+    }
+}
+
+impl Drop for S3InputReader {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+struct Worker {
+    config: Arc<S3InputConfig>,
+    consumer: Box<dyn InputConsumer>,
+    receiver: Receiver<PipelineState>,
+    objects: Arc<Mutex<ReadObjects>>,
+
+    /// Notified when [`S3InputReader::complete`] completes an object.
+    notify: Arc<Notify>,
+
+    /// Key of the last object whose data is part of a step.
+    completed: LastCompleted,
+
+    /// Key of the last object that has been read completely, whether or not
+    /// it has been completed.
+    last_read: Option<String>,
+
+    /// Keys of the objects still to be read, in the order to read them.
+    pending: VecDeque<String>,
+}
+
+impl Worker {
+    /// Waits while the endpoint is paused.  Returns `false` if the endpoint
+    /// was disconnected.
+    async fn wait_for_running(&mut self) -> bool {
+        loop {
+            let state = *self.receiver.borrow_and_update();
+            match state {
+                PipelineState::Paused => (),
+                PipelineState::Running => return true,
+                PipelineState::Terminated => return false,
+            }
+            if self.receiver.changed().await.is_err() {
+                return false;
+            }
+        }
+    }
+
+    async fn run(&mut self) -> AnyResult<()> {
+        let client = new_client(
+            &self.config.region,
+            self.config.endpoint_url.as_deref(),
+            self.config.access_key_id.as_deref(),
+            self.config.secret_access_key.as_deref(),
+        )
+        .await?;
+        let poll_interval = Duration::from_millis(
+            self.config
+                .poll_interval_ms
+                .unwrap_or(DEFAULT_POLL_INTERVAL_MS),
+        );
+
+        let mut eoi = false;
+        while self.wait_for_running().await {
+            self.complete_objects()?;
+            if eoi {
+                // Wait for the objects read so far to be completed.
+                if self.objects.lock().unwrap().read.is_none() {
+                    break;
+                }
+                select! {
+                    _ = self.notify.notified() => (),
+                    _ = self.receiver.changed() => (),
+                }
+                continue;
+            }
+            if self.pending.is_empty() {
+                self.list_objects(&client).await?;
+            }
+            match self.pending.pop_front() {
+                Some(key) => {
+                    if !self.read_object(&client, &key).await? {
+                        break;
+                    }
+                    self.last_read = Some(key.clone());
+                    self.objects.lock().unwrap().read = Some(key);
+                }
+                None if self.config.watch => {
+                    select! {
+                        _ = sleep(poll_interval) => (),
+                        _ = self.receiver.changed() => (),
+                        _ = self.notify.notified() => (),
+                    }
+                }
+                None => {
+                    let _ = self.consumer.eoi();
+                    eoi = true;
+                }
+            }
+        }
+        self.complete_objects()
+    }
+
+    /// Records the last object whose data has become part of a step as
+    /// completed.
+    fn complete_objects(&mut self) -> AnyResult<()> {
+        let completed = self.objects.lock().unwrap().completed.take();
+        match completed {
+            Some(key) => self.completed.set(key),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends the keys of the objects under the configured prefix that sort
+    /// after the last object read to `pending`, in lexicographic order.
+    async fn list_objects(&mut self, client: &Client) -> AnyResult<()> {
+        let mut continuation_token = None;
+        loop {
+            let response = client
+                .list_objects_v2()
+                .bucket(&self.config.bucket_name)
+                .prefix(&self.config.prefix)
+                .set_start_after(self.last_read.clone())
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|error| {
+                    anyhow!(
+                        "Failed to list objects in bucket '{}': {}",
+                        self.config.bucket_name,
+                        DisplayErrorContext(error)
+                    )
+                })?;
+
+            // S3 returns keys in lexicographic order, including across pages.
+            for object in response.contents().unwrap_or_default() {
+                match object.key() {
+                    // Skip "directory" placeholder objects.
+                    Some(key) if !key.ends_with('/') => self.pending.push_back(key.to_string()),
+                    _ => (),
+                }
+            }
+
+            match response.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Passes the contents of the object with the given `key` to the
+    /// consumer.  Returns `false` if the endpoint was disconnected before the
+    /// whole object was read.
+    ///
+    /// On pause, drops the connection, and then resumes reading where it left
+    /// off with a range request when the endpoint starts running again.
+    async fn read_object(&mut self, client: &Client, key: &str) -> AnyResult<bool> {
+        // Number of bytes of the object that we've delivered to `consumer`.
+        let mut consumed_bytes = 0;
+
+        let mut last_byte = None;
+        'connect: loop {
+            if !self.wait_for_running().await {
+                return Ok(false);
+            }
+
+            let mut request = client
+                .get_object()
+                .bucket(&self.config.bucket_name)
+                .key(key);
+            if consumed_bytes > 0 {
+                request = request.range(format!("bytes={consumed_bytes}-"));
+            }
+            let response = match request.send().await {
+                Ok(response) => response,
+
+                // We paused after receiving the last byte of the object but
+                // before learning that it was the last.
+                Err(error)
+                    if consumed_bytes > 0
+                        && error
+                            .raw_response()
+                            .is_some_and(|response| response.http().status().as_u16() == 416) =>
+                {
+                    break;
+                }
+                Err(error) => bail!(
+                    "Failed to read object '{key}' from bucket '{}': {}",
+                    self.config.bucket_name,
+                    DisplayErrorContext(error)
+                ),
+            };
+
+            // The object content offset of the next byte that we'll receive.
+            // The service need not honor our range request, in which case it
+            // starts at the beginning.
+            let mut offset = match response.content_range() {
+                Some(range) => content_range_start(range)?,
+                None => 0,
+            };
+            if offset > consumed_bytes {
+                bail!("S3 skipped past data we need in object '{key}', by starting at {offset} instead of {consumed_bytes}");
+            }
+
+            let mut body = response.body;
+            loop {
+                select! {
+                    _ = self.receiver.changed() => {
+                        let state = *self.receiver.borrow();
+                        if state != PipelineState::Running {
+                            continue 'connect;
+                        }
+                    }
+                    result = body.next() => {
+                        match result {
+                            None => break 'connect,
+                            Some(Ok(data)) => {
+                                let data_len = data.len() as u64;
+
+                                // Discard the data that we already passed
+                                // along, in case the service didn't honor our
+                                // range request.
+                                let chunk = match offset.cmp(&consumed_bytes) {
+                                    Ordering::Equal => &data[..],
+                                    Ordering::Less => {
+                                        let skip = consumed_bytes - offset;
+                                        if skip >= data_len {
+                                            &[]
+                                        } else {
+                                            &data[skip as usize..]
+                                        }
+                                    }
+                                    Ordering::Greater => unreachable!(),
+                                };
+                                if !chunk.is_empty() {
+                                    consumed_bytes += chunk.len() as u64;
+                                    last_byte = chunk.last().copied();
+                                    let _ = self.consumer.input_fragment(chunk);
+                                }
+                                offset += data_len;
+                            }
+                            Some(Err(error)) => bail!("Failed to read object '{key}': {error}"),
+                        }
+                    }
+                }
+            }
+        }
+
+        // Don't let the last record in this object run into the first record
+        // in the next one.
+        if last_byte.is_some_and(|c| c != b'\n') {
+            let _ = self.consumer.input_fragment(b"\n");
+        }
+        Ok(true)
+    }
+}
+
+/// The key of the last object that has been read completely and whose data
+/// is part of a step.
+///
+/// If a state file is specified, the key is recorded there, JSON-encoded, so
+/// that it survives restarts.
+struct LastCompleted {
+    state_path: Option<PathBuf>,
+    key: Option<String>,
+}
+
+impl LastCompleted {
+    /// Reads the last completed key from `state_path`, if it exists.
+    fn open(state_path: Option<PathBuf>) -> AnyResult<Self> {
+        let key = match &state_path {
+            None => None,
+            Some(state_path) => match read(state_path) {
+                Ok(contents) => Some(serde_json::from_slice(&contents).with_context(|| {
+                    format!("Failed to parse state file '{}'", state_path.display())
+                })?),
+                Err(e) if e.kind() == ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to read state file '{}'", state_path.display())
+                    })
+                }
+            },
+        };
+        Ok(Self { state_path, key })
+    }
+
+    /// Durably records `key` as the last completed key.
+    fn set(&mut self, key: String) -> AnyResult<()> {
+        if let Some(state_path) = &self.state_path {
+            // Replace the state file atomically, so that a crash leaves either
+            // the old or the new key behind.
+            let mut tmp_path = state_path.clone().into_os_string();
+            tmp_path.push(".tmp");
+            let tmp_path = PathBuf::from(tmp_path);
+            File::create(&tmp_path)
+                .and_then(|mut file| {
+                    file.write_all(&serde_json::to_vec(&key)?)?;
+                    file.sync_all()
+                })
+                .and_then(|_| rename(&tmp_path, state_path))
+                .with_context(|| {
+                    format!("Failed to update state file '{}'", state_path.display())
+                })?;
+        }
+        self.key = Some(key);
+        Ok(())
+    }
+}
+
+struct S3OutputEndpoint {
+    config: S3OutputConfig,
+    runtime: Runtime,
+    client: Client,
+
+    /// The step of the current batch.
+    step: Step,
+
+    /// The first step of the group of steps whose output is in `buffer`.
+    group: Step,
+
+    /// Output of the current group of steps.
+    buffer: Vec<u8>,
+}
+
+impl S3OutputEndpoint {
+    fn new(config: S3OutputConfig) -> AnyResult<Self> {
+        let runtime = RuntimeBuilder::new_current_thread().enable_all().build()?;
+        let client = runtime.block_on(new_client(
+            &config.region,
+            config.endpoint_url.as_deref(),
+            config.access_key_id.as_deref(),
+            config.secret_access_key.as_deref(),
+        ))?;
+        Ok(Self {
+            config,
+            runtime,
+            client,
+            step: 0,
+            group: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Returns whether `key` names an object that this endpoint could write.
+    fn is_output_key(&self, key: &str) -> bool {
+        key.strip_prefix(&self.config.prefix)
+            .and_then(|rest| rest.strip_suffix(&self.config.suffix))
+            .is_some_and(|step| step.len() == 20 && step.bytes().all(|c| c.is_ascii_digit()))
+    }
+
+    /// Fails if the bucket already contains an object that this endpoint
+    /// could overwrite.
+    async fn check_no_output(&self) -> AnyResult<()> {
+        let mut continuation_token = None;
+        loop {
+            let response = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.config.bucket_name)
+                .prefix(&self.config.prefix)
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|error| {
+                    anyhow!(
+                        "Failed to list objects in bucket '{}': {}",
+                        self.config.bucket_name,
+                        DisplayErrorContext(error)
+                    )
+                })?;
+
+            for object in response.contents().unwrap_or_default() {
+                if let Some(key) = object.key().filter(|key| self.is_output_key(key)) {
+                    bail!(
+                        "Bucket '{}' already contains output object '{key}'; refusing to overwrite it (use a different 'prefix' or remove the existing objects)",
+                        self.config.bucket_name
+                    );
+                }
+            }
+
+            match response.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Writes the output of the current group of steps as an object, if
+    /// there is any.
+    fn flush(&mut self) -> AnyResult<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let key = format!(
+            "{}{:020}{}",
+            self.config.prefix, self.group, self.config.suffix
+        );
+        let body = ByteStream::from(take(&mut self.buffer));
+        self.runtime
+            .block_on(
+                self.client
+                    .put_object()
+                    .bucket(&self.config.bucket_name)
+                    .key(&key)
+                    .body(body)
+                    .send(),
+            )
+            .map_err(|error| {
+                anyhow!(
+                    "Failed to write object '{key}' to bucket '{}': {}",
+                    self.config.bucket_name,
+                    DisplayErrorContext(error)
+                )
+            })?;
+        Ok(())
+    }
+}
+
+impl OutputEndpoint for S3OutputEndpoint {
+    fn connect(&mut self, _async_error_callback: AsyncErrorCallback) -> AnyResult<()> {
+        // Check that the bucket exists and that we can access it.
+        self.runtime
+            .block_on(
+                self.client
+                    .head_bucket()
+                    .bucket(&self.config.bucket_name)
+                    .send(),
+            )
+            .map_err(|error| {
+                anyhow!(
+                    "Failed to access bucket '{}': {}",
+                    self.config.bucket_name,
+                    DisplayErrorContext(error)
+                )
+            })?;
+
+        // Object keys depend only on step numbers, so output from an earlier
+        // run would be overwritten.
+        self.runtime.block_on(self.check_no_output())
+    }
+
+    fn max_buffer_size_bytes(&self) -> usize {
+        usize::MAX
+    }
+
+    fn batch_start(&mut self, step: Step) -> AnyResult<()> {
+        let group = step - step % self.config.steps_per_object;
+        if group != self.group {
+            // Steps may be skipped, so the previous group may be incomplete.
+            self.flush()?;
+            self.group = group;
+        }
+        self.step = step;
+        Ok(())
+    }
+
+    fn push_buffer(&mut self, buffer: &[u8]) -> AnyResult<()> {
+        self.buffer.extend_from_slice(buffer);
+        Ok(())
+    }
+
+    fn batch_end(&mut self) -> AnyResult<()> {
+        if self.step + 1 - self.group >= self.config.steps_per_object {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn is_fault_tolerant(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for S3OutputEndpoint {
+    fn drop(&mut self) {
+        // Write the output of the last, incomplete group of steps.
+        if let Err(error) = self.flush() {
+            error!("{error:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test::{mock_input_pipeline, wait, TestStruct, DEFAULT_TIMEOUT_MS},
+        OutputTransport,
+    };
+    use actix::System;
+    use actix_web::{
+        http::header::{CONTENT_RANGE, ETAG, RANGE},
+        web::{self, Bytes, Data, Path, Query},
+        App, HttpRequest, HttpResponse, HttpServer,
+    };
+    use async_stream::stream;
+    use futures_timer::Delay;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        fs::read_to_string,
+        io::Error as IoError,
+        net::SocketAddr,
+        path::Path as FsPath,
+        sync::{mpsc::channel, Arc, Mutex},
+        thread::{sleep, spawn},
+        time::Duration,
+    };
+    use tempfile::TempDir;
+
+    /// Maximum number of keys in a page of a listing, kept small to exercise
+    /// pagination.
+    const PAGE_SIZE: usize = 2;
+
+    /// State of a mock S3-compatible service with a single bucket.
+    #[derive(Default)]
+    struct MockS3 {
+        objects: BTreeMap<String, Bytes>,
+
+        /// Delay between lines when serving objects.
+        line_delay: Duration,
+
+        /// Key and start offset of each range request received.
+        range_requests: Vec<(String, usize)>,
+    }
+
+    type State = Data<Arc<Mutex<MockS3>>>;
+
+    async fn list_objects(state: State, query: Query<HashMap<String, String>>) -> HttpResponse {
+        let s3 = state.lock().unwrap();
+        let prefix = query.get("prefix").cloned().unwrap_or_default();
+        let after = query
+            .get("continuation-token")
+            .or(query.get("start-after"))
+            .cloned()
+            .unwrap_or_default();
+        let keys = s3
+            .objects
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefix) && key.as_str() > after.as_str())
+            .collect::<Vec<_>>();
+        let page = &keys[..keys.len().min(PAGE_SIZE)];
+
+        let mut body = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Name>test</Name>"#,
+        );
+        body += &format!(
+            "<Prefix>{prefix}</Prefix><KeyCount>{}</KeyCount><MaxKeys>{PAGE_SIZE}</MaxKeys><IsTruncated>{}</IsTruncated>",
+            page.len(),
+            keys.len() > PAGE_SIZE
+        );
+        for (key, data) in page {
+            body += &format!(
+                "<Contents><Key>{key}</Key><Size>{}</Size></Contents>",
+                data.len()
+            );
+        }
+        if keys.len() > PAGE_SIZE {
+            body += &format!(
+                "<NextContinuationToken>{}</NextContinuationToken>",
+                page.last().unwrap().0
+            );
+        }
+        body += "</ListBucketResult>";
+        HttpResponse::Ok()
+            .content_type("application/xml")
+            .body(body)
+    }
+
+    async fn get_object(
+        state: State,
+        path: Path<(String, String)>,
+        request: HttpRequest,
+    ) -> HttpResponse {
+        let (_bucket, key) = path.into_inner();
+        let mut s3 = state.lock().unwrap();
+        let Some(data) = s3.objects.get(&key).cloned() else {
+            return HttpResponse::NotFound().finish();
+        };
+        let start = request.headers().get(RANGE).and_then(|range| {
+            range
+                .to_str()
+                .ok()?
+                .strip_prefix("bytes=")?
+                .strip_suffix('-')?
+                .parse::<usize>()
+                .ok()
+        });
+
+        let mut response = match start {
+            Some(start) => {
+                s3.range_requests.push((key, start));
+                if start >= data.len() {
+                    return HttpResponse::RangeNotSatisfiable().finish();
+                }
+                let mut response = HttpResponse::PartialContent();
+                response.insert_header((
+                    CONTENT_RANGE,
+                    format!("bytes {start}-{}/{}", data.len() - 1, data.len()),
+                ));
+                response
+            }
+            None => HttpResponse::Ok(),
+        };
+        let data = data.slice(start.unwrap_or(0)..);
+        let line_delay = s3.line_delay;
+
+        // Serve the object one line at a time, so that tests can pause in the
+        // middle of an object.
+        let len = data.len();
+        let stream = stream! {
+            let mut rest = data;
+            while !rest.is_empty() {
+                let n = rest.iter().position(|&c| c == b'\n').map_or(rest.len(), |i| i + 1);
+                yield Ok::<_, IoError>(rest.split_to(n));
+                Delay::new(line_delay).await;
+            }
+        };
+        response.no_chunking(len as u64).streaming(stream)
+    }
+
+    async fn put_object(state: State, path: Path<(String, String)>, body: Bytes) -> HttpResponse {
+        let (_bucket, key) = path.into_inner();
+        state.lock().unwrap().objects.insert(key, body);
+        HttpResponse::Ok().insert_header((ETAG, "\"0\"")).finish()
+    }
+
+    /// Starts a mock S3-compatible service with a bucket named `test` that
+    /// contains `objects`.
+    fn start_mock_s3(
+        objects: &[(&str, &str)],
+        line_delay: Duration,
+    ) -> (SocketAddr, Arc<Mutex<MockS3>>) {
+        let state = Arc::new(Mutex::new(MockS3 {
+            objects: objects
+                .iter()
+                .map(|(key, data)| (key.to_string(), Bytes::from(data.to_string())))
+                .collect(),
+            line_delay,
+            range_requests: Vec::new(),
+        }));
+        let state_clone = state.clone();
+
+        let (sender, receiver) = channel();
+        spawn(move || {
+            System::new().block_on(async {
+                let server = HttpServer::new(move || {
+                    App::new()
+                        .app_data(Data::new(state_clone.clone()))
+                        .service(
+                            // Path-style bucket requests may have a trailing
+                            // slash.
+                            web::resource(["/{bucket}", "/{bucket}/"])
+                                .route(web::head().to(HttpResponse::Ok))
+                                .route(web::get().to(list_objects)),
+                        )
+                        .service(
+                            web::resource("/{bucket}/{key:.*}")
+                                .route(web::get().to(get_object))
+                                .route(web::put().to(put_object)),
+                        )
+                })
+                .workers(1)
+                .bind(("127.0.0.1", 0))
+                .unwrap();
+                sender.send(server.addrs()[0]).unwrap();
+                server.run().await.unwrap();
+            });
+        });
+        (receiver.recv().unwrap(), state)
+    }
+
+    fn input_config(addr: SocketAddr, state_path: Option<&FsPath>) -> String {
+        let state_path = state_path
+            .map(|path| format!("state_path: {path:?}"))
+            .unwrap_or_default();
+        format!(
+            r#"
+stream: test_input
+transport:
+    name: s3
+    config:
+        bucket_name: test
+        prefix: data/
+        region: us-east-1
+        endpoint_url: http://{addr}
+        access_key_id: minio
+        secret_access_key: minio123
+        {state_path}
+format:
+    name: csv
+"#
+        )
+    }
+
+    fn record(id: u32) -> TestStruct {
+        TestStruct {
+            id,
+            b: true,
+            i: None,
+            s: format!("record {id}"),
+        }
+    }
+
+    fn csv(ids: impl Iterator<Item = u32>) -> String {
+        ids.map(|id| format!("{id},true,,record {id}\n"))
+            .collect::<Vec<_>>()
+            .concat()
+    }
+
+    #[test]
+    fn test_s3_input() {
+        let data0 = csv(0..10);
+        let data1 = csv(10..20);
+        // No newline at the end of the last record.
+        let data2 = csv(20..30).trim_end().to_string();
+        let (addr, _state) = start_mock_s3(
+            &[
+                ("data/", ""),
+                ("data/0.csv", &data0),
+                ("data/1.csv", &data1),
+                ("data/2.csv", &data2),
+                ("other/3.csv", "30,true,,record 30\n"),
+            ],
+            Duration::ZERO,
+        );
+
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&input_config(addr, None)).unwrap(),
+        )
+        .unwrap();
+        endpoint.start(0).unwrap();
+        wait(|| consumer.state().eoi, DEFAULT_TIMEOUT_MS).unwrap();
+
+        let actual = zset
+            .state()
+            .flushed
+            .iter()
+            .map(|upd| upd.unwrap_insert().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, (0..30).map(record).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_s3_input_state() {
+        let dir = TempDir::new().unwrap();
+        let state_path = dir.path().join("state");
+        let (addr, state) = start_mock_s3(
+            &[("data/0.csv", &csv(0..10)), ("data/1.csv", &csv(10..20))],
+            Duration::ZERO,
+        );
+
+        let read_all = |complete: bool| {
+            let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+                serde_yaml::from_str(&input_config(addr, Some(&state_path))).unwrap(),
+            )
+            .unwrap();
+            endpoint.start(0).unwrap();
+            wait(|| consumer.state().eoi, DEFAULT_TIMEOUT_MS).unwrap();
+            let actual = zset
+                .state()
+                .flushed
+                .iter()
+                .map(|upd| upd.unwrap_insert().clone())
+                .collect::<Vec<_>>();
+
+            // Objects are recorded as completed once their data is part of a
+            // step.
+            if complete {
+                let last_key = state.lock().unwrap().objects.keys().last().cloned();
+                let expected = serde_json::to_string(&last_key.unwrap()).unwrap();
+                endpoint.complete(0);
+                wait(
+                    || read_to_string(&state_path).is_ok_and(|key| key == expected),
+                    DEFAULT_TIMEOUT_MS,
+                )
+                .unwrap();
+            }
+            actual
+        };
+
+        // Objects that were read, but whose data is not part of a step, are
+        // read again after a restart.
+        assert_eq!(read_all(false), (0..20).map(record).collect::<Vec<_>>());
+        assert!(!state_path.exists());
+        assert_eq!(read_all(true), (0..20).map(record).collect::<Vec<_>>());
+
+        // After a restart, only objects after the last completed one are read.
+        state
+            .lock()
+            .unwrap()
+            .objects
+            .insert("data/2.csv".to_string(), Bytes::from(csv(20..30)));
+        assert_eq!(read_all(true), (20..30).map(record).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_s3_pause() {
+        let data = csv(0..100);
+        let (addr, state) = start_mock_s3(&[("data/0.csv", &data)], Duration::from_millis(10));
+
+        let (endpoint, consumer, zset) = mock_input_pipeline::<TestStruct, TestStruct>(
+            serde_yaml::from_str(&input_config(addr, None)).unwrap(),
+        )
+        .unwrap();
+        let n_recs = || zset.state().flushed.len();
+
+        endpoint.start(0).unwrap();
+        wait(|| n_recs() >= 10, DEFAULT_TIMEOUT_MS).unwrap();
+
+        // Pause in the middle of the object.  Once the pause takes effect, no
+        // more records should arrive.
+        endpoint.pause().unwrap();
+        sleep(Duration::from_millis(100));
+        let n = n_recs();
+        assert!(n < 100);
+        sleep(Duration::from_millis(100));
+        assert_eq!(n, n_recs());
+
+        // Resuming should continue where we left off, with a range request.
+        endpoint.start(0).unwrap();
+        wait(|| consumer.state().eoi, DEFAULT_TIMEOUT_MS).unwrap();
+        let actual = zset
+            .state()
+            .flushed
+            .iter()
+            .map(|upd| upd.unwrap_insert().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(actual, (0..100).map(record).collect::<Vec<_>>());
+
+        let range_requests = state.lock().unwrap().range_requests.clone();
+        assert!(!range_requests.is_empty());
+        assert!(range_requests
+            .iter()
+            .all(|(key, start)| key == "data/0.csv" && *start > 0));
+    }
+
+    fn output_config(addr: SocketAddr) -> String {
+        format!(
+            r#"
+stream: test_output1
+transport:
+    name: s3
+    config:
+        bucket_name: test
+        prefix: out/
+        suffix: .csv
+        region: us-east-1
+        endpoint_url: http://{addr}
+        access_key_id: minio
+        secret_access_key: minio123
+        steps_per_object: 2
+format:
+    name: csv
+"#
+        )
+    }
+
+    #[test]
+    fn test_s3_output() {
+        let (addr, state) = start_mock_s3(&[], Duration::ZERO);
+
+        let transport = <dyn OutputTransport>::get_transport("s3").unwrap();
+        let mut endpoint = transport
+            .new_endpoint(&serde_yaml::from_str(&output_config(addr)).unwrap())
+            .unwrap();
+        endpoint.connect(Box::new(|_fatal, _error| ())).unwrap();
+
+        // Step 3 has no output.
+        for step in 0..5 {
+            endpoint.batch_start(step).unwrap();
+            if step != 3 {
+                endpoint
+                    .push_buffer(format!("step {step}\n").as_bytes())
+                    .unwrap();
+            }
+            endpoint.batch_end().unwrap();
+        }
+        assert_eq!(state.lock().unwrap().objects.len(), 2);
+
+        // The output of the last, incomplete group is written on drop.
+        drop(endpoint);
+
+        let objects = state
+            .lock()
+            .unwrap()
+            .objects
+            .iter()
+            .map(|(key, data)| (key.clone(), String::from_utf8(data.to_vec()).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            vec![
+                (
+                    "out/00000000000000000000.csv".to_string(),
+                    "step 0\nstep 1\n".to_string()
+                ),
+                (
+                    "out/00000000000000000002.csv".to_string(),
+                    "step 2\n".to_string()
+                ),
+                (
+                    "out/00000000000000000004.csv".to_string(),
+                    "step 4\n".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_s3_output_no_overwrite() {
+        // Other objects under the prefix are fine.
+        let (addr, _state) = start_mock_s3(&[("out/README", "")], Duration::ZERO);
+        let transport = <dyn OutputTransport>::get_transport("s3").unwrap();
+        let mut endpoint = transport
+            .new_endpoint(&serde_yaml::from_str(&output_config(addr)).unwrap())
+            .unwrap();
+        endpoint.connect(Box::new(|_fatal, _error| ())).unwrap();
+
+        // Output from an earlier run is not.
+        let (addr, _state) = start_mock_s3(
+            &[("out/00000000000000000000.csv", "step 0\n")],
+            Duration::ZERO,
+        );
+        let mut endpoint = transport
+            .new_endpoint(&serde_yaml::from_str(&output_config(addr)).unwrap())
+            .unwrap();
+        let error = endpoint
+            .connect(Box::new(|_fatal, _error| ()))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("refusing to overwrite"), "{error}");
+    }
+}
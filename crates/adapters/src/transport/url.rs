@@ -1,4 +1,6 @@
-use super::{InputConsumer, InputEndpoint, InputReader, InputTransport, Step};
+use super::{
+    compression::decompress, InputConsumer, InputEndpoint, InputReader, InputTransport, Step,
+};
 use crate::PipelineState;
 use actix::System;
 use actix_web::http::header::{ByteRangeSpec, ContentRangeSpec, Range, CONTENT_RANGE};
@@ -62,7 +64,8 @@ struct UrlInputReader {
 }
 
 impl UrlInputReader {
-    fn new(config: &Arc<UrlInputConfig>, mut consumer: Box<dyn InputConsumer>) -> AnyResult<Self> {
+    fn new(config: &Arc<UrlInputConfig>, consumer: Box<dyn InputConsumer>) -> AnyResult<Self> {
+        let mut consumer = decompress(config.compression, &config.path, consumer)?;
         let (sender, receiver) = channel(PipelineState::Paused);
         let config = config.clone();
         let receiver_clone = receiver.clone();
@@ -0,0 +1,90 @@
+//! Support for writing the state of a circuit to a checkpoint and restoring
+//! it later.
+//!
+//! A checkpoint is a directory.  Each worker writes the state of its circuit
+//! to a subdirectory of this directory (see [`worker_dir`]).  Within a worker
+//! directory, each stateful operator stores its state in one or more files
+//! whose names start with the operator's persistent id (see
+//! [`GlobalNodeId::persistent_id`](`crate::circuit::GlobalNodeId::persistent_id`)).
+
+use crate::{
+    trace::{unaligned_deserialize, Deserializable, Serializer},
+    Error as DBSPError,
+};
+use feldera_storage::file::to_bytes;
+use rkyv::{Archive, Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Error as IOError, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Name of the file that stores [`CheckpointMetadata`] in a checkpoint
+/// directory.
+pub(crate) const METADATA_FILE: &str = "checkpoint.meta";
+
+/// Global information about a checkpoint, written by
+/// [`DBSPHandle::checkpoint`](`crate::DBSPHandle::checkpoint`).
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub(crate) struct CheckpointMetadata {
+    /// Number of workers in the runtime that wrote the checkpoint.
+    pub(crate) n_workers: u64,
+}
+
+impl CheckpointMetadata {
+    /// Read checkpoint metadata from checkpoint directory `dir` and check
+    /// that the checkpoint was written by a runtime with `n_workers` workers.
+    pub(crate) fn check(dir: &Path, n_workers: usize) -> Result<Self, DBSPError> {
+        let metadata: Self = read_state(&dir.join(METADATA_FILE))?;
+        if metadata.n_workers != n_workers as u64 {
+            return Err(DBSPError::IO(IOError::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "checkpoint in '{}' was written by {} workers, but the runtime has {n_workers} workers",
+                    dir.display(),
+                    metadata.n_workers
+                ),
+            )));
+        }
+        Ok(metadata)
+    }
+}
+
+/// Directory where worker `worker_index` stores its state within checkpoint
+/// directory `dir`.
+pub(crate) fn worker_dir(dir: &Path, worker_index: usize) -> PathBuf {
+    dir.join(format!("worker-{worker_index}"))
+}
+
+/// Path to the file named `name` that stores a part of the state of the
+/// operator with persistent id `persistent_id` under `base`.
+pub fn state_path(base: &Path, persistent_id: &str, name: &str) -> PathBuf {
+    base.join(format!("{persistent_id}.{name}"))
+}
+
+/// Serialize `value` with `rkyv` and write it to `path`.
+pub fn write_state<T>(path: &Path, value: &T) -> Result<(), DBSPError>
+where
+    T: Serialize<Serializer>,
+{
+    let bytes = to_bytes(value).map_err(|error| {
+        DBSPError::IO(IOError::new(
+            ErrorKind::InvalidData,
+            format!(
+                "can't encode checkpoint state for '{}': {error:?}",
+                path.display()
+            ),
+        ))
+    })?;
+    fs::write(path, bytes.as_slice())?;
+    Ok(())
+}
+
+/// Read a value previously written by [`write_state`] from `path`.
+pub fn read_state<T>(path: &Path) -> Result<T, DBSPError>
+where
+    T: Deserializable,
+{
+    let bytes = fs::read(path)?;
+    Ok(unaligned_deserialize(&bytes))
+}
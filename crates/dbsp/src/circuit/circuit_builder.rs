@@ -27,7 +27,8 @@ use crate::{
     algebra::{IndexedZSet, ZSet},
     operator::{
         time_series::{PartitionedIndexedZSet, RelRange},
-        Aggregator, FilterMap, Fold, Generator, Max, Min,
+        Aggregator, ApproxCountDistinct, ApproxPercentile, FilterMap, Fold, Generator, Max, Median,
+        Min, Mode, PercentileCont, PercentileDisc,
     },
     trace::Batch,
     InputHandle, OutputHandle,
@@ -60,9 +61,11 @@ use std::{
     collections::HashMap,
     fmt,
     fmt::{Debug, Display, Write},
+    fs,
     iter::repeat,
     marker::PhantomData,
     panic::Location,
+    path::Path,
     rc::Rc,
     thread::panicking,
 };
@@ -440,7 +443,15 @@ impl<D> StreamValue<D> {
 /// DBSP implements "range join" of data streams, which joins keys in `a`
 /// against ranges of keys in `b`.  [`Stream::stream_join_range`] implements
 /// range join with non-indexed Z-set output,
-/// [`Stream::stream_join_range_index`] with indexed output.
+/// [`Stream::stream_join_range_index`] with indexed output.  For delta
+/// streams, [`Stream::join_range`] and [`Stream::join_range_index`] compute
+/// range joins incrementally, given a range function that is monotone in the
+/// key of `a`.
+///
+/// Use [`Stream::asof_join`] to join each record in delta stream `a` with the
+/// record in `b` with the same key and the latest timestamp at or before its
+/// own, e.g. to enrich events with the version of a dimension table that was
+/// current when they occurred.
 ///
 /// ## Aggregation
 ///
@@ -464,6 +475,15 @@ impl<D> StreamValue<D> {
 ///
 ///     [`Stream::average`] calculates the average over the values for each key.
 ///
+///     [`PercentileCont`], [`PercentileDisc`], [`Median`], and [`Mode`]
+///     compute order statistics by scanning each group's values in sorted
+///     order, taking weights into account.
+///
+///     [`Stream::aggregate_append_only`] aggregates streams marked
+///     insert-only with [`Stream::mark_append_only`], storing only one
+///     accumulator per key.  It is useful with sketch-based aggregators such
+///     as [`ApproxCountDistinct`] and [`ApproxPercentile`].
+///
 ///   * [`Stream::stream_aggregate`] aggregates data streams.  Each batch from
 ///     the input is separately aggregated and written to the output stream.
 ///
@@ -540,6 +560,11 @@ impl<D> StreamValue<D> {
 ///     input before the waterline, partitions it, aggregates it, and returns
 ///     the result as a `PartitionedIndexedZSet`.
 ///
+/// To aggregate over a frame of rows rather than a range of times, e.g.,
+/// `SUM(x) OVER (PARTITION BY p ORDER BY o ROWS BETWEEN 2 PRECEDING AND 1
+/// FOLLOWING)`, use [`Stream::rows_aggregate_custom_order`], which orders the
+/// values in each partition using a [`CmpFunc`](`crate::operator::CmpFunc`).
+///
 /// ## Windowing
 ///
 /// Use [`Stream::window`] to extract a stream of deltas to windows from a
@@ -789,6 +814,18 @@ pub trait Node {
 
     fn fixedpoint(&self, scope: Scope) -> bool;
 
+    /// Write the state of the node to a checkpoint under `base`.
+    ///
+    /// The node should forward the request to its inner operator (see
+    /// [`Operator::commit`](super::operator_traits::Operator::commit)).
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError>;
+
+    /// Restore the state of the node from a checkpoint under `base`.
+    ///
+    /// The node should forward the request to its inner operator (see
+    /// [`Operator::restore`](super::operator_traits::Operator::restore)).
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError>;
+
     fn map_nodes_recursive(&self, _f: &mut dyn FnMut(&dyn Node)) {}
 }
 
@@ -895,6 +932,23 @@ impl GlobalNodeId {
     pub fn path(&self) -> &[NodeId] {
         &self.0
     }
+
+    /// Identifier of the node used to name its state in a checkpoint.
+    ///
+    /// Unlike the `Display` representation, this string can be used as
+    /// part of a file name.  It is stable across runs as long as the circuit
+    /// is constructed in the same way.
+    pub fn persistent_id(&self) -> String {
+        let path = self.path();
+        let mut id = String::new();
+        for i in 0..path.len() {
+            id.push_str(&path[i].0.to_string());
+            if i < path.len() - 1 {
+                id.push('.');
+            }
+        }
+        id
+    }
 }
 
 type CircuitEventHandler = Box<dyn Fn(&CircuitEvent)>;
@@ -1831,6 +1885,20 @@ where
             }*/
         })
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        for node in self.nodes.iter_mut() {
+            node.commit(base)?;
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        for node in self.nodes.iter_mut() {
+            node.restore(base)?;
+        }
+        Ok(())
+    }
 }
 
 /// A circuit.
@@ -3169,6 +3237,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct SourceNode<C, O, Op> {
@@ -3245,6 +3321,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct UnaryNode<C, I, O, Op> {
@@ -3327,6 +3411,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct SinkNode<C, I, Op> {
@@ -3402,6 +3494,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct BinarySinkNode<C, I1, I2, Op> {
@@ -3497,6 +3597,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct BinaryNode<C, I1, I2, O, Op> {
@@ -3612,6 +3720,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct TernaryNode<C, I1, I2, I3, O, Op> {
@@ -3731,6 +3847,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct QuaternaryNode<C, I1, I2, I3, I4, O, Op> {
@@ -3868,6 +3992,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 struct NaryNode<C, I, O, Op>
@@ -3990,6 +4122,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.commit(base, &self.id.persistent_id())
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.operator.restore(base, &self.id.persistent_id())
+    }
 }
 
 // The output half of a feedback node.  We implement a feedback node using a
@@ -4094,6 +4234,14 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         unsafe { (*self.operator.get()).fixedpoint(scope) }
     }
+
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        unsafe { (*self.operator.get()).commit(base, &self.id.persistent_id()) }
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        unsafe { (*self.operator.get()).restore(base, &self.id.persistent_id()) }
+    }
 }
 
 /// The input half of a feedback node
@@ -4170,6 +4318,16 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         unsafe { (*self.operator.get()).fixedpoint(scope) }
     }
+
+    // The operator is shared with `FeedbackOutputNode`, which takes care of
+    // its checkpoint.
+    fn commit(&mut self, _base: &Path) -> Result<(), DBSPError> {
+        Ok(())
+    }
+
+    fn restore(&mut self, _base: &Path) -> Result<(), DBSPError> {
+        Ok(())
+    }
 }
 
 /// Input connector of a feedback operator.
@@ -4310,6 +4468,14 @@ where
         self.circuit.inner().fixedpoint(scope + 1)
     }
 
+    fn commit(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.circuit.inner_mut().commit(base)
+    }
+
+    fn restore(&mut self, base: &Path) -> Result<(), DBSPError> {
+        self.circuit.inner_mut().restore(base)
+    }
+
     fn map_nodes_recursive(&self, f: &mut dyn FnMut(&dyn Node)) {
         self.circuit.map_nodes_recursive(f);
     }
@@ -4384,6 +4550,27 @@ impl CircuitHandle {
     pub fn unregister_scheduler_event_handler(&self, name: &str) -> bool {
         self.circuit.unregister_scheduler_event_handler(name)
     }
+
+    /// Write the state of all operators in the circuit to directory `base`.
+    ///
+    /// Must be invoked between two calls to [`step`](`Self::step`).  Nested
+    /// circuits are always at the start of a clock epoch at this point, so
+    /// it is sufficient to checkpoint the state that operators carry across
+    /// clock cycles of the root circuit.
+    pub fn commit(&self, base: &Path) -> Result<(), DBSPError> {
+        fs::create_dir_all(base)?;
+        self.circuit.inner_mut().commit(base)
+    }
+
+    /// Restore the state of all operators in the circuit from a checkpoint
+    /// previously written to `base` by [`commit`](`Self::commit`).
+    ///
+    /// The circuit must be constructed in exactly the same way as the circuit
+    /// that wrote the checkpoint.  Invoke this method before the first call
+    /// to [`step`](`Self::step`).
+    pub fn restore(&self, base: &Path) -> Result<(), DBSPError> {
+        self.circuit.inner_mut().restore(base)
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,10 @@
 use crate::{
-    circuit::runtime::RuntimeHandle, profile::Profiler, Error as DBSPError, RootCircuit, Runtime,
-    RuntimeError, SchedulerError,
+    circuit::{
+        checkpoint::{worker_dir, write_state, CheckpointMetadata, METADATA_FILE},
+        runtime::RuntimeHandle,
+    },
+    profile::Profiler,
+    Error as DBSPError, RootCircuit, Runtime, RuntimeConfig, RuntimeError,
 };
 use anyhow::Error as AnyError;
 use core::fmt;
@@ -260,8 +264,64 @@ impl Runtime {
     where
         F: FnOnce(&mut RootCircuit) -> Result<T, AnyError> + Clone + Send + 'static,
         T: Send + 'static,
+    {
+        Self::init_circuit_with_config(layout, RuntimeConfig::default(), constructor)
+    }
+
+    /// Like [`Runtime::init_circuit`], but runs the circuit in a runtime with
+    /// the specified `config` instead of the default [`RuntimeConfig`].
+    pub fn init_circuit_with_config<F, T>(
+        layout: impl IntoLayout,
+        config: RuntimeConfig,
+        constructor: F,
+    ) -> Result<(DBSPHandle, T), DBSPError>
+    where
+        F: FnOnce(&mut RootCircuit) -> Result<T, AnyError> + Clone + Send + 'static,
+        T: Send + 'static,
+    {
+        Self::init_circuit_inner(layout.into_layout(), config, None, constructor)
+    }
+
+    /// Instantiate a circuit in a multithreaded runtime and restore its state
+    /// from a checkpoint.
+    ///
+    /// Works like [`Runtime::init_circuit_with_config`], but, after building
+    /// the circuit, each worker restores the state of its operators from
+    /// `checkpoint_dir`, which must contain a checkpoint written by
+    /// [`DBSPHandle::checkpoint`].  The `constructor` must build the same
+    /// circuit as the one that wrote the checkpoint, and `layout` must have
+    /// the same number of workers.
+    ///
+    /// Inputs fed to the circuit after it is restored are processed as if
+    /// they were fed to the original circuit right after the checkpoint.
+    pub fn init_circuit_from_checkpoint<F, T, P>(
+        layout: impl IntoLayout,
+        config: RuntimeConfig,
+        checkpoint_dir: P,
+        constructor: F,
+    ) -> Result<(DBSPHandle, T), DBSPError>
+    where
+        F: FnOnce(&mut RootCircuit) -> Result<T, AnyError> + Clone + Send + 'static,
+        T: Send + 'static,
+        P: AsRef<Path>,
     {
         let layout = layout.into_layout();
+        let checkpoint_dir = checkpoint_dir.as_ref().to_path_buf();
+        CheckpointMetadata::check(&checkpoint_dir, layout.n_workers())?;
+
+        Self::init_circuit_inner(layout, config, Some(checkpoint_dir), constructor)
+    }
+
+    fn init_circuit_inner<F, T>(
+        layout: Layout,
+        config: RuntimeConfig,
+        checkpoint_dir: Option<PathBuf>,
+        constructor: F,
+    ) -> Result<(DBSPHandle, T), DBSPError>
+    where
+        F: FnOnce(&mut RootCircuit) -> Result<T, AnyError> + Clone + Send + 'static,
+        T: Send + 'static,
+    {
         let nworkers = layout.local_workers().len();
         let worker_ofs = layout.local_workers().start;
 
@@ -279,7 +339,7 @@ impl Runtime {
         let (status_senders, status_receivers): (Vec<_>, Vec<_>) =
             (0..nworkers).map(|_| bounded(1)).unzip();
 
-        let runtime = Self::run(layout, move || {
+        let runtime = Self::run_with_config(layout, config, move || {
             let worker_index = Runtime::worker_index() - worker_ofs;
 
             // Drop all but one channels.  This makes sure that if one of the worker panics
@@ -291,6 +351,12 @@ impl Runtime {
             let (circuit, profiler) = match RootCircuit::build(|circuit| {
                 let profiler = Profiler::new(circuit);
                 constructor(circuit).map(|res| (res, profiler))
+            })
+            .and_then(|(circuit, res)| {
+                if let Some(checkpoint_dir) = &checkpoint_dir {
+                    circuit.restore(&worker_dir(checkpoint_dir, Runtime::worker_index()))?;
+                }
+                Ok((circuit, res))
             }) {
                 Ok((circuit, (res, profiler))) => {
                     if init_sender.send(Ok(res)).is_err() {
@@ -312,7 +378,10 @@ impl Runtime {
                 match command_receiver.try_recv() {
                     Ok(Command::Step) => {
                         //moregc = true;
-                        let status = circuit.step().map(|_| Response::Unit);
+                        let status = circuit
+                            .step()
+                            .map(|_| Response::Unit)
+                            .map_err(DBSPError::Scheduler);
                         // Send response.
                         if status_sender.send(status).is_err() {
                             return;
@@ -341,6 +410,14 @@ impl Runtime {
                             return;
                         }
                     }
+                    Ok(Command::Checkpoint(dir)) => {
+                        let status = circuit
+                            .commit(&worker_dir(&dir, Runtime::worker_index()))
+                            .map(|_| Response::Unit);
+                        if status_sender.send(status).is_err() {
+                            return;
+                        }
+                    }
                     // Nothing to do: do some housekeeping and relinquish the CPU if there's none
                     // left.
                     Err(TryRecvError::Empty) => {
@@ -404,6 +481,7 @@ enum Command {
     EnableProfiler,
     DumpProfile,
     RetrieveProfile,
+    Checkpoint(PathBuf),
 }
 
 enum Response {
@@ -422,14 +500,14 @@ pub struct DBSPHandle {
     command_senders: Vec<Sender<Command>>,
     // Channels used to receive command completion status from
     // workers.
-    status_receivers: Vec<Receiver<Result<Response, SchedulerError>>>,
+    status_receivers: Vec<Receiver<Result<Response, DBSPError>>>,
 }
 
 impl DBSPHandle {
     fn new(
         runtime: RuntimeHandle,
         command_senders: Vec<Sender<Command>>,
-        status_receivers: Vec<Receiver<Result<Response, SchedulerError>>>,
+        status_receivers: Vec<Receiver<Result<Response, DBSPError>>>,
     ) -> Self {
         Self {
             start_time: Instant::now(),
@@ -502,7 +580,7 @@ impl DBSPHandle {
                 }
                 Ok(Err(e)) => {
                     let _ = self.kill_inner();
-                    return Err(DBSPError::Scheduler(e));
+                    return Err(e);
                 }
                 Ok(Ok(resp)) => handler(worker, resp),
             }
@@ -554,6 +632,32 @@ impl DBSPHandle {
         Ok(dir_path)
     }
 
+    /// Write a checkpoint of the state of the circuit to the specified
+    /// directory.
+    ///
+    /// Creates `dir_path` if it doesn't exist.  Each worker writes the state
+    /// of its operators to `dir_path/worker-<index>`, replacing any previous
+    /// checkpoint in the same directory.  Use
+    /// [`Runtime::init_circuit_from_checkpoint`] to instantiate a circuit from
+    /// the checkpoint.
+    ///
+    /// The checkpoint captures the state of the circuit between two clock
+    /// cycles, i.e., after the last call to [`Self::step`] has completed.
+    pub fn checkpoint<P: AsRef<Path>>(&mut self, dir_path: P) -> Result<(), DBSPError> {
+        let dir_path = dir_path.as_ref();
+        create_dir_all(dir_path)?;
+
+        self.broadcast_command(Command::Checkpoint(dir_path.to_path_buf()), |_, _| {})?;
+
+        let n_workers = self.runtime.as_ref().unwrap().runtime().num_workers();
+        write_state(
+            &dir_path.join(METADATA_FILE),
+            &CheckpointMetadata {
+                n_workers: n_workers as u64,
+            },
+        )
+    }
+
     pub fn retrieve_profile(&mut self) -> Result<DbspProfile, DBSPError> {
         let mut profiles = vec![Default::default(); self.status_receivers.len()];
 
@@ -595,8 +699,13 @@ impl Drop for DBSPHandle {
 mod tests {
     use std::time::Duration;
 
-    use crate::{operator::Generator, Circuit, Error as DBSPError, Runtime, RuntimeError};
-    use anyhow::anyhow;
+    use crate::{
+        operator::{FilterMap, Generator, Max},
+        Circuit, CollectionHandle, Error as DBSPError, OrdZSet, OutputHandle, RootCircuit, Runtime,
+        RuntimeConfig, RuntimeError,
+    };
+    use anyhow::{anyhow, Error as AnyError};
+    use tempfile::tempdir;
 
     // Panic during initialization in worker thread.
     #[test]
@@ -732,6 +841,148 @@ mod tests {
         handle.step().unwrap();
     }
 
+    type CheckpointTestHandles = (CollectionHandle<u64, i64>, OutputHandle<OrdZSet<u64, i64>>);
+
+    fn checkpoint_test_circuit(
+        circuit: &mut RootCircuit,
+    ) -> Result<CheckpointTestHandles, AnyError> {
+        let (input, input_handle) = circuit.add_input_zset::<u64, i64>();
+        let output = input.map(|x| x % 7).distinct().integrate().output();
+
+        Ok((input_handle, output))
+    }
+
+    fn checkpoint_aggregate_circuit(
+        circuit: &mut RootCircuit,
+    ) -> Result<CheckpointTestHandles, AnyError> {
+        let (input, input_handle) = circuit.add_input_zset::<u64, i64>();
+        let output = input
+            .map_index(|x| (x % 7, *x))
+            .aggregate(Max)
+            .map(|(k, max)| k * 1000 + max)
+            .integrate()
+            .output();
+
+        Ok((input_handle, output))
+    }
+
+    fn checkpoint_join_circuit(
+        circuit: &mut RootCircuit,
+    ) -> Result<CheckpointTestHandles, AnyError> {
+        let (input, input_handle) = circuit.add_input_zset::<u64, i64>();
+        let output = input
+            .map_index(|x| (x % 7, *x))
+            .join(&input.map_index(|x| (x % 5, *x)), |_k, x, y| x * 1000 + y)
+            .integrate()
+            .output();
+
+        Ok((input_handle, output))
+    }
+
+    fn checkpoint_test_inputs() -> Vec<Vec<(u64, i64)>> {
+        (0..10u64)
+            .map(|step| {
+                let mut batch: Vec<_> = (step * 10..step * 10 + 10).map(|k| (k, 1)).collect();
+                if step > 0 {
+                    batch.extend((step * 10 - 10..step * 10 - 5).map(|k| (k, -1)));
+                }
+                batch
+            })
+            .collect()
+    }
+
+    // Checkpoint a circuit half-way through the input, restore it from the
+    // checkpoint, and check that it produces the same outputs as a circuit
+    // that processes the entire input without interruption.
+    #[test]
+    fn test_checkpoint1() {
+        test_checkpoint(1, checkpoint_test_circuit);
+    }
+
+    #[test]
+    fn test_checkpoint4() {
+        test_checkpoint(4, checkpoint_test_circuit);
+    }
+
+    #[test]
+    fn test_checkpoint_aggregate1() {
+        test_checkpoint(1, checkpoint_aggregate_circuit);
+    }
+
+    #[test]
+    fn test_checkpoint_aggregate4() {
+        test_checkpoint(4, checkpoint_aggregate_circuit);
+    }
+
+    #[test]
+    fn test_checkpoint_join1() {
+        test_checkpoint(1, checkpoint_join_circuit);
+    }
+
+    #[test]
+    fn test_checkpoint_join4() {
+        test_checkpoint(4, checkpoint_join_circuit);
+    }
+
+    fn test_checkpoint<F>(nworkers: usize, constructor: F)
+    where
+        F: Fn(&mut RootCircuit) -> Result<CheckpointTestHandles, AnyError> + Clone + Send + 'static,
+    {
+        let tempdir = tempdir().unwrap();
+        let checkpoint_dir = tempdir.path().join("checkpoint");
+
+        let inputs = checkpoint_test_inputs();
+
+        // Reference run.
+        let (mut handle, (input, output)) =
+            Runtime::init_circuit(nworkers, constructor.clone()).unwrap();
+        let mut expected = Vec::new();
+        for batch in inputs.iter() {
+            input.append(&mut batch.clone());
+            handle.step().unwrap();
+            expected.push(output.consolidate());
+        }
+        handle.kill().unwrap();
+
+        // Run the first half of the input and checkpoint.
+        let (mut handle, (input, output)) =
+            Runtime::init_circuit(nworkers, constructor.clone()).unwrap();
+        let (first, second) = inputs.split_at(inputs.len() / 2);
+        for (step, batch) in first.iter().enumerate() {
+            input.append(&mut batch.clone());
+            handle.step().unwrap();
+            assert_eq!(output.consolidate(), expected[step]);
+        }
+        handle.checkpoint(&checkpoint_dir).unwrap();
+        handle.kill().unwrap();
+
+        // Restore and run the second half.
+        let (mut handle, (input, output)) = Runtime::init_circuit_from_checkpoint(
+            nworkers,
+            RuntimeConfig::default(),
+            &checkpoint_dir,
+            constructor.clone(),
+        )
+        .unwrap();
+        for (step, batch) in second.iter().enumerate() {
+            input.append(&mut batch.clone());
+            handle.step().unwrap();
+            assert_eq!(output.consolidate(), expected[first.len() + step]);
+        }
+        handle.kill().unwrap();
+
+        // A checkpoint can only be restored with the same number of workers.
+        assert!(matches!(
+            Runtime::init_circuit_from_checkpoint(
+                nworkers + 1,
+                RuntimeConfig::default(),
+                &checkpoint_dir,
+                constructor
+            ),
+            Err(DBSPError::IO(_))
+        ));
+    }
+
     #[test]
     fn test_failing_constructor() {
         match Runtime::init_circuit(4, |_circuit| Err::<(), _>(anyhow!("constructor failed"))) {
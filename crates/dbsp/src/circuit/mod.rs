@@ -22,6 +22,7 @@ pub(crate) mod runtime;
 #[macro_use]
 pub mod metadata;
 pub mod cache;
+pub mod checkpoint;
 pub mod circuit_builder;
 pub mod operator_traits;
 pub mod schedule;
@@ -33,6 +34,8 @@ pub use circuit_builder::{
     NodeId, OwnershipPreference, RootCircuit, Scope, Stream, WithClock,
 };
 pub use dbsp_handle::{DBSPHandle, Host, IntoLayout, Layout};
-pub use runtime::{Error as RuntimeError, LocalStore, LocalStoreMarker, Runtime, RuntimeHandle};
+pub use runtime::{
+    Error as RuntimeError, LocalStore, LocalStoreMarker, Runtime, RuntimeConfig, RuntimeHandle,
+};
 
 pub use schedule::Error as SchedulerError;
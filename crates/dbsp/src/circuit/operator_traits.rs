@@ -3,11 +3,14 @@
 //! Operators are the building blocks of DBSP circuits.  An operator
 //! consumes one or more input streams and produces an output stream.
 
-use crate::circuit::{
-    metadata::{OperatorLocation, OperatorMeta},
-    OwnershipPreference, Scope,
+use crate::{
+    circuit::{
+        metadata::{OperatorLocation, OperatorMeta},
+        OwnershipPreference, Scope,
+    },
+    Error as DBSPError,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, path::Path};
 
 /// Minimal requirements for values exchanged by operators.
 pub trait Data: Clone + 'static {}
@@ -194,6 +197,26 @@ pub trait Operator: 'static {
     /// of the fixed point computation, but not as part of an integrator circuit
     /// ([`Stream::integrate`](`crate::circuit::Stream::integrate`)).
     fn fixedpoint(&self, scope: Scope) -> bool;
+
+    /// Write the state of the operator to a checkpoint.
+    ///
+    /// Invoked between two clock cycles of the root circuit (see
+    /// [`CircuitHandle::commit`](`crate::circuit::CircuitHandle::commit`)).
+    /// A stateful operator must store all of its state under `base`, using
+    /// `persistent_id` as a prefix for the names of the files it creates.
+    /// `persistent_id` is unique within the circuit and remains the same
+    /// as long as the circuit is constructed in the same way.
+    ///
+    /// Stateless operators don't need to implement this method.
+    fn commit(&mut self, _base: &Path, _persistent_id: &str) -> Result<(), DBSPError> {
+        Ok(())
+    }
+
+    /// Restore the state of the operator from a checkpoint previously
+    /// written by [`commit`](`Self::commit`).
+    fn restore(&mut self, _base: &Path, _persistent_id: &str) -> Result<(), DBSPError> {
+        Ok(())
+    }
 }
 
 /// A source operator that injects data from the outside world or from the
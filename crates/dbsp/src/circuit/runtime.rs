@@ -1,10 +1,11 @@
 //! A multithreaded runtime for evaluating DBSP circuits in a data-parallel
 //! fashion.
 
-use crate::DetailedError;
+use crate::{circuit::checkpoint::worker_dir, DetailedError};
 use crossbeam::channel::bounded;
 use crossbeam_utils::sync::{Parker, Unparker};
-use once_cell::sync::Lazy;
+use feldera_storage::{backend::AtomicIncrementOnlyI64, buffer_cache::TinyLfuCache};
+use once_cell::sync::{Lazy, OnceCell};
 use serde::Serialize;
 use std::{
     backtrace::Backtrace,
@@ -13,13 +14,16 @@ use std::{
     error::Error as StdError,
     fmt,
     fmt::{Debug, Display, Error as FmtError, Formatter},
-    panic::{self, Location, PanicInfo},
+    fs::create_dir_all,
+    panic::{self, Location, PanicHookInfo},
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc, RwLock,
     },
     thread::{Builder, JoinHandle, LocalKey, Result as ThreadResult},
 };
+use tempfile::TempDir;
 use typedmap::{TypedDashMap, TypedMapKey};
 
 use super::dbsp_handle::{IntoLayout, Layout};
@@ -79,12 +83,12 @@ thread_local! {
 thread_local! {
     // Reference to the `Runtime` that manages this worker thread or `None`
     // if the current thread is not running in a multithreaded runtime.
-    static RUNTIME: RefCell<Option<Runtime>> = RefCell::new(None);
+    static RUNTIME: RefCell<Option<Runtime>> = const { RefCell::new(None) };
 
     // 0-based index of the current worker thread within its runtime.
     // Returns `0` if the current thread in not running in a multithreaded
     // runtime.
-    pub(crate) static WORKER_INDEX: Cell<usize> = Cell::new(0);
+    pub(crate) static WORKER_INDEX: Cell<usize> = const { Cell::new(0) };
 }
 
 pub struct LocalStoreMarker;
@@ -145,7 +149,7 @@ impl Display for WorkerPanicInfo {
 }
 
 impl WorkerPanicInfo {
-    fn new(panic_info: &PanicInfo) -> Self {
+    fn new(panic_info: &PanicHookInfo) -> Self {
         #[allow(clippy::manual_map)]
         let message = if let Some(v) = panic_info.payload().downcast_ref::<String>() {
             Some(v.clone())
@@ -167,9 +171,60 @@ impl WorkerPanicInfo {
     }
 }
 
+/// Configuration for a [`Runtime`].
+///
+/// The storage settings apply to batches stored in layer files (see
+/// [`trace::ord::file`](crate::trace::ord::file)).  Traces do not use layer
+/// files yet: they are kept in memory, with or without the `persistence`
+/// feature.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    /// Directory in which workers store layer files.  Each worker uses its
+    /// own `worker-<index>` subdirectory.
+    ///
+    /// If `None`, the runtime uses a temporary directory, which it deletes
+    /// when it terminates.
+    pub storage: Option<PathBuf>,
+
+    /// Size, in bytes, of the buffer cache shared by all workers on this
+    /// host.
+    ///
+    /// If `None`, the size is taken from the `FELDERA_BUFFER_CACHE_BYTES`
+    /// environment variable or, if that is not set, is about 1/3 of the
+    /// system memory.
+    pub cache_size_bytes: Option<usize>,
+}
+
+impl RuntimeConfig {
+    /// Returns a configuration that stores layer files in `storage`.
+    pub fn with_storage<P: AsRef<Path>>(storage: P) -> Self {
+        Self {
+            storage: Some(storage.as_ref().to_path_buf()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the size of the buffer cache to `cache_size_bytes`.
+    pub fn with_cache_size_bytes(self, cache_size_bytes: usize) -> Self {
+        Self {
+            cache_size_bytes: Some(cache_size_bytes),
+            ..self
+        }
+    }
+}
+
 struct RuntimeInner {
     layout: Layout,
+    config: RuntimeConfig,
     store: LocalStore,
+    // Temporary storage directory, created on first use if `config.storage`
+    // is `None`.
+    temp_storage: OnceCell<TempDir>,
+    // Buffer cache shared by all workers, created on first use.
+    buffer_cache: OnceCell<Arc<TinyLfuCache>>,
+    // Source of file identifiers for all storage backends that share
+    // `buffer_cache`.
+    file_ids: Arc<AtomicIncrementOnlyI64>,
     // Panic info collected from failed worker threads.
     panic_info: Vec<RwLock<Option<WorkerPanicInfo>>>,
 }
@@ -178,12 +233,13 @@ impl Debug for RuntimeInner {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_struct("RuntimeInner")
             .field("layout", &self.layout)
+            .field("config", &self.config)
             .finish()
     }
 }
 
 impl RuntimeInner {
-    fn new(layout: Layout) -> Self {
+    fn new(layout: Layout, config: RuntimeConfig) -> Self {
         let local_workers = layout.local_workers().len();
         let mut panic_info = Vec::with_capacity(local_workers);
         for _ in 0..local_workers {
@@ -192,7 +248,11 @@ impl RuntimeInner {
 
         Self {
             layout,
+            config,
             store: TypedDashMap::new(),
+            temp_storage: OnceCell::new(),
+            buffer_cache: OnceCell::new(),
+            file_ids: Arc::new(AtomicIncrementOnlyI64::new()),
             panic_info,
         }
     }
@@ -207,7 +267,7 @@ impl RuntimeInner {
 // correctly for threads from different DBSP runtimes or for threads
 // that don't belong to any DBSP runtime since it uses `RUNTIME`
 // thread-local variable to detect a DBSP runtime.
-fn panic_hook(panic_info: &PanicInfo<'_>, default_panic_hook: &dyn Fn(&PanicInfo<'_>)) {
+fn panic_hook(panic_info: &PanicHookInfo<'_>, default_panic_hook: &dyn Fn(&PanicHookInfo<'_>)) {
     // Call the default panic hook first.
     default_panic_hook(panic_info);
 
@@ -228,7 +288,7 @@ pub struct Runtime(Arc<RuntimeInner>);
 /// Stores the default Rust panic hook, so we can invoke it as part of
 /// the DBSP custom hook.
 #[allow(clippy::type_complexity)]
-static DEFAULT_PANIC_HOOK: Lazy<Box<dyn Fn(&PanicInfo<'_>) + 'static + Sync + Send>> =
+static DEFAULT_PANIC_HOOK: Lazy<Box<dyn Fn(&PanicHookInfo<'_>) + 'static + Sync + Send>> =
     Lazy::new(|| {
         // Clear any hooks installed by other libraries.
         let _ = panic::take_hook();
@@ -236,7 +296,7 @@ static DEFAULT_PANIC_HOOK: Lazy<Box<dyn Fn(&PanicInfo<'_>) + 'static + Sync + Se
     });
 
 /// Returns the default Rust panic hook.
-fn default_panic_hook() -> &'static (dyn Fn(&PanicInfo<'_>) + 'static + Sync + Send) {
+fn default_panic_hook() -> &'static (dyn Fn(&PanicHookInfo<'_>) + 'static + Sync + Send) {
     &*DEFAULT_PANIC_HOOK
 }
 
@@ -285,13 +345,26 @@ impl Runtime {
     /// # }
     /// ```
     pub fn run<F>(layout: impl IntoLayout, circuit: F) -> RuntimeHandle
+    where
+        F: FnOnce() + Clone + Send + 'static,
+    {
+        Self::run_with_config(layout, RuntimeConfig::default(), circuit)
+    }
+
+    /// Like [`Runtime::run`], but with the specified `config` instead of the
+    /// default [`RuntimeConfig`].
+    pub fn run_with_config<F>(
+        layout: impl IntoLayout,
+        config: RuntimeConfig,
+        circuit: F,
+    ) -> RuntimeHandle
     where
         F: FnOnce() + Clone + Send + 'static,
     {
         let layout = layout.into_layout();
         let workers = layout.local_workers();
         let nworkers = workers.len();
-        let runtime = Self(Arc::new(RuntimeInner::new(layout)));
+        let runtime = Self(Arc::new(RuntimeInner::new(layout, config)));
 
         // Install custom panic hook.
         let default_hook = default_panic_hook();
@@ -376,6 +449,54 @@ impl Runtime {
         &self.inner().layout
     }
 
+    /// Returns the [`RuntimeConfig`] for this runtime.
+    pub fn config(&self) -> &RuntimeConfig {
+        &self.inner().config
+    }
+
+    /// Returns the directory in which worker `worker_index` stores its layer
+    /// files, creating it if necessary.
+    pub fn storage_dir(&self, worker_index: usize) -> PathBuf {
+        let inner = self.inner();
+        let base = match &inner.config.storage {
+            Some(storage) => storage.as_path(),
+            None => inner
+                .temp_storage
+                .get_or_init(|| {
+                    tempfile::tempdir().expect("failed to create temporary storage directory")
+                })
+                .path(),
+        };
+        let dir = worker_dir(base, worker_index);
+        create_dir_all(&dir).unwrap_or_else(|error| {
+            panic!(
+                "failed to create storage directory '{}': {error}",
+                dir.display()
+            )
+        });
+        dir
+    }
+
+    /// Returns the buffer cache shared by all workers in this runtime.
+    pub(crate) fn buffer_cache(&self) -> Arc<TinyLfuCache> {
+        let inner = self.inner();
+        inner
+            .buffer_cache
+            .get_or_init(|| {
+                Arc::new(match inner.config.cache_size_bytes {
+                    Some(bytes) => TinyLfuCache::with_capacity(bytes),
+                    None => TinyLfuCache::default(),
+                })
+            })
+            .clone()
+    }
+
+    /// Returns the counter that storage backends sharing
+    /// [`Runtime::buffer_cache`] use to allocate file identifiers.
+    pub(crate) fn file_ids(&self) -> Arc<AtomicIncrementOnlyI64> {
+        self.inner().file_ids.clone()
+    }
+
     /// Returns reference to the data store shared by all workers within the
     /// runtime.  In a multihost runtime, this data store is local to this
     /// particular host.
@@ -433,7 +554,7 @@ impl Runtime {
     }
 
     // Record information about a worker thread panic in `panic_info`
-    fn panic(&self, panic_info: &PanicInfo) {
+    fn panic(&self, panic_info: &PanicHookInfo) {
         let worker_index = Self::worker_index();
         let panic_info = WorkerPanicInfo::new(panic_info);
         let _ = self.inner().panic_info[worker_index]
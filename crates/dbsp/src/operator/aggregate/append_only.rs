@@ -0,0 +1,360 @@
+use crate::{
+    algebra::{HasOne, IndexedZSet, Semigroup, ZRingValue},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        GlobalNodeId, Scope,
+    },
+    circuit_cache_key,
+    operator::{
+        trace::{TraceBounds, TraceFeedback},
+        Aggregator, FilterMap,
+    },
+    trace::{cursor::CursorGroup, BatchReader, Builder, Cursor, Spine},
+    Circuit, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData, ops::Neg};
+
+circuit_cache_key!(AppendOnlyId<C, D>(GlobalNodeId => Stream<C, D>));
+
+impl<C, D> Stream<C, D>
+where
+    C: Circuit,
+    D: 'static,
+{
+    /// Marks the current stream as append-only, meaning that it never
+    /// contains a retraction, i.e., a record with negative weight.
+    ///
+    /// This enables [`Stream::aggregate_append_only`] to store only one
+    /// accumulator per key.  [`Stream::aggregate_append_only`] panics if a
+    /// stream marked append-only contains a retraction.
+    pub fn mark_append_only(&self) -> Self {
+        self.circuit().cache_insert(
+            AppendOnlyId::new(self.origin_node_id().clone()),
+            self.clone(),
+        );
+        self.clone()
+    }
+
+    /// Returns `true` if the current stream has been marked append-only with
+    /// [`Self::mark_append_only`].
+    pub fn is_append_only(&self) -> bool {
+        self.circuit()
+            .cache_contains(&AppendOnlyId::<C, D>::new(self.origin_node_id().clone()))
+    }
+}
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incremental aggregation of an insert-only indexed Z-set.
+    ///
+    /// Computes the same output as [`Self::aggregate`].  If the stream has
+    /// been marked append-only with [`Self::mark_append_only`], then instead
+    /// of maintaining a trace of the entire input, it only stores the
+    /// accumulator of each key.  When new values arrive for a key, the
+    /// operator aggregates them into an accumulator and combines it with the
+    /// stored one using `A::Semigroup`.  This makes it possible to compute
+    /// aggregates like
+    /// [`ApproxCountDistinct`](`crate::operator::ApproxCountDistinct`) over
+    /// large streams using memory proportional to the number of keys.
+    ///
+    /// The accumulators must form a semigroup consistent with
+    /// [`Aggregator::aggregate`], i.e., `A::Semigroup` must not be
+    /// [`UnimplementedSemigroup`](`crate::algebra::UnimplementedSemigroup`).
+    ///
+    /// Accumulators cannot forget values, so if the stream is not marked
+    /// append-only, this falls back to [`Self::aggregate`], which supports
+    /// retractions.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_append_only<A>(
+        &self,
+        aggregator: A,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, A::Output, Z::R>>
+    where
+        A: Aggregator<Z::Val, (), Z::R>,
+    {
+        if !self.is_append_only() {
+            return self.aggregate(aggregator);
+        }
+
+        self.circuit().region("aggregate_append_only", || {
+            let circuit = self.circuit();
+            let stream = self.shard();
+
+            let feedback = circuit.add_integrate_trace_feedback::<Spine<
+                OrdIndexedZSet<Z::Key, A::Accumulator, Z::R>,
+            >>(TraceBounds::unbounded());
+
+            let accumulators = circuit
+                .add_binary_operator(
+                    <AppendOnlyAggregate<Z, A, _, _>>::new(aggregator.clone()),
+                    &stream,
+                    &feedback.delayed_trace,
+                )
+                .mark_sharded();
+
+            feedback.connect(&accumulators);
+
+            accumulators
+                .map_index(move |(key, accumulator)| {
+                    (key.clone(), aggregator.finalize(accumulator.clone()))
+                })
+                .mark_sharded()
+        })
+    }
+}
+
+/// Binary operator that implements the internals of
+/// [`aggregate_append_only`](`Stream::aggregate_append_only`).
+///
+/// * Input stream 1: updates to the input indexed Z-set.
+/// * Input stream 2: trace of accumulators computed in previous steps.
+///
+/// Outputs changes to the accumulators of keys in the current input batch.
+struct AppendOnlyAggregate<Z, A, T, O> {
+    aggregator: A,
+    _type: PhantomData<(Z, T, O)>,
+}
+
+impl<Z, A, T, O> AppendOnlyAggregate<Z, A, T, O> {
+    fn new(aggregator: A) -> Self {
+        Self {
+            aggregator,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<Z, A, T, O> Operator for AppendOnlyAggregate<Z, A, T, O>
+where
+    Z: 'static,
+    A: 'static,
+    T: 'static,
+    O: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AppendOnlyAggregate")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, A, T, O> BinaryOperator<Z, T, O> for AppendOnlyAggregate<Z, A, T, O>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    A: Aggregator<Z::Val, (), Z::R>,
+    T: BatchReader<Key = Z::Key, Val = A::Accumulator, Time = (), R = Z::R> + Clone,
+    O: IndexedZSet<Key = Z::Key, Val = A::Accumulator, R = Z::R>,
+{
+    fn eval(&mut self, delta: &Z, accumulators: &T) -> O {
+        let mut delta_cursor = delta.cursor();
+        let mut accumulator_cursor = accumulators.cursor();
+
+        let mut builder = O::Builder::with_capacity((), delta.key_count());
+
+        while delta_cursor.key_valid() {
+            // A retraction can't be removed from the stored accumulator, so
+            // the output would silently be wrong.
+            while delta_cursor.val_valid() {
+                assert!(
+                    delta_cursor.weight().ge0(),
+                    "aggregate_append_only: a stream marked append-only contains a retraction"
+                );
+                delta_cursor.step_val();
+            }
+            delta_cursor.rewind_vals();
+
+            if let Some(delta_accumulator) = self
+                .aggregator
+                .aggregate(&mut CursorGroup::new(&mut delta_cursor, ()))
+            {
+                let key = delta_cursor.key();
+
+                // Find the accumulator computed in previous steps.
+                let mut old_accumulator = None;
+                accumulator_cursor.seek_key(key);
+                if accumulator_cursor.key_valid() && accumulator_cursor.key() == key {
+                    while accumulator_cursor.val_valid() {
+                        if !accumulator_cursor.weight().le0() {
+                            old_accumulator = Some(accumulator_cursor.val().clone());
+                            break;
+                        }
+                        accumulator_cursor.step_val();
+                    }
+                }
+
+                match old_accumulator {
+                    None => {
+                        builder.push((O::item_from(key.clone(), delta_accumulator), Z::R::one()));
+                    }
+                    Some(old_accumulator) => {
+                        let new_accumulator =
+                            A::Semigroup::combine(&old_accumulator, &delta_accumulator);
+                        if new_accumulator != old_accumulator {
+                            // Push the retraction and the insertion in order.
+                            let retraction = (old_accumulator, Z::R::one().neg());
+                            let insertion = (new_accumulator, Z::R::one());
+                            let (first, second) = if retraction.0 < insertion.0 {
+                                (retraction, insertion)
+                            } else {
+                                (insertion, retraction)
+                            };
+                            builder.push((O::item_from(key.clone(), first.0), first.1));
+                            builder.push((O::item_from(key.clone(), second.0), second.1));
+                        }
+                    }
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::DefaultSemigroup,
+        indexed_zset,
+        operator::{ApproxCountDistinct, ApproxPercentile, Fold, Max},
+        utils::Tup2,
+        RootCircuit, Runtime,
+    };
+
+    fn append_only_test(workers: usize) {
+        let (mut dbsp, (input_handle, max_output, sum_output, count_output, percentile_output)) =
+            Runtime::init_circuit(workers, move |circuit| {
+                let (input_stream, input_handle) =
+                    circuit.add_input_indexed_zset::<u64, i64, i64>();
+                input_stream.mark_append_only();
+
+                let max_output = input_stream.aggregate_append_only(Max).output();
+                let sum_output = input_stream
+                    .aggregate_append_only(<Fold<_, DefaultSemigroup<_>, _, _>>::new(
+                        0,
+                        |acc: &mut i64, v: &i64, w: i64| *acc += *v * w,
+                    ))
+                    .output();
+                let count_output = input_stream
+                    .aggregate_append_only(ApproxCountDistinct::default())
+                    .output();
+                let percentile_output = input_stream
+                    .aggregate_append_only(ApproxPercentile::new(0.5))
+                    .output();
+
+                Ok((
+                    input_handle,
+                    max_output,
+                    sum_output,
+                    count_output,
+                    percentile_output,
+                ))
+            })
+            .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, Tup2(1, 1)),
+            (1, Tup2(2, 1)),
+            (1, Tup2(3, 1)),
+            (2, Tup2(10, 2)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            max_output.consolidate(),
+            indexed_zset! {1 => {3 => 1}, 2 => {10 => 1}}
+        );
+        assert_eq!(
+            sum_output.consolidate(),
+            indexed_zset! {1 => {6 => 1}, 2 => {20 => 1}}
+        );
+        assert_eq!(
+            count_output.consolidate(),
+            indexed_zset! {1 => {3 => 1}, 2 => {1 => 1}}
+        );
+        assert_eq!(
+            percentile_output.consolidate(),
+            indexed_zset! {1 => {2 => 1}, 2 => {10 => 1}}
+        );
+
+        // Values that don't change the output produce no output.
+        input_handle.append(&mut vec![(1, Tup2(4, 1)), (2, Tup2(5, 1))]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            max_output.consolidate(),
+            indexed_zset! {1 => {3 => -1, 4 => 1}}
+        );
+        assert_eq!(
+            sum_output.consolidate(),
+            indexed_zset! {1 => {6 => -1, 10 => 1}, 2 => {20 => -1, 25 => 1}}
+        );
+        assert_eq!(
+            count_output.consolidate(),
+            indexed_zset! {1 => {3 => -1, 4 => 1}, 2 => {1 => -1, 2 => 1}}
+        );
+        assert_eq!(percentile_output.consolidate(), indexed_zset! {});
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn append_only_test1() {
+        append_only_test(1);
+    }
+
+    #[test]
+    fn append_only_test4() {
+        append_only_test(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "contains a retraction")]
+    fn append_only_marked_retraction() {
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input_stream, input_handle) = circuit.add_input_indexed_zset::<u64, i64, i64>();
+            input_stream
+                .mark_append_only()
+                .aggregate_append_only(Max)
+                .output();
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, Tup2(1, 1))]);
+        circuit.step().unwrap();
+
+        input_handle.append(&mut vec![(1, Tup2(1, -1))]);
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn append_only_retraction() {
+        // A stream that isn't marked append-only falls back to `aggregate`,
+        // which supports retractions.
+        let (mut dbsp, (input_handle, max_output)) = Runtime::init_circuit(1, move |circuit| {
+            let (input_stream, input_handle) = circuit.add_input_indexed_zset::<u64, i64, i64>();
+            let max_output = input_stream.aggregate_append_only(Max).output();
+            Ok((input_handle, max_output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, Tup2(1, 1)), (1, Tup2(2, 1))]);
+        dbsp.step().unwrap();
+        assert_eq!(max_output.consolidate(), indexed_zset! {1 => {2 => 1}});
+
+        input_handle.append(&mut vec![(1, Tup2(2, -1))]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            max_output.consolidate(),
+            indexed_zset! {1 => {1 => 1, 2 => -1}}
+        );
+
+        dbsp.kill().unwrap();
+    }
+}
@@ -25,16 +25,27 @@ use crate::{
     DBData, DBTimestamp, DBWeight, OrdIndexedZSet, OrdZSet,
 };
 
+mod append_only;
+
 // Some standard aggregators.
 mod average;
 mod fold;
 mod max;
 mod min;
+mod mode;
+mod percentile;
+mod sketch;
 
 pub use average::Avg;
 pub use fold::Fold;
 pub use max::{Max, MaxSemigroup};
 pub use min::{Min, MinSemigroup};
+pub use mode::Mode;
+pub use percentile::{Median, PercentileCont, PercentileDisc};
+pub use sketch::{
+    ApproxCountDistinct, ApproxPercentile, HyperLogLog, HyperLogLogSemigroup, KllSemigroup,
+    KllSketch,
+};
 
 /// A trait for aggregator objects.  An aggregator summarizes the contents
 /// of a Z-set into a single value.
@@ -739,10 +750,10 @@ mod test {
     };
 
     use crate::{
-        algebra::DefaultSemigroup,
+        algebra::{DefaultSemigroup, F64},
         indexed_zset,
         operator::GeneratorNested,
-        operator::{Fold, Min},
+        operator::{Fold, Median, Min, Mode, PercentileCont, PercentileDisc},
         trace::{cursor::Cursor, Batch, BatchReader},
         zset, Circuit, OrdIndexedZSet, OrdZSet, RootCircuit, Runtime, Stream,
     };
@@ -882,6 +893,46 @@ mod test {
                         assert_eq!(d1, d2);
                     });
 
+                let percentile_inc = input.aggregate(PercentileDisc::new(0.3)).gather(0);
+                let percentile_noninc = input
+                    .integrate_nested()
+                    .integrate()
+                    .stream_aggregate(PercentileDisc::new(0.3))
+                    .differentiate()
+                    .differentiate_nested()
+                    .gather(0);
+
+                percentile_inc
+                    .apply2(
+                        &percentile_noninc,
+                        |d1: &OrdIndexedZSet<u64, i64, i64>, d2: &OrdIndexedZSet<u64, i64, i64>| {
+                            (d1.clone(), d2.clone())
+                        },
+                    )
+                    .inspect(|(d1, d2)| {
+                        assert_eq!(d1, d2);
+                    });
+
+                let mode_inc = input.aggregate(Mode).gather(0);
+                let mode_noninc = input
+                    .integrate_nested()
+                    .integrate()
+                    .stream_aggregate(Mode)
+                    .differentiate()
+                    .differentiate_nested()
+                    .gather(0);
+
+                mode_inc
+                    .apply2(
+                        &mode_noninc,
+                        |d1: &OrdIndexedZSet<u64, i64, i64>, d2: &OrdIndexedZSet<u64, i64, i64>| {
+                            (d1.clone(), d2.clone())
+                        },
+                    )
+                    .inspect(|(d1, d2)| {
+                        assert_eq!(d1, d2);
+                    });
+
                 Ok((
                     move || {
                         *counter.borrow_mut() += 1;
@@ -1081,4 +1132,196 @@ mod test {
     fn count_test4() {
         count_test(4);
     }
+
+    fn percentile_test(workers: usize) {
+        let (mut dbsp, (input_handle, cont_output, median_output, disc_output, mode_output)) =
+            Runtime::init_circuit(workers, move |circuit| {
+                let (input_stream, input_handle) =
+                    circuit.add_input_indexed_zset::<u64, i32, i64>();
+
+                let cont_output = input_stream.aggregate(PercentileCont::new(0.125)).output();
+                let median_output = input_stream.aggregate(Median).output();
+                let disc_output = input_stream.aggregate(PercentileDisc::new(0.5)).output();
+                let mode_output = input_stream.aggregate(Mode).output();
+
+                Ok((
+                    input_handle,
+                    cont_output,
+                    median_output,
+                    disc_output,
+                    mode_output,
+                ))
+            })
+            .unwrap();
+
+        // Group 1: [1, 2, 4, 4, 10].
+        input_handle.append(&mut vec![
+            (1, Tup2(1, 1)),
+            (1, Tup2(2, 1)),
+            (1, Tup2(4, 2)),
+            (1, Tup2(10, 1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            cont_output.consolidate(),
+            indexed_zset! {1 => {F64::new(1.5) => 1}}
+        );
+        assert_eq!(
+            median_output.consolidate(),
+            indexed_zset! {1 => {F64::new(4.0) => 1}}
+        );
+        assert_eq!(disc_output.consolidate(), indexed_zset! {1 => {4 => 1}});
+        assert_eq!(mode_output.consolidate(), indexed_zset! {1 => {4 => 1}});
+
+        // Group 1: [1, 2, 4, 10], group 2: [5].
+        input_handle.append(&mut vec![(1, Tup2(4, -1)), (2, Tup2(5, 1))]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            cont_output.consolidate(),
+            indexed_zset! {
+                1 => {F64::new(1.5) => -1, F64::new(1.375) => 1},
+                2 => {F64::new(5.0) => 1}
+            }
+        );
+        assert_eq!(
+            median_output.consolidate(),
+            indexed_zset! {
+                1 => {F64::new(4.0) => -1, F64::new(3.0) => 1},
+                2 => {F64::new(5.0) => 1}
+            }
+        );
+        assert_eq!(
+            disc_output.consolidate(),
+            indexed_zset! {1 => {4 => -1, 2 => 1}, 2 => {5 => 1}}
+        );
+        assert_eq!(
+            mode_output.consolidate(),
+            indexed_zset! {1 => {4 => -1, 1 => 1}, 2 => {5 => 1}}
+        );
+
+        // Group 2 becomes empty.
+        input_handle.append(&mut vec![(2, Tup2(5, -1))]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            cont_output.consolidate(),
+            indexed_zset! {2 => {F64::new(5.0) => -1}}
+        );
+        assert_eq!(
+            median_output.consolidate(),
+            indexed_zset! {2 => {F64::new(5.0) => -1}}
+        );
+        assert_eq!(disc_output.consolidate(), indexed_zset! {2 => {5 => -1}});
+        assert_eq!(mode_output.consolidate(), indexed_zset! {2 => {5 => -1}});
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn percentile_test1() {
+        percentile_test(1);
+    }
+
+    #[test]
+    fn percentile_test4() {
+        percentile_test(4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn percentile_out_of_range() {
+        PercentileCont::new(1.5);
+    }
+
+    type PercentileInput = Vec<(u64, Tup2<i32, i64>)>;
+
+    fn percentile_input() -> impl Strategy<Value = Vec<PercentileInput>> {
+        // Mix a narrow range of values, so that steps retract values inserted
+        // earlier, with arbitrary values, so that trees get deep.
+        collection::vec(
+            collection::vec(
+                (
+                    0..NUM_KEYS,
+                    (prop_oneof![-10..10i32, any::<i32>()], -1..=2i64)
+                        .prop_map(|(val, weight)| Tup2(val, weight)),
+                ),
+                0..MAX_TUPLES * 2,
+            ),
+            0..MAX_ROUNDS,
+        )
+    }
+
+    // Checks that incremental percentiles computed over weighted count trees,
+    // and the incremental mode, match the corresponding aggregators.
+    fn percentile_tree_test(workers: usize, inputs: Vec<PercentileInput>) {
+        let (mut dbsp, (input_handle, cont_outputs, disc_outputs, median_output, mode_output)) =
+            Runtime::init_circuit(workers, move |circuit| {
+                let (input_stream, input_handle) =
+                    circuit.add_input_indexed_zset::<u64, i32, i64>();
+
+                let cont_outputs = [0.0, 0.3, 0.5, 1.0].map(|percentile| {
+                    (
+                        input_stream.percentile_cont(percentile).output(),
+                        input_stream
+                            .aggregate(PercentileCont::new(percentile))
+                            .output(),
+                    )
+                });
+                let disc_outputs = [0.0, 0.3, 0.5, 1.0].map(|percentile| {
+                    (
+                        input_stream.percentile_disc(percentile).output(),
+                        input_stream
+                            .aggregate(PercentileDisc::new(percentile))
+                            .output(),
+                    )
+                });
+
+                let median_output = (
+                    input_stream.median().output(),
+                    input_stream.aggregate(Median).output(),
+                );
+                let mode_output = (
+                    input_stream.mode().output(),
+                    input_stream.aggregate(Mode).output(),
+                );
+
+                Ok((
+                    input_handle,
+                    cont_outputs,
+                    disc_outputs,
+                    median_output,
+                    mode_output,
+                ))
+            })
+            .unwrap();
+
+        for mut input in inputs {
+            input_handle.append(&mut input);
+            dbsp.step().unwrap();
+
+            for (output, expected) in cont_outputs.iter() {
+                assert_eq!(output.consolidate(), expected.consolidate());
+            }
+            for (output, expected) in disc_outputs.iter() {
+                assert_eq!(output.consolidate(), expected.consolidate());
+            }
+            assert_eq!(median_output.0.consolidate(), median_output.1.consolidate());
+            assert_eq!(mode_output.0.consolidate(), mode_output.1.consolidate());
+        }
+
+        dbsp.kill().unwrap();
+    }
+
+    proptest! {
+        #[test]
+        #[cfg_attr(feature = "persistence", ignore = "takes a long time?")]
+        fn proptest_percentile_tree_st(inputs in percentile_input()) {
+            percentile_tree_test(1, inputs);
+        }
+
+        #[test]
+        #[cfg_attr(feature = "persistence", ignore = "takes a long time?")]
+        fn proptest_percentile_tree_mt(inputs in percentile_input()) {
+            percentile_tree_test(4, inputs);
+        }
+    }
 }
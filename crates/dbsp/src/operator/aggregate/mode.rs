@@ -0,0 +1,119 @@
+use crate::{
+    algebra::{HasOne, IndexedZSet, MonoidValue, NegByRef, UnimplementedSemigroup, ZRingValue},
+    operator::{aggregate::Aggregator, FilterMap, Min},
+    trace::{Batch, Builder, Cursor},
+    utils::Tup2,
+    DBData, OrdIndexedZSet, RootCircuit, Stream, Timestamp,
+};
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the most
+/// frequent value in a group, i.e., the value with the largest positive
+/// weight (the SQL `MODE` aggregate).
+///
+/// Ties are broken in favor of the smallest value.  Values with non-positive
+/// weights are ignored.
+///
+/// With [`Stream::aggregate`], each evaluation scans all distinct values of
+/// the affected group.  [`Stream::mode`] updates the mode without scanning
+/// the group.
+// `MODE` is holistic and cannot be computed by combining partial aggregates,
+// hence `UnimplementedSemigroup`.
+#[derive(Clone)]
+pub struct Mode;
+
+impl<V, T, R> Aggregator<V, T, R> for Mode
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue + Ord,
+{
+    type Accumulator = V;
+    type Output = V;
+    type Semigroup = UnimplementedSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut mode: Option<(V, R)> = None;
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            let is_mode = match &mode {
+                None => true,
+                Some((_, best)) => &weight > best,
+            };
+            if weight > R::zero() && is_mode {
+                mode = Some((cursor.key().clone(), weight));
+            }
+
+            cursor.step_key();
+        }
+
+        mode.map(|(value, _)| value)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Incrementally computes the most frequent value of each key (the SQL
+    /// `MODE` aggregate).
+    ///
+    /// Computes the same result as `self.aggregate(Mode)` (see [`Mode`]), but
+    /// maintains the weight of each value in a trace indexed by key and
+    /// weight, so that updating the mode after a change to the group,
+    /// including a retraction, only touches the changed values and the
+    /// value with the largest weight.
+    #[allow(clippy::type_complexity)]
+    pub fn mode(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        // We construct the following circuit:
+        //
+        // ```
+        //  stream   ┌────────────┐  ┌────────────────┐  ┌───────────────┐  ┌────────┐  ┌───┐
+        // ─────────►│index values├─►│aggregate_linear├─►│index by weight├─►│Min     ├─►│map├──►
+        //           └────────────┘  └────────────────┘  └───────────────┘  └────────┘  └───┘
+        // ```
+        //
+        // `aggregate_linear` computes the weight `w` of each `(key, value)`
+        // pair.  Indexing the positive weights as `key -> (-w, value)` sorts
+        // the values of each key by descending weight and then by value, so
+        // the mode is the smallest entry in the group.
+        self.apply_named("IndexModeValues", |batch| {
+            let mut values =
+                <OrdIndexedZSet<Tup2<Z::Key, Z::Val>, (), Z::R> as Batch>::Builder::with_capacity(
+                    (),
+                    batch.len(),
+                );
+            let mut cursor = batch.cursor();
+            while cursor.key_valid() {
+                while cursor.val_valid() {
+                    // Tuples are pushed in `(key, value)` order.
+                    values.push((
+                        (Tup2(cursor.key().clone(), cursor.val().clone()), ()),
+                        cursor.weight(),
+                    ));
+                    cursor.step_val();
+                }
+                cursor.step_key();
+            }
+            values.done()
+        })
+        .aggregate_linear(|()| Z::R::one())
+        .flat_map_index(|(Tup2(key, value), weight)| {
+            (!weight.le0()).then(|| (key.clone(), Tup2(weight.neg_by_ref(), value.clone())))
+        })
+        .aggregate(Min)
+        .map_index(|(key, Tup2(_, value))| (key.clone(), value.clone()))
+    }
+}
@@ -0,0 +1,596 @@
+use crate::{
+    algebra::{IndexedZSet, MonoidValue, Semigroup, UnimplementedSemigroup, ZRingValue, F64},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator},
+        Scope,
+    },
+    operator::{
+        aggregate::Aggregator,
+        time_series::{
+            radix_tree::{PartitionedRadixTreeReader, RadixTreeCursor},
+            PartitionCursor, Range,
+        },
+    },
+    trace::{Batch, Builder, Cursor},
+    utils::Tup2,
+    Circuit, DBData, OrdIndexedZSet, RootCircuit, Stream, Timestamp,
+};
+use num::{PrimInt, ToPrimitive};
+use size_of::SizeOf;
+use std::{borrow::Cow, marker::PhantomData};
+
+// Percentile aggregators are holistic: the percentile of a union of two
+// Z-sets cannot be computed from the percentiles of its parts, so they don't
+// implement a semigroup.  Instead, they rely on the group cursor being sorted
+// by value: each value is visited once regardless of its weight, and the
+// group is never materialized or sorted.  This still takes time linear in the
+// number of distinct values in the group, so
+// [`Stream::percentile_cont`], [`Stream::percentile_disc`] and
+// [`Stream::median`] maintain a weighted count tree per group instead.
+
+/// Returns the weight of the current key, ignoring non-positive weights.
+///
+/// Weights that don't fit in `i64` saturate to `i64::MAX`.
+fn positive_weight<V, T, R, C>(cursor: &mut C) -> i64
+where
+    C: Cursor<V, (), T, R>,
+    R: MonoidValue + ToPrimitive,
+{
+    let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+        acc.add_assign_by_ref(weight);
+        acc
+    });
+
+    match weight.to_i64() {
+        Some(weight) => weight.max(0),
+        None if weight.to_f64().is_some_and(|weight| weight > 0.0) => i64::MAX,
+        None => 0,
+    }
+}
+
+/// Returns the total weight of all values in the cursor, saturating at
+/// `i64::MAX`.
+fn total_weight<V, T, R, C>(cursor: &mut C) -> i64
+where
+    C: Cursor<V, (), T, R>,
+    R: MonoidValue + ToPrimitive,
+{
+    let mut total: i64 = 0;
+
+    while cursor.key_valid() {
+        total = total.saturating_add(positive_weight(cursor));
+        cursor.step_key();
+    }
+
+    total
+}
+
+/// Positions the cursor at the value that occupies the `row`'th position
+/// (counting from 0) in the sorted multiset of values with total weight
+/// `total`.  Returns the index of the first row occupied by this value.
+///
+/// Scans from whichever end of the cursor is closer to `row`, unless `total`
+/// has saturated, in which case the position of the last row is unknown.
+fn seek_row<V, T, R, C>(cursor: &mut C, row: i64, total: i64) -> i64
+where
+    C: Cursor<V, (), T, R>,
+    R: MonoidValue + ToPrimitive,
+{
+    debug_assert!((0..total).contains(&row));
+
+    if row < total / 2 || total == i64::MAX {
+        cursor.rewind_keys();
+
+        let mut start: i64 = 0;
+        loop {
+            let end = start.saturating_add(positive_weight(cursor));
+            if row < end {
+                return start;
+            }
+            start = end;
+            cursor.step_key();
+        }
+    } else {
+        cursor.fast_forward_keys();
+
+        let mut end = total;
+        loop {
+            let weight = positive_weight(cursor);
+            if row >= end - weight {
+                return end - weight;
+            }
+            end -= weight;
+            cursor.step_key_reverse();
+        }
+    }
+}
+
+/// Returns the row (counting from 0) that holds the discrete `percentile` of
+/// `total` rows.
+fn disc_row(percentile: f64, total: i64) -> i64 {
+    ((percentile * total as f64).ceil() as i64 - 1).clamp(0, total - 1)
+}
+
+/// Returns the row (counting from 0) at or below the continuous `percentile`
+/// of `total` rows, and how far the percentile lies between that row and the
+/// next one.
+fn cont_position(percentile: f64, total: i64) -> (i64, f64) {
+    let position = percentile * (total - 1) as f64;
+    let row = position.floor() as i64;
+
+    (row, position - row as f64)
+}
+
+pub(super) fn check_percentile(percentile: f64) {
+    assert!(
+        (0.0..=1.0).contains(&percentile),
+        "percentile must be between 0 and 1, found {percentile}"
+    );
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that computes a continuous
+/// percentile of a group of numeric values, interpolating linearly between
+/// adjacent values (the SQL `PERCENTILE_CONT` aggregate).
+///
+/// The weight of each value is its multiplicity.  Values with non-positive
+/// weights are ignored, and weights that don't fit in `i64` are treated as
+/// `i64::MAX`.
+///
+/// With [`Stream::aggregate`], each evaluation scans all distinct values of
+/// the affected group.  For integer values, [`Stream::percentile_cont`]
+/// updates the percentile in time logarithmic in the size of the group.
+#[derive(Clone)]
+pub struct PercentileCont {
+    percentile: f64,
+}
+
+impl PercentileCont {
+    /// Creates an aggregator that computes the given `percentile`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1.
+    pub fn new(percentile: f64) -> Self {
+        check_percentile(percentile);
+        Self { percentile }
+    }
+
+    /// Returns the percentile computed by this aggregator.
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for PercentileCont
+where
+    V: DBData + Into<F64>,
+    T: Timestamp,
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = F64;
+    type Output = F64;
+    type Semigroup = UnimplementedSemigroup<F64>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let total = total_weight(cursor);
+        if total == 0 {
+            return None;
+        }
+
+        let (row, fraction) = cont_position(self.percentile, total);
+
+        let start = seek_row(cursor, row, total);
+        let lower: F64 = cursor.key().clone().into();
+        if fraction == 0.0 {
+            return Some(lower);
+        }
+
+        // The next row either belongs to the same value or to the next value
+        // with positive weight.
+        if row + 1 < start.saturating_add(positive_weight(cursor)) {
+            return Some(lower);
+        }
+        cursor.step_key();
+        while positive_weight(cursor) == 0 {
+            cursor.step_key();
+        }
+        let upper: F64 = cursor.key().clone().into();
+
+        Some(F64::new(
+            lower.into_inner() + (upper.into_inner() - lower.into_inner()) * fraction,
+        ))
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that computes a discrete
+/// percentile of a group of values, i.e., the first value whose cumulative
+/// distribution is greater than or equal to the percentile (the SQL
+/// `PERCENTILE_DISC` aggregate).
+///
+/// The weight of each value is its multiplicity.  Values with non-positive
+/// weights are ignored, and weights that don't fit in `i64` are treated as
+/// `i64::MAX`.
+///
+/// With [`Stream::aggregate`], each evaluation scans all distinct values of
+/// the affected group.  For integer values, [`Stream::percentile_disc`]
+/// updates the percentile in time logarithmic in the size of the group.
+#[derive(Clone)]
+pub struct PercentileDisc {
+    percentile: f64,
+}
+
+impl PercentileDisc {
+    /// Creates an aggregator that computes the given `percentile`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1.
+    pub fn new(percentile: f64) -> Self {
+        check_percentile(percentile);
+        Self { percentile }
+    }
+
+    /// Returns the percentile computed by this aggregator.
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for PercentileDisc
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = V;
+    type Output = V;
+    type Semigroup = UnimplementedSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let total = total_weight(cursor);
+        if total == 0 {
+            return None;
+        }
+
+        let row = disc_row(self.percentile, total);
+        seek_row(cursor, row, total);
+
+        Some(cursor.key().clone())
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that computes the median
+/// of a group of numeric values, averaging the two middle values if the
+/// group has even size (the SQL `MEDIAN` aggregate).
+///
+/// Equivalent to [`PercentileCont`] with percentile `0.5`.
+///
+/// With [`Stream::aggregate`], each evaluation scans all distinct values of
+/// the affected group.  For integer values, [`Stream::median`] updates the
+/// median in time logarithmic in the size of the group.
+#[derive(Clone)]
+pub struct Median;
+
+impl<V, T, R> Aggregator<V, T, R> for Median
+where
+    V: DBData + Into<F64>,
+    T: Timestamp,
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = F64;
+    type Output = F64;
+    type Semigroup = UnimplementedSemigroup<F64>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        <PercentileCont as Aggregator<V, T, R>>::aggregate(&PercentileCont::new(0.5), cursor)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// Number of rows with positive weights in a subtree of a weighted count tree.
+#[derive(Clone)]
+struct PositiveCount;
+
+/// Adds row counts, saturating at `i64::MAX`.
+#[derive(Clone)]
+struct SaturatingSum;
+
+impl Semigroup<i64> for SaturatingSum {
+    fn combine(left: &i64, right: &i64) -> i64 {
+        left.saturating_add(*right)
+    }
+}
+
+impl<R> Aggregator<(), (), R> for PositiveCount
+where
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = i64;
+    type Output = i64;
+    type Semigroup = SaturatingSum;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<(), (), (), R>,
+    {
+        let count = positive_weight(cursor);
+        (count > 0).then_some(count)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+/// Input of the weighted count tree: values of each key encoded as
+/// timestamps.
+type EncodedZSet<K, R> = OrdIndexedZSet<K, Tup2<u64, ()>, R>;
+
+/// Maps an integer to a `u64` with the same relative order, so that it can
+/// be used as a radix tree timestamp.
+///
+/// # Panics
+///
+/// Panics if `value` doesn't fit in 64 bits.
+fn encode_value<V>(value: &V) -> u64
+where
+    V: PrimInt,
+{
+    if V::min_value() < V::zero() {
+        let value = value
+            .to_i64()
+            .expect("percentile values must fit in 64 bits");
+        (value as u64) ^ (1 << 63)
+    } else {
+        value
+            .to_u64()
+            .expect("percentile values must fit in 64 bits")
+    }
+}
+
+/// Inverse of [`encode_value`].
+fn decode_value<V>(encoded: u64) -> V
+where
+    V: PrimInt,
+{
+    if V::min_value() < V::zero() {
+        V::from((encoded ^ (1 << 63)) as i64)
+    } else {
+        V::from(encoded)
+    }
+    .unwrap()
+}
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + SizeOf + Send,
+    Z::Val: PrimInt,
+    Z::R: ZRingValue + ToPrimitive,
+{
+    /// Incrementally computes the continuous percentile of the values of each
+    /// key (the SQL `PERCENTILE_CONT` aggregate).
+    ///
+    /// Computes the same result as
+    /// `self.aggregate(PercentileCont::new(percentile))`
+    /// (see [`PercentileCont`]), but maintains a weighted count tree over
+    /// the values of each key, so that updating the percentile after a
+    /// change to the group, including a retraction, takes time logarithmic
+    /// in the size of the group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1 or if the stream
+    /// contains values that don't fit in 64 bits.
+    pub fn percentile_cont(
+        &self,
+        percentile: f64,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, F64, Z::R>> {
+        check_percentile(percentile);
+
+        self.percentile_generic(move |total, select| {
+            let (row, fraction) = cont_position(percentile, total);
+            let lower = decode_value::<Z::Val>(select(row)).to_f64().unwrap();
+            if fraction == 0.0 {
+                return F64::new(lower);
+            }
+            let upper = decode_value::<Z::Val>(select(row + 1)).to_f64().unwrap();
+
+            F64::new(lower + (upper - lower) * fraction)
+        })
+    }
+
+    /// Incrementally computes the discrete percentile of the values of each
+    /// key (the SQL `PERCENTILE_DISC` aggregate).
+    ///
+    /// Computes the same result as
+    /// `self.aggregate(PercentileDisc::new(percentile))`
+    /// (see [`PercentileDisc`]), but maintains a weighted count tree over
+    /// the values of each key, so that updating the percentile after a
+    /// change to the group, including a retraction, takes time logarithmic
+    /// in the size of the group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1 or if the stream
+    /// contains values that don't fit in 64 bits.
+    #[allow(clippy::type_complexity)]
+    pub fn percentile_disc(
+        &self,
+        percentile: f64,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, Z::Val, Z::R>> {
+        check_percentile(percentile);
+
+        self.percentile_generic(move |total, select| {
+            decode_value::<Z::Val>(select(disc_row(percentile, total)))
+        })
+    }
+
+    /// Incrementally computes the median of the values of each key (the SQL
+    /// `MEDIAN` aggregate).
+    ///
+    /// Equivalent to [`Stream::percentile_cont`] with percentile `0.5`, and
+    /// computes the same result as `self.aggregate(Median)` (see
+    /// [`Median`]).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stream contains values that don't fit in 64 bits.
+    pub fn median(&self) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, F64, Z::R>> {
+        self.percentile_cont(0.5)
+    }
+
+    /// Builds the weighted count tree of each key and evaluates `percentile`
+    /// for keys that changed.  `percentile` takes the total number of rows
+    /// in a group and a function that returns the encoded value at a given
+    /// row.
+    fn percentile_generic<O, F>(
+        &self,
+        percentile: F,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<Z::Key, O, Z::R>>
+    where
+        O: DBData,
+        F: Fn(i64, &mut dyn FnMut(i64) -> u64) -> O + 'static,
+    {
+        let stream = self.shard();
+
+        // We construct the following circuit.  The tree is indexed by the
+        // encoded value, so each leaf counts the rows holding one value, and
+        // each inner node counts the rows in its subtree.
+        //
+        // ```
+        //          ┌─────────────────────────────────────────────────────────────────────┐
+        //          │                                                                     │
+        //          │                                                                     ▼
+        //  stream  │     ┌──────┐  ┌──────────────────────────┐  ┌───────────────┐  ┌─────────────────────┐  ┌──────┐
+        // ─────────┴────►│encode├─►│partitioned_tree_aggregate├─►│integrate_trace├─►│PercentileIncremental├─►│upsert├──►
+        //                └──────┘  └──────────────────────────┘  └───────────────┘  └─────────────────────┘  └──────┘
+        // ```
+        let tree = stream
+            .apply_named("EncodePercentileValues", |batch| {
+                let mut encoded =
+                    <EncodedZSet<Z::Key, Z::R> as Batch>::Builder::with_capacity((), batch.len());
+                let mut cursor = batch.cursor();
+                while cursor.key_valid() {
+                    while cursor.val_valid() {
+                        // The encoding preserves the order of values, so
+                        // tuples are pushed in order.
+                        encoded.push((
+                            (cursor.key().clone(), Tup2(encode_value(cursor.val()), ())),
+                            cursor.weight(),
+                        ));
+                        cursor.step_val();
+                    }
+                    cursor.step_key();
+                }
+                encoded.done()
+            })
+            .mark_sharded()
+            .partitioned_tree_aggregate::<u64, (), PositiveCount>(PositiveCount)
+            .integrate_trace();
+
+        self.circuit()
+            .add_binary_operator(PercentileIncremental::new(percentile), &stream, &tree)
+            .upsert::<OrdIndexedZSet<Z::Key, O, Z::R>>()
+            .mark_sharded()
+    }
+}
+
+/// Binary operator that implements the internals of
+/// [`Stream::percentile_cont`] and [`Stream::percentile_disc`].
+///
+/// * Input stream 1: updates to the input collection.  Used to identify
+///   affected keys.
+/// * Input stream 2: trace containing the weighted count tree of each key.
+///
+/// Outputs an upsert for each affected key.
+struct PercentileIncremental<Z, T, O, F> {
+    percentile: F,
+    phantom: PhantomData<(Z, T, O)>,
+}
+
+impl<Z, T, O, F> PercentileIncremental<Z, T, O, F> {
+    fn new(percentile: F) -> Self {
+        Self {
+            percentile,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Z, T, O, F> Operator for PercentileIncremental<Z, T, O, F>
+where
+    Z: 'static,
+    T: 'static,
+    O: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("PercentileIncremental")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, T, O, F> BinaryOperator<Z, T, Vec<(Z::Key, Option<O>)>>
+    for PercentileIncremental<Z, T, O, F>
+where
+    Z: IndexedZSet,
+    T: PartitionedRadixTreeReader<u64, i64, Key = Z::Key> + Clone,
+    O: 'static,
+    F: Fn(i64, &mut dyn FnMut(i64) -> u64) -> O + 'static,
+{
+    fn eval(&mut self, delta: &Z, tree: &T) -> Vec<(Z::Key, Option<O>)> {
+        let mut result = Vec::with_capacity(delta.key_count());
+
+        let mut delta_cursor = delta.cursor();
+        let mut tree_cursor = tree.cursor();
+
+        while delta_cursor.key_valid() {
+            let key = delta_cursor.key();
+
+            tree_cursor.seek_key(key);
+            let output = if tree_cursor.key_valid() && tree_cursor.key() == key {
+                let mut tree_partition_cursor = PartitionCursor::new(&mut tree_cursor);
+                let total = tree_partition_cursor
+                    .aggregate_range::<SaturatingSum>(&Range::new(u64::MIN, u64::MAX))
+                    .unwrap_or(0);
+
+                (total > 0).then(|| {
+                    (self.percentile)(total, &mut |row| {
+                        tree_partition_cursor.rewind_keys();
+                        tree_partition_cursor.select(row, |count| *count).unwrap()
+                    })
+                })
+            } else {
+                None
+            };
+
+            result.push((key.clone(), output));
+            delta_cursor.step_key();
+        }
+
+        result
+    }
+}
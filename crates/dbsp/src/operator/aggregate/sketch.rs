@@ -0,0 +1,572 @@
+//! Approximate aggregators based on mergeable sketches.
+//!
+//! The accumulators of these aggregators are compact summaries of a group
+//! whose size does not depend on the number of values in the group, and
+//! which can be merged using their [`Semigroup`] implementation.  Sketches
+//! cannot forget values once inserted, so they are best used with
+//! [`Stream::aggregate_append_only`](`crate::Stream::aggregate_append_only`)
+//! on streams marked with
+//! [`Stream::mark_append_only`](`crate::Stream::mark_append_only`), which
+//! keeps a single sketch per key instead of a trace of the whole input.
+//! With [`Stream::aggregate`](`crate::Stream::aggregate`) they are recomputed
+//! from the integrated input of each affected group, which supports
+//! retractions but does not save any memory.
+
+use crate::{
+    algebra::{MonoidValue, Semigroup},
+    operator::aggregate::{percentile::check_percentile, Aggregator},
+    trace::Cursor,
+    DBData, Timestamp,
+};
+use num::ToPrimitive;
+use rkyv::{Archive, Deserialize, Serialize};
+use size_of::SizeOf;
+use std::{
+    cmp::max,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+use xxhash_rust::xxh3::Xxh3;
+
+// Seed used to hash values inserted in a HyperLogLog sketch.  Differs from
+// the seed used to shard records across workers, so that the two hashes are
+// independent.
+const HLL_SEED: u64 = 0x4c1b_9a3e_d2f0_6e71u64;
+
+// Seed used to choose the values promoted by KLL compactions.
+const KLL_SEED: u64 = 0x9e37_79b9_7f4a_7c15u64;
+
+/// Returns the weight of the current key as `u64`, treating non-positive
+/// weights as zero.
+fn key_weight<V, T, R, C>(cursor: &mut C) -> u64
+where
+    C: Cursor<V, (), T, R>,
+    R: MonoidValue + ToPrimitive,
+{
+    let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+        acc.add_assign_by_ref(weight);
+        acc
+    });
+
+    weight.to_u64().unwrap_or(0)
+}
+
+fn check_precision(precision: u8) {
+    assert!(
+        (4..=16).contains(&precision),
+        "HyperLogLog precision must be between 4 and 16, found {precision}"
+    );
+}
+
+fn check_k(k: u32) {
+    assert!(k >= 8, "KLL sketch size must be at least 8, found {k}");
+}
+
+/// HyperLogLog sketch for estimating the number of distinct values in a
+/// multiset.
+///
+/// The sketch consists of `2^precision` one-byte registers and has a relative
+/// standard error of about `1.04 / sqrt(2^precision)`, e.g., 1.6% with the
+/// default precision of 12.
+#[derive(
+    Debug, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(Ord, Eq, PartialEq, PartialOrd))]
+#[archive(compare(PartialEq, PartialOrd))]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Default precision, which uses 4KiB per sketch.
+    pub const DEFAULT_PRECISION: u8 = 12;
+
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 16.
+    pub fn new(precision: u8) -> Self {
+        check_precision(precision);
+
+        Self {
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Returns the precision of the sketch.
+    pub fn precision(&self) -> u8 {
+        self.registers.len().trailing_zeros() as u8
+    }
+
+    /// Adds `value` to the sketch.
+    pub fn insert<T>(&mut self, value: &T)
+    where
+        T: Hash,
+    {
+        let mut hasher = Xxh3::with_seed(HLL_SEED);
+        value.hash(&mut hasher);
+        self.insert_hash(hasher.finish());
+    }
+
+    /// Adds a value with the given 64-bit hash to the sketch.
+    pub fn insert_hash(&mut self, hash: u64) {
+        let precision = self.precision() as u32;
+
+        // The top `precision` bits of the hash select a register; the
+        // register stores the largest position of the first 1 bit among the
+        // remaining bits.
+        let index = (hash >> (64 - precision)) as usize;
+        let rank = ((hash << precision) | (1 << (precision - 1))).leading_zeros() as u8 + 1;
+
+        self.registers[index] = max(self.registers[index], rank);
+    }
+
+    /// Merges `other` into `self`.  After merging, `self` summarizes the
+    /// union of the two multisets.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the two sketches have different precisions.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(
+            self.registers.len(),
+            other.registers.len(),
+            "cannot merge HyperLogLog sketches with different precisions"
+        );
+
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = max(*register, *other);
+        }
+    }
+
+    /// Returns the estimated number of distinct values in the sketch.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-(*register as i32)))
+            .sum();
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+
+        let estimate = alpha * m * m / sum;
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Small range correction: use linear counting.
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+}
+
+/// [`Semigroup`] that merges [`HyperLogLog`] sketches.
+#[derive(Clone)]
+pub struct HyperLogLogSemigroup;
+
+impl Semigroup<HyperLogLog> for HyperLogLogSemigroup {
+    fn combine(left: &HyperLogLog, right: &HyperLogLog) -> HyperLogLog {
+        let mut result = left.clone();
+        result.merge(right);
+        result
+    }
+}
+
+/// KLL sketch for estimating quantiles of a multiset of ordered values.
+///
+/// The sketch keeps a hierarchy of compactors: values at level `i` represent
+/// `2^i` values of the original multiset.  When a level exceeds its
+/// capacity, it is sorted and every other value is promoted to the next
+/// level.  Level capacities decrease geometrically from `k` at the top
+/// level, so the size of the sketch is `O(k)` plus a small number of values
+/// per level, and the rank error is `O(1/k)`.
+///
+/// Unlike the original randomized algorithm, the sketch derives the offset of
+/// promoted values from a hash of the number of compactions performed so far,
+/// so that the result only depends on the order of insertions and merges.
+#[derive(
+    Debug, Clone, Eq, Hash, PartialEq, Ord, PartialOrd, SizeOf, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(Ord, Eq, PartialEq, PartialOrd))]
+#[archive(bound(archive = "
+    V: Archive,
+    <V as Archive>::Archived: Ord,
+    Vec<Vec<V>>: Archive,
+    <Vec<Vec<V>> as Archive>::Archived: Ord + PartialOrd<Vec<Vec<V>>>,
+"))]
+#[archive(compare(PartialEq, PartialOrd))]
+pub struct KllSketch<V> {
+    k: u32,
+    compactions: u64,
+    levels: Vec<Vec<V>>,
+}
+
+impl<V> KllSketch<V>
+where
+    V: Ord + Clone,
+{
+    /// Default capacity of the top level, which yields a rank error of
+    /// roughly 1%.
+    pub const DEFAULT_K: u32 = 200;
+
+    /// Creates an empty sketch whose top level holds up to `k` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is less than 8.
+    pub fn new(k: u32) -> Self {
+        check_k(k);
+
+        Self {
+            k,
+            compactions: 0,
+            levels: vec![Vec::new()],
+        }
+    }
+
+    /// Returns `true` if no values have been inserted in the sketch.
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(Vec::is_empty)
+    }
+
+    /// Returns the total weight of the values summarized by the sketch.
+    pub fn total_weight(&self) -> u64 {
+        self.levels
+            .iter()
+            .enumerate()
+            .map(|(level, values)| (values.len() as u64) << level)
+            .sum()
+    }
+
+    /// Adds `value` to the sketch.
+    pub fn insert(&mut self, value: V) {
+        self.insert_weighted(value, 1);
+    }
+
+    /// Adds `weight` copies of `value` to the sketch.
+    pub fn insert_weighted(&mut self, value: V, weight: u64) {
+        // Level `i` stores values of weight `2^i`, so each bit of `weight`
+        // corresponds to one value at the matching level.
+        let mut weight = weight;
+        let mut level = 0;
+
+        while weight != 0 {
+            if weight & 1 == 1 {
+                self.level_mut(level).push(value.clone());
+            }
+            weight >>= 1;
+            level += 1;
+        }
+
+        self.compress();
+    }
+
+    /// Merges `other` into `self`.  After merging, `self` summarizes the
+    /// union of the two multisets.
+    pub fn merge(&mut self, other: &Self) {
+        for (level, values) in other.levels.iter().enumerate() {
+            self.level_mut(level).extend(values.iter().cloned());
+        }
+        self.compactions += other.compactions;
+        self.compress();
+    }
+
+    /// Returns the estimated `quantile` of the multiset, i.e., the first value
+    /// whose estimated rank is greater than or equal to `quantile` times the
+    /// total weight.  Returns `None` if the sketch is empty.
+    pub fn quantile(&self, quantile: f64) -> Option<V> {
+        let mut values: Vec<(&V, u64)> = self
+            .levels
+            .iter()
+            .enumerate()
+            .flat_map(|(level, values)| values.iter().map(move |value| (value, 1u64 << level)))
+            .collect();
+        values.sort_by(|(v1, _), (v2, _)| v1.cmp(v2));
+
+        let target = max((quantile * self.total_weight() as f64).ceil() as u64, 1);
+        let mut rank = 0;
+        for (value, weight) in values.iter() {
+            rank += weight;
+            if rank >= target {
+                return Some((*value).clone());
+            }
+        }
+
+        values.last().map(|(value, _)| (*value).clone())
+    }
+
+    fn level_mut(&mut self, level: usize) -> &mut Vec<V> {
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, Vec::new);
+        }
+        &mut self.levels[level]
+    }
+
+    fn capacity(&self, level: usize) -> usize {
+        let depth = self.levels.len() - level - 1;
+        max(
+            (self.k as f64 * (2.0f64 / 3.0).powi(depth as i32)).ceil() as usize,
+            8,
+        )
+    }
+
+    fn compress(&mut self) {
+        loop {
+            let overfull = (0..self.levels.len())
+                .find(|level| self.levels[*level].len() > self.capacity(*level));
+
+            match overfull {
+                Some(level) => self.compact(level),
+                None => break,
+            }
+        }
+    }
+
+    fn compact(&mut self, level: usize) {
+        let mut values = std::mem::take(&mut self.levels[level]);
+        values.sort();
+
+        // An odd value out stays at the current level.
+        let leftover = if values.len() % 2 == 1 {
+            values.pop()
+        } else {
+            None
+        };
+
+        let mut hasher = Xxh3::with_seed(KLL_SEED);
+        self.compactions.hash(&mut hasher);
+        let offset = (hasher.finish() & 1) as usize;
+        self.compactions += 1;
+
+        let promoted: Vec<V> = values.into_iter().skip(offset).step_by(2).collect();
+        self.levels[level].extend(leftover);
+        self.level_mut(level + 1).extend(promoted);
+    }
+}
+
+/// [`Semigroup`] that merges [`KllSketch`]es.
+#[derive(Clone)]
+pub struct KllSemigroup<V>(PhantomData<V>);
+
+impl<V> Semigroup<KllSketch<V>> for KllSemigroup<V>
+where
+    V: Ord + Clone,
+{
+    fn combine(left: &KllSketch<V>, right: &KllSketch<V>) -> KllSketch<V> {
+        let mut result = left.clone();
+        result.merge(right);
+        result
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that estimates the number
+/// of distinct values with positive weight in a group using a
+/// [`HyperLogLog`] sketch (the SQL `APPROX_COUNT_DISTINCT` aggregate).
+#[derive(Clone)]
+pub struct ApproxCountDistinct {
+    precision: u8,
+}
+
+impl ApproxCountDistinct {
+    /// Creates an aggregator that uses sketches with `2^precision` registers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `precision` is not between 4 and 16.
+    pub fn new(precision: u8) -> Self {
+        check_precision(precision);
+        Self { precision }
+    }
+}
+
+impl Default for ApproxCountDistinct {
+    fn default() -> Self {
+        Self::new(HyperLogLog::DEFAULT_PRECISION)
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for ApproxCountDistinct
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = HyperLogLog;
+    type Output = u64;
+    type Semigroup = HyperLogLogSemigroup;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut sketch = HyperLogLog::new(self.precision);
+        let mut empty = true;
+
+        while cursor.key_valid() {
+            if key_weight(cursor) > 0 {
+                sketch.insert(cursor.key());
+                empty = false;
+            }
+            cursor.step_key();
+        }
+
+        (!empty).then_some(sketch)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator.estimate().round() as u64
+    }
+}
+
+/// An [aggregator](`crate::operator::Aggregator`) that estimates a percentile
+/// of a group of values using a [`KllSketch`] (the SQL `APPROX_PERCENTILE`
+/// aggregate).
+///
+/// The weight of each value is its multiplicity.  Values with non-positive
+/// weights are ignored.
+#[derive(Clone)]
+pub struct ApproxPercentile {
+    percentile: f64,
+    k: u32,
+}
+
+impl ApproxPercentile {
+    /// Creates an aggregator that estimates the given `percentile` using
+    /// sketches of size [`KllSketch::DEFAULT_K`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1.
+    pub fn new(percentile: f64) -> Self {
+        Self::with_k(percentile, KllSketch::<()>::DEFAULT_K)
+    }
+
+    /// Creates an aggregator that estimates the given `percentile` using
+    /// sketches whose top level holds up to `k` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not between 0 and 1 or `k` is less than 8.
+    pub fn with_k(percentile: f64, k: u32) -> Self {
+        check_percentile(percentile);
+        check_k(k);
+
+        Self { percentile, k }
+    }
+}
+
+impl<V, T, R> Aggregator<V, T, R> for ApproxPercentile
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue + ToPrimitive,
+{
+    type Accumulator = KllSketch<V>;
+    type Output = V;
+    type Semigroup = KllSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut sketch = KllSketch::new(self.k);
+
+        while cursor.key_valid() {
+            let weight = key_weight(cursor);
+            if weight > 0 {
+                sketch.insert_weighted(cursor.key().clone(), weight);
+            }
+            cursor.step_key();
+        }
+
+        (!sketch.is_empty()).then_some(sketch)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+            .quantile(self.percentile)
+            .expect("aggregate never produces an empty sketch")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HyperLogLog, KllSketch};
+
+    #[test]
+    fn hyperloglog_estimate() {
+        let mut sketch = HyperLogLog::new(HyperLogLog::DEFAULT_PRECISION);
+        for i in 0..100_000u64 {
+            // Duplicates don't affect the estimate.
+            sketch.insert(&(i % 10_000));
+        }
+
+        let estimate = sketch.estimate();
+        assert!((estimate - 10_000.0).abs() < 500.0, "estimate: {estimate}");
+    }
+
+    #[test]
+    fn hyperloglog_merge() {
+        let mut left = HyperLogLog::new(10);
+        let mut right = HyperLogLog::new(10);
+        let mut union = HyperLogLog::new(10);
+
+        for i in 0..2_000u64 {
+            left.insert(&i);
+            union.insert(&i);
+        }
+        for i in 1_000..3_000u64 {
+            right.insert(&i);
+            union.insert(&i);
+        }
+
+        left.merge(&right);
+        assert_eq!(left, union);
+    }
+
+    #[test]
+    fn kll_quantiles() {
+        let mut left = KllSketch::new(KllSketch::<u64>::DEFAULT_K);
+        let mut right = KllSketch::new(KllSketch::<u64>::DEFAULT_K);
+
+        for i in 0..50_000u64 {
+            left.insert(i * 2);
+            right.insert_weighted(i * 2 + 1, 1);
+        }
+
+        left.merge(&right);
+        assert_eq!(left.total_weight(), 100_000);
+
+        for quantile in [0.0, 0.1, 0.25, 0.5, 0.75, 0.9, 1.0] {
+            let value = left.quantile(quantile).unwrap() as f64;
+            assert!(
+                (value - quantile * 100_000.0).abs() < 2_000.0,
+                "quantile {quantile}: {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn kll_weighted() {
+        let mut sketch = KllSketch::new(KllSketch::<u64>::DEFAULT_K);
+        sketch.insert_weighted(1, 1);
+        sketch.insert_weighted(2, 1_000_000);
+        sketch.insert_weighted(3, 1);
+
+        assert_eq!(sketch.total_weight(), 1_000_002);
+        assert_eq!(sketch.quantile(0.0), Some(1));
+        assert_eq!(sketch.quantile(0.5), Some(2));
+        assert_eq!(sketch.quantile(1.0), Some(3));
+    }
+}
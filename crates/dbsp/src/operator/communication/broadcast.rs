@@ -0,0 +1,109 @@
+//! Operator to replicate batches across all worker threads.
+
+use crate::{
+    circuit::GlobalNodeId,
+    circuit_cache_key,
+    operator::communication::exchange::new_exchange_operators,
+    trace::{merge_batches, Batch},
+    Circuit, Runtime, Stream,
+};
+use std::panic::Location;
+
+circuit_cache_key!(BroadcastId<C, D>(GlobalNodeId => Stream<C, D>));
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: Batch<Time = ()> + Send,
+{
+    /// Replicate all shards of a stream at every worker.
+    ///
+    /// The output stream in each worker contains a union of all input batches
+    /// across all workers.  This is useful for operators, such as range joins,
+    /// where a tuple in one input can match tuples of the other input in any
+    /// shard.  Since each worker stores a complete copy of the stream, this
+    /// operator should only be applied to relatively small streams.
+    #[track_caller]
+    pub fn broadcast(&self) -> Stream<C, B> {
+        let location = Location::caller();
+
+        match Runtime::runtime() {
+            None => self.clone(),
+            Some(runtime) => {
+                let workers = runtime.num_workers();
+
+                if workers == 1 {
+                    self.clone()
+                } else {
+                    self.circuit()
+                        .cache_get_or_insert_with(
+                            BroadcastId::new(self.origin_node_id().clone()),
+                            move || {
+                                let (sender, receiver) = new_exchange_operators(
+                                    &runtime,
+                                    Runtime::worker_index(),
+                                    Some(location),
+                                    Default::default,
+                                    move |batch: B, batches: &mut Vec<B>| {
+                                        batches.extend((0..workers).map(|_| batch.clone()))
+                                    },
+                                    |batches: &mut Vec<B>, batch: B| batches.push(batch),
+                                );
+
+                                self.circuit()
+                                    .add_exchange(sender, receiver, self)
+                                    .apply_owned_named("merge broadcast", |batches| {
+                                        merge_batches(batches)
+                                    })
+                            },
+                        )
+                        .clone()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{operator::Generator, trace::Batch, Circuit, OrdZSet, RootCircuit, Runtime};
+
+    #[test]
+    fn test_broadcast() {
+        do_test_broadcast(2);
+        do_test_broadcast(4);
+        do_test_broadcast(16);
+    }
+
+    fn test_data(worker_index: usize, num_workers: usize) -> OrdZSet<u64, i64> {
+        let tuples: Vec<_> = (0..1000)
+            .filter(|n| n % num_workers == worker_index)
+            .map(|n| (n as u64, 1i64))
+            .collect();
+        <OrdZSet<u64, i64>>::from_keys((), tuples)
+    }
+
+    fn do_test_broadcast(workers: usize) {
+        let hruntime = Runtime::run(workers, || {
+            let circuit = RootCircuit::build(move |circuit| {
+                let input = circuit.add_source(Generator::new(|| {
+                    let worker_index = Runtime::worker_index();
+                    let num_workers = Runtime::runtime().unwrap().num_workers();
+                    test_data(worker_index, num_workers)
+                }));
+                input
+                    .broadcast()
+                    .inspect(|batch: &OrdZSet<u64, i64>| assert_eq!(batch, &test_data(0, 1)));
+                Ok(())
+            })
+            .unwrap()
+            .0;
+
+            for _ in 0..3 {
+                circuit.step().unwrap();
+            }
+        });
+
+        hruntime.join().unwrap();
+    }
+}
@@ -332,7 +332,10 @@ pub(crate) struct Exchange<T> {
     mailboxes: Arc<Vec<Mutex<Option<T>>>>,
 }
 
-struct ExchangeListener(DropGuard);
+/// Stops the listener task when dropped.
+struct ExchangeListener {
+    _drop: DropGuard,
+}
 
 impl ExchangeListener {
     fn new(address: SocketAddr, directory: ExchangeDirectory) -> Self {
@@ -356,7 +359,7 @@ impl ExchangeListener {
                 _ = token.cancelled() => {}
             }
         });
-        Self(drop)
+        Self { _drop: drop }
     }
 }
 
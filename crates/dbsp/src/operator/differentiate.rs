@@ -5,6 +5,7 @@ use crate::{
     circuit::{Circuit, GlobalNodeId, Stream},
     circuit_cache_key,
     operator::{integrate::IntegralId, Minus},
+    trace::Rkyv,
     NumEntries,
 };
 use size_of::SizeOf;
@@ -15,7 +16,7 @@ circuit_cache_key!(NestedDifferentiateId<C, D>(GlobalNodeId => Stream<C, D>));
 impl<C, D> Stream<C, D>
 where
     C: Circuit + 'static,
-    D: SizeOf + NumEntries + GroupValue,
+    D: SizeOf + NumEntries + GroupValue + Rkyv,
 {
     /// Stream differentiation.
     ///
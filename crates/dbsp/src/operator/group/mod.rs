@@ -18,6 +18,7 @@ use std::{borrow::Cow, marker::PhantomData, ops::Neg};
 
 mod custom_ord;
 mod lag;
+mod rows_aggregate;
 mod topk;
 
 #[cfg(test)]
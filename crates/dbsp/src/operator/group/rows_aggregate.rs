@@ -0,0 +1,384 @@
+use super::{
+    custom_ord::{CmpFunc, WithCustomOrd},
+    GroupTransformer, Monotonicity,
+};
+use crate::{
+    algebra::{Semigroup, ZRingValue},
+    operator::FilterMap,
+    trace::{consolidation::consolidate, cursor::CursorPair, Cursor},
+    utils::Tup2,
+    DBData, DBWeight, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::marker::PhantomData;
+
+impl<K, V, R> Stream<RootCircuit, OrdIndexedZSet<K, V, R>>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight + ZRingValue,
+{
+    /// Compute an aggregate over a sliding frame of rows in each group,
+    /// ordering values within the group based on a custom comparison
+    /// function.
+    ///
+    /// This operator implements the behavior of the following SQL pattern:
+    ///
+    /// ```text
+    /// SELECT
+    ///     ...,
+    ///     AGG(...) OVER (
+    ///         PARTITION BY .. ORDER BY ...
+    ///         ROWS BETWEEN `preceding` PRECEDING AND `following` FOLLOWING)
+    /// FROM table
+    /// ```
+    ///
+    /// For each value `v` in the group, applies `agg_func` to every row in
+    /// the frame of `v`, combines the results using semigroup `S`, and
+    /// outputs `output_func(v, aggregate)`.  The frame of a row consists of
+    /// up to `preceding` rows before it, the row itself, and up to
+    /// `following` rows after it.  A value with weight `w > 1` occupies `w`
+    /// consecutive rows, each of which produces an output.  Values with
+    /// non-positive weights are ignored.
+    ///
+    /// When a value is inserted or deleted, the operator only recomputes and
+    /// re-emits outputs for values whose frames include a changed row.
+    ///
+    /// ## Correctness
+    ///
+    /// * `CF` must establish a _total_ order over `V`, consistent with `impl Eq
+    ///   for V`, i.e., `CF::cmp(v1, v2) == Equal <=> v1.eq(v2)`.
+    pub fn rows_aggregate_custom_order<CF, S, A, AF, OF, OV>(
+        &self,
+        preceding: usize,
+        following: usize,
+        agg_func: AF,
+        output_func: OF,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<K, OV, R>>
+    where
+        CF: CmpFunc<V>,
+        S: Semigroup<A> + 'static,
+        A: Clone + 'static,
+        AF: Fn(&V) -> A + 'static,
+        OF: Fn(&V, A) -> OV + 'static,
+        OV: DBData,
+        i64: From<R>,
+    {
+        self.map_index(|(k, v)| (k.clone(), <WithCustomOrd<V, CF>>::new(v.clone())))
+            .group_transform(<RowsAggregate<_, _, S, _, _, _, _>>::new(
+                preceding,
+                following,
+                move |v: &WithCustomOrd<V, CF>| agg_func(&v.val),
+                move |v: &WithCustomOrd<V, CF>, aggregate| output_func(&v.val, aggregate),
+            ))
+            .map_index(|(k, Tup2(_, v))| (k.clone(), v.clone()))
+    }
+}
+
+/// Number of rows occupied by the current key of the cursor.
+fn row_count<I, R, C>(cursor: &mut C) -> usize
+where
+    C: Cursor<I, (), (), R>,
+    i64: From<R>,
+{
+    i64::from(cursor.weight()).max(0) as usize
+}
+
+/// Moves `cursor` to the last key smaller than `key`.  Leaves the cursor
+/// invalid if there is no such key.
+fn seek_before<I, R, C>(cursor: &mut C, key: &I)
+where
+    I: Ord,
+    C: Cursor<I, (), (), R>,
+{
+    cursor.fast_forward_keys();
+    cursor.seek_key_with_reverse(|k| k < key);
+}
+
+/// Moves `cursor` backward, starting from the current key, until it has
+/// seen `rows` rows.  Returns the key containing the last of these rows
+/// or `None` if the start of the group was reached first.
+///
+/// `rows` must be greater than zero.
+fn rows_backward<I, R, C>(cursor: &mut C, rows: usize) -> Option<I>
+where
+    I: Clone,
+    C: Cursor<I, (), (), R>,
+    i64: From<R>,
+{
+    debug_assert!(rows > 0);
+
+    let mut seen = 0;
+    while cursor.key_valid() {
+        seen += row_count(cursor);
+        if seen >= rows {
+            return Some(cursor.key().clone());
+        }
+        cursor.step_key_reverse();
+    }
+
+    None
+}
+
+/// Like [`rows_backward`], but moves the cursor forward.
+fn rows_forward<I, R, C>(cursor: &mut C, rows: usize) -> Option<I>
+where
+    I: Clone,
+    C: Cursor<I, (), (), R>,
+    i64: From<R>,
+{
+    debug_assert!(rows > 0);
+
+    let mut seen = 0;
+    while cursor.key_valid() {
+        seen += row_count(cursor);
+        if seen >= rows {
+            return Some(cursor.key().clone());
+        }
+        cursor.step_key();
+    }
+
+    None
+}
+
+/// Incremental group transformer that computes an aggregate over a frame
+/// of `preceding` rows before and `following` rows after each row.
+///
+/// Outputs `Tup2(v, output_func(v, aggregate))` for each value `v` in the
+/// group.
+///
+/// A change to the value `v` affects the outputs of all values within
+/// `following` rows before `v` and `preceding` rows after it.  For each
+/// change in the input delta, we compute the range of values it affects
+/// in the new contents of the group, merge overlapping ranges, retract the
+/// old outputs for each range, and recompute the new outputs from the rows
+/// in the range plus the rows in the frames at its boundaries.
+struct RowsAggregate<I, A, S, R, AF, OF, OV> {
+    name: String,
+    preceding: usize,
+    following: usize,
+    agg_func: AF,
+    output_func: OF,
+    /// Output updates, reused across multiple invocations of the operator.
+    updates: Vec<(Tup2<I, OV>, R)>,
+    _phantom: PhantomData<(A, S)>,
+}
+
+impl<I, A, S, R, AF, OF, OV> RowsAggregate<I, A, S, R, AF, OF, OV> {
+    fn new(preceding: usize, following: usize, agg_func: AF, output_func: OF) -> Self {
+        Self {
+            name: format!("rows-aggregate({preceding}, {following})"),
+            preceding,
+            following,
+            agg_func,
+            output_func,
+            updates: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<I, A, S, R, AF, OF, OV> RowsAggregate<I, A, S, R, AF, OF, OV>
+where
+    I: DBData,
+    A: Clone,
+    S: Semigroup<A>,
+    R: DBWeight + ZRingValue,
+    AF: Fn(&I) -> A,
+    OF: Fn(&I, A) -> OV,
+    OV: DBData,
+    i64: From<R>,
+{
+    /// Computes the range of values whose outputs are affected by a change
+    /// to `key`.  `None` denotes an unbounded range end.
+    fn affected_range<C>(&self, input: &mut C, key: &I) -> (Option<I>, Option<I>)
+    where
+        C: Cursor<I, (), (), R>,
+    {
+        let lower = if self.following == 0 {
+            Some(key.clone())
+        } else {
+            seek_before(input, key);
+            rows_backward(input, self.following)
+        };
+
+        let upper = if self.preceding == 0 {
+            Some(key.clone())
+        } else {
+            input.rewind_keys();
+            input.seek_key_with(|k| k > key);
+            rows_forward(input, self.preceding)
+        };
+
+        (lower, upper)
+    }
+
+    /// Retracts the outputs for all values in the `[lower, upper]` range.
+    fn retract_range<C>(&mut self, output_trace: &mut C, lower: &Option<I>, upper: &Option<I>)
+    where
+        C: Cursor<Tup2<I, OV>, (), (), R>,
+    {
+        if let Some(lower) = lower {
+            output_trace.seek_key_with(|Tup2(v, _)| v >= lower);
+        }
+
+        while output_trace.key_valid() {
+            let w = output_trace.weight();
+            let key = output_trace.key();
+            if matches!(upper, Some(upper) if &key.0 > upper) {
+                break;
+            }
+            if !w.is_zero() {
+                self.updates.push((key.clone(), w.neg()));
+            }
+            output_trace.step_key();
+        }
+    }
+
+    /// Computes outputs for all values in the `[lower, upper]` range.
+    fn compute_range<C>(&mut self, input: &mut C, lower: &Option<I>, upper: &Option<I>)
+    where
+        C: Cursor<I, (), (), R>,
+    {
+        // Find the first value in the frame of the first row in the range.
+        let start = match lower {
+            None => None,
+            Some(lower) if self.preceding == 0 => Some(lower.clone()),
+            Some(lower) => {
+                seek_before(input, lower);
+                rows_backward(input, self.preceding)
+            }
+        };
+
+        input.rewind_keys();
+        if let Some(start) = &start {
+            input.seek_key(start);
+        }
+
+        // Collect all rows in the range along with the frames at its
+        // boundaries.
+        let mut values = Vec::new();
+        let mut aggregates = Vec::new();
+        let mut rows = Vec::new();
+        let mut rows_after = 0;
+
+        while input.key_valid() {
+            let count = row_count(input);
+            let key = input.key();
+
+            if matches!(upper, Some(upper) if key > upper) {
+                if rows_after >= self.following {
+                    break;
+                }
+                rows_after += count;
+            }
+
+            if count > 0 {
+                rows.resize(rows.len() + count, values.len());
+                aggregates.push((self.agg_func)(key));
+                values.push(key.clone());
+            }
+
+            input.step_key();
+        }
+
+        let in_range = |v: &I| {
+            !matches!(lower, Some(lower) if v < lower) && !matches!(upper, Some(upper) if v > upper)
+        };
+
+        for (row, &index) in rows.iter().enumerate() {
+            let value = &values[index];
+            if !in_range(value) {
+                continue;
+            }
+
+            let frame_start = row.saturating_sub(self.preceding);
+            let frame_end = (row + self.following).min(rows.len() - 1);
+
+            let mut aggregate = aggregates[rows[frame_start]].clone();
+            for &i in &rows[frame_start + 1..=frame_end] {
+                aggregate = S::combine(&aggregate, &aggregates[i]);
+            }
+
+            self.updates.push((
+                Tup2(value.clone(), (self.output_func)(value, aggregate)),
+                R::one(),
+            ));
+        }
+    }
+}
+
+impl<I, A, S, R, AF, OF, OV> GroupTransformer<I, Tup2<I, OV>, R>
+    for RowsAggregate<I, A, S, R, AF, OF, OV>
+where
+    I: DBData,
+    A: Clone + 'static,
+    S: Semigroup<A> + 'static,
+    R: DBWeight + ZRingValue,
+    AF: Fn(&I) -> A + 'static,
+    OF: Fn(&I, A) -> OV + 'static,
+    OV: DBData,
+    i64: From<R>,
+{
+    fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        // Updates are consolidated before being emitted.
+        Monotonicity::Ascending
+    }
+
+    fn transform<C1, C2, C3, CB>(
+        &mut self,
+        input_delta: &mut C1,
+        input_trace: &mut C2,
+        output_trace: &mut C3,
+        mut output_cb: CB,
+    ) where
+        C1: Cursor<I, (), (), R>,
+        C2: Cursor<I, (), (), R>,
+        C3: Cursor<Tup2<I, OV>, (), (), R>,
+        CB: FnMut(Tup2<I, OV>, R),
+    {
+        let mut changes = Vec::new();
+        while input_delta.key_valid() {
+            changes.push(input_delta.key().clone());
+            input_delta.step_key();
+        }
+        input_delta.rewind_keys();
+
+        // New contents of the group.
+        let mut input = CursorPair::new(input_delta, input_trace);
+
+        // Compute ranges of affected values in ascending order, merging
+        // overlapping ranges.
+        let mut ranges: Vec<(Option<I>, Option<I>)> = Vec::new();
+        for key in changes.iter() {
+            let (lower, upper) = self.affected_range(&mut input, key);
+
+            // Both ends of the range are monotonic in `key`, so the new
+            // range either extends the last one or starts after it.
+            if let Some((_, last_upper)) = ranges.last_mut() {
+                let overlaps = match (&*last_upper, &lower) {
+                    (Some(last_upper), Some(lower)) => lower <= last_upper,
+                    _ => true,
+                };
+                if overlaps {
+                    *last_upper = upper;
+                    continue;
+                }
+            }
+            ranges.push((lower, upper));
+        }
+
+        for (lower, upper) in ranges.iter() {
+            self.retract_range(output_trace, lower, upper);
+            self.compute_range(&mut input, lower, upper);
+        }
+
+        consolidate(&mut self.updates);
+        for (output, w) in self.updates.drain(..) {
+            output_cb(output, w);
+        }
+    }
+}
@@ -2,12 +2,16 @@
 
 use std::cmp::Ordering;
 
+use super::{
+    custom_ord::WithCustomOrd, DiffGroupTransformer, Monotonicity, NonIncrementalGroupTransformer,
+};
 use crate::utils::{Tup3, Tup4};
 use crate::{
-    algebra::ZRingValue,
+    algebra::{DefaultSemigroup, ZRingValue},
     indexed_zset,
-    operator::CmpFunc,
+    operator::{CmpFunc, FilterMap},
     trace::{
+        consolidation::consolidate,
         cursor::Cursor,
         test_batch::{assert_batch_eq, TestBatch},
         BatchReader, Trace,
@@ -249,6 +253,102 @@ fn lead_test_circuit(
     Ok((input_handle, lead_handle))
 }
 
+// Sort values in descending order.
+struct Desc;
+
+impl CmpFunc<i32> for Desc {
+    fn cmp(left: &i32, right: &i32) -> Ordering {
+        right.cmp(left)
+    }
+}
+
+/// Non-incremental implementation of `SUM` over a frame of rows, used as a
+/// reference for `rows_aggregate_custom_order`.
+struct RowsSum {
+    preceding: usize,
+    following: usize,
+}
+
+impl
+    NonIncrementalGroupTransformer<
+        WithCustomOrd<i32, Desc>,
+        Tup2<WithCustomOrd<i32, Desc>, Tup2<i32, i64>>,
+        i32,
+    > for RowsSum
+{
+    fn name(&self) -> &str {
+        "rows-sum"
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Ascending
+    }
+
+    fn transform<C, CB>(&mut self, cursor: &mut C, mut output_cb: CB)
+    where
+        C: Cursor<WithCustomOrd<i32, Desc>, (), (), i32>,
+        CB: FnMut(Tup2<WithCustomOrd<i32, Desc>, Tup2<i32, i64>>, i32),
+    {
+        let mut rows = Vec::new();
+        while cursor.key_valid() {
+            for _ in 0..cursor.weight().max(0) {
+                rows.push(cursor.key().clone());
+            }
+            cursor.step_key();
+        }
+
+        let mut outputs = Vec::new();
+        for (row, value) in rows.iter().enumerate() {
+            let frame_start = row.saturating_sub(self.preceding);
+            let frame_end = (row + self.following).min(rows.len() - 1);
+            let sum: i64 = rows[frame_start..=frame_end]
+                .iter()
+                .map(|v| v.val as i64)
+                .sum();
+            outputs.push((Tup2(value.clone(), Tup2(value.val, sum)), 1));
+        }
+
+        consolidate(&mut outputs);
+        for (output, w) in outputs.into_iter() {
+            output_cb(output, w);
+        }
+    }
+}
+
+fn rows_aggregate_test_circuit(
+    circuit: &mut RootCircuit,
+    preceding: usize,
+    following: usize,
+) -> AnyResult<(
+    CollectionHandle<i32, Tup2<i32, i32>>,
+    OutputHandle<OrdIndexedZSet<i32, Tup2<i32, i64>, i32>>,
+    OutputHandle<OrdIndexedZSet<i32, Tup2<i32, i64>, i32>>,
+)> {
+    let (input_stream, input_handle) = circuit.add_input_indexed_zset::<i32, i32, i32>();
+
+    let rows_sum_handle = input_stream
+        .rows_aggregate_custom_order::<Desc, DefaultSemigroup<i64>, _, _, _, _>(
+            preceding,
+            following,
+            |v| *v as i64,
+            |v, sum| Tup2(*v, sum),
+        )
+        .integrate()
+        .output();
+
+    let expected_handle = input_stream
+        .map_index(|(k, v)| (*k, <WithCustomOrd<i32, Desc>>::new(*v)))
+        .group_transform(DiffGroupTransformer::new(RowsSum {
+            preceding,
+            following,
+        }))
+        .map_index(|(k, Tup2(_, v))| (*k, *v))
+        .integrate()
+        .output();
+
+    Ok((input_handle, rows_sum_handle, expected_handle))
+}
+
 #[test]
 fn test_topk_custom_ord() {
     let (
@@ -499,4 +599,22 @@ proptest! {
             assert_batch_eq(&lead_result, &ref_lead);
         }
     }
+
+    #[test]
+    fn test_rows_aggregate(trace in input_trace(5, 100, 200, 20), preceding in 0..4usize, following in 0..4usize) {
+        let (mut dbsp, (input_handle, rows_sum_handle, expected_handle)) =
+            Runtime::init_circuit(4, move |circuit| rows_aggregate_test_circuit(circuit, preceding, following)).unwrap();
+
+        for batch in trace.into_iter() {
+            for (k, v, r) in batch.into_iter() {
+                input_handle.push(k, Tup2(v, r));
+            }
+            dbsp.step().unwrap();
+
+            let rows_sum_result = rows_sum_handle.consolidate();
+            let expected = expected_handle.consolidate();
+
+            assert_eq!(rows_sum_result, expected);
+        }
+    }
 }
@@ -9,6 +9,7 @@ use crate::{
         z1::{DelayedFeedback, DelayedNestedFeedback},
         Plus,
     },
+    trace::Rkyv,
     NumEntries,
 };
 use size_of::SizeOf;
@@ -28,6 +29,7 @@ where
         + HasZero
         + SizeOf
         + NumEntries
+        + Rkyv
         + 'static,
 {
     /// Integrate the input stream.
@@ -756,42 +756,38 @@ where
 
         let time = self.clock.time();
 
-        while index_cursor.key_valid() && trace_cursor.key_valid() {
-            match index_cursor.key().cmp(trace_cursor.key()) {
-                Ordering::Less => index_cursor.seek_key(trace_cursor.key()),
-                Ordering::Greater => trace_cursor.seek_key(index_cursor.key()),
-                Ordering::Equal => {
-                    //println!("key: {}", index_cursor.key(index));
-
-                    while index_cursor.val_valid() {
-                        let w1 = index_cursor.weight();
-                        let v1 = index_cursor.val();
-                        //println!("v1: {}, w1: {}", v1, w1);
-
-                        while trace_cursor.val_valid() {
-                            let output =
-                                (self.join_func)(index_cursor.key(), v1, trace_cursor.val());
-                            for (k, v) in output {
-                                trace_cursor.map_times(|ts, w2| {
-                                    output_tuples.push((
-                                        ts.join(&time),
-                                        MaybeUninit::new((
-                                            Z::item_from(k.clone(), v.clone()),
-                                            w1.mul_by_ref(w2),
-                                        )),
-                                    ));
-                                });
-                            }
-                            trace_cursor.step_val();
+        // The trace is typically much larger than `index`, so look up each key
+        // in `index` individually, which allows batches in the trace to skip
+        // absent keys cheaply (e.g. using a Bloom filter).
+        while index_cursor.key_valid() {
+            if trace_cursor.seek_key_exact(index_cursor.key()) {
+                //println!("key: {}", index_cursor.key(index));
+
+                while index_cursor.val_valid() {
+                    let w1 = index_cursor.weight();
+                    let v1 = index_cursor.val();
+                    //println!("v1: {}, w1: {}", v1, w1);
+
+                    while trace_cursor.val_valid() {
+                        let output = (self.join_func)(index_cursor.key(), v1, trace_cursor.val());
+                        for (k, v) in output {
+                            trace_cursor.map_times(|ts, w2| {
+                                output_tuples.push((
+                                    ts.join(&time),
+                                    MaybeUninit::new((
+                                        Z::item_from(k.clone(), v.clone()),
+                                        w1.mul_by_ref(w2),
+                                    )),
+                                ));
+                            });
                         }
-                        trace_cursor.rewind_vals();
-                        index_cursor.step_val();
+                        trace_cursor.step_val();
                     }
-
-                    index_cursor.step_key();
-                    trace_cursor.step_key();
+                    trace_cursor.rewind_vals();
+                    index_cursor.step_val();
                 }
             }
+            index_cursor.step_key();
         }
 
         self.stats.output_tuples += output_tuples.len();
@@ -13,12 +13,16 @@
 //! * For each `((k1, v1), w1)` in `z1` and `((k2, v2), w2)` in `z2` where `k2 ∈
 //!   join_range(k1)`, add all values in `join_func(k1,v1,k2,v2)` to the output
 //!   batch with weight `w1 * w2`.
+//!
+//! The incremental versions of the operator additionally require `range_func`
+//! to be monotone, i.e., `k1 <= k1'` must imply `lower <= lower'` and `upper <=
+//! upper'`, as is the case for band joins such as `|k| (k - 5, k + 6)`.
 
 use crate::{
-    algebra::{MulByRef, ZRingValue},
+    algebra::{IndexedZSet, MulByRef, ZRingValue},
     circuit::{
         operator_traits::{BinaryOperator, Operator},
-        Circuit, Scope, Stream,
+        Circuit, RootCircuit, Scope, Stream,
     },
     trace::{cursor::Cursor, Batch, BatchReader},
     DBData, OrdIndexedZSet, OrdZSet,
@@ -105,6 +109,112 @@ where
     }
 }
 
+impl<I1> Stream<RootCircuit, I1>
+where
+    I1: IndexedZSet + Send,
+{
+    /// Incrementally range-join two streams of batches into an `OrdZSet`
+    /// according to the definition of the [range-join
+    /// operator](crate::operator::join_range).
+    ///
+    /// Given streams `self` and `other` of batches that represent changes to
+    /// relations `A` and `B` respectively, computes a stream of changes to the
+    /// range-join of `A` and `B`:
+    ///
+    /// ```text
+    /// delta(A ⋈ B) = a ⋈ B + z^-1(A) ⋈ b
+    /// ```
+    ///
+    /// The first term joins the keys in the current batch of changes `a`
+    /// against the integral `B` of the other input, and the second term finds,
+    /// for each key in the current batch of changes `b`, the keys in the
+    /// previous integral `z^-1(A)` whose ranges contain it.  The latter
+    /// requires `range_func` to be monotone.
+    ///
+    /// This method only works in the top-level scope.  A key in `self` can
+    /// match keys of `other` at any worker, so `self` is sharded on key while
+    /// `other` is [broadcast](`Stream::broadcast`) to all workers, each of
+    /// which keeps a complete copy of its integral.  Use the smaller of the
+    /// two relations as `other`.
+    pub fn join_range<RF, JF, It, I2>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, OrdZSet<It::Item, I1::R>>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        I1::R: ZRingValue,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator + 'static,
+        It::Item: DBData,
+    {
+        self.join_range_generic(other, range_func, move |k1, v1, k2, v2| {
+            join_func(k1, v1, k2, v2).into_iter().map(|k| (k, ()))
+        })
+    }
+
+    /// Incrementally range-join two streams into an `OrdIndexedZSet`.
+    ///
+    /// See [`Self::join_range`] for details.  In this version of the
+    /// operator, the `join_func` closure returns an iterator over `(key,
+    /// value)` pairs used to assemble the output indexed Z-set.
+    pub fn join_range_index<RF, JF, It, K, V, I2>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, OrdIndexedZSet<K, V, I1::R>>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        I1::R: ZRingValue,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        K: DBData,
+        V: DBData,
+        It: IntoIterator<Item = (K, V)> + 'static,
+    {
+        self.join_range_generic(other, range_func, join_func)
+    }
+
+    /// Like [`Self::join_range`], but can return any indexed Z-set type.
+    pub fn join_range_generic<RF, JF, It, I2, O>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        range_func: RF,
+        join_func: JF,
+    ) -> Stream<RootCircuit, O>
+    where
+        I2: IndexedZSet<R = I1::R> + Send,
+        O: IndexedZSet<R = I1::R>,
+        O::R: ZRingValue,
+        RF: Fn(&I1::Key) -> (I2::Key, I2::Key) + Clone + 'static,
+        JF: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator<Item = (O::Key, O::Val)> + 'static,
+    {
+        // A key in `self` can match keys in `other` at any worker, so we can't
+        // shard both inputs on key.  Instead, each worker joins its shard of
+        // `self` against all of `other`.  Since the join is bilinear, the sum of
+        // the outputs of all workers is the join of the complete inputs.
+        let left = self.shard();
+        let right = other.broadcast();
+
+        let left_trace = left.integrate_trace();
+        let right_trace = right.integrate_trace();
+
+        let delta_trace =
+            left.stream_join_range_generic(&right_trace, range_func.clone(), join_func.clone());
+        let trace_delta = self.circuit().add_binary_operator(
+            TraceJoinRange::new(range_func, join_func),
+            &left_trace.delay_trace(),
+            &right,
+        );
+
+        delta_trace.plus(&trace_delta)
+    }
+}
+
 pub struct StreamJoinRange<RF, JF, It, I1, I2, O> {
     range_func: RF,
     join_func: JF,
@@ -194,10 +304,111 @@ where
     }
 }
 
+/// Range-joins a trace of the left input against a batch of the right input.
+///
+/// Unlike [`StreamJoinRange`], which looks up the range of each key in the
+/// left input, this operator looks up the keys in the left input whose ranges
+/// contain each key in the right input.  This requires `range_func` to be
+/// monotone, so that those keys are contiguous.
+pub struct TraceJoinRange<RF, JF, It, T, I2, O> {
+    range_func: RF,
+    join_func: JF,
+    _types: PhantomData<(It, T, I2, O)>,
+}
+
+impl<RF, JF, It, T, I2, O> TraceJoinRange<RF, JF, It, T, I2, O> {
+    pub fn new(range_func: RF, join_func: JF) -> Self {
+        Self {
+            range_func,
+            join_func,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<RF, JF, It, T, I2, O> Operator for TraceJoinRange<RF, JF, It, T, I2, O>
+where
+    T: 'static,
+    I2: 'static,
+    It: 'static,
+    RF: 'static,
+    JF: 'static,
+    O: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("TraceJoinRange")
+    }
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<RF, JF, It, T, I2, O> BinaryOperator<T, I2, O> for TraceJoinRange<RF, JF, It, T, I2, O>
+where
+    T: BatchReader<Time = (), R = O::R> + Clone,
+    I2: BatchReader<Time = (), R = O::R> + Clone,
+    O: Batch<Time = ()>,
+    O::R: ZRingValue,
+    RF: Fn(&T::Key) -> (I2::Key, I2::Key) + 'static,
+    JF: Fn(&T::Key, &T::Val, &I2::Key, &I2::Val) -> It + 'static,
+    It: IntoIterator<Item = (O::Key, O::Val)> + 'static,
+{
+    fn eval(&mut self, trace: &T, delta: &I2) -> O {
+        let mut tuples = Vec::new();
+        let mut trace_cursor = trace.cursor();
+        let mut delta_cursor = delta.cursor();
+
+        // For each key in `delta`.
+        while delta_cursor.key_valid() {
+            // Since `range_func` is monotone, the keys in `trace` whose ranges
+            // contain `k2` start at the first key whose range ends after `k2`
+            // and end before the first key whose range starts after `k2`.
+            let k2 = delta_cursor.key();
+            trace_cursor.rewind_keys();
+            trace_cursor.seek_key_with(|k1| &(self.range_func)(k1).1 > k2);
+
+            while trace_cursor.key_valid()
+                && &(self.range_func)(trace_cursor.key()).0 <= delta_cursor.key()
+            {
+                // Iterate over all pairs of values in `trace` and `delta`.
+                trace_cursor.rewind_vals();
+                while trace_cursor.val_valid() {
+                    let w1 = trace_cursor.weight();
+                    let k1 = trace_cursor.key();
+                    let v1 = trace_cursor.val();
+                    delta_cursor.rewind_vals();
+
+                    while delta_cursor.val_valid() {
+                        let w2 = delta_cursor.weight();
+                        let w = w1.mul_by_ref(&w2);
+
+                        // Add all `(k,v)` tuples output by `join_func` to the output batch.
+                        for (k, v) in
+                            (self.join_func)(k1, v1, delta_cursor.key(), delta_cursor.val())
+                        {
+                            tuples.push((O::item_from(k, v), w.clone()));
+                        }
+                        delta_cursor.step_val();
+                    }
+                    trace_cursor.step_val();
+                }
+                trace_cursor.step_key();
+            }
+            delta_cursor.step_key();
+        }
+
+        O::from_tuples((), tuples)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::utils::Tup2;
-    use crate::{operator::Generator, zset, Circuit, RootCircuit};
+    use crate::{
+        operator::Generator, trace::Batch, zset, Circuit, OrdIndexedZSet, RootCircuit, Runtime,
+    };
+    use anyhow::Result as AnyResult;
+    use proptest::{collection, prelude::*};
 
     #[test]
     fn stream_join_range_test() {
@@ -297,4 +508,109 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    type TestBatch = OrdIndexedZSet<i32, i32, i64>;
+
+    const MAX_STEPS: usize = 20;
+    const MAX_KEY: i32 = 20;
+    const MAX_VAL: i32 = 3;
+    const MAX_TUPLES: usize = 10;
+
+    fn test_batch() -> impl Strategy<Value = TestBatch> {
+        collection::vec(((0..MAX_KEY, 0..MAX_VAL), -1..=1i64), 0..MAX_TUPLES).prop_map(|tuples| {
+            TestBatch::from_tuples(
+                (),
+                tuples
+                    .into_iter()
+                    .map(|((k, v), w)| (TestBatch::item_from(k, v), w))
+                    .collect(),
+            )
+        })
+    }
+
+    fn test_input() -> impl Strategy<Value = Vec<(TestBatch, TestBatch)>> {
+        collection::vec((test_batch(), test_batch()), 0..MAX_STEPS)
+    }
+
+    /// Checks that the integral of the output of `join_range` equals the
+    /// output of `stream_join_range` over the integrals of its inputs.
+    fn join_range_test_circuit(
+        circuit: &mut RootCircuit,
+        inputs: Vec<(TestBatch, TestBatch)>,
+    ) -> AnyResult<()> {
+        let (inputs1, inputs2): (Vec<_>, Vec<_>) = inputs.into_iter().unzip();
+        let mut inputs1 = inputs1.into_iter();
+        let mut inputs2 = inputs2.into_iter();
+
+        let index1 = circuit.add_source(Generator::new(move || {
+            if Runtime::worker_index() == 0 {
+                inputs1.next().unwrap()
+            } else {
+                TestBatch::empty(())
+            }
+        }));
+        let index2 = circuit.add_source(Generator::new(move || {
+            if Runtime::worker_index() == 0 {
+                inputs2.next().unwrap()
+            } else {
+                TestBatch::empty(())
+            }
+        }));
+
+        // A band join, and a join whose ranges are empty for odd keys.
+        type RangeFunc = fn(&i32) -> (i32, i32);
+        let range_funcs: [RangeFunc; 2] = [|&k| (k - 2, k + 3), |&k| (k, k + 1 - k % 2)];
+
+        for range_func in range_funcs {
+            let output = index1
+                .join_range(&index2, range_func, |&k1, &v1, &k2, &v2| {
+                    Some(Tup2(Tup2(k1, v1), Tup2(k2, v2)))
+                })
+                .gather(0)
+                .integrate();
+            let expected = index1.integrate().stream_join_range(
+                &index2.integrate(),
+                range_func,
+                |&k1, &v1, &k2, &v2| Some(Tup2(Tup2(k1, v1), Tup2(k2, v2))),
+            );
+            output.apply2(&expected, |output, expected| assert_eq!(output, expected));
+
+            let output_index = index1
+                .join_range_index(&index2, range_func, |&k1, &v1, &k2, &v2| {
+                    Some((k1, Tup2(Tup2(k1, v1), Tup2(k2, v2))))
+                })
+                .gather(0)
+                .integrate();
+            output
+                .index_with(|Tup2(Tup2(k1, v1), kv2)| Tup2(*k1, Tup2(Tup2(*k1, *v1), *kv2)))
+                .apply2(&output_index, |output, output_index| {
+                    assert_eq!(output, output_index)
+                });
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn proptest_join_range_st(inputs in test_input()) {
+            let steps = inputs.len();
+            let circuit = RootCircuit::build(|circuit| join_range_test_circuit(circuit, inputs)).unwrap().0;
+
+            for _ in 0..steps {
+                circuit.step().unwrap();
+            }
+        }
+
+        #[test]
+        fn proptest_join_range_mt(inputs in test_input(), workers in (2..=4usize)) {
+            let steps = inputs.len();
+            let mut circuit = Runtime::init_circuit(workers, |circuit| join_range_test_circuit(circuit, inputs)).unwrap().0;
+
+            for _ in 0..steps {
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+    }
 }
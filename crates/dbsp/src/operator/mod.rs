@@ -41,7 +41,11 @@ mod z1;
 
 #[cfg(feature = "with-csv")]
 pub use self::csv::CsvSource;
-pub use aggregate::{Aggregator, Avg, Fold, Max, MaxSemigroup, Min, MinSemigroup};
+pub use aggregate::{
+    Aggregator, ApproxCountDistinct, ApproxPercentile, Avg, Fold, HyperLogLog,
+    HyperLogLogSemigroup, KllSemigroup, KllSketch, Max, MaxSemigroup, Median, Min, MinSemigroup,
+    Mode, PercentileCont, PercentileDisc,
+};
 pub use apply::Apply;
 pub use condition::Condition;
 pub use delta0::Delta0;
@@ -54,7 +58,7 @@ use input::Mailbox;
 pub use input::{CollectionHandle, InputHandle, Update, UpsertHandle};
 pub use inspect::Inspect;
 pub use join::Join;
-pub use join_range::StreamJoinRange;
+pub use join_range::{StreamJoinRange, TraceJoinRange};
 pub use neg::UnaryMinus;
 pub use neighborhood::{Neighborhood, NeighborhoodDescr};
 pub use output::OutputHandle;
@@ -70,7 +70,21 @@ pub type Neighborhood<K, V, R> = OrdZSet<Tup2<i64, Tup2<K, V>>, R>;
 ///
 /// The `anchor` value of `None` is equivalent to specifying the
 /// smallest value of type `K`.
-#[derive(Clone, Debug, Default, Deserialize, PartialOrd, Ord, PartialEq, Eq, Hash, SizeOf)]
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    Deserialize,
+    PartialOrd,
+    Ord,
+    PartialEq,
+    Eq,
+    Hash,
+    SizeOf,
+    rkyv::Archive,
+    rkyv::Serialize,
+    rkyv::Deserialize,
+)]
 pub struct NeighborhoodDescr<K, V> {
     pub anchor: Option<K>,
     #[serde(default)]
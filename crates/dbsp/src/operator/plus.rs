@@ -115,6 +115,12 @@ impl<D> Plus<D> {
     }
 }
 
+impl<D> Default for Plus<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<D> Operator for Plus<D>
 where
     D: 'static,
@@ -172,6 +178,12 @@ impl<D> Minus<D> {
     }
 }
 
+impl<D> Default for Minus<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<D> Operator for Minus<D>
 where
     D: 'static,
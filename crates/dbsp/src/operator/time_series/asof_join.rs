@@ -0,0 +1,875 @@
+use crate::{
+    algebra::{HasZero, IndexedZSet, MulByRef, ZRingValue, ZSet},
+    circuit::{
+        operator_traits::{BinaryOperator, Operator, QuaternaryOperator},
+        Scope,
+    },
+    operator::{
+        time_series::{OrdPartitionedIndexedZSet, PartitionedBatch, PartitionedBatchReader},
+        trace::{TraceBound, TraceBounds, TraceFeedback},
+        FilterMap, Z1,
+    },
+    trace::{Builder, Cursor, Spine},
+    utils::Tup2,
+    Circuit, DBData, NumEntries, OrdZSet, RootCircuit, Stream,
+};
+use num::Bounded;
+use std::{borrow::Cow, collections::BTreeMap, marker::PhantomData, ops::Neg};
+
+/// Records of the right input of [`asof_join`](`Stream::asof_join`), tagged
+/// with the half-open interval of timestamps `[start, end)` during which they
+/// are the latest records in their partition: `start` is the timestamp of the
+/// record and `end` is the next timestamp in the same partition.
+type OrdAsofIntervals<PK, TS, V, R> = OrdPartitionedIndexedZSet<PK, Tup2<TS, TS>, V, R>;
+
+impl<B> Stream<RootCircuit, B> {
+    /// As-of join: join each record in `self` with the records in `other` with
+    /// the same key and the greatest timestamp less than or equal to its own.
+    ///
+    /// Both inputs are partitioned by key, and `ts_func_left` and
+    /// `ts_func_right` extract the timestamps of their records.  For each
+    /// record `(k, v1)` in `self`, the operator finds the greatest timestamp
+    /// `ts` in partition `k` of `other` that is less than or equal to
+    /// `ts_func_left(v1)` and outputs `join_func(k, v1, v2)` for each record
+    /// `(k, v2)` in `other` with timestamp `ts`, with the product of the
+    /// weights of the two records.  Records in `self` without such a match are
+    /// not output.
+    ///
+    /// This operator is incremental.  Internally, it tags each record in
+    /// `other` with the interval of timestamps during which it is the latest
+    /// record in its partition, which turns the as-of join into an ordinary
+    /// bilinear join on interval containment.  Thus, an update to `other`
+    /// retracts the outputs for the records in `self` whose match changes and
+    /// emits their new outputs.
+    ///
+    /// The `waterline` stream bounds the out-of-orderedness of both inputs: it
+    /// is a monotonically growing lower bound on timestamps that can appear
+    /// in the input streams.  The operator ignores input records with
+    /// timestamps below the waterline as of the end of the previous step.  It
+    /// discards records in `self` below the waterline and records in `other`
+    /// that have been superseded by a later record below the waterline, since
+    /// their outputs can no longer change.  Thus, it retains only the latest
+    /// record below the waterline in each partition of `other`.  Use
+    /// `Bounded::min_value()` as the waterline to disable this behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - left input, partitioned by `B::Key`.
+    /// * `other` - right input, partitioned by the same key type.
+    /// * `ts_func_left` - extracts the timestamp of a record in `self`.
+    /// * `ts_func_right` - extracts the timestamp of a record in `other`.
+    /// * `join_func` - combines the key, a record in `self`, and its match in
+    ///   `other` into an output record.
+    /// * `waterline` - monotonically growing lower bound on timestamps in the
+    ///   input streams.
+    pub fn asof_join<TS, I2, TSF1, TSF2, F, V>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        ts_func_left: TSF1,
+        ts_func_right: TSF2,
+        join_func: F,
+        waterline: &Stream<RootCircuit, TS>,
+    ) -> Stream<RootCircuit, OrdZSet<V, B::R>>
+    where
+        B: IndexedZSet + Send,
+        Self: for<'a> FilterMap<RootCircuit, ItemRef<'a> = (&'a B::Key, &'a B::Val), R = B::R>,
+        B::R: ZRingValue,
+        I2: IndexedZSet<Key = B::Key, R = B::R> + Send,
+        Stream<RootCircuit, I2>:
+            for<'a> FilterMap<RootCircuit, ItemRef<'a> = (&'a I2::Key, &'a I2::Val), R = I2::R>,
+        TS: DBData + Bounded + NumEntries,
+        TSF1: Fn(&B::Val) -> TS + 'static,
+        TSF2: Fn(&I2::Val) -> TS + 'static,
+        F: Fn(&B::Key, &B::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        self.circuit().region("asof_join", || {
+            let circuit = self.circuit();
+
+            // Index both inputs by timestamp within each partition.
+            let left = self
+                .map_index(move |(k, v)| (k.clone(), Tup2(ts_func_left(v), v.clone())))
+                .shard();
+            let right = other
+                .map_index(move |(k, v)| (k.clone(), Tup2(ts_func_right(v), v.clone())))
+                .shard();
+
+            // Records in the current input batches may have advanced the
+            // waterline; only records below the previous waterline are late.
+            let delayed_waterline = circuit.add_unary_operator(Z1::new(TS::min_value()), waterline);
+
+            let left = left
+                .apply2(&delayed_waterline, |batch, waterline| {
+                    retain_timely::<_, _, _, B::Val>(batch, waterline)
+                })
+                .mark_sharded();
+
+            // Discard left records below the waterline.
+            let left_trace = left.integrate_trace_retain_values_with_key_bound(
+                waterline,
+                TraceBound::new(),
+                |Tup2(ts, _): &Tup2<TS, B::Val>, waterline: &TS| ts >= waterline,
+            );
+
+            // Right records below the waterline can no longer change, so their
+            // weights are only needed in the intervals computed from them.
+            let right_trace = right.integrate_trace_retain_values_with_key_bound(
+                &delayed_waterline,
+                TraceBound::new(),
+                |Tup2(ts, _): &Tup2<TS, I2::Val>, waterline: &TS| ts >= waterline,
+            );
+
+            // Discard right records superseded by a later record below the
+            // waterline.
+            let bounds = TraceBounds::new();
+            bounds.add_key_bound(TraceBound::new());
+            bounds.add_val_bound(TraceBound::new());
+
+            let bounds_clone = bounds.clone();
+            waterline.inspect(move |waterline| {
+                let waterline = waterline.clone();
+                bounds_clone.set_val_filter(Box::new(
+                    move |Tup2(Tup2(_start, end), _): &Tup2<Tup2<TS, TS>, I2::Val>| {
+                        *end > waterline
+                    },
+                ));
+            });
+
+            let feedback = circuit
+                .add_integrate_trace_feedback::<Spine<OrdAsofIntervals<B::Key, TS, I2::Val, B::R>>>(
+                    bounds,
+                );
+            let delayed_intervals = feedback.delayed_trace.clone();
+
+            let intervals = circuit
+                .add_quaternary_operator(
+                    <AsofIntervals<TS, I2::Val>>::new(),
+                    &right,
+                    &right_trace,
+                    &delayed_intervals,
+                    &delayed_waterline,
+                )
+                .mark_sharded();
+
+            feedback.connect(&intervals);
+
+            // delta(L ⋈ I) = l ⋈ z^-1(I) + L ⋈ i
+            let delta_trace = circuit.add_binary_operator(
+                <AsofJoinDeltaTrace<_, TS, B::Val, I2::Val, _>>::new(join_func.clone()),
+                &left,
+                &delayed_intervals,
+            );
+            let trace_delta = circuit.add_binary_operator(
+                <AsofJoinTraceDelta<_, TS, B::Val, I2::Val, _>>::new(join_func),
+                &left_trace,
+                &intervals,
+            );
+
+            delta_trace.plus(&trace_delta)
+        })
+    }
+}
+
+/// Returns the records in partitioned `batch` with timestamps at or above
+/// `waterline`.
+fn retain_timely<B, PK, TS, V>(batch: &B, waterline: &TS) -> B
+where
+    B: PartitionedBatch<TS, V, Key = PK>,
+    PK: DBData,
+    TS: DBData,
+    V: DBData,
+{
+    let mut builder = B::Builder::with_capacity((), batch.len());
+    let mut cursor = batch.cursor();
+
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            if &cursor.val().0 >= waterline {
+                let weight = cursor.weight();
+                builder.push((
+                    B::item_from(cursor.key().clone(), cursor.val().clone()),
+                    weight,
+                ));
+            }
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+
+    builder.done()
+}
+
+/// Quaternary operator that tags the right input of
+/// [`asof_join`](`Stream::asof_join`) with the intervals during which each
+/// record is the latest in its partition.
+///
+/// * Input stream 1: updates to the right input, partitioned and indexed by
+///   timestamp.
+/// * Input stream 2: trace of the right input.  Used to look up the current
+///   weights of records at or above the waterline.
+/// * Input stream 3: trace of previously produced outputs.  Used to find
+///   intervals affected by the updates and to compute retractions.
+/// * Input stream 4: waterline as of the end of the previous step.
+struct AsofIntervals<TS, V> {
+    phantom: PhantomData<(TS, V)>,
+}
+
+impl<TS, V> AsofIntervals<TS, V> {
+    fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<TS, V> Operator for AsofIntervals<TS, V>
+where
+    TS: 'static,
+    V: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AsofIntervals")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<TS, V, B, T, OT, O> QuaternaryOperator<B, T, OT, TS, O> for AsofIntervals<TS, V>
+where
+    TS: DBData + Bounded,
+    V: DBData,
+    B: PartitionedBatchReader<TS, V> + Clone,
+    B::R: ZRingValue,
+    T: PartitionedBatchReader<TS, V, Key = B::Key, R = B::R> + Clone,
+    OT: PartitionedBatchReader<Tup2<TS, TS>, V, Key = B::Key, R = B::R> + Clone,
+    O: IndexedZSet<Key = B::Key, Val = Tup2<Tup2<TS, TS>, V>, R = B::R>,
+{
+    fn eval<'a>(
+        &mut self,
+        input_delta: Cow<'a, B>,
+        input_trace: Cow<'a, T>,
+        output_trace: Cow<'a, OT>,
+        waterline: Cow<'a, TS>,
+    ) -> O {
+        let waterline = waterline.as_ref();
+
+        let mut delta_cursor = input_delta.cursor();
+        let mut input_trace_cursor = input_trace.cursor();
+        let mut output_trace_cursor = output_trace.cursor();
+
+        let mut retraction_builder = O::Builder::new_builder(());
+        let mut insertion_builder = O::Builder::with_capacity((), input_delta.len());
+
+        // Iterate over affected partitions.
+        while delta_cursor.key_valid() {
+            // Range of updated timestamps.  Late records are ignored.
+            let mut first: Option<TS> = None;
+            let mut last: Option<TS> = None;
+            while delta_cursor.val_valid() {
+                let ts = &delta_cursor.val().0;
+                if ts >= waterline {
+                    first.get_or_insert_with(|| ts.clone());
+                    last = Some(ts.clone());
+                }
+                delta_cursor.step_val();
+            }
+
+            if let (Some(first), Some(last)) = (first, last) {
+                // Records to recompute intervals from.
+                let mut records: BTreeMap<Tup2<TS, V>, B::R> = BTreeMap::new();
+
+                // End of the last recomputed interval: the start of the first
+                // interval after the update.
+                let mut end = TS::max_value();
+                let mut from = first.clone();
+
+                output_trace_cursor.seek_key(delta_cursor.key());
+                if output_trace_cursor.key_valid()
+                    && output_trace_cursor.key() == delta_cursor.key()
+                {
+                    // The interval that contains `first`, which starts before
+                    // it, also changes, so start from there.
+                    output_trace_cursor.fast_forward_vals();
+                    output_trace_cursor
+                        .seek_val_with_reverse(|Tup2(Tup2(start, _), _)| start < &first);
+                    while output_trace_cursor.val_valid() && output_trace_cursor.weight().is_zero()
+                    {
+                        output_trace_cursor.step_val_reverse();
+                    }
+                    if output_trace_cursor.val_valid() {
+                        from = output_trace_cursor.val().0 .0.clone();
+                    }
+
+                    output_trace_cursor.rewind_vals();
+                    output_trace_cursor.seek_val_with(|Tup2(Tup2(start, _), _)| start >= &from);
+                    while output_trace_cursor.val_valid() {
+                        let weight = output_trace_cursor.weight();
+                        if !weight.is_zero() {
+                            let Tup2(Tup2(start, _end), v) = output_trace_cursor.val();
+                            if start > &last {
+                                end = start.clone();
+                                break;
+                            }
+                            retraction_builder.push((
+                                O::item_from(
+                                    delta_cursor.key().clone(),
+                                    output_trace_cursor.val().clone(),
+                                ),
+                                weight.clone().neg(),
+                            ));
+                            // Records below the waterline are no longer in
+                            // `input_trace`, but their weights can't change.
+                            if start < waterline {
+                                records.insert(Tup2(start.clone(), v.clone()), weight);
+                            }
+                        }
+                        output_trace_cursor.step_val();
+                    }
+                }
+
+                // Current weights of the records at or above the waterline,
+                // including records with non-positive weights, which don't
+                // have intervals.
+                input_trace_cursor.seek_key(delta_cursor.key());
+                if input_trace_cursor.key_valid() && input_trace_cursor.key() == delta_cursor.key()
+                {
+                    let lower = if &from > waterline { &from } else { waterline };
+                    input_trace_cursor.seek_val_with(|Tup2(ts, _)| ts >= lower);
+                    while input_trace_cursor.val_valid() && input_trace_cursor.val().0 <= last {
+                        let weight = input_trace_cursor.weight();
+                        if !weight.is_zero() {
+                            records.insert(input_trace_cursor.val().clone(), weight);
+                        }
+                        input_trace_cursor.step_val();
+                    }
+                }
+
+                // Compute new intervals.  Each record's interval ends at the
+                // next timestamp with a record.
+                let records = records
+                    .into_iter()
+                    .filter(|(_, w)| !w.le0())
+                    .collect::<Vec<_>>();
+                let mut i = 0;
+                while i < records.len() {
+                    let start = &records[i].0 .0;
+                    let n = records[i..]
+                        .iter()
+                        .take_while(|(Tup2(ts, _), _)| ts == start)
+                        .count();
+                    let interval_end = records
+                        .get(i + n)
+                        .map_or_else(|| end.clone(), |(Tup2(ts, _), _)| ts.clone());
+                    for (Tup2(ts, v), w) in &records[i..i + n] {
+                        insertion_builder.push((
+                            O::item_from(
+                                delta_cursor.key().clone(),
+                                Tup2(Tup2(ts.clone(), interval_end.clone()), v.clone()),
+                            ),
+                            w.clone(),
+                        ));
+                    }
+                    i += n;
+                }
+            }
+
+            delta_cursor.step_key();
+        }
+
+        let retractions = retraction_builder.done();
+        let insertions = insertion_builder.done();
+        retractions.add(insertions)
+    }
+}
+
+/// Binary operator that joins updates to the left input of
+/// [`asof_join`](`Stream::asof_join`) with the trace of right intervals.
+///
+/// * Input stream 1: updates to the left input, partitioned and indexed by
+///   timestamp.
+/// * Input stream 2: trace of right intervals as of the previous step.
+struct AsofJoinDeltaTrace<F, TS, V1, V2, Z> {
+    join_func: F,
+    phantom: PhantomData<(TS, V1, V2, Z)>,
+}
+
+impl<F, TS, V1, V2, Z> AsofJoinDeltaTrace<F, TS, V1, V2, Z> {
+    fn new(join_func: F) -> Self {
+        Self {
+            join_func,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, TS, V1, V2, Z> Operator for AsofJoinDeltaTrace<F, TS, V1, V2, Z>
+where
+    F: 'static,
+    TS: 'static,
+    V1: 'static,
+    V2: 'static,
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AsofJoinDeltaTrace")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<F, TS, V1, V2, B, T, Z> BinaryOperator<B, T, Z> for AsofJoinDeltaTrace<F, TS, V1, V2, Z>
+where
+    F: Fn(&B::Key, &V1, &V2) -> Z::Key + 'static,
+    TS: DBData,
+    V1: DBData,
+    V2: DBData,
+    B: PartitionedBatchReader<TS, V1, R = Z::R> + Clone,
+    T: PartitionedBatchReader<Tup2<TS, TS>, V2, Key = B::Key, R = Z::R> + Clone,
+    Z: ZSet,
+    Z::R: ZRingValue,
+{
+    fn eval(&mut self, delta: &B, trace: &T) -> Z {
+        let mut tuples = Vec::new();
+        let mut delta_cursor = delta.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        while delta_cursor.key_valid() {
+            trace_cursor.seek_key(delta_cursor.key());
+            if trace_cursor.key_valid() && trace_cursor.key() == delta_cursor.key() {
+                // The interval that contains the previous timestamp and the
+                // right records in it.  Left records are ordered by
+                // timestamp, so consecutive records often share a match.
+                let mut interval: Option<(TS, TS)> = None;
+                let mut matches: Vec<(V2, Z::R)> = Vec::new();
+
+                while delta_cursor.val_valid() {
+                    let w1 = delta_cursor.weight();
+                    let Tup2(ts, v1) = delta_cursor.val();
+
+                    if !matches!(&interval, Some((start, end)) if start <= ts && ts < end) {
+                        // Find the latest interval that starts at or before
+                        // `ts`, stepping back over retracted intervals.
+                        interval = None;
+                        matches.clear();
+                        trace_cursor.fast_forward_vals();
+                        trace_cursor.seek_val_with_reverse(|Tup2(Tup2(start, _), _)| start <= ts);
+                        while trace_cursor.val_valid() {
+                            let w2 = trace_cursor.weight();
+                            let Tup2(Tup2(start, end), v2) = trace_cursor.val();
+                            if matches!(&interval, Some((s, _)) if s != start) {
+                                break;
+                            }
+                            if !w2.is_zero() {
+                                interval.get_or_insert_with(|| (start.clone(), end.clone()));
+                                matches.push((v2.clone(), w2));
+                            }
+                            trace_cursor.step_val_reverse();
+                        }
+                    }
+
+                    if matches!(&interval, Some((_, end)) if ts < end) {
+                        for (v2, w2) in matches.iter() {
+                            tuples.push((
+                                Z::item_from((self.join_func)(delta_cursor.key(), v1, v2), ()),
+                                w1.mul_by_ref(w2),
+                            ));
+                        }
+                    }
+                    delta_cursor.step_val();
+                }
+            }
+            delta_cursor.step_key();
+        }
+
+        Z::from_tuples((), tuples)
+    }
+}
+
+/// Binary operator that joins the trace of the left input of
+/// [`asof_join`](`Stream::asof_join`) with updates to right intervals.
+///
+/// * Input stream 1: trace of the left input, including the current step,
+///   partitioned and indexed by timestamp.
+/// * Input stream 2: updates to right intervals.
+struct AsofJoinTraceDelta<F, TS, V1, V2, Z> {
+    join_func: F,
+    phantom: PhantomData<(TS, V1, V2, Z)>,
+}
+
+impl<F, TS, V1, V2, Z> AsofJoinTraceDelta<F, TS, V1, V2, Z> {
+    fn new(join_func: F) -> Self {
+        Self {
+            join_func,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, TS, V1, V2, Z> Operator for AsofJoinTraceDelta<F, TS, V1, V2, Z>
+where
+    F: 'static,
+    TS: 'static,
+    V1: 'static,
+    V2: 'static,
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("AsofJoinTraceDelta")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<F, TS, V1, V2, T, D, Z> BinaryOperator<T, D, Z> for AsofJoinTraceDelta<F, TS, V1, V2, Z>
+where
+    F: Fn(&T::Key, &V1, &V2) -> Z::Key + 'static,
+    TS: DBData,
+    V1: DBData,
+    V2: DBData,
+    T: PartitionedBatchReader<TS, V1, R = Z::R> + Clone,
+    D: PartitionedBatchReader<Tup2<TS, TS>, V2, Key = T::Key, R = Z::R> + Clone,
+    Z: ZSet,
+    Z::R: ZRingValue,
+{
+    fn eval(&mut self, trace: &T, delta: &D) -> Z {
+        let mut tuples = Vec::new();
+        let mut trace_cursor = trace.cursor();
+        let mut delta_cursor = delta.cursor();
+
+        while delta_cursor.key_valid() {
+            trace_cursor.seek_key(delta_cursor.key());
+            if trace_cursor.key_valid() && trace_cursor.key() == delta_cursor.key() {
+                while delta_cursor.val_valid() {
+                    let w2 = delta_cursor.weight();
+                    let Tup2(Tup2(start, end), v2) = delta_cursor.val();
+
+                    // Retracted and inserted intervals may overlap, so rewind
+                    // for each interval.
+                    trace_cursor.rewind_vals();
+                    trace_cursor.seek_val_with(|Tup2(ts, _)| ts >= start);
+                    while trace_cursor.val_valid() && &trace_cursor.val().0 < end {
+                        let w1 = trace_cursor.weight();
+                        if !w1.is_zero() {
+                            tuples.push((
+                                Z::item_from(
+                                    (self.join_func)(delta_cursor.key(), &trace_cursor.val().1, v2),
+                                    (),
+                                ),
+                                w1.mul_by_ref(&w2),
+                            ));
+                        }
+                        trace_cursor.step_val();
+                    }
+                    delta_cursor.step_val();
+                }
+            }
+            delta_cursor.step_key();
+        }
+
+        Z::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{FilterMap, Generator},
+        trace::{Batch, BatchReader, Cursor},
+        utils::{Tup2, Tup3},
+        Circuit, CollectionHandle, DBSPHandle, OrdIndexedZSet, OrdZSet, RootCircuit, Runtime,
+        Stream,
+    };
+    use proptest::{collection, prelude::*};
+
+    type DataBatch = OrdIndexedZSet<u64, Tup2<u64, i64>, i64>;
+    type DataStream = Stream<RootCircuit, DataBatch>;
+    type OutputTuple = Tup3<u64, Tup2<u64, i64>, Tup2<u64, i64>>;
+    type OutputBatch = OrdZSet<OutputTuple, i64>;
+    type OutputStream = Stream<RootCircuit, OutputBatch>;
+
+    fn join_func(k: &u64, left: &Tup2<u64, i64>, right: &Tup2<u64, i64>) -> OutputTuple {
+        Tup3(*k, *left, *right)
+    }
+
+    // Reference implementation of `asof_join` for testing.
+    fn asof_join_slow(left: &DataStream, right: &DataStream) -> OutputStream {
+        left.gather(0)
+            .integrate()
+            .apply2(&right.gather(0).integrate(), asof_join_batch)
+    }
+
+    // Joins all records in `left` with their matches in `right` from scratch.
+    fn asof_join_batch(left: &DataBatch, right: &DataBatch) -> OutputBatch {
+        let mut tuples = Vec::new();
+        let mut left_cursor = left.cursor();
+        let mut right_cursor = right.cursor();
+
+        while left_cursor.key_valid() {
+            let partition = *left_cursor.key();
+
+            // Right records in the partition, in timestamp order.
+            let mut records: Vec<(Tup2<u64, i64>, i64)> = Vec::new();
+            right_cursor.seek_key(&partition);
+            if right_cursor.key_valid() && *right_cursor.key() == partition {
+                while right_cursor.val_valid() {
+                    let w = right_cursor.weight();
+                    if w > 0 {
+                        records.push((*right_cursor.val(), w));
+                    }
+                    right_cursor.step_val();
+                }
+            }
+
+            while left_cursor.val_valid() {
+                let w = left_cursor.weight();
+                let val = *left_cursor.val();
+                let latest = records
+                    .iter()
+                    .map(|(Tup2(ts, _), _)| *ts)
+                    .filter(|ts| *ts <= val.0)
+                    .max();
+                for (record, rw) in records.iter() {
+                    if Some(record.0) == latest {
+                        tuples.push((join_func(&partition, &val, record), w * rw));
+                    }
+                }
+                left_cursor.step_val();
+            }
+
+            left_cursor.step_key();
+        }
+
+        OutputBatch::from_tuples((), tuples)
+    }
+
+    type InputHandle = CollectionHandle<u64, Tup2<Tup2<u64, i64>, i64>>;
+
+    fn asof_join_circuit(
+        workers: usize,
+        lateness: u64,
+    ) -> (DBSPHandle, (InputHandle, InputHandle)) {
+        Runtime::init_circuit(workers, move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+            let (right, right_handle) =
+                circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+
+            let waterline = left
+                .map_index(|(partition, Tup2(ts, val))| (*ts, (*partition, *val)))
+                .waterline_monotonic(|| 0, move |ts| ts.saturating_sub(lateness));
+
+            let expected = asof_join_slow(&left, &right);
+            let actual = left
+                .asof_join(
+                    &right,
+                    |Tup2(ts, _)| *ts,
+                    |Tup2(ts, _)| *ts,
+                    join_func,
+                    &waterline,
+                )
+                .gather(0)
+                .integrate();
+
+            expected.apply2(&actual, |expected, actual| assert_eq!(expected, actual));
+
+            Ok((left_handle, right_handle))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_asof_join() {
+        let (circuit, (left, right, expected)) = RootCircuit::build(move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+            let (right, right_handle) =
+                circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+            let (expected, expected_handle) = circuit.add_input_zset::<OutputTuple, i64>();
+
+            let waterline = circuit.add_source(Generator::new(|| 0u64));
+            left.asof_join(
+                &right,
+                |Tup2(ts, _)| *ts,
+                |Tup2(ts, _)| *ts,
+                join_func,
+                &waterline,
+            )
+            .apply2(&expected, |actual, expected| assert_eq!(actual, expected));
+
+            Ok((left_handle, right_handle, expected_handle))
+        })
+        .unwrap();
+
+        // The left record at 5 has no match yet.
+        right.append(&mut vec![(1, Tup2(Tup2(10, 100), 1))]);
+        left.append(&mut vec![
+            (1, Tup2(Tup2(5, 1), 1)),
+            (1, Tup2(Tup2(15, 2), 1)),
+        ]);
+        expected.append(&mut vec![(Tup3(1, Tup2(15, 2), Tup2(10, 100)), 1)]);
+        circuit.step().unwrap();
+
+        // A later right record replaces the match of the left record at 15.
+        right.append(&mut vec![(1, Tup2(Tup2(12, 200), 1))]);
+        expected.append(&mut vec![
+            (Tup3(1, Tup2(15, 2), Tup2(10, 100)), -1),
+            (Tup3(1, Tup2(15, 2), Tup2(12, 200)), 1),
+        ]);
+        circuit.step().unwrap();
+
+        // An earlier right record gives the left record at 5 a match.
+        right.append(&mut vec![(1, Tup2(Tup2(3, 300), 1))]);
+        expected.append(&mut vec![(Tup3(1, Tup2(5, 1), Tup2(3, 300)), 1)]);
+        circuit.step().unwrap();
+
+        // Deleting a right record restores the previous match.
+        right.append(&mut vec![(1, Tup2(Tup2(12, 200), -1))]);
+        expected.append(&mut vec![
+            (Tup3(1, Tup2(15, 2), Tup2(12, 200)), -1),
+            (Tup3(1, Tup2(15, 2), Tup2(10, 100)), 1),
+        ]);
+        circuit.step().unwrap();
+
+        // Two right records with the same timestamp both match, and
+        // partitions don't interact.
+        right.append(&mut vec![
+            (1, Tup2(Tup2(10, 400), 1)),
+            (2, Tup2(Tup2(0, 500), 1)),
+        ]);
+        left.append(&mut vec![
+            (1, Tup2(Tup2(12, 3), 1)),
+            (3, Tup2(Tup2(20, 4), 1)),
+        ]);
+        expected.append(&mut vec![
+            (Tup3(1, Tup2(12, 3), Tup2(10, 100)), 1),
+            (Tup3(1, Tup2(12, 3), Tup2(10, 400)), 1),
+            (Tup3(1, Tup2(15, 2), Tup2(10, 400)), 1),
+        ]);
+        circuit.step().unwrap();
+
+        // Deleting a left record retracts its output.
+        left.append(&mut vec![(1, Tup2(Tup2(5, 1), -1))]);
+        expected.append(&mut vec![(Tup3(1, Tup2(5, 1), Tup2(3, 300)), -1)]);
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn test_asof_join_out_of_order() {
+        let (mut circuit, (left, right)) = asof_join_circuit(4, u64::MAX);
+
+        left.append(&mut vec![
+            (0, Tup2(Tup2(30, 1), 1)),
+            (0, Tup2(Tup2(50, 1), 1)),
+            (1, Tup2(Tup2(30, 1), 1)),
+        ]);
+        right.append(&mut vec![(0, Tup2(Tup2(40, 1), 1))]);
+        circuit.step().unwrap();
+
+        right.append(&mut vec![
+            (0, Tup2(Tup2(20, 2), 1)),
+            (0, Tup2(Tup2(45, 2), 1)),
+            (1, Tup2(Tup2(0, 2), 1)),
+        ]);
+        left.append(&mut vec![(0, Tup2(Tup2(40, 2), 1))]);
+        circuit.step().unwrap();
+
+        right.append(&mut vec![
+            (0, Tup2(Tup2(40, 1), -1)),
+            (1, Tup2(Tup2(0, 2), -1)),
+        ]);
+        left.append(&mut vec![(0, Tup2(Tup2(30, 1), -1))]);
+        circuit.step().unwrap();
+
+        circuit.kill().unwrap();
+    }
+
+    type InputTuple = (u64, Tup2<Tup2<u64, i64>, i64>);
+    type InputBatch = Vec<InputTuple>;
+
+    fn input_tuple(partitions: u64, window: (u64, u64)) -> impl Strategy<Value = InputTuple> {
+        (
+            (0..partitions),
+            (
+                (window.0..window.1, 0..5i64).prop_map(|(x, y)| Tup2(x, y)),
+                -1..2i64,
+            )
+                .prop_map(|(x, y)| Tup2(x, y)),
+        )
+    }
+
+    fn input_batch(
+        partitions: u64,
+        window: (u64, u64),
+        max_batch_size: usize,
+    ) -> impl Strategy<Value = (InputBatch, InputBatch)> {
+        (
+            collection::vec(input_tuple(partitions, window), 0..max_batch_size),
+            collection::vec(input_tuple(partitions, window), 0..max_batch_size),
+        )
+    }
+
+    fn input_trace(
+        partitions: u64,
+        epoch: u64,
+        max_batch_size: usize,
+        max_batches: usize,
+    ) -> impl Strategy<Value = Vec<(InputBatch, InputBatch)>> {
+        collection::vec(
+            input_batch(partitions, (0, epoch), max_batch_size),
+            0..max_batches,
+        )
+    }
+
+    fn input_trace_quasi_monotone(
+        partitions: u64,
+        window_size: u64,
+        window_step: u64,
+        max_batch_size: usize,
+        batches: usize,
+    ) -> impl Strategy<Value = Vec<(InputBatch, InputBatch)>> {
+        (0..batches)
+            .map(|i| {
+                input_batch(
+                    partitions,
+                    (i as u64 * window_step, i as u64 * window_step + window_size),
+                    max_batch_size,
+                )
+                .boxed()
+            })
+            .collect::<Vec<_>>()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(20))]
+
+        #[test]
+        fn proptest_asof_join(trace in input_trace(5, 1_000, 20, 20)) {
+            let (mut circuit, (left, right)) = asof_join_circuit(4, u64::MAX);
+
+            for (mut left_batch, mut right_batch) in trace {
+                left.append(&mut left_batch);
+                right.append(&mut right_batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+
+        #[test]
+        fn proptest_asof_join_quasi_monotone(trace in input_trace_quasi_monotone(5, 1_000, 200, 20, 50)) {
+            let (mut circuit, (left, right)) = asof_join_circuit(4, 1_000);
+
+            for (mut left_batch, mut right_batch) in trace {
+                left.append(&mut left_batch);
+                right.append(&mut right_batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+    }
+}
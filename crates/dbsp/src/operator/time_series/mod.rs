@@ -1,7 +1,9 @@
+mod asof_join;
 mod partitioned;
-mod radix_tree;
+pub(crate) mod radix_tree;
 mod range;
 mod rolling_aggregate;
+mod session_window;
 mod waterline;
 mod window;
 
@@ -238,8 +238,67 @@ where
         agg
     }
 
+    /// Finds the timestamp at position `row` (counting from 0) in the sorted
+    /// multiset of timestamps in the tree, where `count` returns the number
+    /// of timestamps in a subtree, with multiplicities, given its aggregate.
+    ///
+    /// Returns `None` if the tree contains `row` or fewer timestamps.
+    ///
+    /// # Preconditions
+    ///
+    /// Assumes `self` points to the root of the tree or the
+    /// tree is empty and `self.key_valid()` is false.
+    ///
+    /// # Complexity
+    ///
+    /// This method visits `O(log(n))` nodes, where `n` is the number of
+    /// distinct timestamps in the tree.
+    fn select<F>(&mut self, mut row: i64, count: F) -> Option<TS>
+    where
+        F: Fn(&A) -> i64,
+        A: Clone,
+        R: HasZero,
+    {
+        // Empty tree.
+        if !self.key_valid() {
+            return None;
+        }
+
+        self.skip_zero_weights();
+        if !self.val_valid() {
+            return None;
+        }
+        let mut node = self.val().clone();
+
+        loop {
+            // Find the child subtree that contains `row`, skipping over the
+            // rows in the subtrees before it.
+            let mut next = None;
+            for child in node.children.into_iter().flatten() {
+                let child_count = count(&child.child_agg);
+                if row < child_count {
+                    next = Some(child.child_prefix);
+                    break;
+                }
+                row -= child_count;
+            }
+
+            let prefix = next?;
+            if prefix.is_leaf() {
+                return Some(prefix.key);
+            }
+
+            self.seek_key(&prefix);
+            self.skip_zero_weights();
+            debug_assert!(self.key_valid());
+            debug_assert_eq!(self.key(), &prefix);
+            node = self.val().clone();
+        }
+    }
+
     /// Produce a semi-human-readable representation of the tree for debugging
     /// purposes.
+    #[allow(dead_code)]
     fn format_tree<W>(&mut self, writer: &mut W) -> Result<(), fmt::Error>
     where
         TS: Debug,
@@ -261,6 +320,7 @@ where
 
     /// Self-diagnostics: validate that `self` points to a well-formed
     /// radix-tree whose contents is equivalent to `contents`.
+    #[allow(dead_code)]
     fn validate<S>(&mut self, contents: &BTreeMap<TS, A>)
     where
         R: Eq + HasOne + HasZero + Debug,
@@ -57,6 +57,7 @@ type OrdPartitionedRadixTreeStream<PK, TS, A, R> =
     Stream<RootCircuit, OrdPartitionedRadixTree<PK, TS, A, R>>;
 
 /// Cursor over partitioned radix tree.
+#[allow(dead_code)]
 pub trait PartitionedRadixTreeCursor<PK, TS, A, R>:
     Cursor<PK, Tup2<Prefix<TS>, TreeNode<TS, A>>, (), R> + Sized
 {
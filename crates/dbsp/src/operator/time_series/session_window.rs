@@ -0,0 +1,610 @@
+use crate::{
+    algebra::{HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        operator_traits::{Operator, QuaternaryOperator},
+        Scope,
+    },
+    operator::{
+        time_series::{OrdPartitionedIndexedZSet, PartitionedBatchReader, PartitionedIndexedZSet},
+        trace::{TraceBound, TraceBounds, TraceFeedback},
+        Z1,
+    },
+    trace::{Builder, Cursor, Spine},
+    utils::Tup2,
+    Circuit, DBData, NumEntries, RootCircuit, Stream,
+};
+use num::PrimInt;
+use std::{borrow::Cow, collections::BTreeMap, marker::PhantomData, ops::Neg};
+
+/// Output of the [`session_window`](`Stream::session_window`) operator:
+/// input records tagged with the `(start, end)` bounds of their session.
+pub type OrdSessionWindowStream<PK, TS, V, R> =
+    Stream<RootCircuit, OrdPartitionedIndexedZSet<PK, Tup2<TS, TS>, Tup2<TS, V>, R>>;
+
+impl<B> Stream<RootCircuit, B> {
+    /// Split a partitioned time series into sessions.
+    ///
+    /// A session is a maximal sequence of records within a partition such
+    /// that the distance between consecutive timestamps is less than `gap`.
+    /// The operator tags each input record `(pk, (ts, v))` with the bounds of
+    /// the session it belongs to, producing `(pk, ((start, end), (ts, v)))`,
+    /// where `start` is the timestamp of the first record in the session and
+    /// `end` is the timestamp of the last record plus `gap`.
+    ///
+    /// This operator is incremental: when a record arrives out of order,
+    /// it may extend a session, merge two adjacent sessions, or start a new
+    /// session.  Likewise, deleting a record may shrink or split its session.
+    /// In all cases, the operator retracts the outputs of the affected
+    /// sessions and emits updated outputs.
+    ///
+    /// The `waterline` stream bounds the out-of-orderedness of the input
+    /// data: it is a monotonically growing lower bound on timestamps that
+    /// can appear in the input stream.  The operator ignores input records
+    /// with timestamps below the waterline as of the end of the previous step
+    /// and discards the state of sessions that end at or before the waterline,
+    /// as such sessions can no longer change.  Use `Bounded::min_value()` as
+    /// the waterline to disable this behavior.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - time series data partitioned by `B::Key` and indexed by time
+    ///   within each partition.
+    /// * `gap` - minimal distance between consecutive timestamps that starts a
+    ///   new session.
+    /// * `waterline` - monotonically growing lower bound on timestamps in the
+    ///   input stream.
+    pub fn session_window<TS, V>(
+        &self,
+        gap: TS,
+        waterline: &Stream<RootCircuit, TS>,
+    ) -> OrdSessionWindowStream<B::Key, TS, V, B::R>
+    where
+        B: PartitionedIndexedZSet<TS, V>,
+        B::R: ZRingValue,
+        TS: DBData + PrimInt + NumEntries,
+        V: DBData,
+    {
+        self.circuit().region("session_window", || {
+            let circuit = self.circuit();
+            let stream = self.shard();
+
+            // Records in the current input batch may have advanced the
+            // waterline; only records below the previous waterline are late.
+            let delayed_waterline = circuit.add_unary_operator(Z1::new(TS::min_value()), waterline);
+
+            // Records below the waterline can no longer change, so their
+            // weights are only needed in the sessions computed from them.
+            let input_trace = stream.integrate_trace_retain_values_with_key_bound(
+                &delayed_waterline,
+                TraceBound::new(),
+                |Tup2(ts, _): &Tup2<TS, V>, waterline: &TS| ts >= waterline,
+            );
+
+            // Discard sessions that end before the waterline.
+            let bounds = TraceBounds::new();
+            bounds.add_key_bound(TraceBound::new());
+            bounds.add_val_bound(TraceBound::new());
+
+            let bounds_clone = bounds.clone();
+            waterline.inspect(move |waterline| {
+                let waterline = *waterline;
+                bounds_clone.set_val_filter(Box::new(
+                    move |Tup2(Tup2(_start, end), _): &Tup2<Tup2<TS, TS>, Tup2<TS, V>>| {
+                        *end > waterline
+                    },
+                ));
+            });
+
+            let feedback = circuit.add_integrate_trace_feedback::<Spine<
+                OrdPartitionedIndexedZSet<B::Key, Tup2<TS, TS>, Tup2<TS, V>, B::R>,
+            >>(bounds);
+
+            let output = circuit
+                .add_quaternary_operator(
+                    <SessionWindow<TS, V>>::new(gap),
+                    &stream,
+                    &input_trace,
+                    &feedback.delayed_trace,
+                    &delayed_waterline,
+                )
+                .mark_sharded();
+
+            feedback.connect(&output);
+
+            output
+        })
+    }
+}
+
+/// Quaternary operator that implements the internals of
+/// [`session_window`](`Stream::session_window`).
+///
+/// * Input stream 1: updates to the time series.
+/// * Input stream 2: trace of the time series.  Used to look up the current
+///   weights of records at or above the waterline.
+/// * Input stream 3: trace of previously produced outputs.  Used to find
+///   sessions affected by the updates and to compute retractions.
+/// * Input stream 4: waterline as of the end of the previous step.
+struct SessionWindow<TS, V> {
+    gap: TS,
+    phantom: PhantomData<V>,
+}
+
+impl<TS, V> SessionWindow<TS, V>
+where
+    TS: PrimInt,
+{
+    fn new(gap: TS) -> Self {
+        Self {
+            gap,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if a record with timestamp `ts` belongs to or is
+    /// adjacent to session `[start, end)`.
+    fn affects(&self, ts: TS, start: TS, end: TS) -> bool {
+        ts < end && ts.saturating_add(self.gap) > start
+    }
+
+    /// Split records sorted by timestamp into sessions and push them to
+    /// `builder`.
+    fn build_sessions<K, R, O>(&self, key: &K, records: Vec<(TS, V, R)>, builder: &mut O::Builder)
+    where
+        K: DBData,
+        V: DBData,
+        R: DBData,
+        O: IndexedZSet<Key = K, Val = Tup2<Tup2<TS, TS>, Tup2<TS, V>>, R = R>,
+    {
+        let gap = self.gap;
+        let mut session: Vec<(TS, V, R)> = Vec::new();
+
+        let mut flush = |session: &mut Vec<(TS, V, R)>| {
+            if let (Some(first), Some(last)) = (session.first(), session.last()) {
+                let bounds = Tup2(first.0, last.0.saturating_add(gap));
+                for (ts, v, w) in session.drain(..) {
+                    builder.push((O::item_from(key.clone(), Tup2(bounds, Tup2(ts, v))), w));
+                }
+            }
+        };
+
+        for record in records {
+            if matches!(session.last(), Some(last) if record.0 >= last.0.saturating_add(gap)) {
+                flush(&mut session);
+            }
+            session.push(record);
+        }
+        flush(&mut session);
+    }
+}
+
+impl<TS, V> Operator for SessionWindow<TS, V>
+where
+    TS: 'static,
+    V: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("SessionWindow")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<TS, V, B, T, OT, O> QuaternaryOperator<B, T, OT, TS, O> for SessionWindow<TS, V>
+where
+    TS: DBData + PrimInt,
+    V: DBData,
+    B: PartitionedBatchReader<TS, V> + Clone,
+    B::R: ZRingValue,
+    T: PartitionedBatchReader<TS, V, Key = B::Key, R = B::R> + Clone,
+    OT: PartitionedBatchReader<Tup2<TS, TS>, Tup2<TS, V>, Key = B::Key, R = B::R> + Clone,
+    O: IndexedZSet<Key = B::Key, Val = Tup2<Tup2<TS, TS>, Tup2<TS, V>>, R = B::R>,
+{
+    fn eval<'a>(
+        &mut self,
+        input_delta: Cow<'a, B>,
+        input_trace: Cow<'a, T>,
+        output_trace: Cow<'a, OT>,
+        waterline: Cow<'a, TS>,
+    ) -> O {
+        let waterline = *waterline;
+
+        let mut delta_cursor = input_delta.cursor();
+        let mut input_trace_cursor = input_trace.cursor();
+        let mut output_trace_cursor = output_trace.cursor();
+
+        let mut retraction_builder = O::Builder::new_builder(());
+        let mut insertion_builder = O::Builder::with_capacity((), input_delta.len());
+
+        // Iterate over affected partitions.
+        while delta_cursor.key_valid() {
+            // Records to recompute sessions from, indexed by `(ts, v)`.  The
+            // weights of records at or above the waterline are filled in from
+            // `input_trace` below.
+            let mut records: BTreeMap<(TS, V), B::R> = BTreeMap::new();
+
+            // Timestamps in `input_delta`, in ascending order.  Late records
+            // are ignored.
+            let mut delta_times: Vec<TS> = Vec::new();
+            while delta_cursor.val_valid() {
+                let Tup2(ts, v) = delta_cursor.val();
+                if *ts >= waterline {
+                    if delta_times.last() != Some(ts) {
+                        delta_times.push(*ts);
+                    }
+                    records.insert((*ts, v.clone()), HasZero::zero());
+                }
+                delta_cursor.step_val();
+            }
+
+            if let (Some(&first), Some(&last)) = (delta_times.first(), delta_times.last()) {
+                // Find existing sessions affected by the update.  Live
+                // sessions don't overlap, but the trace also contains
+                // retracted sessions with zero weight, so session end times
+                // are not ordered.  The only session that starts at or before
+                // `first` and may be affected is the last live one; start
+                // scanning from there.
+                output_trace_cursor.seek_key(delta_cursor.key());
+                if output_trace_cursor.key_valid()
+                    && output_trace_cursor.key() == delta_cursor.key()
+                {
+                    output_trace_cursor.fast_forward_vals();
+                    output_trace_cursor
+                        .seek_val_with_reverse(|Tup2(Tup2(start, _), _)| *start <= first);
+                    while output_trace_cursor.val_valid() && output_trace_cursor.weight().is_zero()
+                    {
+                        output_trace_cursor.step_val_reverse();
+                    }
+                    let from = if output_trace_cursor.val_valid() {
+                        output_trace_cursor.val().0 .0
+                    } else {
+                        TS::min_value()
+                    };
+
+                    output_trace_cursor.rewind_vals();
+                    output_trace_cursor.seek_val_with(|Tup2(Tup2(start, _), _)| *start >= from);
+
+                    let mut times = delta_times.iter().copied().peekable();
+
+                    while output_trace_cursor.val_valid() {
+                        let weight = output_trace_cursor.weight();
+                        let Tup2(Tup2(start, end), Tup2(ts, v)) = output_trace_cursor.val();
+                        if *start >= last.saturating_add(self.gap) {
+                            break;
+                        }
+
+                        // Skip delta timestamps that precede the current session.
+                        while times
+                            .next_if(|time| time.saturating_add(self.gap) <= *start)
+                            .is_some()
+                        {}
+
+                        let affected = times
+                            .peek()
+                            .map(|time| self.affects(*time, *start, *end))
+                            .unwrap_or(false);
+
+                        if affected && !weight.is_zero() {
+                            retraction_builder.push((
+                                O::item_from(
+                                    delta_cursor.key().clone(),
+                                    output_trace_cursor.val().clone(),
+                                ),
+                                weight.clone().neg(),
+                            ));
+                            // Records below the waterline are no longer in
+                            // `input_trace`, but their weights can't change.
+                            let weight = if *ts < waterline {
+                                weight
+                            } else {
+                                HasZero::zero()
+                            };
+                            records.insert((*ts, v.clone()), weight);
+                        }
+
+                        output_trace_cursor.step_val();
+                    }
+                }
+
+                // Current weights of the records at or above the waterline.
+                input_trace_cursor.seek_key(delta_cursor.key());
+                if input_trace_cursor.key_valid() && input_trace_cursor.key() == delta_cursor.key()
+                {
+                    for ((ts, v), weight) in
+                        records.iter_mut().filter(|((ts, _), _)| *ts >= waterline)
+                    {
+                        let record = Tup2(*ts, v.clone());
+                        input_trace_cursor.seek_val(&record);
+                        if input_trace_cursor.val_valid() && input_trace_cursor.val() == &record {
+                            *weight = input_trace_cursor.weight();
+                        }
+                    }
+                }
+
+                // Compute new sessions.
+                let records = records
+                    .into_iter()
+                    .filter(|(_, w)| !w.le0())
+                    .map(|((ts, v), w)| (ts, v, w))
+                    .collect::<Vec<_>>();
+                self.build_sessions::<_, _, O>(delta_cursor.key(), records, &mut insertion_builder);
+            }
+
+            delta_cursor.step_key();
+        }
+
+        let retractions = retraction_builder.done();
+        let insertions = insertion_builder.done();
+        retractions.add(insertions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{time_series::PartitionedBatchReader, FilterMap, Generator},
+        trace::{Batch, Cursor},
+        utils::Tup2,
+        Circuit, CollectionHandle, DBSPHandle, OrdIndexedZSet, RootCircuit, Runtime, Stream,
+    };
+    use proptest::{collection, prelude::*};
+
+    type DataBatch = OrdIndexedZSet<u64, Tup2<u64, i64>, i64>;
+    type DataStream = Stream<RootCircuit, DataBatch>;
+    type OutputBatch = OrdIndexedZSet<u64, Tup2<Tup2<u64, u64>, Tup2<u64, i64>>, i64>;
+    type OutputStream = Stream<RootCircuit, OutputBatch>;
+
+    // Reference implementation of `session_window` for testing.
+    fn session_window_slow(stream: &DataStream, gap: u64) -> OutputStream {
+        stream
+            .gather(0)
+            .integrate()
+            .apply(move |batch: &DataBatch| session_window_batch(batch, gap))
+    }
+
+    // Splits all records in `batch` into sessions from scratch.
+    fn session_window_batch<B>(batch: &B, gap: u64) -> OutputBatch
+    where
+        B: PartitionedBatchReader<u64, i64, Key = u64, R = i64>,
+    {
+        let mut tuples = Vec::new();
+        let mut cursor = batch.cursor();
+
+        while cursor.key_valid() {
+            let partition = *cursor.key();
+            let mut session: Vec<(u64, i64, i64)> = Vec::new();
+            let mut flush = |session: &mut Vec<(u64, i64, i64)>| {
+                if let (Some(first), Some(last)) = (session.first(), session.last()) {
+                    let bounds = Tup2(first.0, last.0 + gap);
+                    for (ts, v, w) in session.drain(..) {
+                        tuples.push(((partition, Tup2(bounds, Tup2(ts, v))), w));
+                    }
+                }
+            };
+
+            while cursor.val_valid() {
+                let w = cursor.weight();
+                let Tup2(ts, v) = *cursor.val();
+                if w > 0 {
+                    if matches!(session.last(), Some(last) if ts >= last.0 + gap) {
+                        flush(&mut session);
+                    }
+                    session.push((ts, v, w));
+                }
+                cursor.step_val();
+            }
+            flush(&mut session);
+
+            cursor.step_key();
+        }
+
+        OutputBatch::from_tuples((), tuples)
+    }
+
+    type InputHandle = CollectionHandle<u64, Tup2<Tup2<u64, i64>, i64>>;
+
+    fn session_window_circuit(
+        workers: usize,
+        gap: u64,
+        lateness: u64,
+    ) -> (DBSPHandle, InputHandle) {
+        Runtime::init_circuit(workers, move |circuit| {
+            let (input_stream, input_handle) =
+                circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+
+            let waterline = input_stream
+                .map_index(|(partition, Tup2(ts, val))| (*ts, (*partition, *val)))
+                .waterline_monotonic(|| 0, move |ts| ts.saturating_sub(lateness));
+
+            let expected = session_window_slow(&input_stream, gap);
+            let actual = input_stream
+                .session_window(gap, &waterline)
+                .gather(0)
+                .integrate();
+
+            expected.apply2(&actual, |expected, actual| assert_eq!(expected, actual));
+
+            Ok(input_handle)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_session_window() {
+        let (circuit, (input, expected)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) =
+                circuit.add_input_indexed_zset::<u64, Tup2<u64, i64>, i64>();
+            let (expected, expected_handle) =
+                circuit.add_input_indexed_zset::<u64, Tup2<Tup2<u64, u64>, Tup2<u64, i64>>, i64>();
+
+            let waterline = circuit.add_source(Generator::new(|| 0u64));
+            input
+                .session_window(10, &waterline)
+                .apply2(&expected, |actual, expected| assert_eq!(actual, expected));
+
+            Ok((input_handle, expected_handle))
+        })
+        .unwrap();
+
+        // Two sessions.
+        input.append(&mut vec![
+            (1, Tup2(Tup2(0, 1), 1)),
+            (1, Tup2(Tup2(5, 2), 1)),
+            (1, Tup2(Tup2(20, 3), 1)),
+        ]);
+        expected.append(&mut vec![
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(0, 1)), 1)),
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(5, 2)), 1)),
+            (1, Tup2(Tup2(Tup2(20, 30), Tup2(20, 3)), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        // A late record merges the two sessions.
+        input.append(&mut vec![(1, Tup2(Tup2(12, 4), 1))]);
+        expected.append(&mut vec![
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(0, 1)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(5, 2)), -1)),
+            (1, Tup2(Tup2(Tup2(20, 30), Tup2(20, 3)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(0, 1)), 1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(5, 2)), 1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(12, 4)), 1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(20, 3)), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        // Deleting the record splits the session again.
+        input.append(&mut vec![(1, Tup2(Tup2(12, 4), -1))]);
+        expected.append(&mut vec![
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(0, 1)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(5, 2)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(12, 4)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 30), Tup2(20, 3)), -1)),
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(0, 1)), 1)),
+            (1, Tup2(Tup2(Tup2(0, 15), Tup2(5, 2)), 1)),
+            (1, Tup2(Tup2(Tup2(20, 30), Tup2(20, 3)), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        // Sessions far from the update are not affected.
+        input.append(&mut vec![
+            (1, Tup2(Tup2(100, 5), 1)),
+            (2, Tup2(Tup2(3, 6), 1)),
+        ]);
+        expected.append(&mut vec![
+            (1, Tup2(Tup2(Tup2(100, 110), Tup2(100, 5)), 1)),
+            (2, Tup2(Tup2(Tup2(3, 13), Tup2(3, 6)), 1)),
+        ]);
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn test_session_window_out_of_order() {
+        let (mut circuit, input) = session_window_circuit(4, 10, u64::MAX);
+
+        input.append(&mut vec![
+            (0, Tup2(Tup2(30, 1), 1)),
+            (0, Tup2(Tup2(50, 1), 1)),
+            (1, Tup2(Tup2(30, 1), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        input.append(&mut vec![
+            (0, Tup2(Tup2(40, 2), 1)),
+            (0, Tup2(Tup2(0, 2), 1)),
+            (1, Tup2(Tup2(21, 2), 1)),
+        ]);
+        circuit.step().unwrap();
+
+        input.append(&mut vec![
+            (0, Tup2(Tup2(30, 1), -1)),
+            (0, Tup2(Tup2(40, 2), 1)),
+            (1, Tup2(Tup2(30, 1), -1)),
+        ]);
+        circuit.step().unwrap();
+
+        circuit.kill().unwrap();
+    }
+
+    type InputTuple = (u64, Tup2<Tup2<u64, i64>, i64>);
+    type InputBatch = Vec<InputTuple>;
+
+    fn input_tuple(partitions: u64, window: (u64, u64)) -> impl Strategy<Value = InputTuple> {
+        (
+            (0..partitions),
+            (
+                (window.0..window.1, 0..5i64).prop_map(|(x, y)| Tup2(x, y)),
+                -1..2i64,
+            )
+                .prop_map(|(x, y)| Tup2(x, y)),
+        )
+    }
+
+    fn input_batch(
+        partitions: u64,
+        window: (u64, u64),
+        max_batch_size: usize,
+    ) -> impl Strategy<Value = InputBatch> {
+        collection::vec(input_tuple(partitions, window), 0..max_batch_size)
+    }
+
+    fn input_trace(
+        partitions: u64,
+        epoch: u64,
+        max_batch_size: usize,
+        max_batches: usize,
+    ) -> impl Strategy<Value = Vec<InputBatch>> {
+        collection::vec(
+            input_batch(partitions, (0, epoch), max_batch_size),
+            0..max_batches,
+        )
+    }
+
+    fn input_trace_quasi_monotone(
+        partitions: u64,
+        window_size: u64,
+        window_step: u64,
+        max_batch_size: usize,
+        batches: usize,
+    ) -> impl Strategy<Value = Vec<InputBatch>> {
+        (0..batches)
+            .map(|i| {
+                input_batch(
+                    partitions,
+                    (i as u64 * window_step, i as u64 * window_step + window_size),
+                    max_batch_size,
+                )
+                .boxed()
+            })
+            .collect::<Vec<_>>()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(20))]
+
+        #[test]
+        fn proptest_session_window(trace in input_trace(5, 1_000, 20, 20)) {
+            let (mut circuit, input) = session_window_circuit(4, 50, u64::MAX);
+
+            for mut batch in trace {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+
+        #[test]
+        fn proptest_session_window_quasi_monotone(trace in input_trace_quasi_monotone(5, 1_000, 200, 20, 50)) {
+            let (mut circuit, input) = session_window_circuit(4, 50, 1_000);
+
+            for mut batch in trace {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+            }
+
+            circuit.kill().unwrap();
+        }
+    }
+}
@@ -3,13 +3,15 @@
 use crate::{
     algebra::{IndexedZSet, NegByRef},
     circuit::{
+        checkpoint::{read_state, state_path, write_state},
         operator_traits::{Operator, TernaryOperator},
         Circuit, OwnershipPreference, Scope, Stream,
     },
     operator::trace::TraceBound,
     trace::{cursor::Cursor, BatchReader, Spine},
+    Error as DBSPError,
 };
-use std::{borrow::Cow, cmp::max, marker::PhantomData};
+use std::{borrow::Cow, cmp::max, marker::PhantomData, path::Path};
 
 impl<C, B> Stream<C, B>
 where
@@ -125,6 +127,15 @@ where
         // Do we have meaningful examples of using windows inside nested scopes?
         panic!("'Window' operator used in fixedpoint iteration")
     }
+
+    fn commit(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        write_state(&state_path(base, persistent_id, "window"), &self.window)
+    }
+
+    fn restore(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        self.window = read_state(&state_path(base, persistent_id, "window"))?;
+        Ok(())
+    }
 }
 
 impl<B> TernaryOperator<Spine<B>, B, (B::Key, B::Key), B> for Window<B>
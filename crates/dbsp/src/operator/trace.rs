@@ -3,6 +3,7 @@ use crate::circuit::metadata::{
 };
 use crate::{
     circuit::{
+        checkpoint::{read_state, state_path, write_state},
         metadata::{MetaItem, OperatorMeta},
         operator_traits::{BinaryOperator, Operator, StrictOperator, StrictUnaryOperator},
         Circuit, ExportId, ExportStream, FeedbackConnector, GlobalNodeId, OwnershipPreference,
@@ -10,11 +11,11 @@ use crate::{
     },
     circuit_cache_key,
     trace::{cursor::Cursor, Batch, BatchReader, Builder, Filter, Spine, Trace},
-    DBData, Timestamp,
+    DBData, Error as DBSPError, Timestamp,
 };
 use crate::{DBTimestamp, IndexedZSet};
 use size_of::SizeOf;
-use std::{borrow::Cow, cell::RefCell, marker::PhantomData, ops::DerefMut, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, marker::PhantomData, ops::DerefMut, path::Path, rc::Rc};
 
 circuit_cache_key!(TraceId<B, D, K, V>(GlobalNodeId => (Stream<B, D>, TraceBounds<K, V>)));
 circuit_cache_key!(DelayedTraceId<B, D>(GlobalNodeId => Stream<B, D>));
@@ -466,6 +467,39 @@ where
         trace
     }
 
+    /// Like
+    /// [`integrate_trace_retain_values`](`Self::integrate_trace_retain_values`),
+    /// but also adds `lower_key_bound` to the bounds of the trace.
+    ///
+    /// A trace needs at least one key bound.  Use this method for traces that
+    /// no other operator installs a key bound on, i.e., traces that are not
+    /// also consumed through [`integrate_trace`](`Self::integrate_trace`) or
+    /// similar.
+    #[track_caller]
+    pub(crate) fn integrate_trace_retain_values_with_key_bound<TS, RV>(
+        &self,
+        bounds_stream: &Stream<C, TS>,
+        lower_key_bound: TraceBound<B::Key>,
+        retain_value: RV,
+    ) -> Stream<C, Spine<B>>
+    where
+        B: Batch<Time = ()> + Send,
+        TS: DBData,
+        RV: Fn(&B::Val, &TS) -> bool + Clone + 'static,
+    {
+        // See `integrate_trace_retain_values` for why `shard` is needed here.
+        let (trace, bounds) = self.shard().integrate_trace_inner();
+
+        bounds.add_key_bound(lower_key_bound);
+        bounds_stream.inspect(move |ts| {
+            let ts = ts.clone();
+            let retain_value = retain_value.clone();
+            bounds.set_val_filter(Box::new(move |val| retain_value(val, &ts)));
+        });
+
+        trace
+    }
+
     // TODO: this method should replace `Stream::integrate()`.
     #[track_caller]
     pub fn integrate_trace(&self) -> Stream<C, Spine<B>>
@@ -887,6 +921,25 @@ where
     fn fixedpoint(&self, scope: Scope) -> bool {
         !self.dirty[scope as usize]
     }
+
+    fn commit(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        write_state(&state_path(base, persistent_id, "time"), &self.time)?;
+        if let Some(trace) = self.trace.as_ref() {
+            trace.commit(&state_path(base, persistent_id, "trace"))?;
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        self.time = read_state(&state_path(base, persistent_id, "time"))?;
+        let trace_path = state_path(base, persistent_id, "trace");
+        if trace_path.exists() {
+            self.trace
+                .get_or_insert_with(|| T::new(None))
+                .restore(&trace_path)?;
+        }
+        Ok(())
+    }
 }
 
 impl<T> StrictOperator<T> for Z1Trace<T>
@@ -6,15 +6,18 @@ use crate::circuit::metadata::{
 use crate::{
     algebra::HasZero,
     circuit::{
+        checkpoint::{read_state, state_path, write_state},
         metadata::{MetaItem, OperatorMeta},
         operator_traits::{Operator, StrictOperator, StrictUnaryOperator, UnaryOperator},
         Circuit, ExportId, ExportStream, FeedbackConnector, GlobalNodeId, OwnershipPreference,
         Scope, Stream,
     },
-    circuit_cache_key, NumEntries,
+    circuit_cache_key,
+    trace::Rkyv,
+    Error as DBSPError, NumEntries,
 };
 use size_of::{Context, SizeOf};
-use std::{borrow::Cow, mem::replace};
+use std::{borrow::Cow, mem::replace, path::Path};
 
 circuit_cache_key!(DelayedId<C, D>(GlobalNodeId => Stream<C, D>));
 circuit_cache_key!(NestedDelayedId<C, D>(GlobalNodeId => Stream<C, D>));
@@ -36,7 +39,7 @@ where
 impl<C, D> DelayedFeedback<C, D>
 where
     C: Circuit,
-    D: Eq + SizeOf + NumEntries + Clone + HasZero + 'static,
+    D: Eq + SizeOf + NumEntries + Clone + HasZero + Rkyv + 'static,
 {
     /// Create a feedback loop with `Z1` operator.  Use [`Self::connect`] to
     /// close the loop.
@@ -85,7 +88,7 @@ pub struct DelayedNestedFeedback<C, D> {
 impl<C, D> DelayedNestedFeedback<C, D>
 where
     C: Circuit,
-    D: Eq + SizeOf + NumEntries + Clone + 'static,
+    D: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     /// Create a feedback loop with `Z1` operator.  Use [`Self::connect`] to
     /// close the loop.
@@ -119,7 +122,7 @@ where
     /// Applies [`Z1`] operator to `self`.
     pub fn delay(&self) -> Stream<C, D>
     where
-        D: Eq + SizeOf + NumEntries + Clone + HasZero + 'static,
+        D: Eq + SizeOf + NumEntries + Clone + HasZero + Rkyv + 'static,
     {
         self.circuit()
             .cache_get_or_insert_with(DelayedId::new(self.origin_node_id().clone()), || {
@@ -131,7 +134,7 @@ where
     /// Applies [`Z1Nested`] operator to `self`.
     pub fn delay_nested(&self) -> Stream<C, D>
     where
-        D: Eq + Clone + HasZero + SizeOf + NumEntries + 'static,
+        D: Eq + Clone + HasZero + SizeOf + NumEntries + Rkyv + 'static,
     {
         self.circuit()
             .cache_get_or_insert_with(NestedDelayedId::new(self.origin_node_id().clone()), || {
@@ -185,7 +188,7 @@ where
 
 impl<T> Operator for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn name(&self) -> Cow<'static, str> {
         Cow::from("Z^-1")
@@ -215,11 +218,20 @@ where
             true
         }
     }
+
+    fn commit(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        write_state(&state_path(base, persistent_id, "values"), &self.values)
+    }
+
+    fn restore(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        self.values = read_state(&state_path(base, persistent_id, "values"))?;
+        Ok(())
+    }
 }
 
 impl<T> UnaryOperator<T, T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn eval(&mut self, i: &T) -> T {
         replace(&mut self.values, i.clone())
@@ -236,7 +248,7 @@ where
 
 impl<T> StrictOperator<T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn get_output(&mut self) -> T {
         self.empty_output = self.values.num_entries_shallow() == 0;
@@ -250,7 +262,7 @@ where
 
 impl<T> StrictUnaryOperator<T, T> for Z1<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn eval_strict(&mut self, i: &T) {
         self.values = i.clone();
@@ -323,7 +335,7 @@ impl<T> Z1Nested<T> {
 
 impl<T> Operator for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn name(&self) -> Cow<'static, str> {
         Cow::from("Z^-1 (nested)")
@@ -383,11 +395,25 @@ where
             false
         }
     }
+
+    fn commit(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        write_state(&state_path(base, persistent_id, "values"), &self.values)?;
+        write_state(
+            &state_path(base, persistent_id, "timestamp"),
+            &(self.timestamp as u64),
+        )
+    }
+
+    fn restore(&mut self, base: &Path, persistent_id: &str) -> Result<(), DBSPError> {
+        self.values = read_state(&state_path(base, persistent_id, "values"))?;
+        self.timestamp = read_state::<u64>(&state_path(base, persistent_id, "timestamp"))? as usize;
+        Ok(())
+    }
 }
 
 impl<T> UnaryOperator<T, T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn eval(&mut self, i: &T) -> T {
         debug_assert!(self.timestamp <= self.values.len());
@@ -426,7 +452,7 @@ where
 
 impl<T> StrictOperator<T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn get_output(&mut self) -> T {
         if self.timestamp >= self.values.len() {
@@ -450,7 +476,7 @@ where
 
 impl<T> StrictUnaryOperator<T, T> for Z1Nested<T>
 where
-    T: Eq + SizeOf + NumEntries + Clone + 'static,
+    T: Eq + SizeOf + NumEntries + Clone + Rkyv + 'static,
 {
     fn eval_strict(&mut self, i: &T) {
         debug_assert!(self.timestamp < self.values.len());
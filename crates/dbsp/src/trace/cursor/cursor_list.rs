@@ -248,6 +248,22 @@ where
         self.minimize_keys();
     }
 
+    // Cursors that rule out `key` may be left behind, so unlike `seek_key` this
+    // only tracks the cursors positioned on `key` rather than minimizing across
+    // all of them.
+    fn seek_key_exact(&mut self, key: &K) -> bool {
+        self.current_key.clear();
+        for (index, cursor) in self.cursors.iter_mut().enumerate() {
+            if cursor.seek_key_exact(key) {
+                self.current_key.push(index);
+            }
+        }
+
+        self.set_val_direction(Direction::Forward);
+        self.minimize_vals();
+        !self.current_key.is_empty()
+    }
+
     fn seek_key_with<P>(&mut self, predicate: P)
     where
         P: Fn(&K) -> bool + Clone,
@@ -202,6 +202,24 @@ pub trait Cursor<K, V, T, R> {
         self.seek_key_with(|k| k >= key)
     }
 
+    /// Looks up `key`, returning `true` and positioning the cursor on it if it
+    /// is present, and otherwise returning `false`.
+    ///
+    /// Unlike [`seek_key`](Self::seek_key), this method may return `false`
+    /// without moving the cursor if it can cheaply rule out `key`, e.g. using a
+    /// Bloom filter.  Therefore, after this method returns, the cursor should
+    /// only be used to access the values for `key` (if it returned `true`)
+    /// or for seeking forward to keys greater than `key`, with this method or
+    /// [`seek_key`](Self::seek_key).  In particular, it is not safe to step
+    /// through keys with [`step_key`](Self::step_key).
+    fn seek_key_exact(&mut self, key: &K) -> bool
+    where
+        K: PartialOrd,
+    {
+        self.seek_key(key);
+        self.key_valid() && self.key() == key
+    }
+
     /// Advances the cursor to the first key that satisfies `predicate`.
     /// Assumes that `predicate` remains true once it turns true.
     fn seek_key_with<P>(&mut self, predicate: P)
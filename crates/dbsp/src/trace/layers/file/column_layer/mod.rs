@@ -4,8 +4,7 @@ pub(crate) mod cursor;
 
 use feldera_storage::file::reader::{FallibleEq, Reader};
 use rand::{seq::index::sample, Rng};
-use rkyv::ser::Serializer;
-use rkyv::{Archive, Archived, Deserialize, Fallible, Serialize};
+use rkyv::{Archive, Archived, Deserialize, Fallible, Resolver, Serialize};
 use size_of::SizeOf;
 use std::ops::AddAssign;
 use std::{
@@ -16,7 +15,7 @@ use std::{
 
 use crate::algebra::{AddAssignByRef, AddByRef, NegByRef};
 use crate::trace::layers::{Builder, Trie, TupleBuilder};
-use crate::trace::ord::file::StorageBackend;
+use crate::trace::ord::file::{storage_backend, StorageBackend};
 use crate::{DBData, DBWeight, NumEntries};
 
 pub use self::builders::FileColumnLayerBuilder;
@@ -44,7 +43,7 @@ where
 
     pub fn empty() -> Self {
         Self {
-            file: Reader::empty(&StorageBackend::default_for_thread()).unwrap(),
+            file: Reader::empty(&storage_backend()).unwrap(),
             lower_bound: 0,
         }
     }
@@ -145,16 +144,28 @@ where
     }
 }
 
+/// Contents of a [`FileColumnLayer`] in the form in which it is archived.
+#[derive(Archive, Serialize)]
+pub struct FileColumnLayerTuples<K, R> {
+    tuples: Vec<(K, R)>,
+}
+
+/// A `FileColumnLayer` is archived as [`FileColumnLayerTuples`].
+/// Deserializing it writes the tuples to a new layer file.
 impl<K, R> Archive for FileColumnLayer<K, R>
 where
     K: DBData,
     R: DBWeight,
 {
-    type Archived = ();
-    type Resolver = ();
-
-    unsafe fn resolve(&self, _pos: usize, _resolver: Self::Resolver, _out: *mut Self::Archived) {
-        unimplemented!();
+    type Archived = ArchivedFileColumnLayerTuples<K, R>;
+    type Resolver = (
+        FileColumnLayerTuples<K, R>,
+        Resolver<FileColumnLayerTuples<K, R>>,
+    );
+
+    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+        let (tuples, resolver) = resolver;
+        tuples.resolve(pos, resolver, out);
     }
 }
 
@@ -162,21 +173,33 @@ impl<K, R, S> Serialize<S> for FileColumnLayer<K, R>
 where
     K: DBData,
     R: DBWeight,
-    S: Serializer + ?Sized,
+    S: Fallible + ?Sized,
+    FileColumnLayerTuples<K, R>: Serialize<S>,
 {
-    fn serialize(&self, _serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        unimplemented!();
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let mut tuples = Vec::with_capacity(self.keys());
+        let mut cursor = self.cursor();
+        while let Some(tuple) = cursor.take_current_item() {
+            tuples.push(tuple);
+        }
+        let tuples = FileColumnLayerTuples { tuples };
+        let resolver = tuples.serialize(serializer)?;
+        Ok((tuples, resolver))
     }
 }
 
-impl<K, R, D> Deserialize<FileColumnLayer<K, R>, D> for Archived<FileColumnLayer<K, R>>
+impl<K, R, D> Deserialize<FileColumnLayer<K, R>, D> for ArchivedFileColumnLayerTuples<K, R>
 where
     K: DBData,
     R: DBWeight,
-    D: Fallible,
+    D: Fallible + ?Sized,
+    Archived<Vec<(K, R)>>: Deserialize<Vec<(K, R)>, D>,
 {
-    fn deserialize(&self, _deserializer: &mut D) -> Result<FileColumnLayer<K, R>, D::Error> {
-        unimplemented!();
+    fn deserialize(&self, deserializer: &mut D) -> Result<FileColumnLayer<K, R>, D::Error> {
+        let tuples: Vec<(K, R)> = self.tuples.deserialize(deserializer)?;
+        let mut builder = FileColumnLayerBuilder::with_capacity(tuples.len());
+        builder.extend_tuples(tuples);
+        Ok(builder.done())
     }
 }
 
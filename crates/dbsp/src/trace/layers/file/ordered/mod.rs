@@ -6,12 +6,13 @@ use feldera_storage::file::{
     writer::{Parameters, Writer2},
 };
 use rand::{seq::index::sample, Rng};
+use rkyv::{Archive, Archived, Deserialize, Fallible, Resolver, Serialize};
 
 use crate::{
     algebra::{AddAssignByRef, AddByRef, NegByRef},
     trace::{
         layers::{Builder, Cursor, MergeBuilder, Trie, TupleBuilder},
-        ord::file::StorageBackend,
+        ord::file::{storage_backend, StorageBackend},
     },
     DBData, DBWeight, NumEntries,
 };
@@ -85,7 +86,7 @@ where
 {
     pub fn empty() -> Self {
         Self {
-            file: Reader::empty(&StorageBackend::default_for_thread()).unwrap(),
+            file: Reader::empty(&storage_backend()).unwrap(),
             lower_bound: 0,
         }
     }
@@ -136,6 +137,71 @@ where
     }
 }
 
+/// Contents of a [`FileOrderedLayer`] in the form in which it is archived.
+#[derive(Archive, Serialize)]
+pub struct FileOrderedLayerTuples<K, V, R> {
+    tuples: Vec<(K, (V, R))>,
+}
+
+/// A `FileOrderedLayer` is archived as [`FileOrderedLayerTuples`].
+/// Deserializing it writes the tuples to a new layer file.
+impl<K, V, R> Archive for FileOrderedLayer<K, V, R>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+{
+    type Archived = ArchivedFileOrderedLayerTuples<K, V, R>;
+    type Resolver = (
+        FileOrderedLayerTuples<K, V, R>,
+        Resolver<FileOrderedLayerTuples<K, V, R>>,
+    );
+
+    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+        let (tuples, resolver) = resolver;
+        tuples.resolve(pos, resolver, out);
+    }
+}
+
+impl<K, V, R, S> Serialize<S> for FileOrderedLayer<K, V, R>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+    S: Fallible + ?Sized,
+    FileOrderedLayerTuples<K, V, R>: Serialize<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let mut tuples = Vec::with_capacity(self.tuples());
+        let mut cursor = self.cursor();
+        while let Some((key, mut values)) = cursor.take_current_key_and_values() {
+            while let Some(value) = values.take_current_item() {
+                tuples.push((key.clone(), value));
+            }
+        }
+        let tuples = FileOrderedLayerTuples { tuples };
+        let resolver = tuples.serialize(serializer)?;
+        Ok((tuples, resolver))
+    }
+}
+
+impl<K, V, R, D> Deserialize<FileOrderedLayer<K, V, R>, D>
+    for ArchivedFileOrderedLayerTuples<K, V, R>
+where
+    K: DBData,
+    V: DBData,
+    R: DBWeight,
+    D: Fallible + ?Sized,
+    Archived<Vec<(K, (V, R))>>: Deserialize<Vec<(K, (V, R))>, D>,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<FileOrderedLayer<K, V, R>, D::Error> {
+        let tuples: Vec<(K, (V, R))> = self.tuples.deserialize(deserializer)?;
+        let mut builder = FileOrderedTupleBuilder::with_capacity(tuples.len());
+        builder.extend_tuples(tuples);
+        Ok(builder.done())
+    }
+}
+
 impl<K, V, R> NumEntries for FileOrderedLayer<K, V, R>
 where
     K: DBData,
@@ -240,7 +306,7 @@ where
                 value_cursor.step();
             }
             if n > 0 {
-                self.0.write0((&key, &())).unwrap();
+                self.0.write0((key, &())).unwrap();
             }
         }
         cursor.step();
@@ -384,7 +450,7 @@ where
     }
 
     fn with_key_capacity(_capacity: usize) -> Self {
-        Self(Writer2::new(&StorageBackend::default_for_thread(), Parameters::default()).unwrap())
+        Self(Writer2::new(&storage_backend(), Parameters::default()).unwrap())
     }
 
     fn reserve(&mut self, _additional: usize) {}
@@ -464,8 +530,7 @@ where
 
     fn new() -> Self {
         Self {
-            writer: Writer2::new(&StorageBackend::default_for_thread(), Parameters::default())
-                .unwrap(),
+            writer: Writer2::new(&storage_backend(), Parameters::default()).unwrap(),
             key: None,
         }
     }
@@ -526,6 +591,12 @@ where
         self.key.as_ref().unwrap()
     }
 
+    /// Returns `false` if `key` is definitely not in the layer, or `true` if it
+    /// might be, using the layer file's Bloom filter.
+    pub fn maybe_contains(&self, key: &K) -> bool {
+        self.storage.file.maybe_contains(key)
+    }
+
     pub fn take_current_key(&mut self) -> Option<K> {
         let key = self.key.take();
         self.step();
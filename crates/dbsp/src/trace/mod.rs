@@ -37,17 +37,12 @@ pub mod consolidation;
 pub mod cursor;
 pub mod layers;
 pub mod ord;
-#[cfg(feature = "persistence")]
-pub mod persistent;
 pub mod spine_fueled;
 
 pub use cursor::{Consumer, Cursor, ValueConsumer};
 use feldera_storage::buffer_cache::FBuf;
 pub use feldera_storage::file::{Deserializable, Deserializer, Rkyv, Serializer};
 
-#[cfg(feature = "persistence")]
-pub use persistent::PersistentTrace as Spine;
-#[cfg(not(feature = "persistence"))]
 pub use spine_fueled::Spine;
 
 #[cfg(test)]
@@ -57,12 +52,12 @@ use crate::{
     algebra::{HasZero, MonoidValue},
     circuit::Activator,
     time::{AntichainRef, Timestamp},
-    NumEntries,
+    Error, NumEntries,
 };
 use rand::Rng;
 use rkyv::{archived_root, Archive, Archived, Deserialize, Infallible, Serialize};
 use size_of::SizeOf;
-use std::{fmt::Debug, hash::Hash};
+use std::{fmt::Debug, hash::Hash, path::Path};
 
 /// Trait for data stored in batches.
 ///
@@ -262,6 +257,14 @@ pub trait Trace: BatchReader {
 
     fn key_filter(&self) -> &Option<Filter<Self::Key>>;
     fn value_filter(&self) -> &Option<Filter<Self::Val>>;
+
+    /// Writes the contents of the trace to directory `path`, replacing
+    /// any previous contents of the directory.
+    fn commit(&self, path: &Path) -> Result<(), Error>;
+
+    /// Replaces the contents of the trace with a checkpoint previously
+    /// written to `path` by [`Self::commit`].
+    fn restore(&mut self, path: &Path) -> Result<(), Error>;
 }
 
 /// A set of `(key, value, time, diff)` tuples whose contents may be read in
@@ -427,7 +430,7 @@ where
 
     /// Merges `self` with `other` by running merger to completion.
     fn merge(&self, other: &Self) -> Self {
-        let mut fuel = isize::max_value();
+        let mut fuel = isize::MAX;
         let mut merger = Self::Merger::new_merger(self, other);
         merger.work(self, other, &None, &None, &mut fuel);
         merger.done()
@@ -564,7 +567,7 @@ where
             let new_len = new.len();
             let position = batches
                 .binary_search_by(|element| element.len().cmp(&new_len).reverse())
-                .map_or_else(|err| err, |ok| ok);
+                .unwrap_or_else(|err| err);
             batches.insert(position, new);
         }
     }
@@ -15,7 +15,7 @@ use crate::{
     DBData, DBWeight, NumEntries,
 };
 use rand::Rng;
-use rkyv::{ser::Serializer, Archive, Archived, Deserialize, Fallible, Serialize};
+use rkyv::{Archive, Deserialize, Serialize};
 use size_of::SizeOf;
 use std::{
     fmt::{self, Debug, Display},
@@ -27,7 +27,7 @@ use std::{
 ///
 /// Each tuple in `FileIndexedZSet<K, V, R>` has key type `K`, value type `V`,
 /// weight type `R`, and time `()`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
 #[cfg_attr(test, derive(Eq))]
 pub struct FileIndexedZSet<K, V, R>
 where
@@ -494,6 +494,14 @@ where
         self.move_key(|key_cursor| key_cursor.seek(key));
     }
 
+    fn seek_key_exact(&mut self, key: &K) -> bool {
+        if !self.key_cursor.maybe_contains(key) {
+            return false;
+        }
+        self.seek_key(key);
+        self.key_valid() && self.key() == key
+    }
+
     fn seek_key_with<P>(&mut self, predicate: P)
     where
         P: Fn(&K) -> bool + Clone,
@@ -707,41 +715,3 @@ where
         // XXX
     }
 }
-
-impl<K, V, R> Archive for FileIndexedZSet<K, V, R>
-where
-    K: DBData,
-    V: DBData,
-    R: DBWeight,
-{
-    type Archived = ();
-    type Resolver = ();
-
-    unsafe fn resolve(&self, _pos: usize, _resolver: Self::Resolver, _out: *mut Self::Archived) {
-        unimplemented!();
-    }
-}
-
-impl<K, V, R, S> Serialize<S> for FileIndexedZSet<K, V, R>
-where
-    K: DBData,
-    V: DBData,
-    R: DBWeight,
-    S: Serializer + ?Sized,
-{
-    fn serialize(&self, _serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        unimplemented!();
-    }
-}
-
-impl<K, V, R, D> Deserialize<FileIndexedZSet<K, V, R>, D> for Archived<FileIndexedZSet<K, V, R>>
-where
-    K: DBData,
-    V: DBData,
-    R: DBWeight,
-    D: Fallible,
-{
-    fn deserialize(&self, _deserializer: &mut D) -> Result<FileIndexedZSet<K, V, R>, D::Error> {
-        unimplemented!();
-    }
-}
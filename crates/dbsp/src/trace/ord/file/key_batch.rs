@@ -1,6 +1,7 @@
 use crate::{
     time::{Antichain, AntichainRef},
     trace::{
+        consolidation::consolidate,
         layers::{
             file::ordered::{
                 FileOrderedCursor, FileOrderedLayer, FileOrderedLayerConsumer,
@@ -14,7 +15,7 @@ use crate::{
     DBData, DBTimestamp, DBWeight, NumEntries,
 };
 use rand::Rng;
-use rkyv::{ser::Serializer, Archive, Archived, Deserialize, Fallible, Serialize};
+use rkyv::{Archive, Deserialize, Serialize};
 use size_of::SizeOf;
 use std::fmt::{self, Debug, Display};
 
@@ -22,7 +23,7 @@ use std::fmt::{self, Debug, Display};
 ///
 /// Each tuple in `FileKeyBatch<K, T, R>` has key type `K`, value type `()`,
 /// weight type `R`, and time type `R`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Archive, Serialize, Deserialize)]
 pub struct FileKeyBatch<K, T, R>
 where
     K: DBData,
@@ -87,7 +88,9 @@ where
     }
 
     fn consumer(self) -> Self::Consumer {
-        todo!()
+        FileKeyConsumer {
+            consumer: FileOrderedLayerConsumer::from(self.layer),
+        }
     }
 
     fn key_count(&self) -> usize {
@@ -156,8 +159,28 @@ where
     T: DBTimestamp,
     R: DBWeight,
 {
-    fn do_recede_to(&mut self, _frontier: &T) {
-        todo!()
+    fn do_recede_to(&mut self, frontier: &T) {
+        let mut builder = FileOrderedTupleBuilder::<K, T, R>::new();
+        let mut times = Vec::new();
+        let mut cursor = self.layer.cursor();
+        while cursor.valid() {
+            // Advance each time to the frontier, then combine the weights of
+            // times that became equal.
+            let mut values = cursor.values();
+            while values.valid() {
+                let (time, diff) = values.item();
+                times.push((time.meet(frontier), diff.clone()));
+                values.step();
+            }
+            consolidate(&mut times);
+
+            let key = cursor.current_key();
+            for (time, diff) in times.drain(..) {
+                builder.push_tuple((key.clone(), (time, diff)));
+            }
+            cursor.step();
+        }
+        self.layer = builder.done();
     }
 }
 
@@ -559,41 +582,3 @@ where
         // XXX
     }
 }
-
-impl<K, T, R> Archive for FileKeyBatch<K, T, R>
-where
-    K: DBData,
-    T: DBTimestamp,
-    R: DBWeight,
-{
-    type Archived = ();
-    type Resolver = ();
-
-    unsafe fn resolve(&self, _pos: usize, _resolver: Self::Resolver, _out: *mut Self::Archived) {
-        unimplemented!();
-    }
-}
-
-impl<K, T, R, S> Serialize<S> for FileKeyBatch<K, T, R>
-where
-    K: DBData,
-    T: DBTimestamp,
-    R: DBWeight,
-    S: Serializer + ?Sized,
-{
-    fn serialize(&self, _serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        unimplemented!();
-    }
-}
-
-impl<K, T, R, D> Deserialize<FileKeyBatch<K, T, R>, D> for Archived<FileKeyBatch<K, T, R>>
-where
-    K: DBData,
-    T: DBTimestamp,
-    R: DBWeight,
-    D: Fallible,
-{
-    fn deserialize(&self, _deserializer: &mut D) -> Result<FileKeyBatch<K, T, R>, D::Error> {
-        unimplemented!();
-    }
-}
@@ -1,4 +1,16 @@
 //! Trace and batch implementations based on files.
+//!
+//! Batches in this module store their contents in immutable layer files
+//! written through a per-worker [`StorageBackend`].  Merging two batches
+//! writes a new layer file, and dropping a batch deletes its file, so a
+//! [`Spine`](crate::trace::Spine) of these batches keeps its state on storage
+//! rather than in memory.
+//!
+//! When the current thread is a [`Runtime`] worker, the layer files go in
+//! [`Runtime::storage_dir`] and blocks are cached in a buffer cache shared by
+//! all the runtime's workers, both configured via
+//! [`RuntimeConfig`](crate::RuntimeConfig).  Other threads use a temporary
+//! directory and a process-wide buffer cache.
 
 pub mod indexed_zset_batch;
 pub mod key_batch;
@@ -10,4 +22,70 @@ pub use key_batch::FileKeyBatch;
 pub use val_batch::FileValBatch;
 pub use zset_batch::FileZSet;
 
-pub type StorageBackend = feldera_storage::backend::DefaultBackend;
+use crate::Runtime;
+use feldera_storage::{
+    backend::{AtomicIncrementOnlyI64, DefaultBackend},
+    buffer_cache::{BufferCache, TinyLfuCache},
+};
+use once_cell::sync::Lazy;
+use std::{rc::Rc, sync::Arc};
+use tempfile::TempDir;
+
+pub type StorageBackend = BufferCache<DefaultBackend>;
+
+/// Buffer cache shared by threads that don't belong to a [`Runtime`].
+static DEFAULT_BUFFER_CACHE: Lazy<Arc<TinyLfuCache>> = Lazy::new(Default::default);
+
+/// File identifiers for backends that share [`DEFAULT_BUFFER_CACHE`].
+static DEFAULT_FILE_IDS: Lazy<Arc<AtomicIncrementOnlyI64>> = Lazy::new(Default::default);
+
+/// Per-thread storage backend, followed by the temporary directory that holds
+/// its files if the thread doesn't belong to a [`Runtime`].  The backend is
+/// declared first so that it is dropped before the directory.
+struct ThreadStorage {
+    backend: Rc<StorageBackend>,
+    _tempdir: Option<TempDir>,
+}
+
+impl ThreadStorage {
+    fn new() -> Self {
+        match Runtime::runtime() {
+            Some(runtime) => {
+                let backend = DefaultBackend::new(
+                    runtime.storage_dir(Runtime::worker_index()),
+                    runtime.file_ids(),
+                );
+                Self {
+                    backend: Rc::new(BufferCache::with_backend_lfu(
+                        backend,
+                        runtime.buffer_cache(),
+                    )),
+                    _tempdir: None,
+                }
+            }
+            None => {
+                let tempdir =
+                    tempfile::tempdir().expect("failed to create temporary storage directory");
+                let backend = DefaultBackend::new(tempdir.path(), DEFAULT_FILE_IDS.clone());
+                Self {
+                    backend: Rc::new(BufferCache::with_backend_lfu(
+                        backend,
+                        DEFAULT_BUFFER_CACHE.clone(),
+                    )),
+                    _tempdir: Some(tempdir),
+                }
+            }
+        }
+    }
+}
+
+thread_local! {
+    static STORAGE: ThreadStorage = ThreadStorage::new();
+}
+
+/// Returns the storage backend for layer files created by the current thread.
+pub fn storage_backend() -> Rc<StorageBackend> {
+    STORAGE.with(|storage| storage.backend.clone())
+}
+
+mod tests;
@@ -0,0 +1,288 @@
+//! Tests for the file-based batches.
+//!
+//! Besides the unit tests in this module, there are proptests that check that
+//! a [`Spine`] of file batches behaves like the [`TestBatch`] reference trace.
+//! The unit tests at the end of this module are cases found by the proptests.
+#![cfg(test)]
+
+mod proptests;
+
+use super::{FileIndexedZSet, FileKeyBatch, FileValBatch, FileZSet};
+use crate::{
+    time::NestedTimestamp32,
+    trace::{
+        ord::VecKeyBatch,
+        test_batch::{assert_trace_eq, batch_to_tuples},
+        unaligned_deserialize, Batch, BatchReader, Consumer, Cursor, Spine, Trace, ValueConsumer,
+    },
+    Runtime, RuntimeConfig,
+};
+use feldera_storage::file::to_bytes;
+use proptests::{spine_and_model, ComplexKey};
+use std::fs::read_dir;
+
+// Returns the number of files in the current worker's storage directory.
+fn count_files() -> usize {
+    let dir = Runtime::runtime()
+        .unwrap()
+        .storage_dir(Runtime::worker_index());
+    read_dir(dir).unwrap().count()
+}
+
+#[test]
+fn test_spine_merges_delete_files() {
+    let storage = tempfile::tempdir().unwrap();
+    Runtime::run_with_config(1, RuntimeConfig::with_storage(storage.path()), || {
+        let mut spine: Spine<FileZSet<u64, i64>> = Spine::new(None);
+        for i in 0..100 {
+            spine.insert(FileZSet::from_keys((), vec![(i, 1), (i + 1, 1)]));
+        }
+
+        // Merging deletes the input batches' files, leaving only the file
+        // for the merged batch.
+        let batch = spine.consolidate().unwrap();
+        assert_eq!(batch.len(), 101);
+        assert_eq!(count_files(), 1);
+
+        drop(batch);
+        assert_eq!(count_files(), 0);
+    })
+    .join()
+    .unwrap();
+
+    assert!(storage.path().join("worker-0").is_dir());
+}
+
+#[test]
+fn test_key_batch_recede_to() {
+    let mut file_spine: Spine<FileKeyBatch<u64, u32, i64>> = Spine::new(None);
+    let mut vec_spine: Spine<VecKeyBatch<u64, u32, i64>> = Spine::new(None);
+    for time in 0..10 {
+        let tuples = (0..20)
+            .map(|key| (key, if (key + time as u64) % 3 == 0 { -1 } else { 1 }))
+            .collect::<Vec<_>>();
+        file_spine.insert(FileKeyBatch::from_tuples(time, tuples.clone()));
+        vec_spine.insert(VecKeyBatch::from_tuples(time, tuples));
+    }
+
+    let mut file_batch = file_spine.consolidate().unwrap();
+    let mut vec_batch = vec_spine.consolidate().unwrap();
+    file_batch.recede_to(&5);
+    vec_batch.recede_to(&5);
+    assert_eq!(batch_to_tuples(&file_batch), batch_to_tuples(&vec_batch));
+}
+
+#[test]
+fn test_seek_key_exact() {
+    let mut spine: Spine<FileIndexedZSet<u64, u64, i64>> = Spine::new(None);
+    for batch in 0..5 {
+        let tuples = (0..100)
+            .map(|key| ((key * 10 + batch * 2, key), 1))
+            .collect::<Vec<_>>();
+        spine.insert(FileIndexedZSet::from_tuples((), tuples));
+    }
+
+    let mut cursor = spine.cursor();
+    for key in 0..1000 {
+        let present = key % 2 == 0;
+        assert_eq!(cursor.seek_key_exact(&key), present, "key {key}");
+        if present {
+            assert_eq!(cursor.key(), &key);
+            assert_eq!(cursor.val(), &(key / 10));
+            assert_eq!(cursor.weight(), 1);
+        }
+    }
+}
+
+// Checks that `batch` is unchanged by archiving and restoring it, and that
+// consuming the restored batch yields all of its tuples.
+fn check_archive_and_consume<B>(batch: B)
+where
+    B: Batch,
+{
+    let expected = batch_to_tuples(&batch);
+
+    let bytes = to_bytes(&batch).unwrap();
+    let restored: B = unaligned_deserialize(&bytes);
+    assert_eq!(batch_to_tuples(&restored), expected);
+    assert_eq!(restored.lower(), batch.lower());
+    assert_eq!(restored.upper(), batch.upper());
+
+    let mut tuples = Vec::new();
+    let mut consumer = restored.consumer();
+    while consumer.key_valid() {
+        let (key, mut values) = consumer.next_key();
+        while values.value_valid() {
+            let (val, diff, time) = values.next_value();
+            tuples.push(((key.clone(), val, time), diff));
+        }
+    }
+    tuples.sort();
+    assert_eq!(tuples, expected);
+}
+
+#[test]
+fn test_archive_and_consume() {
+    let tuples = (0..100u64)
+        .map(|i| ((i / 3, i % 7), if i % 5 == 0 { -1 } else { 1 }))
+        .collect::<Vec<_>>();
+    let keys = tuples
+        .iter()
+        .map(|((key, _), diff)| (*key, *diff))
+        .collect::<Vec<_>>();
+
+    check_archive_and_consume(FileZSet::<u64, i64>::from_keys((), keys.clone()));
+    check_archive_and_consume(FileIndexedZSet::<u64, u64, i64>::from_tuples(
+        (),
+        tuples.clone(),
+    ));
+    check_archive_and_consume(FileKeyBatch::<u64, u32, i64>::from_tuples(3, keys));
+    check_archive_and_consume(FileValBatch::<u64, u64, u32, i64>::from_tuples(3, tuples));
+}
+
+#[test]
+fn vals_are_sorted() {
+    let (spine, model) = spine_and_model::<FileIndexedZSet<String, u64, u64>>(vec![
+        ((), vec![((String::from(""), 9777), 1)]),
+        (
+            (),
+            vec![((String::from(""), 0), 1), ((String::from(""), 0), 1)],
+        ),
+    ]);
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn empty_batch() {
+    let (spine, model) =
+        spine_and_model::<FileValBatch<String, String, u32, u64>>(vec![(0, vec![])]);
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn insert_bug() {
+    let (spine, model) = spine_and_model::<FileValBatch<String, String, u32, u64>>(vec![
+        (0, vec![((String::from("a"), String::from("b")), 1)]),
+        (
+            0,
+            vec![
+                ((String::from("a"), String::from("b")), 1),
+                ((String::from("a"), String::from("c")), 1),
+            ],
+        ),
+    ]);
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn times_are_sorted() {
+    let (spine, model) = spine_and_model::<FileValBatch<String, String, u32, u64>>(vec![
+        (473291538, vec![((String::from("y"), String::from("")), 1)]),
+        (0, vec![((String::from("y"), String::from("")), 1)]),
+    ]);
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn recede_to_bug() {
+    let (mut spine, mut model) = spine_and_model::<FileValBatch<String, u64, u32, u64>>(vec![
+        (2, vec![((String::from("a"), 1), 1)]),
+        (0, vec![((String::from("a"), 1), 1)]),
+    ]);
+    spine.recede_to(&1);
+    Trace::recede_to(&mut model, &1);
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn weights_cancellation() {
+    let (spine, model) = spine_and_model::<FileValBatch<String, u64, u32, i64>>(vec![
+        (0, vec![((String::from("a"), 1), -8)]),
+        (0, vec![((String::from("a"), 1), 8)]),
+    ]);
+    assert_trace_eq(&spine, &model);
+}
+
+fn complex_key() -> ComplexKey {
+    ComplexKey {
+        _a: 0,
+        ord: String::from(""),
+    }
+}
+
+#[test]
+fn timestamp_aggregation() {
+    let (mut spine, mut model) =
+        spine_and_model::<FileKeyBatch<ComplexKey, NestedTimestamp32, i64>>(vec![
+            (
+                NestedTimestamp32::new(true, 1),
+                vec![((complex_key(), ()), 1)],
+            ),
+            (
+                NestedTimestamp32::new(false, 2),
+                vec![((complex_key(), ()), 1)],
+            ),
+            (
+                NestedTimestamp32::new(false, 1),
+                vec![((complex_key(), ()), 1)],
+            ),
+        ]);
+    spine.recede_to(&NestedTimestamp32::new(false, 2));
+    Trace::recede_to(&mut model, &NestedTimestamp32::new(false, 2));
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn weights_cancellation2() {
+    let (mut spine, mut model) =
+        spine_and_model::<FileKeyBatch<ComplexKey, NestedTimestamp32, i64>>(vec![
+            (
+                NestedTimestamp32::new(false, 0),
+                vec![((complex_key(), ()), -1)],
+            ),
+            (
+                NestedTimestamp32::new(false, 4),
+                vec![((complex_key(), ()), -9)],
+            ),
+            (
+                NestedTimestamp32::new(false, 4),
+                vec![((complex_key(), ()), 9)],
+            ),
+        ]);
+    spine.recede_to(&NestedTimestamp32::new(false, 2));
+    Trace::recede_to(&mut model, &NestedTimestamp32::new(false, 2));
+    assert_trace_eq(&spine, &model);
+}
+
+#[test]
+fn cursor_weight() {
+    // `weight()` is only defined for batches with `()` timestamps, so the
+    // proptests don't call it.
+    let (spine, model) =
+        spine_and_model::<FileZSet<String, i32>>(vec![((), vec![((String::from("a"), ()), -8)])]);
+
+    let mut spine_cursor = spine.cursor();
+    let mut model_cursor = model.cursor();
+    assert_eq!(spine_cursor.weight(), -8);
+    assert_eq!(spine_cursor.weight(), model_cursor.weight());
+}
+
+#[test]
+fn cursor_weight_multiple_values_bug() {
+    // Checks that stepping through multiple values of a key reports the weight
+    // of each value.
+    let (spine, model) = spine_and_model::<FileIndexedZSet<String, String, i32>>(vec![(
+        (),
+        vec![
+            ((String::from("k"), String::from("v")), 1),
+            ((String::from("k"), String::from("v2")), 2),
+        ],
+    )]);
+
+    let mut spine_cursor = spine.cursor();
+    let mut model_cursor = model.cursor();
+    assert_eq!(spine_cursor.weight(), model_cursor.weight());
+    spine_cursor.step_val();
+    model_cursor.step_val();
+    assert_eq!(spine_cursor.weight(), model_cursor.weight());
+}
@@ -0,0 +1,459 @@
+//! Property based tests that ensure correctness of the file-based batches.
+//!
+//! We define "correct" by testing if a [`Spine`] of file batches behaves the
+//! same as the [`TestBatch`] reference trace.
+#![allow(clippy::type_complexity)]
+
+use crate::{
+    algebra::HasZero,
+    time::NestedTimestamp32,
+    trace::{
+        cursor::Cursor,
+        ord::{FileIndexedZSet, FileKeyBatch, FileValBatch, FileZSet},
+        test_batch::{assert_trace_eq, TestBatch},
+        Batch, BatchReader, Builder, Spine, Trace,
+    },
+    Timestamp,
+};
+use proptest::prelude::*;
+use proptest_derive::Arbitrary;
+use rkyv::{Archive, Deserialize, Serialize};
+use size_of::SizeOf;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+/// Maximum number of actions to perform on a trace.
+const TRACE_ACTIONS_RANGE: Range<usize> = 0..8;
+
+/// Range for possible numbers of entries in batches.
+const ENTRIES_RANGE: Range<usize> = 0..24;
+
+/// How many actions to execute on the cursors.
+const ACTIONS_RANGE: Range<usize> = 0..64;
+
+/// The sample range for `R` weights.
+///
+/// The bound would ideally be unconstrained
+/// - but neither Spine nor TestBatch handles integer overflows.
+/// - and by having a very small range we force more "interesting cases" where
+///   weights cancel each other out.
+const WEIGHT_RANGE: Range<i64> = -10..10;
+
+/// This is a "complex" key for testing.
+///
+/// It's "complex" because it defines a custom ordering logic & has a heap
+/// allocated [`String`] inside of it.
+///
+/// The tests ensure that the layer files adhere to the same ordering as the
+/// reference implementation, which is defined through the [`Ord`] trait.
+#[derive(Clone, Debug, Arbitrary, SizeOf, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(Clone, Ord, Eq, PartialEq, PartialOrd))]
+#[archive(compare(PartialEq, PartialOrd))]
+pub(super) struct ComplexKey {
+    /// We ignore this type for ordering.
+    pub(super) _a: i64,
+    /// We use this to define the order of `Self`.
+    pub(super) ord: String,
+}
+
+impl Display for ComplexKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.ord)
+    }
+}
+
+impl Hash for ComplexKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ord.hash(state);
+    }
+}
+
+impl PartialEq for ComplexKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ord.eq(&other.ord)
+    }
+}
+
+impl Eq for ComplexKey {}
+
+impl PartialOrd for ComplexKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ComplexKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ord.cmp(&other.ord)
+    }
+}
+
+/// Sorts `tuples`, drops duplicate `(key, value)` pairs and zero weights, and
+/// builds a batch of type `B` from what remains with `B`'s [`Builder`].
+fn build_batch<B>(time: B::Time, tuples: &mut Vec<((B::Key, B::Val), B::R)>) -> B
+where
+    B: Batch,
+{
+    // Builder interface wants sorted, unique keys:
+    tuples.sort_unstable();
+    tuples.dedup_by(|a, b| a.0.eq(&b.0));
+    tuples.retain(|(_, r)| !r.is_zero());
+
+    let mut builder = B::Builder::new_builder(time);
+    for ((key, val), r) in tuples.iter().cloned() {
+        builder.push((B::item_from(key, val), r));
+    }
+    builder.done()
+}
+
+/// Inserts a batch for each of the `(time, tuples)` in `batches` into a
+/// [`Spine`] of `B` and into a [`TestBatch`] trace, and returns both.  Unlike
+/// [`build_batch`], this consolidates duplicate tuples.
+pub(super) fn spine_and_model<B>(
+    batches: Vec<(B::Time, Vec<((B::Key, B::Val), B::R)>)>,
+) -> (Spine<B>, TestBatch<B::Key, B::Val, B::Time, B::R>)
+where
+    B: Batch,
+{
+    let mut spine = Spine::new(None);
+    let mut model = TestBatch::new(None);
+    for (time, tuples) in batches {
+        let items = tuples
+            .iter()
+            .cloned()
+            .map(|((key, val), r)| (B::item_from(key, val), r))
+            .collect();
+        spine.insert(B::from_tuples(time.clone(), items));
+        model.insert(TestBatch::from_tuples(time, tuples));
+        let mut fuel = isize::MAX;
+        spine.exert(&mut fuel);
+    }
+    (spine, model)
+}
+
+/// Models calls we can make on the data-structure once we implement the
+/// [`Cursor`] trait.
+#[derive(Debug, Clone)]
+enum CursorAction<
+    K: Arbitrary + Clone + 'static,
+    V: Arbitrary + Clone + 'static,
+    T: Arbitrary + Clone + 'static,
+> {
+    StepKey,
+    StepVal,
+    SeekKey(K),
+    SeekVal(V),
+    SeekValWith(V),
+    RewindKeys,
+    RewindVals,
+    Key,
+    Val,
+    MapTimes,
+    MapTimesThrough(T),
+}
+
+/// Generates a random action to perform on a cursor.
+fn action<
+    K: Arbitrary + Clone + 'static,
+    V: Arbitrary + Clone + 'static,
+    T: Arbitrary + Clone + 'static,
+>() -> impl Strategy<Value = CursorAction<K, V, T>> {
+    prop_oneof![
+        Just(CursorAction::StepKey),
+        Just(CursorAction::StepVal),
+        Just(CursorAction::RewindKeys),
+        Just(CursorAction::RewindVals),
+        Just(CursorAction::Key),
+        Just(CursorAction::Val),
+        Just(CursorAction::MapTimes),
+        any::<T>().prop_map(CursorAction::MapTimesThrough),
+        any::<K>().prop_map(CursorAction::SeekKey),
+        any::<V>().prop_map(CursorAction::SeekVal),
+        any::<V>().prop_map(CursorAction::SeekValWith),
+    ]
+}
+
+/// Generates a sequence of actions to perform on a cursor.
+fn actions<
+    K: Arbitrary + Clone + 'static,
+    V: Arbitrary + Clone + 'static,
+    T: Arbitrary + Clone + 'static,
+>() -> impl Strategy<Value = Vec<CursorAction<K, V, T>>> {
+    prop::collection::vec(action::<K, V, T>(), ACTIONS_RANGE)
+}
+
+/// Collects the `(time, weight)` pairs passed to `map_times` into a sorted
+/// vector, because cursors don't promise any particular order of times.
+fn sorted_times<T: Ord, R: Ord>(mut times: Vec<(T, R)>) -> Vec<(T, R)> {
+    times.sort();
+    times
+}
+
+/// Performs a random series of actions on a [`TestBatch`] cursor and the cursor
+/// of a [`Spine`] of `B`, and makes sure they produce the same results.
+fn cursor_trait<B>(
+    mut data: Vec<((B::Key, B::Val), B::R)>,
+    ops: Vec<CursorAction<B::Key, B::Val, B::Time>>,
+) where
+    B: Batch,
+    B::Key: Arbitrary,
+    B::Val: Arbitrary,
+    B::Time: Arbitrary,
+{
+    let batch = build_batch::<B>(B::Time::minimum(), &mut data);
+    let model = TestBatch::from_tuples(B::Time::minimum(), data);
+    let mut model_cursor = model.cursor();
+
+    let mut spine = Spine::<B>::new(None);
+    spine.insert(batch);
+    let mut totest_cursor = spine.cursor();
+
+    // We check the non-mutating cursor interface after every
+    // command/mutation.
+    fn check_eq_invariants<K, V, T, R>(
+        step: usize,
+        model_cursor: &impl Cursor<K, V, T, R>,
+        totest_cursor: &impl Cursor<K, V, T, R>,
+    ) {
+        assert_eq!(
+            model_cursor.key_valid(),
+            totest_cursor.key_valid(),
+            "key_valid() mismatch in step {}",
+            step
+        );
+        assert_eq!(
+            model_cursor.val_valid(),
+            totest_cursor.val_valid(),
+            "val_valid() mismatch in step {}",
+            step
+        );
+    }
+
+    // The reference cursor only supports moving within the current key and
+    // value while they are valid, so we skip actions that would go beyond
+    // that.
+    for (i, action) in ops.iter().enumerate() {
+        match action {
+            CursorAction::StepKey => {
+                if model_cursor.key_valid() {
+                    model_cursor.step_key();
+                    totest_cursor.step_key();
+                }
+            }
+            CursorAction::StepVal => {
+                if model_cursor.val_valid() {
+                    model_cursor.step_val();
+                    totest_cursor.step_val();
+                }
+            }
+            CursorAction::SeekKey(k) => {
+                model_cursor.seek_key(k);
+                totest_cursor.seek_key(k);
+            }
+            CursorAction::SeekVal(v) => {
+                if model_cursor.key_valid() {
+                    model_cursor.seek_val(v);
+                    totest_cursor.seek_val(v);
+                }
+            }
+            CursorAction::SeekValWith(v) => {
+                if model_cursor.key_valid() {
+                    model_cursor.seek_val_with(|cmp_with| cmp_with >= v);
+                    totest_cursor.seek_val_with(|cmp_with| cmp_with >= v);
+                }
+            }
+            CursorAction::RewindKeys => {
+                model_cursor.rewind_keys();
+                totest_cursor.rewind_keys();
+            }
+            CursorAction::RewindVals => {
+                if model_cursor.key_valid() {
+                    model_cursor.rewind_vals();
+                    totest_cursor.rewind_vals();
+                }
+            }
+            CursorAction::Key => {
+                if model_cursor.key_valid() {
+                    assert_eq!(model_cursor.key(), totest_cursor.key());
+                }
+            }
+            CursorAction::Val => {
+                if model_cursor.val_valid() {
+                    assert_eq!(model_cursor.val(), totest_cursor.val());
+                }
+            }
+            CursorAction::MapTimes => {
+                if model_cursor.val_valid() {
+                    let mut model_invocations = Vec::new();
+                    let mut test_invocations = Vec::new();
+                    model_cursor.map_times(|t, r| {
+                        model_invocations.push((t.clone(), r.clone()));
+                    });
+                    totest_cursor.map_times(|t, r| {
+                        test_invocations.push((t.clone(), r.clone()));
+                    });
+                    assert_eq!(
+                        sorted_times(model_invocations),
+                        sorted_times(test_invocations)
+                    );
+                }
+            }
+            CursorAction::MapTimesThrough(upper) => {
+                if model_cursor.val_valid() {
+                    let mut model_invocations = Vec::new();
+                    let mut test_invocations = Vec::new();
+                    model_cursor.map_times_through(upper, |t, r| {
+                        model_invocations.push((t.clone(), r.clone()));
+                    });
+                    totest_cursor.map_times_through(upper, |t, r| {
+                        test_invocations.push((t.clone(), r.clone()));
+                    });
+                    assert_eq!(
+                        sorted_times(model_invocations),
+                        sorted_times(test_invocations)
+                    );
+                }
+            }
+        }
+        check_eq_invariants(i, &model_cursor, &totest_cursor);
+    }
+}
+
+/// Generates a `(K, V)` tuple for insertion into batches.
+fn kv_tuple<K: Arbitrary + Clone, V: Arbitrary + Clone>() -> impl Strategy<Value = (K, V)> {
+    (any::<K>(), any::<V>())
+}
+
+/// Generates a vector of `((K, V), R)` tuples for insertion into batches.  For
+/// batches that don't store values, `V` is `()`.
+fn kvr_tuples<K: Arbitrary + Clone, V: Arbitrary + Clone, R: Arbitrary + Clone>(
+    rs: impl Strategy<Value = R>,
+) -> impl Strategy<Value = Vec<((K, V), R)>> {
+    prop::collection::vec(((any::<K>(), any::<V>()), rs), ENTRIES_RANGE)
+}
+
+/// Mutable calls we can perform on something that implements the [`Trace`]
+/// trait.
+#[derive(Clone, Debug)]
+enum TraceAction<K, V, T, R> {
+    ClearDirtyFlag,
+    RecedeTo(T),
+    Insert((T, Vec<((K, V), R)>)),
+    // Exert -- performed with unlimited fuel after each insertion
+    // Consolidate -- consumes self so we should just do it once at the end of the test
+}
+
+/// Generate one action to perform on a [`Trace`].
+fn trace_action<K, V, T, R>(
+    kvs: impl Strategy<Value = (K, V)>,
+    rs: impl Strategy<Value = R>,
+) -> impl Strategy<Value = TraceAction<K, V, T, R>>
+where
+    K: Debug + Clone,
+    V: Debug + Clone,
+    T: Arbitrary + Debug + Clone,
+    R: Debug + Clone,
+{
+    prop_oneof![
+        Just(TraceAction::ClearDirtyFlag),
+        any::<T>().prop_map(TraceAction::RecedeTo),
+        (any::<T>(), prop::collection::vec((kvs, rs), ENTRIES_RANGE)).prop_map(TraceAction::Insert),
+    ]
+}
+
+/// Generate a sequence of actions to perform on a [`Trace`].
+fn trace_actions<K, V, T, R>(
+    kvs: impl Strategy<Value = (K, V)>,
+    rs: impl Strategy<Value = R>,
+) -> impl Strategy<Value = Vec<TraceAction<K, V, T, R>>>
+where
+    K: Debug + Clone,
+    V: Debug + Clone,
+    T: Arbitrary + Debug + Clone,
+    R: Debug + Clone,
+{
+    prop::collection::vec(trace_action::<K, V, T, R>(kvs, rs), TRACE_ACTIONS_RANGE)
+}
+
+/// Performs a random series of actions on a [`TestBatch`] trace and a
+/// [`Spine`] of `B`, and makes sure they produce the same results.
+fn trace_trait<B>(actions: Vec<TraceAction<B::Key, B::Val, B::Time, B::R>>)
+where
+    B: Batch,
+{
+    let mut model = TestBatch::new(None);
+    let mut spine = Spine::<B>::new(None);
+
+    for action in actions {
+        match action {
+            TraceAction::RecedeTo(t) => {
+                Trace::recede_to(&mut model, &t);
+                spine.recede_to(&t);
+            }
+            TraceAction::ClearDirtyFlag => {
+                model.clear_dirty_flag();
+                spine.clear_dirty_flag();
+            }
+            TraceAction::Insert((time, mut tuples)) => {
+                let batch = build_batch(time.clone(), &mut tuples);
+                model.insert(TestBatch::from_tuples(time, tuples));
+                spine.insert(batch);
+                let mut fuel = isize::MAX;
+                spine.exert(&mut fuel);
+            }
+        }
+        assert_trace_eq(&spine, &model);
+    }
+}
+
+proptest! {
+    // Verify that the [`Cursor`] implementation for a spine of file batches
+    // behaves the same as the reference implementation:
+
+    #[test]
+    fn cursor_val_batch_behaves_like_model(ks in kvr_tuples::<ComplexKey, String, i64>(WEIGHT_RANGE), ops in actions::<ComplexKey, String, u32>()) {
+        cursor_trait::<FileValBatch<ComplexKey, String, u32, i64>>(ks, ops);
+    }
+
+    #[test]
+    fn cursor_key_batch_behaves_like_model(ks in kvr_tuples::<ComplexKey, (), i64>(WEIGHT_RANGE), ops in actions::<ComplexKey, (), u32>()) {
+        cursor_trait::<FileKeyBatch<ComplexKey, u32, i64>>(ks, ops);
+    }
+
+    #[test]
+    fn cursor_zset_behaves_like_model(ks in kvr_tuples::<ComplexKey, (), i64>(WEIGHT_RANGE), ops in actions::<ComplexKey, (), ()>()) {
+        cursor_trait::<FileZSet<ComplexKey, i64>>(ks, ops);
+    }
+
+    #[test]
+    fn cursor_indexed_zset_behaves_like_model(ks in kvr_tuples::<ComplexKey, u64, i64>(WEIGHT_RANGE), ops in actions::<ComplexKey, u64, ()>()) {
+        cursor_trait::<FileIndexedZSet<ComplexKey, u64, i64>>(ks, ops);
+    }
+
+    // Verify that the [`Trace`] implementation for a spine of file batches
+    // behaves the same as the reference implementation:
+
+    #[test]
+    fn val_batch_trace_behaves_like_model(actions in trace_actions::<ComplexKey, String, NestedTimestamp32, i64>(kv_tuple::<ComplexKey, String>(), WEIGHT_RANGE)) {
+        trace_trait::<FileValBatch<ComplexKey, String, NestedTimestamp32, i64>>(actions);
+    }
+
+    #[test]
+    fn key_batch_trace_behaves_like_model(actions in trace_actions::<ComplexKey, (), NestedTimestamp32, i64>(kv_tuple::<ComplexKey, ()>(), WEIGHT_RANGE)) {
+        trace_trait::<FileKeyBatch<ComplexKey, NestedTimestamp32, i64>>(actions);
+    }
+
+    #[test]
+    fn zset_trace_behaves_like_model(actions in trace_actions::<ComplexKey, (), (), i64>(kv_tuple::<ComplexKey, ()>(), WEIGHT_RANGE)) {
+        trace_trait::<FileZSet<ComplexKey, i64>>(actions);
+    }
+
+    #[test]
+    fn indexed_zset_trace_behaves_like_model(actions in trace_actions::<ComplexKey, u64, (), i64>(kv_tuple::<ComplexKey, u64>(), WEIGHT_RANGE)) {
+        trace_trait::<FileIndexedZSet<ComplexKey, u64, i64>>(actions);
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     cmp::Ordering,
     fmt::{Debug, Display, Formatter},
     marker::PhantomData,
+    vec::IntoIter,
 };
 
 use feldera_storage::{
@@ -12,8 +13,9 @@ use feldera_storage::{
     },
 };
 use itertools::Itertools;
+use ouroboros::self_referencing;
 use rand::{seq::index::sample, Rng};
-use rkyv::{ser::Serializer, Archive, Archived, Deserialize, Fallible, Serialize};
+use rkyv::{Archive, Archived, Deserialize, Fallible, Resolver, Serialize};
 use size_of::SizeOf;
 
 use crate::{
@@ -26,7 +28,7 @@ use crate::{
     DBData, DBTimestamp, DBWeight, NumEntries, Rkyv,
 };
 
-use super::StorageBackend;
+use super::{storage_backend, StorageBackend};
 
 type RawValBatch<K, V, T, R> = Reader<StorageBackend, (K, (), (V, Vec<(T, R)>, ()))>;
 
@@ -105,7 +107,11 @@ where
     }
 
     fn consumer(self) -> Self::Consumer {
-        todo!()
+        FileValConsumerBuilder {
+            batch: self,
+            cursor_builder: |batch| FileValCursor::new(batch),
+        }
+        .build()
     }
 
     fn key_count(&self) -> usize {
@@ -193,8 +199,7 @@ where
     fn recede_to(&mut self, frontier: &T) {
         // Nothing to do if the batch is entirely before the frontier.
         if !self.upper().less_equal(frontier) {
-            let mut writer =
-                Writer2::new(&StorageBackend::default_for_thread(), Parameters::default()).unwrap();
+            let mut writer = Writer2::new(&storage_backend(), Parameters::default()).unwrap();
             let mut key_cursor = self.file.rows().first().unwrap();
             while key_cursor.has_value() {
                 let mut val_cursor = key_cursor.next_column().unwrap().first().unwrap();
@@ -344,7 +349,7 @@ where
             value_cursor.move_next().unwrap();
         }
         if n > 0 {
-            output.write0((&key, &())).unwrap();
+            output.write0((key, &())).unwrap();
         }
         key_cursor.move_next().unwrap();
     }
@@ -408,8 +413,7 @@ where
         key_filter: &Option<Filter<K>>,
         value_filter: &Option<Filter<V>>,
     ) -> RawValBatch<K, V, T, R> {
-        let mut output =
-            Writer2::new(&StorageBackend::default_for_thread(), Parameters::default()).unwrap();
+        let mut output = Writer2::new(&storage_backend(), Parameters::default()).unwrap();
         let mut cursor1 = source1.file.rows().nth(source1.lower_bound as u64).unwrap();
         let mut cursor2 = source2.file.rows().nth(source2.lower_bound as u64).unwrap();
         loop {
@@ -472,7 +476,7 @@ where
             file: self
                 .result
                 .take()
-                .unwrap_or(Reader::empty(&StorageBackend::default_for_thread()).unwrap()),
+                .unwrap_or(Reader::empty(&storage_backend()).unwrap()),
             lower_bound: 0,
             lower: self.lower,
             upper: self.upper,
@@ -714,8 +718,7 @@ where
     fn new_builder(time: T) -> Self {
         Self {
             time,
-            writer: Writer2::new(&StorageBackend::default_for_thread(), Parameters::default())
-                .unwrap(),
+            writer: Writer2::new(&storage_backend(), Parameters::default()).unwrap(),
             cur_key: None,
         }
     }
@@ -771,50 +774,93 @@ where
     }
 }
 
-pub struct FileValConsumer<K, V, T, R> {
-    __type: PhantomData<(K, V, T, R)>,
+/// A [`Consumer`] implementation for [`FileValBatch`].
+#[self_referencing]
+pub struct FileValConsumer<K, V, T, R>
+where
+    K: DBData,
+    V: DBData,
+    T: DBTimestamp,
+    R: DBWeight,
+{
+    batch: FileValBatch<K, V, T, R>,
+    #[borrows(batch)]
+    #[covariant]
+    cursor: FileValCursor<'this, K, V, T, R>,
 }
 
-impl<K, V, T, R> Consumer<K, V, R, T> for FileValConsumer<K, V, T, R> {
+impl<K, V, T, R> Consumer<K, V, R, T> for FileValConsumer<K, V, T, R>
+where
+    K: DBData,
+    V: DBData,
+    T: DBTimestamp,
+    R: DBWeight,
+{
     type ValueConsumer<'a> = FileValValueConsumer<'a, K, V, T, R>
     where
         Self: 'a;
 
     fn key_valid(&self) -> bool {
-        todo!()
+        self.with_cursor(|cursor| cursor.key_valid())
     }
 
     fn peek_key(&self) -> &K {
-        todo!()
+        self.with_cursor(|cursor| cursor.key())
     }
 
     fn next_key(&mut self) -> (K, Self::ValueConsumer<'_>) {
-        todo!()
+        self.with_cursor_mut(|cursor| {
+            // The values and times of a key are stored together, so read
+            // them all at once.
+            let key = cursor.key().clone();
+            let mut values = Vec::new();
+            while cursor.val_valid() {
+                let val = cursor.val();
+                for (time, diff) in cursor.times() {
+                    values.push((val.clone(), diff, time));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+            (key, FileValValueConsumer::new(values))
+        })
     }
 
-    fn seek_key(&mut self, _key: &K)
+    fn seek_key(&mut self, key: &K)
     where
         K: Ord,
     {
-        todo!()
+        self.with_cursor_mut(|cursor| cursor.seek_key(key))
     }
 }
 
+/// A [`ValueConsumer`] for the values of a key yielded by
+/// [`FileValConsumer`].
 pub struct FileValValueConsumer<'a, K, V, T, R> {
-    __type: PhantomData<&'a (K, V, T, R)>,
+    values: IntoIter<(V, R, T)>,
+    __type: PhantomData<&'a K>,
+}
+
+impl<'a, K, V, T, R> FileValValueConsumer<'a, K, V, T, R> {
+    fn new(values: Vec<(V, R, T)>) -> Self {
+        Self {
+            values: values.into_iter(),
+            __type: PhantomData,
+        }
+    }
 }
 
 impl<'a, K, V, T, R> ValueConsumer<'a, V, R, T> for FileValValueConsumer<'a, K, V, T, R> {
     fn value_valid(&self) -> bool {
-        todo!()
+        self.values.len() > 0
     }
 
     fn next_value(&mut self) -> (V, R, T) {
-        todo!()
+        self.values.next().unwrap()
     }
 
     fn remaining_values(&self) -> usize {
-        todo!()
+        self.values.len()
     }
 }
 
@@ -830,6 +876,24 @@ where
     }
 }
 
+/// The values and `(time, diff)` pairs under each key of a [`FileValBatch`].
+type KeyTuples<K, V, T, R> = Vec<(K, Vec<(V, Vec<(T, R)>)>)>;
+
+/// Contents of a [`FileValBatch`] in the form in which it is archived: the
+/// values and `(time, diff)` pairs under each key, and the bounds of the
+/// batch.
+#[derive(Archive, Serialize)]
+pub struct FileValBatchTuples<K, V, T, R>
+where
+    T: 'static,
+{
+    keys: KeyTuples<K, V, T, R>,
+    lower: Antichain<T>,
+    upper: Antichain<T>,
+}
+
+/// A `FileValBatch` is archived as [`FileValBatchTuples`].  Deserializing it
+/// writes the tuples to a new layer file.
 impl<K, V, T, R> Archive for FileValBatch<K, V, T, R>
 where
     K: DBData,
@@ -837,11 +901,15 @@ where
     T: DBTimestamp,
     R: DBWeight,
 {
-    type Archived = ();
-    type Resolver = ();
+    type Archived = ArchivedFileValBatchTuples<K, V, T, R>;
+    type Resolver = (
+        FileValBatchTuples<K, V, T, R>,
+        Resolver<FileValBatchTuples<K, V, T, R>>,
+    );
 
-    unsafe fn resolve(&self, _pos: usize, _resolver: Self::Resolver, _out: *mut Self::Archived) {
-        unimplemented!();
+    unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+        let (tuples, resolver) = resolver;
+        tuples.resolve(pos, resolver, out);
     }
 }
 
@@ -851,22 +919,61 @@ where
     V: DBData,
     T: DBTimestamp,
     R: DBWeight,
-    S: Serializer + ?Sized,
+    S: Fallible + ?Sized,
+    FileValBatchTuples<K, V, T, R>: Serialize<S>,
 {
-    fn serialize(&self, _serializer: &mut S) -> Result<Self::Resolver, S::Error> {
-        unimplemented!();
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let mut keys = Vec::with_capacity(self.key_count());
+        let mut cursor = self.cursor();
+        while cursor.key_valid() {
+            let mut values = Vec::new();
+            while cursor.val_valid() {
+                values.push((cursor.val().clone(), cursor.times()));
+                cursor.step_val();
+            }
+            keys.push((cursor.key().clone(), values));
+            cursor.step_key();
+        }
+
+        let tuples = FileValBatchTuples {
+            keys,
+            lower: self.lower.clone(),
+            upper: self.upper.clone(),
+        };
+        let resolver = tuples.serialize(serializer)?;
+        Ok((tuples, resolver))
     }
 }
 
-impl<K, V, T, R, D> Deserialize<FileValBatch<K, V, T, R>, D> for Archived<FileValBatch<K, V, T, R>>
+impl<K, V, T, R, D> Deserialize<FileValBatch<K, V, T, R>, D>
+    for ArchivedFileValBatchTuples<K, V, T, R>
 where
     K: DBData,
     V: DBData,
     T: DBTimestamp,
     R: DBWeight,
-    D: Fallible,
+    D: Fallible + ?Sized,
+    Archived<KeyTuples<K, V, T, R>>: Deserialize<KeyTuples<K, V, T, R>, D>,
+    Archived<Antichain<T>>: Deserialize<Antichain<T>, D>,
 {
-    fn deserialize(&self, _deserializer: &mut D) -> Result<FileValBatch<K, V, T, R>, D::Error> {
-        unimplemented!();
+    fn deserialize(&self, deserializer: &mut D) -> Result<FileValBatch<K, V, T, R>, D::Error> {
+        let keys: KeyTuples<K, V, T, R> = self.keys.deserialize(deserializer)?;
+        let lower = self.lower.deserialize(deserializer)?;
+        let upper = self.upper.deserialize(deserializer)?;
+
+        let mut writer = Writer2::new(&storage_backend(), Parameters::default()).unwrap();
+        for (key, values) in keys {
+            for (val, times) in values {
+                writer.write1((&val, &times)).unwrap();
+            }
+            writer.write0((&key, &())).unwrap();
+        }
+
+        Ok(FileValBatch {
+            file: writer.into_reader().unwrap(),
+            lower_bound: 0,
+            lower,
+            upper,
+        })
     }
 }
@@ -21,6 +21,11 @@
 //!
 //! Likewise, `OrdIndexedZSet` and `OrdZSet` are less general than `OrdVal` and
 //! `OrdKey` respectively, but are more light-weight.
+//!
+//! The `Ord` types are aliases for the in-memory `Vec` batches.  The `File`
+//! batches keep their data in layer files on storage instead.  They can't
+//! replace the `Vec` batches yet because they can't be sent between worker
+//! threads.
 
 mod merge_batcher;
 
@@ -87,13 +87,16 @@
 
 use crate::{
     algebra::HasZero,
-    circuit::Activator,
+    circuit::{
+        checkpoint::{read_state, write_state},
+        Activator,
+    },
     time::{Antichain, AntichainRef, Timestamp},
     trace::{
         cursor::{Cursor, CursorList},
         Batch, BatchReader, Consumer, Merger, Trace, ValueConsumer,
     },
-    NumEntries,
+    Error, NumEntries,
 };
 use rand::Rng;
 use rkyv::{ser::Serializer, Archive, Archived, Deserialize, Fallible, Serialize};
@@ -101,8 +104,10 @@ use size_of::SizeOf;
 use std::{
     cmp::max,
     fmt::{self, Debug, Display, Formatter, Write},
+    fs,
     marker::PhantomData,
     mem::replace,
+    path::{Path, PathBuf},
 };
 use textwrap::indent;
 
@@ -529,6 +534,10 @@ where
         self.cursor.seek_key(key);
     }
 
+    fn seek_key_exact(&mut self, key: &B::Key) -> bool {
+        self.cursor.seek_key_exact(key)
+    }
+
     fn seek_key_with<P>(&mut self, predicate: P)
     where
         P: Fn(&B::Key) -> bool + Clone,
@@ -709,7 +718,7 @@ where
 
     fn consolidate(mut self) -> Option<B> {
         // Merge batches until there is nothing left to merge.
-        let mut fuel = isize::max_value();
+        let mut fuel = isize::MAX;
         while !self.reduced() {
             self.exert(&mut fuel);
         }
@@ -783,6 +792,43 @@ where
     fn value_filter(&self) -> &Option<Filter<Self::Val>> {
         &self.value_filter
     }
+
+    fn commit(&self, path: &Path) -> Result<(), Error> {
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+        fs::create_dir_all(path)?;
+
+        // Store each batch, including inputs of merges in progress, in a
+        // separate file.  Merges are restarted from scratch on restore.
+        self.try_fold_batches(0, |index, batch| {
+            write_state(&batch_path(path, index), batch)?;
+            Ok::<_, Error>(index + 1)
+        })?;
+
+        Ok(())
+    }
+
+    fn restore(&mut self, path: &Path) -> Result<(), Error> {
+        self.merging.clear();
+        self.lower = Antichain::from_elem(B::Time::minimum());
+        self.upper = Antichain::new();
+
+        for index in 0.. {
+            let batch_path = batch_path(path, index);
+            if !batch_path.exists() {
+                break;
+            }
+            self.insert(read_state(&batch_path)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Path to the file that stores the `index`th batch of a spine checkpoint.
+fn batch_path(path: &Path, index: usize) -> PathBuf {
+    path.join(format!("{index}.batch"))
 }
 
 impl<B> Spine<B>
@@ -1107,7 +1153,7 @@ where
     fn complete_merges(&mut self) {
         for merge_state in self.merging.iter_mut() {
             if merge_state.is_inprogress() {
-                let mut fuel = isize::max_value();
+                let mut fuel = isize::MAX;
                 merge_state.work(&self.key_filter, &self.value_filter, &mut fuel);
             }
         }
@@ -1302,7 +1348,7 @@ where
         key_filter: &Option<Filter<B::Key>>,
         value_filter: &Option<Filter<B::Val>>,
     ) -> Option<B> {
-        let mut fuel = isize::max_value();
+        let mut fuel = isize::MAX;
         self.work(key_filter, value_filter, &mut fuel);
         if let MergeVariant::Complete(batch) = self {
             batch
@@ -8,7 +8,12 @@ use super::{
     Activator, AntichainRef, Batch, BatchReader, Batcher, Builder, Consumer, Cursor, Merger, Trace,
     ValueConsumer,
 };
-use crate::{algebra::HasZero, utils::VecExt, DBData, DBTimestamp, DBWeight, NumEntries};
+use crate::{
+    algebra::HasZero,
+    circuit::checkpoint::{read_state, write_state},
+    utils::VecExt,
+    DBData, DBTimestamp, DBWeight, Error, NumEntries,
+};
 use rand::seq::IteratorRandom;
 use rand::thread_rng;
 use rand::Rng;
@@ -25,9 +30,15 @@ use std::{
     cmp::max,
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug},
+    fs,
     marker::PhantomData,
+    path::Path,
 };
 
+/// Name of the file that stores the contents of a [`TestBatch`] trace
+/// checkpoint.
+const TUPLES_FILE: &str = "tuples";
+
 /// Convert any batch into a vector of tuples.
 pub fn batch_to_tuples<B>(batch: &B) -> Vec<((B::Key, B::Val, B::Time), B::R)>
 where
@@ -669,11 +680,17 @@ where
         acc
     }
 
-    fn fold_times_through<F, U>(&mut self, _upper: &T, _init: U, _fold: F) -> U
+    fn fold_times_through<F, U>(&mut self, upper: &T, init: U, mut fold: F) -> U
     where
         F: FnMut(U, &T, &R) -> U,
     {
-        todo!()
+        self.fold_times(init, |acc, time, diff| {
+            if time.less_equal(upper) {
+                fold(acc, time, diff)
+            } else {
+                acc
+            }
+        })
     }
 
     fn weight(&mut self) -> R
@@ -704,6 +721,7 @@ where
         while self.index < self.data.len() && !predicate(&self.data[self.index].0 .0) {
             self.index += 1;
         }
+        self.rewind_vals();
     }
 
     fn seek_key_with_reverse<P>(&mut self, _predicate: P)
@@ -736,15 +754,18 @@ where
         }
     }
 
-    fn seek_val_with<P>(&mut self, _predicate: P)
+    fn seek_val_with<P>(&mut self, predicate: P)
     where
         P: Fn(&V) -> bool + Clone,
     {
-        todo!()
+        while self.val_valid() && !predicate(self.val()) {
+            self.step_val();
+        }
     }
 
     fn rewind_keys(&mut self) {
         self.index = 0;
+        self.val_valid = !self.data.is_empty();
     }
 
     fn fast_forward_keys(&mut self) {
@@ -752,7 +773,17 @@ where
     }
 
     fn rewind_vals(&mut self) {
-        todo!()
+        if !self.key_valid() {
+            return;
+        }
+
+        let current_key = self.data[self.index].0 .0.clone();
+
+        while self.index > 0 && self.data[self.index - 1].0 .0 == current_key {
+            self.index -= 1;
+        }
+
+        self.val_valid = true;
     }
 
     fn step_val_reverse(&mut self) {
@@ -985,6 +1016,26 @@ where
     fn value_filter(&self) -> &Option<Filter<Self::Val>> {
         &self.value_filter
     }
+
+    fn commit(&self, path: &Path) -> Result<(), Error> {
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+        fs::create_dir_all(path)?;
+
+        let tuples = self
+            .data
+            .iter()
+            .map(|(kvt, r)| (kvt.clone(), r.clone()))
+            .collect::<Vec<_>>();
+        write_state(&path.join(TUPLES_FILE), &tuples)
+    }
+
+    fn restore(&mut self, path: &Path) -> Result<(), Error> {
+        let tuples: Vec<((K, V, T), R)> = read_state(&path.join(TUPLES_FILE))?;
+        self.data = tuples.into_iter().collect();
+        Ok(())
+    }
 }
 
 /// Test random sampling methods.
@@ -38,6 +38,7 @@ pub(crate) trait VecExt<T> {
     where
         F: FnMut(&T, &T) -> Option<Ordering>;
 
+    #[allow(dead_code)]
     fn is_sorted_by_key<F, U>(&self, mut key: F) -> bool
     where
         F: FnMut(&T) -> U,
@@ -300,6 +300,31 @@ pub trait StorageRead {
         size: usize,
     ) -> Result<Arc<FBuf>, StorageError>;
 
+    /// Reads a block of data from a file and transforms it with `decode`.
+    ///
+    /// This is for blocks that are stored in an encoded form, e.g. compressed.
+    /// An implementation that caches blocks should cache the result of
+    /// `decode`, rather than the block as stored, so that cache hits do not
+    /// need to decode the block again.  Such an implementation must keep
+    /// decoded blocks separate from blocks cached by
+    /// [`read_block`](Self::read_block) or [`StorageWrite::write_block`].
+    ///
+    /// The arguments and pre-conditions are the same as for
+    /// [`read_block`](Self::read_block).
+    async fn read_block_decoded<F, E>(
+        &self,
+        fd: &ImmutableFileHandle,
+        offset: u64,
+        size: usize,
+        decode: F,
+    ) -> Result<Arc<FBuf>, E>
+    where
+        F: FnOnce(Arc<FBuf>) -> Result<Arc<FBuf>, E>,
+        E: From<StorageError>,
+    {
+        decode(self.read_block(fd, offset, size).await?)
+    }
+
     /// Returns the file's size in bytes.
     async fn get_size(&self, fd: &ImmutableFileHandle) -> Result<u64, StorageError>;
 }
@@ -8,6 +8,10 @@
 //!
 //! Run `metrics-observer` in another terminal to see the metrics.
 //!
+//! With `--codecs`, the benchmark additionally writes and reads back a layer
+//! file with each block compression codec and reports the throughput and
+//! compression ratio for each one.
+//!
 //! There are still some issues with this benchmark to make it useful:
 //! - Threads indicate they're done writing but are still writing, potentially
 //!   async code is just wrong/needs join.
@@ -16,6 +20,7 @@
 
 use async_lock::Barrier;
 use std::fs::create_dir_all;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
@@ -29,6 +34,8 @@ use feldera_storage::backend::glommio_impl::GlommioBackend;
 use feldera_storage::backend::monoio_impl::MonoioBackend;
 use feldera_storage::backend::{AtomicIncrementOnlyI64, StorageControl, StorageRead, StorageWrite};
 use feldera_storage::buffer_cache::{BufferCache, FBuf, TinyLfuCache};
+use feldera_storage::file::format::Compression;
+use feldera_storage::file::writer::{Parameters, Writer1};
 
 #[derive(Debug, Clone, Default)]
 struct ThreadBenchResult {
@@ -225,6 +232,10 @@ struct Args {
     /// Print data as CSV.
     #[clap(long, default_value = "false")]
     csv: bool,
+
+    /// Also benchmark layer files with each block compression codec.
+    #[clap(long, default_value = "false")]
+    codecs: bool,
 }
 
 fn allocate_buffer(sz: usize) -> FBuf {
@@ -278,6 +289,90 @@ async fn benchmark<T: StorageControl + StorageWrite + StorageRead>(
     }
 }
 
+/// Writes a layer file with about `args.per_thread_file_size` bytes of
+/// uncompressed data, using `compression`, then reads it back.  Returns the
+/// write time, the read time, and the size of the file on disk.
+fn codec_benchmark(args: &Args, compression: Compression) -> (Duration, Duration, u64) {
+    // Keys and values resemble typical state: increasing integers paired with
+    // repetitive strings and timestamps.
+    fn row(i: u64) -> (u64, String) {
+        (
+            i,
+            format!(
+                "user-{:06} 2024-01-{:02}T{:02}:{:02}:00Z",
+                i % 1000,
+                i / 86_400 % 28 + 1,
+                i / 3600 % 24,
+                i / 60 % 60
+            ),
+        )
+    }
+    let row_size = 8 + row(0).1.len() as u64;
+    let n_rows = args.per_thread_file_size as u64 / row_size;
+
+    let storage = Rc::new(MonoioBackend::new(args.path.clone(), Default::default()));
+    let parameters = Parameters {
+        min_data_block: args.buffer_size.max(4096).next_power_of_two(),
+        compression,
+        ..Parameters::default()
+    };
+
+    let start_write = Instant::now();
+    let mut writer = Writer1::new(&storage, parameters).unwrap();
+    for i in 0..n_rows {
+        let (key, value) = row(i);
+        writer.write0((&key, &value)).unwrap();
+    }
+    let reader = writer.into_reader().unwrap();
+    let write_time = start_write.elapsed();
+
+    let start_read = Instant::now();
+    let mut cursor = reader.rows().first().unwrap();
+    let mut n_read = 0;
+    while let Some((key, value)) = unsafe { cursor.item() } {
+        if args.verify {
+            assert_eq!((key, value), row(n_read));
+        }
+        n_read += 1;
+        cursor.move_next().unwrap();
+    }
+    assert_eq!(n_read, n_rows);
+    let read_time = start_read.elapsed();
+
+    (write_time, read_time, reader.byte_size().unwrap())
+}
+
+fn codec_main(args: &Args) {
+    const ONE_MIB: f64 = 1024f64 * 1024f64;
+    let size = args.per_thread_file_size as f64;
+
+    if args.csv {
+        println!("codec,per_thread_file_size,buffer_size,read_time,write_time,file_size");
+    }
+    for compression in [Compression::None, Compression::Lz4, Compression::Zstd] {
+        let (write_time, read_time, file_size) = codec_benchmark(args, compression);
+        if !args.csv {
+            println!(
+                "{compression:?}: read: {} MiB/s, write: {} MiB/s, file size: {} MiB (ratio {:.2})",
+                size / ONE_MIB / read_time.as_secs_f64(),
+                size / ONE_MIB / write_time.as_secs_f64(),
+                file_size as f64 / ONE_MIB,
+                size / file_size as f64,
+            );
+        } else {
+            println!(
+                "{:?},{},{},{},{},{}",
+                compression,
+                args.per_thread_file_size,
+                args.buffer_size,
+                read_time.as_secs_f64(),
+                write_time.as_secs_f64(),
+                file_size,
+            );
+        }
+    }
+}
+
 #[cfg(feature = "glommio")]
 fn glommio_main(args: Args) -> BenchResult {
     use glommio::{
@@ -393,6 +488,9 @@ fn main() {
     };
 
     br.display(args.clone());
+    if args.codecs {
+        codec_main(&args);
+    }
     if !args.csv {
         if let Err(e) = br.validate() {
             println!("Result validation failed: {}", e);
@@ -25,9 +25,10 @@ use crate::buffer_cache::FBuf;
 #[cfg(test)]
 use crate::test::init_test_logger;
 
-/// The key for the cache is a tuple of (file_handle, offset, size), and
-/// identifies a slice in a file.
-type CacheKey = (i64, u64, usize);
+/// The key for the cache is a tuple of (file_handle, offset, size, decoded),
+/// and identifies a slice in a file.  `decoded` distinguishes blocks as stored
+/// from blocks transformed by [`StorageRead::read_block_decoded`].
+type CacheKey = (i64, u64, usize, bool);
 
 /// CachedFBuf (the values of the cache) are reference-counted buffers.
 type CachedFBuf = Arc<FBuf>;
@@ -220,7 +221,7 @@ impl<B: StorageRead> StorageRead for BufferCache<B> {
         size: usize,
     ) -> Result<CachedFBuf, StorageError> {
         let request_start = Instant::now();
-        if let Some(buf) = self.get(&(fd.into(), offset, size)).await {
+        if let Some(buf) = self.get(&(fd.into(), offset, size, false)).await {
             counter!(METRIC_BUFFER_CACHE_HIT).increment(1);
             histogram!(METRIC_BUFFER_CACHE_LATENCY).record(request_start.elapsed().as_secs_f64());
             Ok(buf)
@@ -228,7 +229,8 @@ impl<B: StorageRead> StorageRead for BufferCache<B> {
             counter!(METRIC_BUFFER_CACHE_MISS).increment(1);
             match self.backend.read_block(fd, offset, size).await {
                 Ok(buf) => {
-                    self.insert((fd.into(), offset, size), buf.clone()).await;
+                    self.insert((fd.into(), offset, size, false), buf.clone())
+                        .await;
                     Ok(buf)
                 }
                 Err(e) => Err(e),
@@ -236,6 +238,31 @@ impl<B: StorageRead> StorageRead for BufferCache<B> {
         }
     }
 
+    async fn read_block_decoded<F, E>(
+        &self,
+        fd: &ImmutableFileHandle,
+        offset: u64,
+        size: usize,
+        decode: F,
+    ) -> Result<CachedFBuf, E>
+    where
+        F: FnOnce(CachedFBuf) -> Result<CachedFBuf, E>,
+        E: From<StorageError>,
+    {
+        let request_start = Instant::now();
+        if let Some(buf) = self.get(&(fd.into(), offset, size, true)).await {
+            counter!(METRIC_BUFFER_CACHE_HIT).increment(1);
+            histogram!(METRIC_BUFFER_CACHE_LATENCY).record(request_start.elapsed().as_secs_f64());
+            Ok(buf)
+        } else {
+            counter!(METRIC_BUFFER_CACHE_MISS).increment(1);
+            let buf = decode(self.backend.read_block(fd, offset, size).await?)?;
+            self.insert((fd.into(), offset, size, true), buf.clone())
+                .await;
+            Ok(buf)
+        }
+    }
+
     async fn get_size(&self, fd: &ImmutableFileHandle) -> Result<u64, StorageError> {
         self.backend.get_size(fd).await
     }
@@ -258,7 +285,7 @@ impl<B: StorageWrite> StorageWrite for BufferCache<B> {
         let res = self.backend.write_block(fd, offset, data).await;
         match res {
             Ok(buf) => {
-                self.insert((fd.into(), offset, buf.len()), buf.clone())
+                self.insert((fd.into(), offset, buf.len(), false), buf.clone())
                     .await;
 
                 // !overlaps_with_previous_write => range not in the list yet
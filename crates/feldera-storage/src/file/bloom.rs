@@ -0,0 +1,157 @@
+//! Bloom filters for approximate set membership queries on layer files.
+//!
+//! The writer hashes each key written to column 0 and, when the file is
+//! closed, builds a [`BloomFilter`] over the hashes and writes it to a filter
+//! block (see [`FilterBlockHeader`]).  The reader loads the filter and uses it
+//! to answer "definitely absent" queries without reading any index or data
+//! blocks.
+//!
+//! Bit positions are derived from a single 64-bit [xxh3] hash per key using the
+//! Kirsch-Mitzenmacher technique, in which the `i`th probe is `h1 + i * h2`
+//! modulo the number of bits.
+//!
+//! [xxh3]: xxhash_rust::xxh3
+//! [`FilterBlockHeader`]: super::format::FilterBlockHeader
+
+use std::hash::{Hash, Hasher};
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::buffer_cache::FBuf;
+
+/// Maximum number of hash functions.  More than this gains very little even
+/// for very low false positive rates.
+const MAX_HASHES: u32 = 16;
+
+/// Maximum number of 64-bit words in a filter, chosen so that a filter block,
+/// including its header, does not exceed the `2**31`-byte maximum block size.
+const MAX_WORDS: usize = ((1 << 31) - 4096) / 8;
+
+/// Returns the hash of `key` used for Bloom filter lookups.
+///
+/// This hash must be stable across processes, because filters are written to
+/// files that may be read back later, so it must not use a randomly seeded
+/// hasher.
+pub(crate) fn hash_key<K>(key: &K) -> u64
+where
+    K: Hash + ?Sized,
+{
+    let mut hasher = Xxh3::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A Bloom filter over 64-bit key hashes.
+#[derive(Debug)]
+pub(crate) struct BloomFilter {
+    n_hashes: u32,
+    words: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// Builds a filter that contains each of `hashes`, using approximately
+    /// `bits_per_key` bits for each hash.
+    pub fn new(hashes: &[u64], bits_per_key: usize) -> Self {
+        let n_bits = hashes.len().saturating_mul(bits_per_key).max(64);
+        let n_words = n_bits.div_ceil(64).min(MAX_WORDS);
+
+        // The optimal number of hash functions is `ln(2) * bits_per_key`.
+        let n_hashes =
+            ((bits_per_key as f64 * std::f64::consts::LN_2).round() as u32).clamp(1, MAX_HASHES);
+
+        let mut filter = Self {
+            n_hashes,
+            words: vec![0; n_words],
+        };
+        for &hash in hashes {
+            filter.insert(hash);
+        }
+        filter
+    }
+
+    /// Constructs a filter from its serialized parts, as read from a filter
+    /// block.  Returns `None` if the parts are invalid.
+    pub fn from_parts(n_hashes: u32, words: Vec<u64>) -> Option<Self> {
+        if (1..=MAX_HASHES).contains(&n_hashes) && !words.is_empty() && words.len() <= MAX_WORDS {
+            Some(Self { n_hashes, words })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of hash functions.
+    pub fn n_hashes(&self) -> u32 {
+        self.n_hashes
+    }
+
+    /// Returns the filter's bits as 64-bit words.
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Appends the filter's bits to `dst` in little-endian byte order.
+    pub fn put_words(&self, dst: &mut FBuf) {
+        for word in &self.words {
+            dst.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    fn n_bits(&self) -> u64 {
+        self.words.len() as u64 * 64
+    }
+
+    fn insert(&mut self, hash: u64) {
+        for bit in probes(self.n_hashes, self.n_bits(), hash) {
+            self.words[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    /// Returns `false` if `hash` was definitely not inserted into the filter,
+    /// `true` if it might have been.
+    pub fn maybe_contains(&self, hash: u64) -> bool {
+        probes(self.n_hashes, self.n_bits(), hash)
+            .all(|bit| self.words[(bit / 64) as usize] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// Returns the `n_hashes` bit positions, in a filter of `n_bits` bits, for
+/// `hash`.
+fn probes(n_hashes: u32, n_bits: u64, hash: u64) -> impl Iterator<Item = u64> {
+    let h1 = hash;
+    let h2 = hash.rotate_left(32) | 1;
+    (0..n_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % n_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash_key, BloomFilter};
+
+    #[test]
+    fn no_false_negatives() {
+        let hashes: Vec<_> = (0..10_000u64).map(|i| hash_key(&i)).collect();
+        let filter = BloomFilter::new(&hashes, 10);
+        assert!(hashes.iter().all(|&hash| filter.maybe_contains(hash)));
+    }
+
+    #[test]
+    fn false_positive_rate() {
+        let hashes: Vec<_> = (0..10_000u64).map(|i| hash_key(&i)).collect();
+        let filter = BloomFilter::new(&hashes, 10);
+        let false_positives = (10_000..110_000u64)
+            .filter(|i| filter.maybe_contains(hash_key(i)))
+            .count();
+
+        // 10 bits per key should yield a false positive rate of about 1%.
+        assert!(false_positives < 2_000, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn round_trip() {
+        let hashes: Vec<_> = (0..100u64).map(|i| hash_key(&i)).collect();
+        let filter = BloomFilter::new(&hashes, 10);
+        let copy = BloomFilter::from_parts(filter.n_hashes(), filter.words().to_vec()).unwrap();
+        assert!(hashes.iter().all(|&hash| copy.maybe_contains(hash)));
+        assert!(BloomFilter::from_parts(0, vec![0]).is_none());
+        assert!(BloomFilter::from_parts(1, Vec::new()).is_none());
+    }
+}
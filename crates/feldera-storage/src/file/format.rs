@@ -12,6 +12,16 @@
 //!
 //! Each block begins with an 8-byte [`BlockHeader`].
 //!
+//! # Compression
+//!
+//! If [`FileTrailer::compression`] is not [`Compression::None`], then data
+//! blocks and index blocks may be compressed.  A compressed block begins with
+//! a [`CompressedBlockHeader`], whose magic number distinguishes it from an
+//! uncompressed block, followed by the compressed form of the complete
+//! uncompressed block, including its own header.  The writer stores a block
+//! uncompressed if compression would not make it smaller on disk.  Filter
+//! blocks and the file trailer are never compressed.
+//!
 //! # Data blocks
 //!
 //! A data block consists of the following, in order:
@@ -57,6 +67,18 @@
 //!   block.  For `size` and `offset` both in bytes, each pointer is encoded as
 //!   `(offset >> 7) | size.trailing_zeros()`, which allows `size` to range up
 //!   to `2**31` bytes.
+//!
+//! # Filter blocks
+//!
+//! A layer file with at least one row contains a single filter block, which
+//! holds a Bloom filter over the keys in column 0.  The [`FileTrailer`] points
+//! to it with [`FileTrailer::filter_offset`] and [`FileTrailer::filter_size`].
+//! A filter block consists of the following, in order:
+//!
+//! * [`FilterBlockHeader`].
+//!
+//! * An array of [`FilterBlockHeader::n_words`] 64-bit little-endian words that
+//!   contain the filter's bits.  Bit `i` is bit `i % 64` in word `i / 64`.
 
 use super::Rkyv;
 use crate::buffer_cache::FBuf;
@@ -69,7 +91,7 @@ use num_traits::FromPrimitive;
 use rkyv::{with::Inline, Archive, Serialize};
 
 /// Increment this on each incompatible change.
-pub const VERSION_NUMBER: u32 = 1;
+pub const VERSION_NUMBER: u32 = 3;
 
 /// 8-byte header at the beginning of each block.
 ///
@@ -111,6 +133,18 @@ pub struct FileTrailer {
     /// detecting version changes and supporting backward compatibility.
     pub version: u32,
 
+    /// File offset in bytes of the filter block.  If the file has no filter
+    /// block, this should be 0.
+    pub filter_offset: u64,
+
+    /// Length of the filter block in bytes.  If the file has no filter block,
+    /// this should be 0.
+    pub filter_size: u32,
+
+    /// Compression used for data and index blocks.
+    #[brw(align_after = 4)]
+    pub compression: Compression,
+
     /// Number of columns.
     #[bw(calc(columns.len() as u32))]
     pub n_columns: u32,
@@ -146,6 +180,68 @@ pub struct FileTrailerColumn {
     pub n_rows: u64,
 }
 
+/// Filter block header.
+///
+/// Serialized and deserialized automatically with [`mod@binrw`].
+#[binrw]
+pub struct FilterBlockHeader {
+    /// Block header with "LFBF" magic.
+    #[brw(assert(&header.magic == b"LFBF", "filter block has bad magic"))]
+    pub header: BlockHeader,
+
+    /// Number of bit positions probed for each key.
+    pub n_hashes: u32,
+
+    /// Number of 64-bit words in the filter, which immediately follow the
+    /// header.
+    pub n_words: u32,
+}
+
+impl FixedLen for FilterBlockHeader {
+    const LEN: usize = 16;
+}
+
+/// Block compression algorithm.
+///
+/// Serialized and deserialized automatically with [`mod@binrw`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[binrw]
+#[brw(repr(u8))]
+pub enum Compression {
+    /// No compression.
+    #[default]
+    None = 0,
+
+    /// [LZ4](https://lz4.org/) block compression, which is very fast but
+    /// compresses less than [`Compression::Zstd`].
+    Lz4 = 1,
+
+    /// [Zstandard](https://facebook.github.io/zstd/) compression at its
+    /// default level.
+    Zstd = 2,
+}
+
+/// Compressed block header.
+///
+/// Serialized and deserialized automatically with [`mod@binrw`].
+#[binrw]
+pub struct CompressedBlockHeader {
+    /// Block header with "LFCB" magic.
+    #[brw(assert(&header.magic == b"LFCB", "compressed block has bad magic"))]
+    pub header: BlockHeader,
+
+    /// Length of the block after decompression, in bytes.
+    pub uncompressed_len: u32,
+
+    /// Length of the compressed data, in bytes, which immediately follows the
+    /// header.
+    pub compressed_len: u32,
+}
+
+impl FixedLen for CompressedBlockHeader {
+    const LEN: usize = 16;
+}
+
 /// Type of a node in a column B-tree.
 ///
 /// Serialized and deserialized automatically with [`mod@binrw`].
@@ -37,7 +37,7 @@
 //! by data value for workloads that don't require it.[^0]
 //!
 //! Layer files should support approximate set membership query in `~O(1)`
-//! time.[^0]
+//! time.
 //!
 //! Layer files should support 1 TB data size.
 //!
@@ -66,6 +66,12 @@
 //! large data.  This could be ameliorated if the layer file's clients were
 //! permitted to provide a way to summarize data for comparisons.  The need for
 //! this improvement is not yet clear, so it is not yet implemented.
+//!
+//! Layer files include a Bloom filter over the keys in column 0, which the
+//! reader keeps in memory to support [approximate set
+//! membership](`reader::Reader::maybe_contains`) queries.  The filter's size
+//! can be set as a [parameter](`writer::Parameters::bloom_filter_bits_per_key`)
+//! at write time.
 
 #![warn(missing_docs)]
 
@@ -83,6 +89,7 @@ use rkyv::{
 
 use crate::buffer_cache::{FBuf, FBufSerializer};
 
+mod bloom;
 pub mod format;
 pub mod reader;
 pub mod writer;
@@ -171,23 +178,26 @@ where
 
 #[cfg(test)]
 mod test {
-    use std::{fmt::Debug, rc::Rc};
+    use std::{fmt::Debug, hash::Hash, rc::Rc, sync::Arc};
 
     use crate::{
         backend::{DefaultBackend, StorageControl, StorageExecutor, StorageRead, StorageWrite},
+        buffer_cache::{BufferCache, TinyLfuCache},
         test::init_test_logger,
     };
 
     use super::{
+        format::Compression,
         reader::{ColumnSpec, RowGroup},
         writer::{Parameters, Writer1, Writer2},
         Rkyv,
     };
 
     use rand::{seq::SliceRandom, thread_rng, Rng};
+    use tempfile::tempdir;
 
     trait TwoColumns {
-        type K0: Rkyv + Debug + Ord + Eq + Clone;
+        type K0: Rkyv + Debug + Ord + Eq + Clone + Hash;
         type A0: Rkyv + Debug + Eq + Clone;
         type K1: Rkyv + Debug + Ord + Eq + Clone;
         type A1: Rkyv + Debug + Eq + Clone;
@@ -383,6 +393,9 @@ mod test {
         }
 
         let reader = layer_file.into_reader().unwrap();
+        for row0 in 0..n0 {
+            assert!(reader.maybe_contains(&T::key0(row0)));
+        }
         let rows0 = reader.rows();
         test_cursor(&rows0, n0, |row0| {
             let key0 = T::key0(row0);
@@ -461,6 +474,30 @@ mod test {
         test_2_columns_helper(&GlommioBackend::default_for_thread(), Parameters::default());
     }
 
+    #[test]
+    fn test_2_columns_lz4() {
+        init_test_logger();
+        test_2_columns_helper(
+            &DefaultBackend::default_for_thread(),
+            Parameters {
+                compression: Compression::Lz4,
+                ..Parameters::default()
+            },
+        );
+    }
+
+    #[test]
+    fn test_2_columns_zstd() {
+        init_test_logger();
+        test_2_columns_helper(
+            &DefaultBackend::default_for_thread(),
+            Parameters {
+                compression: Compression::Zstd,
+                ..Parameters::default()
+            },
+        );
+    }
+
     #[test]
     fn test_2_columns_max_branch_2() {
         init_test_logger();
@@ -477,7 +514,7 @@ mod test {
         parameters: Parameters,
     ) where
         S: StorageControl + StorageRead + StorageWrite + StorageExecutor,
-        K: Rkyv + Debug + Ord + Eq + Clone,
+        K: Rkyv + Debug + Ord + Eq + Clone + Hash,
         A: Rkyv + Debug + Eq + Clone,
     {
         let mut writer = Writer1::new(storage, parameters).unwrap();
@@ -488,6 +525,10 @@ mod test {
 
         let reader = writer.into_reader().unwrap();
         assert_eq!(reader.rows().len(), n as u64);
+        for row in 0..n {
+            let (_before, key, _after, _aux) = expected(row);
+            assert!(reader.maybe_contains(&key));
+        }
         test_cursor(&reader.rows(), n, expected);
     }
 
@@ -506,6 +547,22 @@ mod test {
         test_i64_helper(Parameters::default());
     }
 
+    #[test]
+    fn test_i64_lz4() {
+        test_i64_helper(Parameters {
+            compression: Compression::Lz4,
+            ..Parameters::default()
+        });
+    }
+
+    #[test]
+    fn test_i64_zstd() {
+        test_i64_helper(Parameters {
+            compression: Compression::Zstd,
+            ..Parameters::default()
+        });
+    }
+
     #[test]
     fn test_i64_max_branch_32() {
         test_i64_helper(Parameters::with_max_branch(32));
@@ -536,6 +593,43 @@ mod test {
         );
     }
 
+    // Reads compressed blocks through a buffer cache, twice, so that the second
+    // pass reads decompressed blocks from the cache.  Large data blocks ensure
+    // that compression makes blocks smaller on disk.
+    #[test]
+    fn test_string_compressed_cached() {
+        fn f(x: usize) -> String {
+            format!("{x:09}")
+        }
+
+        init_test_logger();
+        let dir = tempdir().unwrap();
+        let cache = Rc::new(BufferCache::with_backend_lfu(
+            DefaultBackend::new(dir.path(), Default::default()),
+            Arc::new(TinyLfuCache::with_capacity(1 << 24)),
+        ));
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let mut writer = Writer1::new(
+                &cache,
+                Parameters {
+                    min_data_block: 1 << 16,
+                    compression,
+                    ..Parameters::default()
+                },
+            )
+            .unwrap();
+            for row in 0..1000 {
+                writer.write0((&f(row * 2 + 1), &())).unwrap();
+            }
+            let reader = writer.into_reader().unwrap();
+            for _ in 0..2 {
+                test_cursor(&reader.rows(), 1000, |row| {
+                    (f(row * 2), f(row * 2 + 1), f(row * 2 + 2), ())
+                });
+            }
+        }
+    }
+
     #[test]
     fn test_tuple() {
         init_test_logger();
@@ -560,4 +654,47 @@ mod test {
             Parameters::default(),
         );
     }
+
+    fn test_filter_helper(parameters: Parameters) -> usize {
+        init_test_logger();
+        let mut writer = Writer1::new(&DefaultBackend::default_for_thread(), parameters).unwrap();
+        for i in (0..10_000_i64).map(|i| i * 2) {
+            writer.write0((&i, &())).unwrap();
+        }
+        let reader = writer.into_reader().unwrap();
+        for i in (0..10_000_i64).map(|i| i * 2) {
+            assert!(reader.maybe_contains(&i));
+        }
+        (0..10_000_i64)
+            .map(|i| i * 2 + 1)
+            .filter(|i| reader.maybe_contains(i))
+            .count()
+    }
+
+    #[test]
+    fn test_filter() {
+        let false_positives = test_filter_helper(Parameters::default());
+        assert!(false_positives < 500, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn test_no_filter() {
+        let false_positives = test_filter_helper(Parameters {
+            bloom_filter_bits_per_key: 0,
+            ..Parameters::default()
+        });
+        assert_eq!(false_positives, 10_000);
+    }
+
+    #[test]
+    fn test_empty_filter() {
+        init_test_logger();
+        let writer = Writer1::<_, i64, ()>::new(
+            &DefaultBackend::default_for_thread(),
+            Parameters::default(),
+        )
+        .unwrap();
+        let reader = writer.into_reader().unwrap();
+        assert!(!reader.maybe_contains(&0));
+    }
 }
@@ -5,6 +5,7 @@
 use std::{
     cmp::Ordering::{self, *},
     fmt::{Debug, Formatter, Result as FmtResult},
+    hash::Hash,
     marker::PhantomData,
     ops::{Bound, Range, RangeBounds},
     rc::Rc,
@@ -25,9 +26,13 @@ use crate::{
         StorageWrite,
     },
     buffer_cache::FBuf,
-    file::format::{
-        ArchivedItem, DataBlockHeader, FileTrailer, FileTrailerColumn, IndexBlockHeader, Item,
-        NodeType, Varint, VERSION_NUMBER,
+    file::{
+        bloom::{hash_key, BloomFilter},
+        format::{
+            ArchivedItem, CompressedBlockHeader, Compression, DataBlockHeader, FileTrailer,
+            FileTrailerColumn, FilterBlockHeader, FixedLen, IndexBlockHeader, Item, NodeType,
+            Varint, VERSION_NUMBER,
+        },
     },
 };
 
@@ -237,6 +242,27 @@ pub enum CorruptionError {
         node_size: u32,
     },
 
+    /// Invalid filter block pointer in file trailer block, or invalid contents
+    /// in the filter block.
+    #[error("File trailer has invalid filter block offset {offset} or size {size}, or the filter block it points to is invalid.")]
+    InvalidFilter {
+        /// Block offset in bytes.
+        offset: u64,
+        /// Block size in bytes.
+        size: u32,
+    },
+
+    /// Compressed block could not be decompressed.
+    #[error("{size}-byte compressed block at offset {offset} could not be decompressed to {uncompressed_len} bytes.")]
+    Decompression {
+        /// Block offset in bytes.
+        offset: u64,
+        /// Block size in bytes.
+        size: usize,
+        /// Expected length of the decompressed block.
+        uncompressed_len: usize,
+    },
+
     /// Invalid row group in data block.
     #[error("Row group {index} in {size}-byte data block at offset {offset} has invalid row range {start}..{end}.")]
     InvalidRowGroup {
@@ -399,11 +425,15 @@ where
     K: Rkyv,
     A: Rkyv,
 {
-    fn new<S>(file: &ImmutableFileRef<S>, node: &TreeNode) -> Result<Self, Error>
+    fn new<S>(
+        file: &ImmutableFileRef<S>,
+        compression: Compression,
+        node: &TreeNode,
+    ) -> Result<Self, Error>
     where
         S: StorageRead + StorageControl + StorageExecutor,
     {
-        let raw = read_block(file, node.location)?;
+        let raw = read_tree_block(file, compression, node.location)?;
         let header = DataBlockHeader::read_le(&mut io::Cursor::new(raw.as_slice()))?;
         let expected_rows = node.rows.end - node.rows.start;
         if header.n_values as u64 != expected_rows {
@@ -548,15 +578,19 @@ struct TreeNode {
 }
 
 impl TreeNode {
-    fn read<S, K, A>(self, file: &ImmutableFileRef<S>) -> Result<TreeBlock<K, A>, Error>
+    fn read<S, K, A>(
+        self,
+        file: &ImmutableFileRef<S>,
+        compression: Compression,
+    ) -> Result<TreeBlock<K, A>, Error>
     where
         S: StorageRead + StorageControl + StorageExecutor,
         K: Rkyv + Debug,
         A: Rkyv,
     {
         match self.node_type {
-            NodeType::Data => Ok(TreeBlock::Data(DataBlock::new(file, &self)?)),
-            NodeType::Index => Ok(TreeBlock::Index(IndexBlock::new(file, &self)?)),
+            NodeType::Data => Ok(TreeBlock::Data(DataBlock::new(file, compression, &self)?)),
+            NodeType::Index => Ok(TreeBlock::Index(IndexBlock::new(file, compression, &self)?)),
         }
     }
 }
@@ -620,7 +654,11 @@ impl<K> IndexBlock<K>
 where
     K: Rkyv,
 {
-    fn new<S>(file: &ImmutableFileRef<S>, node: &TreeNode) -> Result<Self, Error>
+    fn new<S>(
+        file: &ImmutableFileRef<S>,
+        compression: Compression,
+        node: &TreeNode,
+    ) -> Result<Self, Error>
     where
         S: StorageRead + StorageControl + StorageExecutor,
     {
@@ -636,7 +674,7 @@ where
             .into());
         }
 
-        let raw = read_block(file, node.location)?;
+        let raw = read_tree_block(file, compression, node.location)?;
         let header = IndexBlockHeader::read_le(&mut io::Cursor::new(raw.as_slice()))?;
         let BlockLocation { size, offset } = node.location;
         if header.n_children == 0 {
@@ -922,6 +960,12 @@ where
     file: Rc<ImmutableFileRef<S>>,
     columns: Vec<Column>,
 
+    /// Bloom filter over the keys in column 0, if the file has one.
+    filter: Option<BloomFilter>,
+
+    /// Compression for data and index blocks.
+    compression: Compression,
+
     /// `fn() -> T` is `Send` and `Sync` regardless of `T`.  See
     /// <https://doc.rust-lang.org/nomicon/phantom-data.html>.
     _phantom: PhantomData<fn() -> T>,
@@ -936,6 +980,11 @@ where
         location.offset,
         location.size,
     ))?;
+    verify_checksum(&block, location)?;
+    Ok(block)
+}
+
+fn verify_checksum(block: &FBuf, location: BlockLocation) -> Result<(), Error> {
     let computed_checksum = crc32c(&block[4..]);
     let checksum = u32::from_le_bytes(block[..4].try_into().unwrap());
     if checksum != computed_checksum {
@@ -948,7 +997,110 @@ where
         }
         .into());
     }
-    Ok(block)
+    Ok(())
+}
+
+/// Reads the data or index block at `location`, decompressing it if necessary.
+///
+/// Compressed blocks are verified and decompressed before the storage layer
+/// caches them, so that cache hits return the decompressed block directly.
+fn read_tree_block<S>(
+    file: &ImmutableFileRef<S>,
+    compression: Compression,
+    location: BlockLocation,
+) -> Result<Arc<FBuf>, Error>
+where
+    S: StorageRead + StorageControl + StorageExecutor,
+{
+    if compression == Compression::None {
+        return read_block(file, location);
+    }
+    file.storage.block_on(file.storage.read_block_decoded(
+        file.file_handle.as_ref().unwrap(),
+        location.offset,
+        location.size,
+        |block| {
+            verify_checksum(&block, location)?;
+            decompress(compression, block, location)
+        },
+    ))
+}
+
+fn decompress(
+    compression: Compression,
+    block: Arc<FBuf>,
+    location: BlockLocation,
+) -> Result<Arc<FBuf>, Error> {
+    // Blocks that didn't compress well are stored uncompressed.
+    if &block[4..8] != b"LFCB" {
+        return Ok(block);
+    }
+
+    let header = CompressedBlockHeader::read_le(&mut io::Cursor::new(block.as_slice()))?;
+    let start = CompressedBlockHeader::LEN;
+    let count = header.compressed_len as usize;
+    if start + count > block.len() {
+        return Err(CorruptionError::InvalidArray {
+            block_size: block.len(),
+            offset: start,
+            count,
+            each: 1,
+        }
+        .into());
+    }
+    let compressed = &block[start..start + count];
+
+    let uncompressed_len = header.uncompressed_len as usize;
+    let error = || {
+        let BlockLocation { size, offset } = location;
+        Error::from(CorruptionError::Decompression {
+            offset,
+            size,
+            uncompressed_len,
+        })
+    };
+    if uncompressed_len > 1 << 31 {
+        return Err(error());
+    }
+    let mut raw = FBuf::with_capacity(uncompressed_len);
+    raw.resize(uncompressed_len, 0);
+    let len = match compression {
+        Compression::None => unreachable!(),
+        Compression::Lz4 => lz4_flex::block::decompress_into(compressed, raw.as_mut_slice()).ok(),
+        Compression::Zstd => zstd::bulk::decompress_to_buffer(compressed, raw.as_mut_slice()).ok(),
+    };
+    if len != Some(uncompressed_len) {
+        return Err(error());
+    }
+    Ok(Arc::new(raw))
+}
+
+fn read_filter<S>(file: &ImmutableFileRef<S>, offset: u64, size: u32) -> Result<BloomFilter, Error>
+where
+    S: StorageRead + StorageControl + StorageExecutor,
+{
+    let invalid = || Error::from(CorruptionError::InvalidFilter { offset, size });
+    let location = BlockLocation::new(offset, size as usize).map_err(|_| invalid())?;
+    let block = read_block(file, location)?;
+    let header = FilterBlockHeader::read_le(&mut io::Cursor::new(block.as_slice()))?;
+
+    let count = header.n_words as usize;
+    let start = FilterBlockHeader::LEN;
+    let end = start + count * 8;
+    if end > block.len() {
+        return Err(CorruptionError::InvalidArray {
+            block_size: block.len(),
+            offset: start,
+            count,
+            each: 8,
+        }
+        .into());
+    }
+    let words = block[start..end]
+        .chunks_exact(8)
+        .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+        .collect();
+    BloomFilter::from_parts(header.n_hashes, words).ok_or_else(invalid)
 }
 
 /// Layer file column specification.
@@ -1042,9 +1194,22 @@ where
             }
         }
 
+        let compression = file_trailer.compression;
+        let filter = if file_trailer.filter_size != 0 {
+            Some(read_filter(
+                &file,
+                file_trailer.filter_offset,
+                file_trailer.filter_size,
+            )?)
+        } else {
+            None
+        };
+
         Ok(Self(Arc::new(ReaderInner {
             file,
             columns,
+            filter,
+            compression,
             _phantom: PhantomData,
         })))
     }
@@ -1062,6 +1227,8 @@ where
         Ok(Self(Arc::new(ReaderInner {
             file: Rc::new(ImmutableFileRef::new(storage, file_handle)),
             columns: (0..T::n_columns()).map(|_| Column::empty()).collect(),
+            filter: None,
+            compression: Compression::None,
             _phantom: PhantomData,
         })))
     }
@@ -1082,6 +1249,14 @@ where
     pub fn n_rows(&self, column: usize) -> u64 {
         self.0.columns[column].n_rows
     }
+
+    /// Returns the size of the layer file in bytes.
+    pub fn byte_size(&self) -> Result<u64, Error> {
+        let file = &self.0.file;
+        Ok(file
+            .storage
+            .block_on(file.storage.get_size(file.file_handle.as_ref().unwrap()))?)
+    }
 }
 
 impl<S, T> Clone for Reader<S, T>
@@ -1104,6 +1279,25 @@ where
     pub fn rows(&self) -> RowGroup<S, K, A, N, (K, A, N)> {
         RowGroup::new(self, 0, 0..self.0.columns[0].n_rows)
     }
+
+    /// Returns `false` if `key` is definitely not in column 0, or `true` if it
+    /// might be.
+    ///
+    /// This consults the file's Bloom filter, which is held in memory, so it
+    /// is much cheaper than seeking with a cursor.  If the file was written
+    /// without a filter (see [`Parameters::bloom_filter_bits_per_key`]), this
+    /// returns `true` for any key unless the file is empty.
+    ///
+    /// [`Parameters::bloom_filter_bits_per_key`]: super::writer::Parameters::bloom_filter_bits_per_key
+    pub fn maybe_contains(&self, key: &K) -> bool
+    where
+        K: Hash,
+    {
+        match &self.0.filter {
+            Some(filter) => filter.maybe_contains(hash_key(key)),
+            None => self.0.columns[0].n_rows > 0,
+        }
+    }
 }
 
 /// A sorted, indexed group of unique rows in a [`Reader`].
@@ -1710,7 +1904,7 @@ where
         S: StorageRead + StorageControl + StorageExecutor,
     {
         loop {
-            let block = node.read(&reader.0.file)?;
+            let block = node.read(&reader.0.file, reader.0.compression)?;
             let next = block.lookup_row(row)?;
             match block {
                 TreeBlock::Data(data) => return Ok(Self { row, indexes, data }),
@@ -1779,7 +1973,7 @@ where
             return Ok(None);
         };
         loop {
-            match node.read(&row_group.reader.0.file)? {
+            match node.read(&row_group.reader.0.file, row_group.reader.0.compression)? {
                 TreeBlock::Index(index_block) => {
                     let Some(child_idx) =
                         index_block.find_best_match(&row_group.rows, compare, bias)
@@ -5,6 +5,7 @@
 //! struct, which is easily done, or mark the currently private `Writer` as
 //! `pub`.
 use std::{
+    hash::Hash,
     marker::PhantomData,
     mem::{replace, take},
     ops::Range,
@@ -25,9 +26,11 @@ use crate::{
     },
     buffer_cache::{FBuf, FBufSerializer},
     file::{
+        bloom::{hash_key, BloomFilter},
         format::{
-            BlockHeader, DataBlockHeader, FileTrailer, FileTrailerColumn, FixedLen,
-            IndexBlockHeader, Item, NodeType, Varint, VERSION_NUMBER,
+            BlockHeader, CompressedBlockHeader, Compression, DataBlockHeader, FileTrailer,
+            FileTrailerColumn, FilterBlockHeader, FixedLen, IndexBlockHeader, Item, NodeType,
+            Varint, VERSION_NUMBER,
         },
         BlockLocation,
     },
@@ -123,6 +126,30 @@ pub struct Parameters {
     /// pathological and silly.
     pub min_branch: usize,
 
+    /// Number of bits per key in the Bloom filter over column 0, or 0 to omit
+    /// the filter.
+    ///
+    /// The filter allows [`Reader::maybe_contains`] to rule out most keys that
+    /// are not in the file without reading any index or data blocks.  With
+    /// the default of 10 bits per key, about 1% of absent keys are false
+    /// positives.  Each additional bit per key reduces the false positive rate
+    /// by about a third, at the cost of a larger filter that readers keep in
+    /// memory.
+    pub bloom_filter_bits_per_key: usize,
+
+    /// Compression for data and index blocks.
+    ///
+    /// Compression reduces the size of the file on disk at the cost of CPU
+    /// time to compress blocks when writing and to decompress them when
+    /// reading.  Blocks are cached in decompressed form, so cache hits do not
+    /// pay for decompression.
+    ///
+    /// Blocks occupy a power-of-2 number of bytes on disk, so compression can
+    /// only shrink a block to the next smaller power of 2.  Thus, compression
+    /// is more effective with larger [`min_data_block`](Self::min_data_block)
+    /// and [`min_index_block`](Self::min_index_block).
+    pub compression: Compression,
+
     #[cfg(test)]
     pub max_branch: usize,
 }
@@ -157,6 +184,8 @@ impl Default for Parameters {
             min_data_block: 8192,
             min_index_block: 8192,
             min_branch: 32,
+            bloom_filter_bits_per_key: 10,
+            compression: Compression::None,
             #[cfg(test)]
             max_branch: usize::MAX,
         }
@@ -280,7 +309,7 @@ impl ColumnWriter {
         W: StorageWrite + StorageControl + StorageExecutor,
         K: Rkyv,
     {
-        let location = block_writer.write_block(data_block.raw)?;
+        let location = block_writer.write_tree_block(data_block.raw)?;
 
         if let Some(index_block) = self.get_index_block(0).add_entry(
             location,
@@ -303,7 +332,7 @@ impl ColumnWriter {
         K: Rkyv,
     {
         loop {
-            let location = block_writer.write_block(index_block.raw)?;
+            let location = block_writer.write_tree_block(index_block.raw)?;
 
             level += 1;
             let opt_index_block = self.get_index_block(level).add_entry(
@@ -828,17 +857,19 @@ where
     storage: Rc<W>,
     file_handle: Option<FileHandle>,
     offset: u64,
+    compression: Compression,
 }
 
 impl<W> BlockWriter<W>
 where
     W: StorageWrite + StorageControl + StorageExecutor,
 {
-    fn new(storage: &Rc<W>, file_handle: FileHandle) -> Self {
+    fn new(storage: &Rc<W>, file_handle: FileHandle, compression: Compression) -> Self {
         Self {
             storage: storage.clone(),
             file_handle: Some(file_handle),
             offset: 0,
+            compression,
         }
     }
 
@@ -861,6 +892,40 @@ where
         ))?;
         Ok(location)
     }
+
+    /// Writes `block`, which must be a data or index block, compressing it if
+    /// compression is enabled and doing so makes it smaller on disk.
+    fn write_tree_block(&mut self, block: FBuf) -> Result<BlockLocation, StorageError> {
+        fn disk_size(block: &FBuf) -> usize {
+            block.len().max(4096).next_power_of_two()
+        }
+
+        if self.compression != Compression::None {
+            let compressed = compress(self.compression, &block)?;
+            if disk_size(&compressed) < disk_size(&block) {
+                return self.write_block(compressed);
+            }
+        }
+        self.write_block(block)
+    }
+}
+
+/// Compresses `raw` with `compression` and returns the compressed block,
+/// including its [`CompressedBlockHeader`].
+fn compress(compression: Compression, raw: &FBuf) -> Result<FBuf, StorageError> {
+    let compressed = match compression {
+        Compression::None => unreachable!(),
+        Compression::Lz4 => lz4_flex::block::compress(raw),
+        Compression::Zstd => zstd::bulk::compress(raw, zstd::DEFAULT_COMPRESSION_LEVEL)?,
+    };
+    let mut block = CompressedBlockHeader {
+        header: BlockHeader::new(b"LFCB"),
+        uncompressed_len: raw.len() as u32,
+        compressed_len: compressed.len() as u32,
+    }
+    .into_block();
+    block.extend_from_slice(&compressed);
+    Ok(block)
 }
 
 impl<W> Drop for BlockWriter<W>
@@ -887,6 +952,8 @@ where
     writer: BlockWriter<W>,
     cws: Vec<ColumnWriter>,
     finished_columns: Vec<FileTrailerColumn>,
+    bloom_filter_bits_per_key: usize,
+    key_hashes: Vec<u64>,
 }
 
 impl<W> Writer<W>
@@ -898,15 +965,19 @@ where
         parameters: Parameters,
         n_columns: usize,
     ) -> Result<Self, StorageError> {
+        let bloom_filter_bits_per_key = parameters.bloom_filter_bits_per_key;
+        let compression = parameters.compression;
         let parameters = Rc::new(parameters);
         let cws = (0..n_columns)
             .map(|column| ColumnWriter::new(&parameters, column))
             .collect();
         let finished_columns = Vec::with_capacity(n_columns);
         let writer = Self {
-            writer: BlockWriter::new(writer, writer.block_on(writer.create())?),
+            writer: BlockWriter::new(writer, writer.block_on(writer.create())?, compression),
             cws,
             finished_columns,
+            bloom_filter_bits_per_key,
+            key_hashes: Vec::new(),
         };
         Ok(writer)
     }
@@ -929,6 +1000,18 @@ where
         self.cws[column].add_item(&mut self.writer, item, &row_group)
     }
 
+    pub fn write0<K, A>(&mut self, item: (&K, &A)) -> Result<(), StorageError>
+    where
+        K: Rkyv + Ord + Hash,
+        A: Rkyv,
+    {
+        self.write(0, item)?;
+        if self.bloom_filter_bits_per_key > 0 {
+            self.key_hashes.push(hash_key(item.0));
+        }
+        Ok(())
+    }
+
     pub fn finish_column<K, A>(&mut self, column: usize) -> Result<(), StorageError>
     where
         K: Rkyv + Ord,
@@ -947,10 +1030,29 @@ where
     pub fn close(mut self) -> Result<ImmutableFileHandle, StorageError> {
         debug_assert_eq!(self.cws.len(), self.finished_columns.len());
 
+        // Write the filter block, if any.
+        let (filter_offset, filter_size) = if !self.key_hashes.is_empty() {
+            let filter = BloomFilter::new(&self.key_hashes, self.bloom_filter_bits_per_key);
+            let mut block = FilterBlockHeader {
+                header: BlockHeader::new(b"LFBF"),
+                n_hashes: filter.n_hashes(),
+                n_words: filter.words().len() as u32,
+            }
+            .into_block();
+            filter.put_words(&mut block);
+            let location = self.writer.write_block(block)?;
+            (location.offset, location.size as u32)
+        } else {
+            (0, 0)
+        };
+
         // Write the file trailer block.
         let file_trailer = FileTrailer {
             header: BlockHeader::new(b"LFFT"),
             version: VERSION_NUMBER,
+            filter_offset,
+            filter_size,
+            compression: self.writer.compression,
             columns: take(&mut self.finished_columns),
         };
         self.writer.write_block(file_trailer.into_block())?;
@@ -1004,7 +1106,7 @@ where
 impl<S, K0, A0> Writer1<S, K0, A0>
 where
     S: StorageControl + StorageWrite + StorageExecutor,
-    K0: Rkyv + Ord,
+    K0: Rkyv + Ord + Hash,
     A0: Rkyv,
 {
     /// Creates a new writer with the given parameters.
@@ -1017,7 +1119,7 @@ where
     /// Writes `item` to column 0.  `item.0` must be greater than passed in the
     /// previous call to this function (if any).
     pub fn write0(&mut self, item: (&K0, &A0)) -> Result<(), StorageError> {
-        self.inner.write(0, item)
+        self.inner.write0(item)
     }
 
     /// Returns the number of calls to [`write0`](Self::write0) so far.
@@ -1093,7 +1195,7 @@ where
 impl<S, K0, A0, K1, A1> Writer2<S, K0, A0, K1, A1>
 where
     S: StorageControl + StorageWrite + StorageExecutor,
-    K0: Rkyv + Ord,
+    K0: Rkyv + Ord + Hash,
     A0: Rkyv,
     K1: Rkyv + Ord,
     A1: Rkyv,
@@ -1112,7 +1214,7 @@ where
     /// `item.0` must be greater than passed in the previous call to this
     /// function (if any).
     pub fn write0(&mut self, item: (&K0, &A0)) -> Result<(), StorageError> {
-        self.inner.write(0, item)
+        self.inner.write0(item)
     }
 
     /// Writes `item` to column 1.  `item.0` must be greater than passed in the
@@ -39,6 +39,8 @@ declare_queries! {
     q7,
     q8,
     q9,
+    q10,
+    q11,
     q12,
     q13,
     q14,
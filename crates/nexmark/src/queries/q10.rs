@@ -0,0 +1,151 @@
+use super::NexmarkStream;
+use crate::model::Event;
+use dbsp::{operator::FilterMap, utils::Tup7, OrdZSet, RootCircuit, Stream};
+use time::{format_description, OffsetDateTime};
+
+/// Query 10: Log to File System (Not in original suite)
+///
+/// Log all events to file system. Illustrates windows streaming data into
+/// partitioned file system.
+///
+/// Every minute, save all events from the last period into partitioned log
+/// files.
+///
+/// ```sql
+/// CREATE TABLE fs_sink (
+///   auction  BIGINT,
+///   bidder  BIGINT,
+///   price  BIGINT,
+///   dateTime  TIMESTAMP(3),
+///   extra  VARCHAR,
+///   dt STRING,
+///   hm STRING
+/// ) PARTITIONED BY (dt, hm) WITH (
+///   'connector' = 'filesystem',
+///   'path' = 'file://${NEXMARK_DIR}/data/output/${SUBMIT_TIME}/bid/',
+///   'format' = 'csv',
+///   'sink.partition-commit.trigger' = 'partition-time',
+///   'sink.partition-commit.delay' = '1 min',
+///   'sink.partition-commit.policy.kind' = 'success-file',
+///   'partition.time-extractor.timestamp-pattern' = '$dt $hm:00',
+///   'sink.rolling-policy.rollover-interval' = '1min',
+///   'sink.rolling-policy.check-interval' = '1min'
+/// );
+///
+/// INSERT INTO fs_sink
+/// SELECT auction, bidder, price, dateTime, extra, DATE_FORMAT(dateTime, 'yyyy-MM-dd'), DATE_FORMAT(dateTime, 'HH:mm')
+/// FROM bid;
+/// ```
+///
+/// The DBSP version computes the partitioned records and leaves writing them
+/// to the file system to the output connector.
+
+type Q10Stream =
+    Stream<RootCircuit, OrdZSet<Tup7<u64, u64, u64, u64, String, String, String>, i64>>;
+
+pub fn q10(input: NexmarkStream) -> Q10Stream {
+    let day_format = format_description::parse("[year]-[month]-[day]").unwrap();
+    let minute_format = format_description::parse("[hour]:[minute]").unwrap();
+
+    input.flat_map(move |event| match event {
+        Event::Bid(b) => {
+            let date_time =
+                OffsetDateTime::from_unix_timestamp_nanos(b.date_time as i128 * 1_000_000).unwrap();
+            Some(Tup7(
+                b.auction,
+                b.bidder,
+                b.price,
+                b.date_time,
+                b.extra.clone(),
+                date_time.format(&day_format).unwrap(),
+                date_time.format(&minute_format).unwrap(),
+            ))
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generator::tests::{make_bid, make_person},
+        model::{Bid, Event},
+    };
+    use dbsp::{zset, RootCircuit};
+
+    #[test]
+    fn test_q10() {
+        let input_vecs = vec![
+            vec![
+                (
+                    Event::Bid(Bid {
+                        auction: 1,
+                        bidder: 2,
+                        price: 99,
+                        // 2023-09-05 11:42:03.123 UTC
+                        date_time: 1_693_914_123_123,
+                        extra: String::from("extra"),
+                        ..make_bid()
+                    }),
+                    1,
+                ),
+                (Event::Person(make_person()), 1),
+            ],
+            vec![(
+                Event::Bid(Bid {
+                    auction: 3,
+                    bidder: 4,
+                    price: 101,
+                    // 1970-01-01 00:01:00 UTC
+                    date_time: 60_000,
+                    ..make_bid()
+                }),
+                1,
+            )],
+        ]
+        .into_iter();
+
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (stream, input_handle) = circuit.add_input_zset::<Event, i64>();
+
+            let mut expected_output = vec![
+                zset! {
+                    Tup7(
+                        1,
+                        2,
+                        99,
+                        1_693_914_123_123,
+                        String::from("extra"),
+                        String::from("2023-09-05"),
+                        String::from("11:42"),
+                    ) => 1,
+                },
+                zset! {
+                    Tup7(
+                        3,
+                        4,
+                        101,
+                        60_000,
+                        String::new(),
+                        String::from("1970-01-01"),
+                        String::from("00:01"),
+                    ) => 1,
+                },
+            ]
+            .into_iter();
+
+            let output = q10(stream);
+
+            output.inspect(move |batch| assert_eq!(batch, &expected_output.next().unwrap()));
+
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        for mut vec in input_vecs {
+            input_handle.append(&mut vec);
+            circuit.step().unwrap();
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use super::{NexmarkStream, WATERMARK_INTERVAL_SECONDS};
+use crate::model::Event;
+use dbsp::{
+    operator::FilterMap,
+    utils::{Tup2, Tup4},
+    OrdIndexedZSet, OrdZSet, RootCircuit, Stream,
+};
+
+///
+/// Query 11: User Sessions (Not in original suite)
+///
+/// How many bids did a user make in each session they were active?
+/// Illustrates session windows.
+///
+/// Group bids by the same user into sessions with max session gap.
+/// Emit the number of bids per session.
+///
+/// ```sql
+/// CREATE TABLE discard_sink (
+///   bidder BIGINT,
+///   bid_count BIGINT,
+///   starttime TIMESTAMP(3),
+///   endtime TIMESTAMP(3)
+/// ) WITH (
+///   'connector' = 'blackhole'
+/// );
+///
+/// INSERT INTO discard_sink
+/// SELECT
+///     B.bidder,
+///     count(*) as bid_count,
+///     SESSION_START(B.dateTime, INTERVAL '10' SECOND) as starttime,
+///     SESSION_END(B.dateTime, INTERVAL '10' SECOND) as endtime
+/// FROM bid B
+/// GROUP BY B.bidder, SESSION(B.dateTime, INTERVAL '10' SECOND);
+/// ```
+
+type Q11Stream = Stream<RootCircuit, OrdZSet<Tup4<u64, u64, u64, u64>, i64>>;
+
+const SESSION_GAP_SECONDS: u64 = 10;
+
+pub fn q11(input: NexmarkStream) -> Q11Stream {
+    // All bids indexed by bidder, with date time as the secondary key.
+    let bids_by_bidder: Stream<_, OrdIndexedZSet<u64, Tup2<u64, ()>, _>> =
+        input.flat_map_index(|event| match event {
+            Event::Bid(b) => Some((b.bidder, Tup2(b.date_time, ()))),
+            _ => None,
+        });
+
+    // Set the watermark to `WATERMARK_INTERVAL_SECONDS` behind the largest
+    // timestamp seen so far.  Sessions that end before the watermark are
+    // final.
+    let watermark = input
+        .flat_map_index(|event| match event {
+            Event::Bid(b) => Some((b.date_time, ())),
+            _ => None,
+        })
+        .waterline_monotonic(
+            || 0,
+            |date_time| date_time.saturating_sub(WATERMARK_INTERVAL_SECONDS * 1000),
+        );
+
+    let sessions = bids_by_bidder.session_window(SESSION_GAP_SECONDS * 1000, &watermark);
+
+    // Count the number of bids in each session.
+    sessions
+        .map_index(|(bidder, Tup2(Tup2(starttime, endtime), _))| {
+            ((*bidder, *starttime, *endtime), ())
+        })
+        .weighted_count()
+        .map(|(&(bidder, starttime, endtime), &count)| {
+            Tup4(bidder, count as u64, starttime, endtime)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        generator::tests::make_bid,
+        model::{Bid, Event},
+    };
+    use dbsp::{trace::Batch, zset, IndexedZSet, RootCircuit};
+    use rstest::rstest;
+    use std::collections::BTreeMap;
+
+    fn bid_events(batch: Vec<(u64, u64)>) -> Vec<(Event, i64)> {
+        batch
+            .into_iter()
+            .map(|(bidder, date_time)| {
+                (
+                    Event::Bid(Bid {
+                        bidder,
+                        date_time,
+                        ..make_bid()
+                    }),
+                    1,
+                )
+            })
+            .collect()
+    }
+
+    // Non-incremental implementation of q11 over all bids received so far.
+    fn q11_reference(bids: &OrdZSet<Event, i64>) -> OrdZSet<Tup4<u64, u64, u64, u64>, i64> {
+        let gap = SESSION_GAP_SECONDS * 1000;
+
+        let mut bids_by_bidder: BTreeMap<u64, BTreeMap<u64, i64>> = BTreeMap::new();
+        for (event, (), weight) in bids.iter() {
+            if let Event::Bid(b) = event {
+                *bids_by_bidder
+                    .entry(b.bidder)
+                    .or_default()
+                    .entry(b.date_time)
+                    .or_default() += weight;
+            }
+        }
+
+        let mut tuples = Vec::new();
+        for (bidder, times) in bids_by_bidder {
+            let mut session: Option<(u64, u64, u64)> = None;
+            for (date_time, count) in times.into_iter().filter(|(_, count)| *count > 0) {
+                session = match session {
+                    Some((start, end, n)) if date_time < end => {
+                        Some((start, date_time + gap, n + count as u64))
+                    }
+                    Some((start, end, n)) => {
+                        tuples.push((Tup4(bidder, n, start, end), 1));
+                        Some((date_time, date_time + gap, count as u64))
+                    }
+                    None => Some((date_time, date_time + gap, count as u64)),
+                };
+            }
+            if let Some((start, end, n)) = session {
+                tuples.push((Tup4(bidder, n, start, end), 1));
+            }
+        }
+
+        OrdZSet::from_keys((), tuples)
+    }
+
+    #[rstest]
+    #[case::one_bidder_single_session(
+        vec![vec![(1, 1_000), (1, 5_000), (1, 12_000)], vec![(1, 20_000)]],
+        vec![
+            zset! { Tup4(1, 3, 1_000, 22_000) => 1 },
+            zset! { Tup4(1, 3, 1_000, 22_000) => -1, Tup4(1, 4, 1_000, 30_000) => 1 },
+        ],
+    )]
+    #[case::one_bidder_multiple_sessions(
+        vec![vec![(1, 1_000), (1, 5_000)], vec![(1, 25_000), (1, 27_000)]],
+        vec![
+            zset! { Tup4(1, 2, 1_000, 15_000) => 1 },
+            zset! { Tup4(1, 2, 25_000, 37_000) => 1 },
+        ],
+    )]
+    #[case::late_bid_merges_sessions(
+        vec![vec![(1, 1_000), (1, 13_000)], vec![(1, 10_000)]],
+        vec![
+            zset! { Tup4(1, 1, 1_000, 11_000) => 1, Tup4(1, 1, 13_000, 23_000) => 1 },
+            zset! {
+                Tup4(1, 1, 1_000, 11_000) => -1,
+                Tup4(1, 1, 13_000, 23_000) => -1,
+                Tup4(1, 3, 1_000, 23_000) => 1,
+            },
+        ],
+    )]
+    // The watermark is at 16_000 after the first batch, so the bid at 2_000 is
+    // ignored.
+    #[case::bid_behind_watermark_is_dropped(
+        vec![vec![(1, 1_000), (1, 20_000)], vec![(1, 2_000)]],
+        vec![
+            zset! { Tup4(1, 1, 1_000, 11_000) => 1, Tup4(1, 1, 20_000, 30_000) => 1 },
+            zset! {},
+        ],
+    )]
+    #[case::multiple_bidders(
+        vec![vec![(1, 1_000), (2, 2_000), (1, 9_000)], vec![(2, 15_000), (1, 18_000)]],
+        vec![
+            zset! { Tup4(1, 2, 1_000, 19_000) => 1, Tup4(2, 1, 2_000, 12_000) => 1 },
+            zset! {
+                Tup4(1, 2, 1_000, 19_000) => -1,
+                Tup4(1, 3, 1_000, 28_000) => 1,
+                Tup4(2, 1, 15_000, 25_000) => 1,
+            },
+        ],
+    )]
+    fn test_q11(
+        #[case] bidder_bid_batches: Vec<Vec<(u64, u64)>>,
+        #[case] expected_zsets: Vec<OrdZSet<Tup4<u64, u64, u64, u64>, i64>>,
+    ) {
+        let input_vecs = bidder_bid_batches.into_iter().map(bid_events);
+
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (stream, input_handle) = circuit.add_input_zset::<Event, i64>();
+
+            let output = q11(stream);
+
+            let mut expected_output = expected_zsets.into_iter();
+            output.inspect(move |batch| assert_eq!(batch, &expected_output.next().unwrap()));
+
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        for mut vec in input_vecs {
+            input_handle.append(&mut vec);
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_q11_reference() {
+        // Generate out-of-order bids for a few bidders, with bursts of
+        // activity separated by gaps of varying length.  All bids in a batch
+        // are within `WATERMARK_INTERVAL_SECONDS` of each other, so none of
+        // them are late.
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next_random = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        let mut base = 0;
+        let input_vecs = (0..100)
+            .map(|_| {
+                base += 1_000 + next_random(4) * 3_000;
+                let batch = (0..next_random(10))
+                    .map(|_| (next_random(4), base + next_random(3_000)))
+                    .collect();
+                bid_events(batch)
+            })
+            .collect::<Vec<_>>();
+
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (stream, input_handle) = circuit.add_input_zset::<Event, i64>();
+
+            let expected = stream.integrate().apply(q11_reference);
+            let output = q11(stream).integrate();
+
+            expected.apply2(&output, |expected, output| assert_eq!(expected, output));
+
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        for mut vec in input_vecs {
+            input_handle.append(&mut vec);
+            circuit.step().unwrap();
+        }
+    }
+}
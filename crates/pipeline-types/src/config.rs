@@ -10,7 +10,7 @@ use serde_yaml::Value as YamlValue;
 use std::{borrow::Cow, collections::BTreeMap};
 use utoipa::ToSchema;
 
-use crate::query::OutputQuery;
+use crate::query::{OutputQuery, SelectQuery};
 
 /// Default value of `InputEndpointConfig::max_buffered_records`.
 /// It is declared as a function and not as a constant, so it can
@@ -19,6 +19,11 @@ pub(crate) const fn default_max_buffered_records() -> u64 {
     1_000_000
 }
 
+/// Default value of `ConnectorConfig::weight`.
+const fn default_weight() -> u64 {
+    1
+}
+
 /// Default number of DBSP worker threads.
 const fn default_workers() -> u16 {
     1
@@ -122,6 +127,53 @@ pub struct ConnectorConfig {
     /// The default is 1 million.
     #[serde(default = "default_max_buffered_records")]
     pub max_buffered_records: u64,
+
+    /// Dead-letter output for records that fail to parse.
+    ///
+    /// When set, every parse error reported by an input connector is written
+    /// to this output as a JSON record that contains the name of the
+    /// connector and the error, including the invalid input text or bytes,
+    /// when available.  Ignored by output connectors.
+    #[serde(default)]
+    pub dead_letter: Option<DeadLetterConfig>,
+
+    /// Maximal number of parse errors tolerated by an input connector.
+    ///
+    /// The connector fails with a fatal error and stops ingesting data once
+    /// the number of parse errors exceeds this threshold.  By default,
+    /// parse errors never cause the connector to fail.
+    #[serde(default)]
+    pub max_parse_errors: Option<u64>,
+
+    /// Maximal rate, in records per second, at which an input connector
+    /// ingests data.
+    ///
+    /// The connector is paused whenever it gets ahead of this rate, after an
+    /// initial burst of up to one second's worth of records.  By default,
+    /// the rate is not limited.  Ignored by output connectors.
+    #[serde(default)]
+    pub max_records_per_second: Option<u64>,
+
+    /// Maximal rate, in bytes per second, at which an input connector
+    /// ingests data.
+    ///
+    /// The connector is paused whenever it gets ahead of this rate, after an
+    /// initial burst of up to one second's worth of bytes.  By default, the
+    /// rate is not limited.  Ignored by output connectors.
+    #[serde(default)]
+    pub max_bytes_per_second: Option<u64>,
+
+    /// Relative share of each step's input for an input connector.
+    ///
+    /// While several input connectors have input buffered for the next step,
+    /// each one is paused once it buffers `max_buffered_records * weight / W`
+    /// records, where `W` is the largest weight among them.  Giving a live
+    /// connector a higher weight than a bulk backfill prevents the backfill
+    /// from crowding it out.  Ignored by output connectors.
+    ///
+    /// The default is 1.
+    #[serde(default = "default_weight")]
+    pub weight: u64,
 }
 
 impl ConnectorConfig {
@@ -134,6 +186,22 @@ impl ConnectorConfig {
     }
 }
 
+/// Dead-letter output configuration.
+///
+/// The dead-letter output must use an output transport that can be created
+/// from configuration, such as `file` or `kafka`.  The `http` transport is
+/// not supported, because HTTP output connectors are created by clients
+/// that connect to the pipeline, not by configuration; a pipeline that
+/// specifies it fails to start with an `UnsupportedDeadLetterTransport`
+/// error.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetterConfig {
+    /// Output transport that receives records that failed to parse.
+    ///
+    /// Records are written as newline-delimited JSON.
+    pub transport: TransportConfig,
+}
+
 /// Describes an output connector configuration
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct OutputEndpointConfig {
@@ -145,6 +213,12 @@ pub struct OutputEndpointConfig {
     #[serde(skip)]
     pub query: OutputQuery,
 
+    /// Ad hoc query evaluated over the output of a
+    /// [`Select`](`OutputQuery::Select`) query.  Only used for HTTP API
+    /// endpoints.
+    #[serde(skip)]
+    pub select: Option<SelectQuery>,
+
     /// Connector configuration.
     #[serde(flatten)]
     pub connector_config: ConnectorConfig,
@@ -0,0 +1,55 @@
+use super::UpdateFormat;
+use crate::program_schema::Relation;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Avro parser configuration.
+///
+/// Messages are decoded either using a schema retrieved from a Confluent
+/// schema registry (`registry_url`), or using a fixed schema (`schema`).
+/// Exactly one of the two must be specified.
+///
+/// Each record is inserted into the input table, unless the schema contains
+/// an `__action` or `__weight` field (see [`UpdateFormat`]), which
+/// specifies whether the record is inserted or deleted.
+#[derive(Default, Deserialize, Serialize, ToSchema)]
+pub struct AvroParserConfig {
+    /// URL of the schema registry, e.g., `http://localhost:8081`.
+    ///
+    /// When specified, each message must start with the 5-byte header
+    /// written by Confluent serializers: a zero magic byte followed by the
+    /// big-endian 32-bit id of the writer's schema in the registry.
+    #[serde(default)]
+    pub registry_url: Option<String>,
+
+    /// Avro schema of messages that do not carry a schema registry header,
+    /// as a JSON string.
+    #[serde(default)]
+    pub schema: Option<String>,
+}
+
+/// Avro encoder configuration.
+///
+/// The encoder derives an Avro schema from the schema of the output relation,
+/// registers it with a Confluent schema registry, and writes each output
+/// record as a separate message prefixed with the 5-byte schema registry
+/// header.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct AvroEncoderConfig {
+    /// URL of the schema registry, e.g., `http://localhost:8081`.
+    pub registry_url: String,
+
+    /// Subject to register the schema under.
+    ///
+    /// Defaults to `<relation name>-value`.
+    #[serde(default)]
+    pub subject: Option<String>,
+
+    /// How deletions are represented in the output.
+    #[serde(default)]
+    pub update_format: UpdateFormat,
+
+    /// Schema of the output table or view, as it appears in the `outputs`
+    /// section of the program schema.
+    pub relation_schema: Relation,
+}
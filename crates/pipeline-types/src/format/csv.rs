@@ -1,15 +1,134 @@
+use crate::program_schema::Relation;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, ToSchema)]
-pub struct CsvParserConfig {}
+const fn default_delimiter() -> char {
+    ','
+}
+
+const fn default_quote() -> char {
+    '"'
+}
+
+/// CSV parser configuration.
+///
+/// Describes the CSV dialect of the input stream.  The default dialect
+/// matches RFC 4180: comma-separated fields, optionally enclosed in double
+/// quotes, with quotes inside quoted fields escaped by doubling them, and no
+/// header row.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct CsvParserConfig {
+    /// Field delimiter, e.g., `\t` for TSV or `|` for pipe-delimited files.
+    /// Must be an ASCII character.
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+
+    /// Quote character.  Must be an ASCII character.
+    #[serde(default = "default_quote")]
+    pub quote: char,
+
+    /// Escape character for quotes inside quoted fields, e.g., `\`.
+    ///
+    /// When not specified, a quote inside a quoted field is escaped by
+    /// doubling it.
+    #[serde(default)]
+    pub escape: Option<char>,
+
+    /// Set to `true` if the first record of the stream is a header row that
+    /// lists column names.
+    ///
+    /// When `relation_schema` is specified, columns are mapped to the
+    /// columns of the table by name, ignoring case: they can appear in any
+    /// order, columns that are not in the table are ignored, and table
+    /// columns missing from the header are `NULL`.  Otherwise, the header
+    /// row is skipped and columns are mapped by position.
+    #[serde(default)]
+    pub has_headers: bool,
+
+    /// String that represents `NULL`, e.g., `\N`.
+    ///
+    /// Empty fields are always `NULL`.
+    #[serde(default)]
+    pub null_string: Option<String>,
+
+    /// Lines that start with this character are ignored.
+    #[serde(default)]
+    pub comment: Option<char>,
+
+    /// Schema of the input table, as it appears in the `inputs` section of
+    /// the program schema.  Required to map columns by header name.
+    #[serde(default)]
+    pub relation_schema: Option<Relation>,
+}
+
+impl Default for CsvParserConfig {
+    fn default() -> Self {
+        Self {
+            delimiter: default_delimiter(),
+            quote: default_quote(),
+            escape: None,
+            has_headers: false,
+            null_string: None,
+            comment: None,
+            relation_schema: None,
+        }
+    }
+}
 
 const fn default_buffer_size_records() -> usize {
     10_000
 }
 
-#[derive(Deserialize, Serialize, ToSchema)]
+/// CSV encoder configuration.
+///
+/// Describes the CSV dialect of the output stream; the defaults match
+/// [`CsvParserConfig`].
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct CsvEncoderConfig {
     #[serde(default = "default_buffer_size_records")]
     pub buffer_size_records: usize,
+
+    /// Field delimiter.  Must be an ASCII character.
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+
+    /// Quote character.  Must be an ASCII character.
+    #[serde(default = "default_quote")]
+    pub quote: char,
+
+    /// Escape character for quotes inside quoted fields.
+    ///
+    /// When not specified, a quote inside a quoted field is escaped by
+    /// doubling it.
+    #[serde(default)]
+    pub escape: Option<char>,
+
+    /// Set to `true` to write a header row with column names before the
+    /// first record.
+    #[serde(default)]
+    pub has_headers: bool,
+
+    /// String written for `NULL` values instead of an empty field.
+    #[serde(default)]
+    pub null_string: Option<String>,
+
+    /// Comment character of the reader of the output.  Fields that start
+    /// with this character are quoted, so that they are not mistaken for
+    /// comments.
+    #[serde(default)]
+    pub comment: Option<char>,
+}
+
+impl Default for CsvEncoderConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size_records: default_buffer_size_records(),
+            delimiter: default_delimiter(),
+            quote: default_quote(),
+            escape: None,
+            has_headers: false,
+            null_string: None,
+            comment: None,
+        }
+    }
 }
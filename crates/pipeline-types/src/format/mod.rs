@@ -1,2 +1,24 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub mod avro;
 pub mod csv;
 pub mod json;
+pub mod parquet;
+
+/// Supported Avro and Parquet data change event formats.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq, ToSchema)]
+pub enum UpdateFormat {
+    /// Each record contains a string `__action` field (a column, in Parquet)
+    /// whose value is `"insert"` or `"delete"`.  A record with weight `w` is
+    /// written `|w|` times.
+    #[default]
+    #[serde(rename = "insert_delete")]
+    InsertDelete,
+
+    /// Each record contains a 64-bit `__weight` field (a column, in Parquet)
+    /// that contains the weight of the record, which is negative for
+    /// deletions.
+    #[serde(rename = "weighted")]
+    Weighted,
+}
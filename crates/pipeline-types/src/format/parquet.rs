@@ -0,0 +1,49 @@
+use super::UpdateFormat;
+use crate::program_schema::Relation;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Parquet parser configuration.
+///
+/// The parser ingests complete Parquet files.  Each row is inserted into the
+/// input table, unless the file contains an `__action` or `__weight` column
+/// (see [`UpdateFormat`]), which specifies whether the row is inserted
+/// or deleted.
+#[derive(Default, Deserialize, Serialize, ToSchema)]
+pub struct ParquetParserConfig {
+    /// Schema of the input table, as it appears in the `inputs` section of
+    /// the program schema.
+    ///
+    /// When specified, the parser checks the schema of each file before
+    /// ingesting it: every column of the table that does not accept NULL
+    /// values must be present in the file, and every column of the table
+    /// that is present must have an Arrow type that can be converted to the
+    /// SQL type of the column.
+    #[serde(default)]
+    pub relation_schema: Option<Relation>,
+}
+
+const fn default_buffer_size_records() -> usize {
+    10_000
+}
+
+/// Parquet encoder configuration.
+///
+/// The encoder writes each batch of output updates as a complete Parquet
+/// file, with up to `buffer_size_records` records in each row group.
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ParquetEncoderConfig {
+    /// Maximum number of records in a row group.
+    #[serde(default = "default_buffer_size_records")]
+    pub buffer_size_records: usize,
+
+    /// How deletions are represented in the output.
+    #[serde(default)]
+    pub update_format: UpdateFormat,
+
+    /// Schema of the output table or view, as it appears in the `outputs`
+    /// section of the program schema.
+    ///
+    /// The Arrow schema of the Parquet file is derived from this relation.
+    pub relation_schema: Relation,
+}
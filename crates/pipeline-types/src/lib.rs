@@ -1,6 +1,7 @@
 pub mod config;
 pub mod error;
 pub mod format;
+pub mod program_schema;
 pub mod query;
 pub mod secret_ref;
 pub mod transport;
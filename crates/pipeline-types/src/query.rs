@@ -1,10 +1,11 @@
 use serde::Deserialize;
+use serde_json::Value as JsonValue;
 use utoipa::ToSchema;
 
 /// A query over an output stream.
 ///
-/// We currently do not support ad hoc queries.  Instead the client can use
-/// three pre-defined queries to inspect the contents of a table or view.
+/// The client can use three pre-defined queries to inspect the contents of a
+/// table or view, or run an ad hoc [`SelectQuery`] over its current contents.
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, PartialOrd, ToSchema, Ord)]
 pub enum OutputQuery {
     /// Query the entire contents of the table (similar to `SELECT * FROM`).
@@ -16,6 +17,10 @@ pub enum OutputQuery {
     /// Quantiles query (see `Stream::stream_key_quantiles` in dbsp).
     #[serde(rename = "quantiles")]
     Quantiles,
+    /// Ad hoc query over the current contents of the table (see
+    /// [`SelectQuery`]).
+    #[serde(rename = "select")]
+    Select,
 }
 
 impl Default for OutputQuery {
@@ -36,3 +41,65 @@ pub struct NeighborhoodQuery {
     pub before: u32,
     pub after: u32,
 }
+
+/// An ad hoc query over the current contents of a table or view, similar to
+/// `SELECT columns FROM view WHERE filter LIMIT limit`.
+///
+/// The query is evaluated over a snapshot of the table by the pipeline
+/// without recompiling the program.
+///
+/// The query does not use indexes: evaluating it scans the entire snapshot
+/// and converts each record to JSON to evaluate `filter`, even if the filter
+/// only selects a few records.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct SelectQuery {
+    /// Only output records that satisfy all of these predicates.
+    #[serde(default)]
+    pub filter: Vec<Predicate>,
+
+    /// Only output these columns, in this order.  Outputs all columns when
+    /// not specified.
+    ///
+    /// Column projection is only supported with the JSON format.
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+
+    /// Output at most this many records.
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+/// A predicate that compares a column with a constant, e.g., `x >= 5`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, ToSchema)]
+pub struct Predicate {
+    /// Column name.  Matched ignoring case.
+    pub column: String,
+
+    /// Comparison operator.
+    pub op: PredicateOp,
+
+    /// Value to compare the column with, in the JSON format of the column
+    /// type.
+    ///
+    /// As in SQL, comparisons with `NULL` are false, except that `eq` and
+    /// `ne` with a `null` value test whether the column is or is not `NULL`.
+    #[schema(value_type = Object)]
+    pub value: JsonValue,
+}
+
+/// Comparison operator of a [`Predicate`].
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum PredicateOp {
+    #[serde(rename = "eq")]
+    Eq,
+    #[serde(rename = "ne")]
+    Ne,
+    #[serde(rename = "lt")]
+    Lt,
+    #[serde(rename = "le")]
+    Le,
+    #[serde(rename = "gt")]
+    Gt,
+    #[serde(rename = "ge")]
+    Ge,
+}
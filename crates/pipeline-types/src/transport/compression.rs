@@ -0,0 +1,31 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Compression applied to input data.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum Compression {
+    /// The input is not compressed.
+    #[default]
+    #[serde(rename = "none")]
+    None,
+
+    /// Detect the compression codec from the file name extension (`.gz`,
+    /// `.zst`, or `.bz2`), or, failing that, from the magic bytes at the
+    /// start of the input.  Input that matches neither is passed through
+    /// unchanged.
+    #[serde(rename = "auto")]
+    Auto,
+
+    /// gzip compression.  Input made of multiple concatenated gzip members is
+    /// decompressed as a whole.
+    #[serde(rename = "gzip")]
+    Gzip,
+
+    /// Zstandard compression.
+    #[serde(rename = "zstd")]
+    Zstd,
+
+    /// bzip2 compression.
+    #[serde(rename = "bzip2")]
+    Bzip2,
+}
@@ -0,0 +1,97 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Configuration for reading data from the files in a directory with
+/// `DirectoryInputTransport`.
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct DirectoryInputConfig {
+    /// Glob pattern that selects the files to read, e.g.,
+    /// `/data/events/events-*.json`.
+    ///
+    /// Files that match the pattern are read one at a time, in the order
+    /// specified by `order`, and their contents are concatenated.  A newline
+    /// is inserted after a file that does not end in one.
+    pub path: String,
+
+    /// The order in which matching files are read.
+    #[serde(default)]
+    pub order: FileOrder,
+
+    /// Keep watching for new files.
+    ///
+    /// When `true`, the endpoint polls for new files that match `path` after
+    /// it reads all of the existing ones.  When `false`, the endpoint outputs
+    /// an `InputConsumer::eoi` message and stops after reading the files that
+    /// match `path` at the time it runs out of files.
+    ///
+    /// The endpoint reads a file only once its size and modification time
+    /// are unchanged across two consecutive polls, so that it doesn't read a
+    /// file that is still being written.  Writers that might pause for
+    /// longer than `poll_interval_ms` should create files atomically instead,
+    /// e.g., by writing them under a name that does not match `path` and then
+    /// renaming them.
+    #[serde(default = "default_watch")]
+    pub watch: bool,
+
+    /// How often to check for new files and for changes to files that are
+    /// still being written, in milliseconds.
+    ///
+    /// Default: 1000.
+    pub poll_interval_ms: Option<u64>,
+
+    /// Path of a file in which the endpoint records the files it has
+    /// completely read, so that they are not read again after a restart.
+    ///
+    /// A file is recorded as completed only once all of its data has been
+    /// included in a step.  If this is not set, completed files are only
+    /// remembered while the endpoint is running.  A file that was not
+    /// completed at the time of a restart is read again from the beginning.
+    pub state_path: Option<String>,
+
+    /// What to do with a file after it is completed.
+    #[serde(default)]
+    pub after_read: AfterRead,
+
+    /// Directory to move files into when `after_read` is `move`.
+    pub move_to: Option<String>,
+
+    /// Read buffer size.
+    ///
+    /// Default: when this parameter is not specified, a platform-specific
+    /// default is used.
+    pub buffer_size_bytes: Option<usize>,
+}
+
+fn default_watch() -> bool {
+    true
+}
+
+/// The order in which `DirectoryInputTransport` reads files.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum FileOrder {
+    /// Lexicographic order of file names.
+    #[default]
+    #[serde(rename = "name")]
+    Name,
+
+    /// Order of modification time, oldest first, with ties broken by name.
+    #[serde(rename = "mtime")]
+    Mtime,
+}
+
+/// What `DirectoryInputTransport` does with a file after reading all of it.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, ToSchema)]
+pub enum AfterRead {
+    /// Leave the file in place.
+    #[default]
+    #[serde(rename = "keep")]
+    Keep,
+
+    /// Delete the file.
+    #[serde(rename = "delete")]
+    Delete,
+
+    /// Move the file into the directory specified by `move_to`.
+    #[serde(rename = "move")]
+    Move,
+}
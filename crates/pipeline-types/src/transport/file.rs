@@ -1,3 +1,4 @@
+use super::compression::Compression;
 use serde::Deserialize;
 use utoipa::ToSchema;
 
@@ -21,6 +22,38 @@ pub struct FileInputConfig {
     /// appended to it.
     #[serde(default)]
     pub follow: bool,
+
+    /// Compression of the file's contents.
+    ///
+    /// Compressed input is decompressed as it is read.  Compression may not
+    /// be combined with `fault_tolerance`.
+    #[serde(default)]
+    pub compression: Compression,
+
+    /// Enable fault tolerance.
+    ///
+    /// When this is set, the endpoint records the range of bytes of the file
+    /// consumed in each step in a journal, so that a restarted pipeline can
+    /// re-read exactly the same data for each step and resume where it left
+    /// off.  Fault tolerance requires newline-delimited records, because step
+    /// boundaries are only placed after a newline.
+    pub fault_tolerance: Option<FileInputFtConfig>,
+}
+
+/// Fault tolerance configuration for the file input connector.
+#[derive(Deserialize, Clone, Debug, ToSchema)]
+pub struct FileInputFtConfig {
+    /// Path of the journal file that records the division of the input file
+    /// into steps.  Defaults to the input file path with a `.journal`
+    /// suffix appended.
+    ///
+    /// If two or more fault-tolerant file endpoints read the same file, they
+    /// must specify different journal paths.
+    pub journal_path: Option<String>,
+
+    /// Maximum number of bytes in a step.  A single line bigger than this
+    /// will be given a step of its own.
+    pub max_step_bytes: Option<u64>,
 }
 
 /// Configuration for writing data to a file with `FileOutputTransport`.
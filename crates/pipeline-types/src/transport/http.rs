@@ -40,8 +40,8 @@ pub enum EgressMode {
     Watch,
     /// Output a single snapshot of query results.
     ///
-    /// Currently only supported for `OutputQuery::Quantiles`
-    /// and `OutputQuery::Neighborhood` queries.
+    /// Currently only supported for `OutputQuery::Quantiles`,
+    /// `OutputQuery::Neighborhood`, and `OutputQuery::Select` queries.
     #[serde(rename = "snapshot")]
     Snapshot,
 }
@@ -229,6 +229,38 @@ pub struct KafkaOutputConfig {
     /// If specified, this enables fault tolerance in the Kafka output
     /// connector.
     pub fault_tolerance: Option<KafkaOutputFtConfig>,
+
+    /// How output records are packaged into Kafka messages.
+    ///
+    /// Defaults to `batch`.
+    #[serde(default)]
+    pub message_mode: KafkaMessageMode,
+
+    /// Columns whose values form the key of each message in the `record`
+    /// message mode.
+    ///
+    /// Must be non-empty in the `record` mode and empty in the `batch` mode.
+    #[serde(default)]
+    pub key_fields: Vec<String>,
+}
+
+/// How the Kafka output connector packages output records into messages.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaMessageMode {
+    /// Each message contains a buffer of records, as produced by the encoder,
+    /// and has no meaningful key.
+    #[default]
+    Batch,
+
+    /// Each message contains a single record and is keyed by the values of
+    /// the `key_fields` columns of the record, so that Kafka assigns all
+    /// changes to a given key to the same partition.  A deleted record is
+    /// written as a tombstone, i.e., a message with a null value, which lets
+    /// the output topic be compacted.
+    ///
+    /// This mode is only supported by the JSON format.
+    Record,
 }
 
 /// Fault tolerance configuration for Kafka output connector.
@@ -265,7 +297,24 @@ impl KafkaOutputConfig {
     /// adapter.
     pub fn validate(&mut self) -> AnyResult<()> {
         self.set_option_if_missing("bootstrap.servers", &default_redpanda_server());
-        Ok(())
+        match self.message_mode {
+            KafkaMessageMode::Batch if !self.key_fields.is_empty() => Err(AnyError::msg(
+                "'key_fields' can only be specified with 'message_mode: record'",
+            )),
+            KafkaMessageMode::Record if self.key_fields.is_empty() => Err(AnyError::msg(
+                "'message_mode: record' requires at least one column in 'key_fields'",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the columns that key each message, if the connector writes one
+    /// message per record.
+    pub fn record_key_fields(&self) -> Option<&[String]> {
+        match self.message_mode {
+            KafkaMessageMode::Batch => None,
+            KafkaMessageMode::Record => Some(&self.key_fields),
+        }
     }
 }
 
@@ -1,4 +1,8 @@
+pub mod compression;
+pub mod directory;
 pub mod file;
 pub mod http;
 pub mod kafka;
+pub mod postgres;
+pub mod s3;
 pub mod url;
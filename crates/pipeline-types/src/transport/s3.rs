@@ -0,0 +1,132 @@
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+/// Configuration for reading data from the objects in an S3 bucket with
+/// `S3InputTransport`.
+///
+/// The connector reads the objects whose keys start with `prefix`, one at a
+/// time, in lexicographic order of their keys, and concatenates their
+/// contents.  A newline is inserted after an object that does not end in one.
+///
+/// The connector remembers the key of the last object that it read
+/// completely and afterward only reads objects whose keys sort after it, so
+/// when `watch` is `true`, new objects must be added in increasing key order.
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct S3InputConfig {
+    /// Name of the bucket to read from.
+    pub bucket_name: String,
+
+    /// Only read objects whose keys start with this prefix, e.g.,
+    /// `events/2024/`.
+    ///
+    /// Default: read all objects in the bucket.
+    #[serde(default)]
+    pub prefix: String,
+
+    /// Region of the bucket, e.g., `us-east-1`.
+    pub region: String,
+
+    /// URL of an S3-compatible service, such as MinIO, e.g.,
+    /// `http://localhost:9000`.
+    ///
+    /// Objects are addressed in path style when this is set.
+    ///
+    /// Default: the AWS S3 endpoint for `region`.
+    pub endpoint_url: Option<String>,
+
+    /// Access key id, which may be a secret reference of the form
+    /// `${secret:<identifier>}`.
+    ///
+    /// If neither `access_key_id` nor `secret_access_key` is set, credentials
+    /// are obtained from the environment, e.g., from the
+    /// `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY` environment variables
+    /// or from an instance profile.
+    pub access_key_id: Option<String>,
+
+    /// Secret access key, which may be a secret reference of the form
+    /// `${secret:<identifier>}`.
+    pub secret_access_key: Option<String>,
+
+    /// Keep watching for new objects.
+    ///
+    /// When `true`, the endpoint lists the bucket again for new objects
+    /// after it reads all of the existing ones.  When `false`, the endpoint
+    /// outputs an `InputConsumer::eoi` message and stops after reading the
+    /// objects that exist at the time it runs out of objects.
+    #[serde(default)]
+    pub watch: bool,
+
+    /// How often to list the bucket for new objects, in milliseconds, when
+    /// `watch` is `true`.
+    ///
+    /// Default: 10000.
+    pub poll_interval_ms: Option<u64>,
+
+    /// Path of a file in which the endpoint records the key of the last
+    /// object it has completely read and whose data has become part of a
+    /// step, so that objects are not read again after a restart.
+    ///
+    /// If this is not set, the last completed object is only remembered
+    /// while the endpoint is running.  An object that was partially read, or
+    /// whose data had not yet become part of a step, at the time of a
+    /// restart is read again from the beginning.
+    pub state_path: Option<String>,
+}
+
+/// Configuration for writing data to objects in an S3 bucket with
+/// `S3OutputTransport`.
+///
+/// The connector groups output steps into consecutive runs of
+/// `steps_per_object` steps and writes the output of each group as one
+/// object, named `<prefix><step><suffix>`, where `<step>` is the number of
+/// the group's first step, zero-padded to 20 digits so that the objects sort
+/// in output order.  A group without any output is not written.
+///
+/// The connector never overwrites objects: it refuses to start if the bucket
+/// already contains an object named this way under `prefix`, e.g., from an
+/// earlier run of the pipeline.
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct S3OutputConfig {
+    /// Name of the bucket to write to.
+    pub bucket_name: String,
+
+    /// Prefix of the keys of the objects written, e.g., `orders/`.
+    #[serde(default)]
+    pub prefix: String,
+
+    /// Suffix of the keys of the objects written, e.g., `.csv`.
+    #[serde(default)]
+    pub suffix: String,
+
+    /// Region of the bucket, e.g., `us-east-1`.
+    pub region: String,
+
+    /// URL of an S3-compatible service, such as MinIO, e.g.,
+    /// `http://localhost:9000`.
+    ///
+    /// Objects are addressed in path style when this is set.
+    ///
+    /// Default: the AWS S3 endpoint for `region`.
+    pub endpoint_url: Option<String>,
+
+    /// Access key id, which may be a secret reference of the form
+    /// `${secret:<identifier>}`.
+    ///
+    /// If neither `access_key_id` nor `secret_access_key` is set, credentials
+    /// are obtained from the environment.
+    pub access_key_id: Option<String>,
+
+    /// Secret access key, which may be a secret reference of the form
+    /// `${secret:<identifier>}`.
+    pub secret_access_key: Option<String>,
+
+    /// Number of steps whose output is written to each object.
+    ///
+    /// Default: 1.
+    #[serde(default = "default_steps_per_object")]
+    pub steps_per_object: u64,
+}
+
+fn default_steps_per_object() -> u64 {
+    1
+}
@@ -1,3 +1,4 @@
+use super::compression::Compression;
 use serde::Deserialize;
 use utoipa::ToSchema;
 
@@ -7,4 +8,11 @@ use utoipa::ToSchema;
 pub struct UrlInputConfig {
     /// URL.
     pub path: String,
+
+    /// Compression of the URL's content.
+    ///
+    /// Compressed content is decompressed as it is received.  This is
+    /// independent of any `Content-Encoding` negotiated with the server.
+    #[serde(default)]
+    pub compression: Compression,
 }
@@ -162,14 +162,14 @@ async fn http_input(
         ("table_name" = String, Path,
             description = "SQL table name. Unquoted SQL names have to be capitalized. Quoted SQL names have to exactly match the case from the SQL program."),
         ("format" = String, Query, description = "Output data format, e.g., 'csv' or 'json'."),
-        ("query" = Option<OutputQuery>, Query, description = "Query to execute on the table. Must be one of 'table', 'neighborhood', or 'quantiles'. The default value is 'table'"),
+        ("query" = Option<OutputQuery>, Query, description = "Query to execute on the table. Must be one of 'table', 'neighborhood', 'quantiles', or 'select'. The default value is 'table'"),
         ("mode" = Option<EgressMode>, Query, description = "Output mode. Must be one of 'watch' or 'snapshot'. The default value is 'watch'"),
         ("quantiles" = Option<u32>, Query, description = "For 'quantiles' queries: the number of quantiles to output. The default value is 100."),
         ("array" = Option<bool>, Query, description = "Set to `true` to group updates in this stream into JSON arrays (used in conjunction with `format=json`). The default value is `false`"),
     ),
     request_body(
         content = Option<NeighborhoodQuery>,
-        description = "When the `query` parameter is set to 'neighborhood', the body of the request must contain a neighborhood specification.",
+        description = "When the `query` parameter is set to 'neighborhood', the body of the request must contain a neighborhood specification.  When it is set to 'select', the body must contain a `SelectQuery`.",
         content_type = "application/json",
     ),
     context_path = "/v0",
@@ -160,9 +160,9 @@ request is rejected."
         crate::db::AttachedConnector,
         crate::db::ProgramDescr,
         crate::db::ProgramSchema,
-        crate::db::Relation,
-        crate::db::Field,
-        crate::db::ColumnType,
+        pipeline_types::program_schema::Relation,
+        pipeline_types::program_schema::Field,
+        pipeline_types::program_schema::ColumnType,
         crate::db::ConnectorDescr,
         crate::db::ServiceDescr,
         crate::db::Pipeline,
@@ -176,6 +176,9 @@ request is rejected."
         crate::db::ApiPermission,
         pipeline_types::query::NeighborhoodQuery,
         pipeline_types::query::OutputQuery,
+        pipeline_types::query::SelectQuery,
+        pipeline_types::query::Predicate,
+        pipeline_types::query::PredicateOp,
         pipeline_types::config::PipelineConfig,
         pipeline_types::config::InputEndpointConfig,
         pipeline_types::config::OutputEndpointConfig,
@@ -183,10 +186,16 @@ request is rejected."
         pipeline_types::config::FormatConfig,
         pipeline_types::config::RuntimeConfig,
         pipeline_types::config::ConnectorConfig,
+        pipeline_types::config::DeadLetterConfig,
         pipeline_types::config::TransportConfig,
         pipeline_types::config::FormatConfig,
         pipeline_types::config::ResourceConfig,
+        pipeline_types::transport::compression::Compression,
+        pipeline_types::transport::directory::DirectoryInputConfig,
+        pipeline_types::transport::directory::FileOrder,
+        pipeline_types::transport::directory::AfterRead,
         pipeline_types::transport::file::FileInputConfig,
+        pipeline_types::transport::file::FileInputFtConfig,
         pipeline_types::transport::file::FileOutputConfig,
         pipeline_types::transport::url::UrlInputConfig,
         pipeline_types::transport::kafka::KafkaInputConfig,
@@ -194,14 +203,25 @@ request is rejected."
         pipeline_types::transport::kafka::KafkaOutputConfig,
         pipeline_types::transport::kafka::KafkaOutputFtConfig,
         pipeline_types::transport::kafka::KafkaLogLevel,
+        pipeline_types::transport::kafka::KafkaMessageMode,
+        pipeline_types::transport::postgres::PostgresCdcInputConfig,
+        pipeline_types::transport::postgres::PostgresOutputConfig,
+        pipeline_types::transport::postgres::PostgresOutputFtConfig,
+        pipeline_types::transport::s3::S3InputConfig,
+        pipeline_types::transport::s3::S3OutputConfig,
         pipeline_types::transport::http::Chunk,
         pipeline_types::transport::http::EgressMode,
+        pipeline_types::format::avro::AvroEncoderConfig,
+        pipeline_types::format::avro::AvroParserConfig,
         pipeline_types::format::csv::CsvEncoderConfig,
         pipeline_types::format::csv::CsvParserConfig,
         pipeline_types::format::json::JsonEncoderConfig,
         pipeline_types::format::json::JsonParserConfig,
         pipeline_types::format::json::JsonFlavor,
         pipeline_types::format::json::JsonUpdateFormat,
+        pipeline_types::format::parquet::ParquetEncoderConfig,
+        pipeline_types::format::parquet::ParquetParserConfig,
+        pipeline_types::format::UpdateFormat,
         pipeline_types::error::ErrorResponse,
         TenantId,
         ProgramId,
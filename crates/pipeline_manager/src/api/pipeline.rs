@@ -74,7 +74,7 @@ pub(crate) struct UpdatePipelineRequest {
     /// - If absent, existing connectors will be kept unmodified.
     ///
     /// - If present all existing connectors will be replaced with the new
-    /// specified list.
+    ///   specified list.
     connectors: Option<Vec<AttachedConnector>>,
 }
 
@@ -390,7 +390,7 @@ impl Compiler {
         let mut command = Command::new("cargo");
 
         command
-            .current_dir(&config.workspace_dir())
+            .current_dir(config.workspace_dir())
             .arg("build")
             .arg("--workspace")
             .stdin(Stdio::null())
@@ -112,12 +112,9 @@ impl FromStr for ApiPermission {
 // Re-exports
 // Program
 mod program;
-pub(crate) use self::program::ColumnType;
-pub(crate) use self::program::Field;
 pub(crate) use self::program::ProgramDescr;
 pub use self::program::ProgramId;
 pub(crate) use self::program::ProgramSchema;
-pub(crate) use self::program::Relation;
 
 // Connectors
 mod connector;
@@ -1288,7 +1285,7 @@ impl ProjectDB {
             .get_migrations()
             .iter()
             .map(|m| m.version())
-            .fold(std::u32::MIN, |a, b| a.max(b));
+            .fold(u32::MIN, |a, b| a.max(b));
         let migration = runner.get_last_applied_migration_async(&mut **client).await;
         if let Ok(Some(m)) = migration {
             let v = m.version();
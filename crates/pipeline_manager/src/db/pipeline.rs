@@ -445,9 +445,10 @@ impl PipelineRevision {
             }
             let output_endpoint_config = OutputEndpointConfig {
                 stream: Cow::from(ac.relation_name.clone()),
-                // This field gets skipped during serialization/deserialization,
-                // so it doesn't matter what value we use here
+                // These fields get skipped during serialization/deserialization,
+                // so it doesn't matter what values we use here
                 query: OutputQuery::default(),
+                select: None,
                 connector_config: connector.unwrap().config.clone(),
             };
             expanded_outputs.insert(Cow::from(ac.name.clone()), output_endpoint_config);
@@ -1155,6 +1156,7 @@ pub(crate) async fn set_pipeline_desired_status(
 }
 
 /// Returns true if the connector of a given name is an input connector.
+#[allow(dead_code)] // Reason: only used in tests
 pub(crate) async fn attached_connector_is_input(
     db: &ProjectDB,
     tenant_id: TenantId,
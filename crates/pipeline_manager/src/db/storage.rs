@@ -28,6 +28,7 @@ pub(crate) trait Storage {
     /// # Note
     /// This should be called after the SQL compilation succeeded, e.g., in the
     /// same transaction that sets status to  [`ProgramStatus::CompilingRust`].
+    #[allow(dead_code)] // Reason: only used in tests
     async fn set_program_schema(
         &self,
         tenant_id: TenantId,
@@ -188,6 +189,7 @@ pub(crate) trait Storage {
     ) -> Result<Version, DBError>;
 
     /// Get input/output status for an attached connector.
+    #[allow(dead_code)] // Reason: only used in tests
     async fn attached_connector_is_input(
         &self,
         tenant_id: TenantId,
@@ -205,6 +205,7 @@ pub enum Operation {
 }
 
 #[derive(Debug)]
+#[allow(dead_code)] // Reason: the fields are only read through `Debug`
 enum NotificationError {
     MissingPayloadComponents(String),
     InvalidOperationError(String),
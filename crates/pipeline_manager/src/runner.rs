@@ -457,7 +457,7 @@ impl RunnerApi {
     ) -> Result<reqwest::Response, RunnerError> {
         let client = reqwest::Client::new();
         client
-            .request(method, &format!("http://{location}/{endpoint}",))
+            .request(method, format!("http://{location}/{endpoint}",))
             .timeout(Self::PIPELINE_HTTP_REQUEST_TIMEOUT)
             .send()
             .await
@@ -70,7 +70,10 @@ const sidebars = {
               items: [
                   'connectors/sources/http',
                   'connectors/sources/http-get',
+                  'connectors/sources/directory',
                   'connectors/sources/kafka',
+                  'connectors/sources/postgres-cdc',
+                  'connectors/sources/s3',
                   'connectors/sources/debezium-mysql'
               ]
           },
@@ -84,6 +87,8 @@ const sidebars = {
               items: [
                   'connectors/sinks/http',
                   'connectors/sinks/kafka',
+                  'connectors/sinks/postgres',
+                  'connectors/sinks/s3',
                   'connectors/sinks/snowflake'
               ]
           }
@@ -115,7 +120,7 @@ const sidebars = {
     {
       type: 'category',
       label: 'API References',
-      items: ['api/rest', 'api/json', 'api/csv', 'api/rust']
+      items: ['api/rest', 'api/json', 'api/csv', 'api/parquet', 'api/avro', 'api/rust']
     },
     'papers',
     'videos',
@@ -5,7 +5,10 @@ use crate::binary::ByteArray;
 use crate::timestamp::*;
 use core::ops::Add;
 use dbsp::algebra::{F32, F64};
-use num::PrimInt;
+use dbsp::operator::{
+    ApproxCountDistinct, ApproxPercentile, Median, Mode, PercentileCont, PercentileDisc,
+};
+use num::{PrimInt, ToPrimitive};
 use rust_decimal::Decimal;
 use std::cmp::Ord;
 use std::marker::Copy;
@@ -186,3 +189,47 @@ pub fn agg_xor_bytes(left: &ByteArray, right: &ByteArray) -> ByteArray {
 }
 
 some_aggregate!(agg_xor_bytes, agg_xor, bytes, ByteArray);
+
+// Percentile aggregates are not computed by folding values with a function
+// like the ones above; instead, these functions construct the DBSP
+// aggregators that evaluate them over each group.
+
+pub fn percentile_cont_d(fraction: F64) -> PercentileCont {
+    PercentileCont::new(fraction.into_inner())
+}
+
+pub fn percentile_cont_decimal(fraction: Decimal) -> PercentileCont {
+    PercentileCont::new(fraction.to_f64().unwrap())
+}
+
+pub fn percentile_disc_d(fraction: F64) -> PercentileDisc {
+    PercentileDisc::new(fraction.into_inner())
+}
+
+pub fn percentile_disc_decimal(fraction: Decimal) -> PercentileDisc {
+    PercentileDisc::new(fraction.to_f64().unwrap())
+}
+
+pub fn median() -> Median {
+    Median
+}
+
+pub fn mode() -> Mode {
+    Mode
+}
+
+// Approximate aggregates are based on sketches that cannot forget values, so
+// the compiler should evaluate them with `aggregate_append_only` when the
+// input is insert-only.
+
+pub fn approx_count_distinct() -> ApproxCountDistinct {
+    ApproxCountDistinct::default()
+}
+
+pub fn approx_percentile_d(fraction: F64) -> ApproxPercentile {
+    ApproxPercentile::new(fraction.into_inner())
+}
+
+pub fn approx_percentile_decimal(fraction: Decimal) -> ApproxPercentile {
+    ApproxPercentile::new(fraction.to_f64().unwrap())
+}